@@ -0,0 +1,482 @@
+use crate::io_basic::checksum::md5_hex;
+use crate::netdisk_api::base_api::{api_get, api_post};
+use crate::netdisk_auth::basic_env::NetDiskEnv;
+use crate::responses::prelude::*;
+use actix_web::{HttpRequest, HttpResponse};
+use chrono::{DateTime, Duration, Utc};
+use log::{debug, warn};
+use lru::LruCache;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::error::Error;
+use std::num::NonZeroUsize;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+/// 磁盘缓存目录，位于配置目录下
+pub const CACHE_DIR: &str = "cache";
+
+/// 磁盘缓存目录中记录各标签失效时间的文件，重启后仍能使此前失效的条目作废
+const INVALIDATED_FILE: &str = "invalidated.json";
+
+/// 网关返回缓存命中情况的响应头
+pub const CACHE_STATUS_HEADER: &str = "X-Cache";
+
+/// `max-age` 的上限，更大的取值按该值处理（RFC 9111 §1.2.2）
+pub const MAX_AGE_LIMIT: u64 = 1 << 31;
+
+/// `time` 加上 `secs` 秒，超出时间范围时返回 `None`，调用方按不设上限处理
+fn checked_add_secs(time: DateTime<Utc>, secs: u64) -> Option<DateTime<Utc>> {
+    let delta = Duration::try_seconds(i64::try_from(secs).ok()?)?;
+    time.checked_add_signed(delta)
+}
+
+/// 缓存条目关联的对象，对象发生变化时关联的条目全部失效
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(rename_all = "camelCase")]
+pub enum CacheTag {
+    /// 目录的文件列表
+    Folder(u64),
+    /// 文件详情，以及包含该文件的列表
+    File(u64),
+}
+
+/// 单次请求的缓存策略
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CachePolicy {
+    /// 命中且未过期时直接返回，否则请求上游并写入缓存
+    #[default]
+    Use,
+    /// 只接受不超过指定秒数的缓存
+    MaxAge(u64),
+    /// 跳过缓存读取，但写入最新结果
+    Refresh,
+    /// 既不读取也不写入
+    NoStore,
+}
+
+impl CachePolicy {
+    /// 由 `RequestConfig.cache` 得到策略，`false` 表示不使用缓存
+    pub fn from_flag(cache: bool) -> Self {
+        if cache {
+            CachePolicy::Use
+        } else {
+            CachePolicy::NoStore
+        }
+    }
+
+    /// 解析网关请求的 `Cache-Control` 头：`no-store`、`no-cache` 与 `max-age=N`
+    pub fn from_cache_control(value: Option<&str>) -> Self {
+        let value = match value {
+            Some(value) => value,
+            None => return CachePolicy::Use,
+        };
+        let mut policy = CachePolicy::Use;
+        for directive in value.split(',').map(str::trim) {
+            let directive = directive.to_ascii_lowercase();
+            if directive == "no-store" {
+                return CachePolicy::NoStore;
+            } else if directive == "no-cache" {
+                policy = CachePolicy::Refresh;
+            } else if let Some(secs) = directive.strip_prefix("max-age=") {
+                if policy == CachePolicy::Use {
+                    let secs = secs.trim_matches('"');
+                    policy = if secs.is_empty() || !secs.bytes().all(|b| b.is_ascii_digit()) {
+                        CachePolicy::Use
+                    } else {
+                        match secs
+                            .parse::<u64>()
+                            .map_or(MAX_AGE_LIMIT, |secs| secs.min(MAX_AGE_LIMIT))
+                        {
+                            0 => CachePolicy::Refresh,
+                            secs => CachePolicy::MaxAge(secs),
+                        }
+                    };
+                }
+            }
+        }
+        policy
+    }
+
+    /// 读取网关请求的 `Cache-Control` 头
+    pub fn from_request(req: &HttpRequest) -> Self {
+        Self::from_cache_control(
+            req.headers()
+                .get("Cache-Control")
+                .and_then(|value| value.to_str().ok()),
+        )
+    }
+}
+
+/// 本次请求是否命中缓存，网关通过 `X-Cache` 响应头返回
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CacheStatus {
+    Hit,
+    Miss,
+    Bypass,
+}
+
+impl CacheStatus {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            CacheStatus::Hit => "HIT",
+            CacheStatus::Miss => "MISS",
+            CacheStatus::Bypass => "BYPASS",
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+struct CacheEntry {
+    key: String,
+    /// 发起上游请求的时间，晚于该时间的失效会使条目作废
+    stored_at: DateTime<Utc>,
+    expires_at: DateTime<Utc>,
+    tags: Vec<CacheTag>,
+    value: String,
+}
+
+/// 上游响应的读穿缓存：内存 LRU，可选写入磁盘。
+///
+/// 失效只记录标签的失效时间，读取时与条目的写入时间比较，不需要遍历条目。
+pub struct ResponseCache {
+    config: CacheConfig,
+    memory: Mutex<LruCache<String, CacheEntry>>,
+    invalidated: Mutex<HashMap<CacheTag, DateTime<Utc>>>,
+    disk_dir: Option<PathBuf>,
+}
+
+impl ResponseCache {
+    pub fn new(config: &CacheConfig, env: &NetDiskEnv) -> Self {
+        let capacity = NonZeroUsize::new(config.capacity.max(1)).expect("容量至少为 1");
+        let disk_dir = config.disk.then(|| env.config_dir.join(CACHE_DIR));
+        let invalidated = disk_dir
+            .as_deref()
+            .map(load_invalidated)
+            .unwrap_or_default();
+        ResponseCache {
+            config: config.clone(),
+            memory: Mutex::new(LruCache::new(capacity)),
+            invalidated: Mutex::new(invalidated),
+            disk_dir,
+        }
+    }
+
+    /// 缓存键包含令牌的摘要，避免不同账号之间共用缓存
+    pub fn key<Q: Serialize + ?Sized>(
+        token: &AccessToken,
+        method: &str,
+        endpoint: &str,
+        params: &Q,
+    ) -> String {
+        format!(
+            "{}:{} {}?{}",
+            &md5_hex(token.access_token.as_bytes())[..8],
+            method,
+            endpoint,
+            serde_json::to_string(params).unwrap_or_default()
+        )
+    }
+
+    pub fn ttl_for(&self, endpoint: &str) -> u64 {
+        self.config.ttl_for(endpoint)
+    }
+
+    fn is_valid(&self, entry: &CacheEntry, policy: CachePolicy, now: DateTime<Utc>) -> bool {
+        if entry.expires_at <= now {
+            return false;
+        }
+        if let CachePolicy::MaxAge(secs) = policy {
+            if checked_add_secs(entry.stored_at, secs).map_or(false, |limit| limit < now) {
+                return false;
+            }
+        }
+        let invalidated = self.invalidated.lock().unwrap();
+        !entry.tags.iter().any(|tag| {
+            invalidated
+                .get(tag)
+                .map_or(false, |at| *at >= entry.stored_at)
+        })
+    }
+
+    fn disk_path(&self, key: &str) -> Option<PathBuf> {
+        self.disk_dir
+            .as_ref()
+            .map(|dir| dir.join(format!("{}.json", md5_hex(key.as_bytes()))))
+    }
+
+    async fn load_disk(&self, key: &str) -> Option<CacheEntry> {
+        let path = self.disk_path(key)?;
+        let data = tokio::fs::read(&path).await.ok()?;
+        match serde_json::from_slice::<CacheEntry>(&data) {
+            Ok(entry) if entry.key == key => Some(entry),
+            _ => None,
+        }
+    }
+
+    /// 读取未过期且未失效的缓存值
+    pub async fn get(&self, key: &str, policy: CachePolicy) -> Option<String> {
+        if matches!(policy, CachePolicy::Refresh | CachePolicy::NoStore) {
+            return None;
+        }
+        let now = Utc::now();
+        let memory_entry = self.memory.lock().unwrap().get(key).cloned();
+        let entry = match memory_entry {
+            Some(entry) => entry,
+            None => {
+                let entry = self.load_disk(key).await?;
+                if self.is_valid(&entry, CachePolicy::Use, now) {
+                    self.memory
+                        .lock()
+                        .unwrap()
+                        .put(key.to_string(), entry.clone());
+                }
+                entry
+            }
+        };
+        if self.is_valid(&entry, policy, now) {
+            return Some(entry.value);
+        }
+        if entry.expires_at <= now || !self.is_valid(&entry, CachePolicy::Use, now) {
+            self.memory.lock().unwrap().pop(key);
+            if let Some(path) = self.disk_path(key) {
+                let _ = tokio::fs::remove_file(path).await;
+            }
+        }
+        None
+    }
+
+    /// 写入缓存，`stored_at` 应为发起上游请求的时间
+    pub async fn put(
+        &self,
+        key: &str,
+        value: String,
+        ttl_secs: u64,
+        tags: Vec<CacheTag>,
+        stored_at: DateTime<Utc>,
+    ) {
+        if ttl_secs == 0 {
+            return;
+        }
+        let entry = CacheEntry {
+            key: key.to_string(),
+            stored_at,
+            expires_at: checked_add_secs(stored_at, ttl_secs).unwrap_or(DateTime::<Utc>::MAX_UTC),
+            tags,
+            value,
+        };
+        // 请求期间发生的失效会使该结果作废
+        if !self.is_valid(&entry, CachePolicy::Use, Utc::now()) {
+            return;
+        }
+        if let Some(path) = self.disk_path(key) {
+            let result = async {
+                if let Some(dir) = path.parent() {
+                    tokio::fs::create_dir_all(dir).await?;
+                }
+                tokio::fs::write(&path, serde_json::to_vec(&entry)?).await?;
+                Ok::<_, Box<dyn Error>>(())
+            }
+            .await;
+            if let Err(e) = result {
+                warn!("写入磁盘缓存失败 {:?}: {}", path, e);
+            }
+        }
+        self.memory.lock().unwrap().put(key.to_string(), entry);
+    }
+
+    /// 使关联这些标签的缓存全部失效
+    pub fn invalidate(&self, tags: &[CacheTag]) {
+        if tags.is_empty() {
+            return;
+        }
+        debug!("缓存失效: {:?}", tags);
+        let now = Utc::now();
+        let mut invalidated = self.invalidated.lock().unwrap();
+        // 早于最长有效期的失效记录不会再影响任何条目；有效期超出时间范围时全部保留
+        let horizon = i64::try_from(self.config.max_ttl())
+            .ok()
+            .and_then(Duration::try_seconds)
+            .and_then(|max_ttl| now.checked_sub_signed(max_ttl));
+        if let Some(horizon) = horizon {
+            invalidated.retain(|_, at| *at >= horizon);
+        }
+        for tag in tags {
+            invalidated.insert(*tag, now);
+        }
+        if let Some(dir) = &self.disk_dir {
+            if let Err(e) = save_invalidated(dir, &invalidated) {
+                warn!("写入缓存失效记录失败 {:?}: {}", dir, e);
+            }
+        }
+    }
+
+    /// 目录内容发生变化（新建、上传、移入）
+    pub fn invalidate_folders(&self, folder_ids: &[u64]) {
+        let tags: Vec<CacheTag> = folder_ids.iter().map(|id| CacheTag::Folder(*id)).collect();
+        self.invalidate(&tags);
+    }
+
+    /// 文件本身发生变化（改名、移动、删除、恢复），包含它的列表一并失效
+    pub fn invalidate_files(&self, file_ids: &[u64]) {
+        let tags: Vec<CacheTag> = file_ids
+            .iter()
+            .flat_map(|id| [CacheTag::File(*id), CacheTag::Folder(*id)])
+            .collect();
+        self.invalidate(&tags);
+    }
+
+    /// 清空全部缓存
+    pub async fn clear(&self) {
+        self.memory.lock().unwrap().clear();
+        if let Some(dir) = &self.disk_dir {
+            if let Err(e) = tokio::fs::remove_dir_all(dir).await {
+                if e.kind() != std::io::ErrorKind::NotFound {
+                    warn!("清空磁盘缓存失败 {:?}: {}", dir, e);
+                }
+            }
+        }
+    }
+}
+
+/// 读取磁盘缓存目录中的失效记录，文件不存在或损坏时返回空记录
+fn load_invalidated(dir: &Path) -> HashMap<CacheTag, DateTime<Utc>> {
+    std::fs::read(dir.join(INVALIDATED_FILE))
+        .ok()
+        .and_then(|data| serde_json::from_slice::<Vec<(CacheTag, DateTime<Utc>)>>(&data).ok())
+        .map(|records| records.into_iter().collect())
+        .unwrap_or_default()
+}
+
+/// 先写临时文件再改名，避免进程中途退出留下不完整的记录
+fn save_invalidated(
+    dir: &Path,
+    invalidated: &HashMap<CacheTag, DateTime<Utc>>,
+) -> Result<(), Box<dyn Error>> {
+    let records: Vec<(&CacheTag, &DateTime<Utc>)> = invalidated.iter().collect();
+    std::fs::create_dir_all(dir)?;
+    let tmp = dir.join(format!("{}.tmp", INVALIDATED_FILE));
+    std::fs::write(&tmp, serde_json::to_vec(&records)?)?;
+    std::fs::rename(&tmp, dir.join(INVALIDATED_FILE))?;
+    Ok(())
+}
+
+/// 读穿缓存调用上游接口，只缓存业务成功（`code == 0`）的响应；
+/// `tags` 根据响应生成缓存关联的对象
+#[allow(clippy::too_many_arguments)]
+async fn cached_call<Q, T, F>(
+    cache: &ResponseCache,
+    token: &AccessToken,
+    method: &str,
+    endpoint: &str,
+    params: &Q,
+    policy: CachePolicy,
+    tags: F,
+) -> Result<(ApiResponse<T>, CacheStatus), Box<dyn Error>>
+where
+    Q: Serialize + ?Sized,
+    T: Serialize + DeserializeOwned,
+    F: FnOnce(&ApiResponse<T>) -> Vec<CacheTag>,
+{
+    let ttl = cache.ttl_for(endpoint);
+    let key = ResponseCache::key(token, method, endpoint, params);
+    if ttl > 0 {
+        if let Some(value) = cache.get(&key, policy).await {
+            if let Ok(response) = serde_json::from_str(&value) {
+                return Ok((response, CacheStatus::Hit));
+            }
+        }
+    }
+    let started_at = Utc::now();
+    let response: ApiResponse<T> = if method == "GET" {
+        api_get(token, endpoint, params).await?
+    } else {
+        api_post(token, endpoint, params).await?
+    };
+    if ttl == 0 || policy == CachePolicy::NoStore {
+        return Ok((response, CacheStatus::Bypass));
+    }
+    if response.is_ok() {
+        let tags = tags(&response);
+        cache
+            .put(
+                &key,
+                serde_json::to_string(&response)?,
+                ttl,
+                tags,
+                started_at,
+            )
+            .await;
+    }
+    Ok((response, CacheStatus::Miss))
+}
+
+/// 以 GET 方式读穿缓存调用上游接口
+pub async fn cached_get<Q, T, F>(
+    cache: &ResponseCache,
+    token: &AccessToken,
+    endpoint: &str,
+    query: &Q,
+    policy: CachePolicy,
+    tags: F,
+) -> Result<(ApiResponse<T>, CacheStatus), Box<dyn Error>>
+where
+    Q: Serialize + ?Sized,
+    T: Serialize + DeserializeOwned,
+    F: FnOnce(&ApiResponse<T>) -> Vec<CacheTag>,
+{
+    cached_call(cache, token, "GET", endpoint, query, policy, tags).await
+}
+
+/// 以 POST 方式读穿缓存调用只读的上游接口（例如批量查询文件详情）
+pub async fn cached_post<B, T, F>(
+    cache: &ResponseCache,
+    token: &AccessToken,
+    endpoint: &str,
+    body: &B,
+    policy: CachePolicy,
+    tags: F,
+) -> Result<(ApiResponse<T>, CacheStatus), Box<dyn Error>>
+where
+    B: Serialize + ?Sized,
+    T: Serialize + DeserializeOwned,
+    F: FnOnce(&ApiResponse<T>) -> Vec<CacheTag>,
+{
+    cached_call(cache, token, "POST", endpoint, body, policy, tags).await
+}
+
+/// 以 `RequestConfig` 的 `cache` 标记决定是否使用缓存的 GET 调用
+pub async fn request_get<Q, T, F>(
+    cache: &ResponseCache,
+    token: &AccessToken,
+    endpoint: &str,
+    request: &RequestConfig<Q>,
+    tags: F,
+) -> Result<ApiResponse<T>, Box<dyn Error>>
+where
+    Q: Serialize,
+    T: Serialize + DeserializeOwned,
+    F: FnOnce(&ApiResponse<T>) -> Vec<CacheTag>,
+{
+    let policy = CachePolicy::from_flag(request.cache);
+    Ok(
+        cached_get(cache, token, endpoint, &request.data, policy, tags)
+            .await?
+            .0,
+    )
+}
+
+/// 返回 JSON 响应并带上 `X-Cache` 头
+pub fn cached_json<T: Serialize>(response: &T, status: CacheStatus) -> HttpResponse {
+    HttpResponse::Ok()
+        .insert_header((CACHE_STATUS_HEADER, status.as_str()))
+        .json(response)
+}
+
+/// 文件列表关联所在目录与列表中的每个文件
+pub fn list_tags(parent_file_id: u64, items: &[FileItem]) -> Vec<CacheTag> {
+    std::iter::once(CacheTag::Folder(parent_file_id))
+        .chain(items.iter().map(|item| CacheTag::File(item.file_id as u64)))
+        .collect()
+}
@@ -0,0 +1,241 @@
+use crate::io_basic::checksum::md5_hex;
+use crate::netdisk_api::direct_link_api::build_direct_link;
+use crate::netdisk_api::file_api::{ensure_dir_path, split_dir_path};
+use crate::netdisk_api::file_upload_api::upload_local_file;
+use crate::netdisk_api::webdav_api::dav_href;
+use crate::responses::prelude::*;
+use chrono::{DateTime, Local};
+use image::imageops::FilterType;
+use image::{GenericImageView, ImageOutputFormat};
+use log::info;
+use std::error::Error;
+use std::fmt::Write as _;
+use std::io::Cursor;
+use std::io::Write as _;
+
+/// 缩小后重新编码 JPEG 使用的质量
+const JPEG_QUALITY: u8 = 90;
+
+/// 校验并按需缩小后的图片
+#[derive(Debug, Clone)]
+pub struct PreparedImage {
+    pub data: Vec<u8>,
+    pub format: ImageFormat,
+    pub width: Option<u32>,
+    pub height: Option<u32>,
+    pub resized: bool,
+}
+
+fn decode_format(format: ImageFormat) -> Option<image::ImageFormat> {
+    match format {
+        ImageFormat::Png => Some(image::ImageFormat::Png),
+        ImageFormat::Jpeg => Some(image::ImageFormat::Jpeg),
+        ImageFormat::Gif => Some(image::ImageFormat::Gif),
+        // WebP 只识别格式，不解码
+        ImageFormat::Webp => None,
+    }
+}
+
+/// 按文件头识别格式并检查大小；先只读取尺寸，像素数超过 `max_pixels` 时拒绝，
+/// 宽度超过 `max_width`（非 0）时才解码，等比缩小并以原格式重新编码。
+/// GIF 缩放会丢失动画，因此保持原样
+pub fn prepare_image(
+    data: Vec<u8>,
+    max_bytes: u64,
+    max_width: u32,
+    max_pixels: u64,
+) -> Result<PreparedImage, String> {
+    if data.is_empty() {
+        return Err("图片内容为空".to_string());
+    }
+    if data.len() as u64 > max_bytes {
+        return Err(format!("图片超过大小上限 {} 字节", max_bytes));
+    }
+    let format = ImageFormat::sniff(&data).ok_or("只支持 PNG、JPEG、GIF 与 WebP 图片")?;
+    let decode = match decode_format(format) {
+        Some(decode) => decode,
+        None => {
+            return Ok(PreparedImage {
+                data,
+                format,
+                width: None,
+                height: None,
+                resized: false,
+            })
+        }
+    };
+    let (width, height) = image::io::Reader::with_format(Cursor::new(&data), decode)
+        .into_dimensions()
+        .map_err(|e| format!("图片数据损坏: {}", e))?;
+    if u64::from(width) * u64::from(height) > max_pixels {
+        return Err(format!(
+            "图片尺寸 {}x{} 超过像素上限 {}",
+            width, height, max_pixels
+        ));
+    }
+    if max_width == 0 || width <= max_width || format == ImageFormat::Gif {
+        return Ok(PreparedImage {
+            data,
+            format,
+            width: Some(width),
+            height: Some(height),
+            resized: false,
+        });
+    }
+
+    let img = image::load_from_memory_with_format(&data, decode)
+        .map_err(|e| format!("图片数据损坏: {}", e))?;
+    let resized = img.resize(max_width, u32::MAX, FilterType::Lanczos3);
+    let output = match format {
+        ImageFormat::Jpeg => ImageOutputFormat::Jpeg(JPEG_QUALITY),
+        _ => ImageOutputFormat::Png,
+    };
+    let mut encoded = Vec::new();
+    resized
+        .write_to(&mut encoded, output)
+        .map_err(|e| format!("图片编码失败: {}", e))?;
+    Ok(PreparedImage {
+        data: encoded,
+        format,
+        width: Some(resized.width()),
+        height: Some(resized.height()),
+        resized: true,
+    })
+}
+
+/// 生成网盘中的文件名：`<原文件名>-<MD5 前 8 位>.<扩展名>`，没有原文件名时使用 MD5 前 16 位。
+/// 扩展名以识别出的格式为准，同一张图片重复上传得到同一个文件名；
+/// 网盘不允许的字符替换为 `_`，过长的原文件名会被截断
+pub fn image_file_name(name: Option<&str>, data: &[u8], format: ImageFormat) -> String {
+    // 为 `-<MD5 前 8 位>.<扩展名>` 留出位置
+    const MAX_STEM_CHARS: usize = MAX_FILE_NAME_CHARS - 16;
+    let hash = md5_hex(data);
+    let stem = name
+        .map(|name| name.rsplit(['/', '\\']).next().unwrap_or(name))
+        .map(|name| name.rsplit_once('.').map_or(name, |(stem, _)| stem))
+        .map(|stem| {
+            stem.chars()
+                .filter(|c| !c.is_control())
+                .map(|c| {
+                    if ILLEGAL_NAME_CHARS.contains(&c) {
+                        '_'
+                    } else {
+                        c
+                    }
+                })
+                .take(MAX_STEM_CHARS)
+                .collect::<String>()
+                .trim()
+                .to_string()
+        })
+        .filter(|stem| !stem.is_empty());
+    match stem {
+        Some(stem) => format!("{}-{}.{}", stem, &hash[..8], format.extension()),
+        None => format!("{}.{}", &hash[..16], format.extension()),
+    }
+}
+
+/// 按日期生成存放目录，例如 `/images/2024/06/01`
+pub fn dated_dir(root: &str, date_format: &str, now: DateTime<Local>) -> Result<String, String> {
+    let mut date = String::new();
+    write!(date, "{}", now.format(date_format))
+        .map_err(|_| format!("date_format 格式错误: {}", date_format))?;
+    let mut names = split_dir_path(root);
+    names.extend(split_dir_path(&date));
+    Ok(format!("/{}", names.join("/")))
+}
+
+/// 生成 Markdown 与 HTML 引用片段
+pub fn image_snippets(url: &str, alt: &str) -> (String, String) {
+    let markdown_alt = alt.replace('[', "\\[").replace(']', "\\]");
+    let markdown_url = url.replace('(', "%28").replace(')', "%29");
+    let html_escape = |s: &str| {
+        s.replace('&', "&amp;")
+            .replace('"', "&quot;")
+            .replace('<', "&lt;")
+            .replace('>', "&gt;")
+    };
+    (
+        format!("![{}]({})", markdown_alt, markdown_url),
+        format!(
+            "<img src=\"{}\" alt=\"{}\">",
+            html_escape(url),
+            html_escape(alt)
+        ),
+    )
+}
+
+/// 经网关 `/d/<路径>` 跳转的永久链接，未启用跳转或 `path` 不在直链根目录下时返回 `None`
+pub fn public_image_url(
+    base_url: &str,
+    direct_link: &DirectLinkConfig,
+    path: &str,
+) -> Option<String> {
+    if !direct_link.enabled {
+        return None;
+    }
+    let root = split_dir_path(&direct_link.root);
+    let names = split_dir_path(path);
+    if names.len() <= root.len() || names[..root.len()] != root[..] {
+        return None;
+    }
+    let relative = names[root.len()..].join("/");
+    Some(format!(
+        "{}{}",
+        base_url.trim_end_matches('/'),
+        dav_href(&direct_link.normalized_prefix(), &relative, false)
+    ))
+}
+
+/// 上传图片到按日期划分的目录，并生成可嵌入的链接
+pub async fn host_image(
+    token: &AccessToken,
+    gateway: &GatewayConfig,
+    image: PreparedImage,
+    name: Option<&str>,
+) -> Result<ImageHostResult, Box<dyn Error>> {
+    let config = &gateway.image_host;
+    let dir = dated_dir(&config.root, &config.date_format, Local::now())?;
+    let filename = image_file_name(name, &image.data, image.format);
+    let dir_id = ensure_dir_path(token, &dir, 0).await?;
+
+    let mut temp = tempfile::Builder::new()
+        .prefix("netdisk-image-")
+        .tempfile()?;
+    temp.write_all(&image.data)?;
+    temp.flush()?;
+    // 文件名包含内容的 MD5，同名即同一张图片，直接覆盖
+    let outcome = upload_local_file(token, temp.path(), dir_id, &filename, Some(2)).await?;
+    let path = format!("{}/{}", dir, filename);
+    info!("图片已上传: {} -> {}", path, outcome.file_id);
+
+    let public_url = Some(config.public_base_url.as_str())
+        .filter(|base| !base.is_empty())
+        .and_then(|base| public_image_url(base, &gateway.direct_link, &path));
+    let url = match public_url {
+        Some(url) => url,
+        None => {
+            build_direct_link(token, &gateway.direct_link, outcome.file_id, None)
+                .await?
+                .url
+        }
+    };
+    let alt = name
+        .map(|name| name.rsplit(['/', '\\']).next().unwrap_or(name))
+        .filter(|name| !name.is_empty())
+        .unwrap_or(&filename);
+    let (markdown, html) = image_snippets(&url, alt);
+    Ok(ImageHostResult {
+        file_id: outcome.file_id,
+        parent_file_id: dir_id,
+        path,
+        url,
+        markdown,
+        html,
+        format: image.format,
+        width: image.width,
+        height: image.height,
+        size: image.data.len() as u64,
+        resized: image.resized,
+    })
+}
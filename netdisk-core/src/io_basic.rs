@@ -1 +1,2 @@
+pub mod checksum;
 pub mod read_and_write;
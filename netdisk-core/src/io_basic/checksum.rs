@@ -0,0 +1,33 @@
+use actix_web::web;
+use md5::{Digest, Md5};
+use std::fs::File;
+use std::io::{self, ErrorKind, Read};
+use std::path::Path;
+
+/// 计算内存数据的 MD5，返回小写十六进制字符串（即开放平台的 etag 格式）
+pub fn md5_hex(data: &[u8]) -> String {
+    format!("{:x}", Md5::digest(data))
+}
+
+/// 同步计算文件的 MD5
+pub fn file_md5<U: AsRef<Path>>(path: U) -> Result<String, io::Error> {
+    let mut file = File::open(path)?;
+    let mut hasher = Md5::new();
+    let mut buffer = vec![0u8; 1024 * 1024];
+    loop {
+        let n = file.read(&mut buffer)?;
+        if n == 0 {
+            break;
+        }
+        hasher.update(&buffer[..n]);
+    }
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+/// 在阻塞线程池中计算文件的 MD5，避免大文件阻塞异步运行时
+pub async fn async_file_md5<U: AsRef<Path>>(path: U) -> Result<String, io::Error> {
+    let path_buf = path.as_ref().to_path_buf();
+    web::block(move || file_md5(&path_buf))
+        .await
+        .map_err(|e| io::Error::new(ErrorKind::Other, format!("阻塞线程失败: {}", e)))?
+}
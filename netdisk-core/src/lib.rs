@@ -1,25 +1,57 @@
+pub mod cache;
 pub mod endpoints;
+pub mod image_host;
 pub mod io_basic;
+pub mod local_db;
+pub mod mount;
 pub mod netdisk_api;
 pub mod netdisk_auth;
+pub mod offline;
+pub mod openapi;
 pub mod responses;
+pub mod share_stats;
+pub mod sync;
+pub mod telemetry;
+pub mod video;
 
-use actix_files as fs;
-use actix_web::dev::Service;
 use actix_web::{web, App};
+use cache::ResponseCache;
 use netdisk_api::prelude::*;
 use netdisk_auth::basic_env::NetDiskEnv;
 use responses::prelude::*;
 
-pub fn configure(cfg: &mut actix_web::web::ServiceConfig) {
-    netdisk_api::file_api::file_config(cfg);
-    netdisk_api::share_file_api::share_config(cfg);
-    // netdisk_api::file_move_api::move_config(cfg);
+/// 使用按网关配置新建的响应缓存创建应用
+pub fn create_app(
+    config_path_data: web::Data<NetDiskEnv>,
+    access_token_data: web::Data<AccessToken>,
+    gateway_config_data: web::Data<GatewayConfig>,
+) -> App<
+    impl actix_web::dev::ServiceFactory<
+        actix_web::dev::ServiceRequest,
+        Config = (),
+        Response = actix_web::dev::ServiceResponse,
+        Error = actix_web::Error,
+        InitError = (),
+    >,
+> {
+    let cache_data = web::Data::new(ResponseCache::new(
+        &gateway_config_data.cache,
+        &config_path_data,
+    ));
+    create_app_with_cache(
+        config_path_data,
+        access_token_data,
+        gateway_config_data,
+        cache_data,
+    )
 }
 
-pub fn create_app(
+/// 创建应用，`cache_data` 需在各工作线程之间共享，否则缓存与失效只在单个线程内生效
+pub fn create_app_with_cache(
     config_path_data: web::Data<NetDiskEnv>,
     access_token_data: web::Data<AccessToken>,
+    gateway_config_data: web::Data<GatewayConfig>,
+    cache_data: web::Data<ResponseCache>,
 ) -> App<
     impl actix_web::dev::ServiceFactory<
         actix_web::dev::ServiceRequest,
@@ -30,28 +62,13 @@ pub fn create_app(
     >,
 > {
     App::new()
-        .wrap_fn(|req, srv| {
-            let method = req.method().clone();
-            let path = req.path().to_string();
-            let fut = srv.call(req);
-            async move {
-                println!("📥 收到请求: {} {}", method, path);
-                let res = fut.await;
-                println!("返回结果: {:?}", &res);
-                res
-            }
-        })
+        .wrap_fn(telemetry::trace_request)
         .app_data(config_path_data.clone())
         .app_data(access_token_data.clone())
-        .service(echo)
-        .service(user_info)
-        .service(file_search)
-        .service(file_upload)
-        .service(trash)
-        .service(delete)
-        .service(move_file)
-        .configure(configure)
-        .route("/access_token", web::post().to(access_token_and_cache))
-        .route("/hey", web::get().to(manual_hello))
-        .service(fs::Files::new("/static", "./static/").index_file("index.html"))
+        .app_data(gateway_config_data.clone())
+        .app_data(cache_data)
+        .configure(openapi::gateway_routes)
+        .configure(|cfg| webdav_config(cfg, &gateway_config_data.webdav))
+        .configure(|cfg| s3_config(cfg, &gateway_config_data.s3))
+        .configure(|cfg| direct_link_config(cfg, &gateway_config_data.direct_link))
 }
@@ -0,0 +1,60 @@
+use crate::netdisk_auth::basic_env::NetDiskEnv;
+use rusqlite::Connection;
+
+/// 本地 SQLite 数据库文件，位于配置目录下，保存分享统计快照、离线下载任务、视频转码状态等记录
+pub const LOCAL_DB_FILE: &str = "netdisk.db";
+
+/// 建表语句，全部使用 `IF NOT EXISTS`，每次打开时执行
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS share_snapshots (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    taken_at TEXT NOT NULL,
+    share_id INTEGER NOT NULL,
+    share_key TEXT NOT NULL,
+    share_name TEXT NOT NULL,
+    paid INTEGER NOT NULL,
+    preview_count INTEGER NOT NULL,
+    download_count INTEGER NOT NULL,
+    save_count INTEGER NOT NULL,
+    bytes_charge INTEGER NOT NULL,
+    amount INTEGER NOT NULL
+);
+CREATE INDEX IF NOT EXISTS share_snapshots_share_time ON share_snapshots (share_id, taken_at);
+CREATE TABLE IF NOT EXISTS offline_tasks (
+    task_id INTEGER PRIMARY KEY,
+    url TEXT NOT NULL,
+    file_name TEXT,
+    dir_id INTEGER,
+    source TEXT,
+    submitted_at TEXT NOT NULL,
+    status TEXT NOT NULL,
+    process REAL NOT NULL,
+    updated_at TEXT NOT NULL,
+    file_ids TEXT NOT NULL
+);
+CREATE INDEX IF NOT EXISTS offline_tasks_url ON offline_tasks (url);
+CREATE TABLE IF NOT EXISTS video_transcodes (
+    file_id INTEGER PRIMARY KEY,
+    status TEXT NOT NULL,
+    resolutions TEXT NOT NULL,
+    ready TEXT NOT NULL,
+    failed TEXT NOT NULL,
+    submitted_at TEXT NOT NULL,
+    updated_at TEXT NOT NULL
+);
+";
+
+/// 打开本地数据库并确保表结构存在
+pub fn open_local_db(env: &NetDiskEnv) -> rusqlite::Result<Connection> {
+    if let Err(e) = std::fs::create_dir_all(&env.config_dir) {
+        log::warn!("无法创建配置目录 {:?}: {}", env.config_dir, e);
+    }
+    let conn = Connection::open(env.config_dir.join(LOCAL_DB_FILE))?;
+    init_schema(&conn)?;
+    Ok(conn)
+}
+
+/// 在已打开的连接上建表，测试中用于内存数据库
+pub fn init_schema(conn: &Connection) -> rusqlite::Result<()> {
+    conn.execute_batch(SCHEMA)
+}
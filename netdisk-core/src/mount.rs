@@ -0,0 +1,23 @@
+pub mod cache;
+#[cfg(all(feature = "fuse", target_os = "linux"))]
+pub mod fuse_fs;
+
+/// FUSE 根目录的 inode 编号
+pub const ROOT_INO: u64 = 1;
+
+/// 文件 ID 映射为 inode：挂载根目录固定为 1，其余为 `fileId + 1`（fileId 从 1 开始，不会与根冲突）
+pub fn file_id_to_ino(file_id: u64, root_id: u64) -> u64 {
+    if file_id == root_id {
+        ROOT_INO
+    } else {
+        file_id + 1
+    }
+}
+
+pub fn ino_to_file_id(ino: u64, root_id: u64) -> u64 {
+    if ino == ROOT_INO {
+        root_id
+    } else {
+        ino - 1
+    }
+}
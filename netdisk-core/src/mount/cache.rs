@@ -0,0 +1,184 @@
+use crate::responses::prelude::*;
+use std::collections::HashMap;
+use std::ops::Range;
+use std::time::{Duration, Instant};
+
+/// 分段下载与块缓存的块大小
+pub const BLOCK_SIZE: u64 = 4 * 1024 * 1024;
+/// 目录列表的缓存时间
+pub const METADATA_TTL: Duration = Duration::from_secs(30);
+/// 下载地址的缓存时间，需短于上游签名链接的有效期
+pub const DOWNLOAD_URL_TTL: Duration = Duration::from_secs(10 * 60);
+
+/// 读取 `[offset, offset+size)` 需要的块序号，超出文件末尾的部分会被截掉
+pub fn block_range(offset: u64, size: u64, file_size: u64, block_size: u64) -> Range<u64> {
+    let end = offset.saturating_add(size).min(file_size);
+    if offset >= end {
+        return 0..0;
+    }
+    offset / block_size..(end - 1) / block_size + 1
+}
+
+/// 第 `block_index` 块在文件中的字节范围
+pub fn block_bounds(block_index: u64, file_size: u64, block_size: u64) -> Range<u64> {
+    let start = block_index * block_size;
+    start.min(file_size)..(start + block_size).min(file_size)
+}
+
+/// 第 `block_index` 块中落在读取区间 `[offset, end)` 内的部分，返回块内的下标范围
+pub fn block_slice(block_index: u64, offset: u64, end: u64, block_size: u64) -> Range<usize> {
+    let block_start = block_index * block_size;
+    let from = offset.max(block_start) - block_start;
+    let to = end.min(block_start + block_size).max(block_start) - block_start;
+    from as usize..to.max(from) as usize
+}
+
+/// 目录列表与条目元数据缓存，目录在 `ttl` 后过期重新拉取
+#[derive(Debug)]
+pub struct MetadataCache {
+    ttl: Duration,
+    dirs: HashMap<u64, (Instant, Vec<u64>)>,
+    items: HashMap<u64, FileItem>,
+}
+
+impl MetadataCache {
+    pub fn new(ttl: Duration) -> Self {
+        MetadataCache {
+            ttl,
+            dirs: HashMap::new(),
+            items: HashMap::new(),
+        }
+    }
+
+    /// 未过期时返回目录下的条目
+    pub fn dir(&self, dir_id: u64, now: Instant) -> Option<Vec<&FileItem>> {
+        let (cached_at, children) = self.dirs.get(&dir_id)?;
+        if now.duration_since(*cached_at) >= self.ttl {
+            return None;
+        }
+        Some(
+            children
+                .iter()
+                .filter_map(|id| self.items.get(id))
+                .collect(),
+        )
+    }
+
+    pub fn insert_dir(&mut self, dir_id: u64, items: Vec<FileItem>, now: Instant) {
+        let children = items.iter().map(|item| item.file_id as u64).collect();
+        for item in items {
+            self.items.insert(item.file_id as u64, item);
+        }
+        self.dirs.insert(dir_id, (now, children));
+    }
+
+    pub fn insert_item(&mut self, item: FileItem) {
+        self.items.insert(item.file_id as u64, item);
+    }
+
+    /// 最近一次见到的条目元数据，不受目录过期影响
+    pub fn item(&self, file_id: u64) -> Option<&FileItem> {
+        self.items.get(&file_id)
+    }
+}
+
+/// 以 `(fileId, 块序号)` 为键的内存 LRU 块缓存，总大小不超过 `capacity` 字节
+#[derive(Debug)]
+pub struct BlockCache {
+    capacity: usize,
+    used: usize,
+    tick: u64,
+    blocks: HashMap<(u64, u64), (u64, Vec<u8>)>,
+}
+
+impl BlockCache {
+    pub fn new(capacity: usize) -> Self {
+        BlockCache {
+            capacity,
+            used: 0,
+            tick: 0,
+            blocks: HashMap::new(),
+        }
+    }
+
+    pub fn used(&self) -> usize {
+        self.used
+    }
+
+    pub fn len(&self) -> usize {
+        self.blocks.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.blocks.is_empty()
+    }
+
+    /// 命中时刷新该块的最近使用时间
+    pub fn get(&mut self, file_id: u64, block_index: u64) -> Option<&[u8]> {
+        self.tick += 1;
+        let tick = self.tick;
+        let (used_at, data) = self.blocks.get_mut(&(file_id, block_index))?;
+        *used_at = tick;
+        Some(data.as_slice())
+    }
+
+    /// 放入一块数据，空间不足时淘汰最久未使用的块；大于容量的块不缓存
+    pub fn insert(&mut self, file_id: u64, block_index: u64, data: Vec<u8>) {
+        if data.len() > self.capacity {
+            return;
+        }
+        self.tick += 1;
+        if let Some((_, old)) = self
+            .blocks
+            .insert((file_id, block_index), (self.tick, data))
+        {
+            self.used -= old.len();
+        }
+        self.used += self.blocks[&(file_id, block_index)].1.len();
+        while self.used > self.capacity {
+            let oldest = self
+                .blocks
+                .iter()
+                .min_by_key(|(_, (used_at, _))| *used_at)
+                .map(|(key, _)| *key);
+            match oldest.and_then(|key| self.blocks.remove(&key)) {
+                Some((_, evicted)) => self.used -= evicted.len(),
+                None => break,
+            }
+        }
+    }
+
+    /// 文件内容变化（etag 改变）后丢弃其所有块
+    pub fn invalidate(&mut self, file_id: u64) {
+        let used = &mut self.used;
+        self.blocks.retain(|(id, _), (_, data)| {
+            if *id == file_id {
+                *used -= data.len();
+                false
+            } else {
+                true
+            }
+        });
+    }
+}
+
+/// 下载地址缓存，避免每次读取都请求 `download_info`
+#[derive(Debug, Default)]
+pub struct UrlCache {
+    urls: HashMap<u64, (Instant, String)>,
+}
+
+impl UrlCache {
+    pub fn get(&self, file_id: u64, now: Instant) -> Option<&str> {
+        let (cached_at, url) = self.urls.get(&file_id)?;
+        (now.duration_since(*cached_at) < DOWNLOAD_URL_TTL).then_some(url.as_str())
+    }
+
+    pub fn insert(&mut self, file_id: u64, url: String, now: Instant) {
+        self.urls.insert(file_id, (now, url));
+    }
+
+    pub fn remove(&mut self, file_id: u64) {
+        self.urls.remove(&file_id);
+    }
+}
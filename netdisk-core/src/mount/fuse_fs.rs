@@ -0,0 +1,308 @@
+use super::cache::*;
+use super::{file_id_to_ino, ino_to_file_id, ROOT_INO};
+use crate::netdisk_api::prelude::*;
+use crate::responses::prelude::*;
+use fuser::{
+    Config, Errno, FileAttr, FileHandle, FileType, Filesystem, FopenFlags, Generation, INodeNo,
+    MountOption, OpenAccMode, OpenFlags, ReplyAttr, ReplyData, ReplyDirectory, ReplyEntry,
+    ReplyOpen, Request,
+};
+use log::{debug, error, info};
+use std::error::Error;
+use std::ffi::OsStr;
+use std::io;
+use std::path::Path;
+use std::sync::Mutex;
+use std::time::{Duration, Instant, SystemTime};
+
+/// 内核缓存属性与目录项的时间
+const ATTR_TTL: Duration = Duration::from_secs(1);
+/// 处理 FUSE 请求的线程数，允许播放器并发读取多个区间
+const FUSE_THREADS: usize = 4;
+
+/// 只读挂载的网盘文件系统：元数据来自目录列表缓存，内容通过分段下载按块读取
+pub struct NetDiskFs {
+    token: AccessToken,
+    root_id: u64,
+    runtime: tokio::runtime::Runtime,
+    metadata: Mutex<MetadataCache>,
+    blocks: Mutex<BlockCache>,
+    urls: Mutex<UrlCache>,
+}
+
+impl NetDiskFs {
+    pub fn new(token: AccessToken, root_id: u64, cache_bytes: usize) -> io::Result<Self> {
+        let runtime = tokio::runtime::Builder::new_multi_thread()
+            .worker_threads(2)
+            .enable_all()
+            .build()?;
+        Ok(NetDiskFs {
+            token,
+            root_id,
+            runtime,
+            metadata: Mutex::new(MetadataCache::new(METADATA_TTL)),
+            blocks: Mutex::new(BlockCache::new(cache_bytes)),
+            urls: Mutex::new(UrlCache::default()),
+        })
+    }
+
+    /// 目录下的条目，缓存过期时重新拉取；文件 etag 变化时丢弃其块缓存
+    fn children(&self, dir_id: u64) -> Result<Vec<FileItem>, Box<dyn Error>> {
+        let now = Instant::now();
+        if let Some(items) = self.metadata.lock().unwrap().dir(dir_id, now) {
+            return Ok(items.into_iter().cloned().collect());
+        }
+        let items = self.runtime.block_on(list_dir(&self.token, dir_id))?;
+        let mut metadata = self.metadata.lock().unwrap();
+        for item in &items {
+            let file_id = item.file_id as u64;
+            if metadata
+                .item(file_id)
+                .map_or(false, |old| old.etag != item.etag)
+            {
+                self.blocks.lock().unwrap().invalidate(file_id);
+                self.urls.lock().unwrap().remove(file_id);
+            }
+        }
+        metadata.insert_dir(dir_id, items.clone(), now);
+        Ok(items)
+    }
+
+    fn item(&self, ino: u64) -> Option<FileItem> {
+        self.metadata
+            .lock()
+            .unwrap()
+            .item(ino_to_file_id(ino, self.root_id))
+            .cloned()
+    }
+
+    fn attr(&self, req: &Request, ino: u64, item: Option<&FileItem>) -> FileAttr {
+        let (kind, perm, nlink, size, mtime) = match item {
+            Some(item) if item.r#type != 1 => (
+                FileType::RegularFile,
+                0o444,
+                1,
+                item.size,
+                SystemTime::from(item.update_at),
+            ),
+            Some(item) => (
+                FileType::Directory,
+                0o555,
+                2,
+                0,
+                SystemTime::from(item.update_at),
+            ),
+            None => (FileType::Directory, 0o555, 2, 0, SystemTime::UNIX_EPOCH),
+        };
+        FileAttr {
+            ino: INodeNo(ino),
+            size,
+            blocks: (size + 511) / 512,
+            atime: mtime,
+            mtime,
+            ctime: mtime,
+            crtime: mtime,
+            kind,
+            perm,
+            nlink,
+            uid: req.uid(),
+            gid: req.gid(),
+            rdev: 0,
+            blksize: BLOCK_SIZE as u32,
+            flags: 0,
+        }
+    }
+
+    fn download_url(&self, file_id: u64, refresh: bool) -> Result<String, Box<dyn Error>> {
+        let now = Instant::now();
+        if !refresh {
+            if let Some(url) = self.urls.lock().unwrap().get(file_id, now) {
+                return Ok(url.to_string());
+            }
+        }
+        let url = self
+            .runtime
+            .block_on(fetch_download_url(&self.token, file_id))?;
+        self.urls.lock().unwrap().insert(file_id, url.clone(), now);
+        Ok(url)
+    }
+
+    /// 读取一块，未命中缓存时分段下载；下载地址失效时刷新后重试一次
+    fn block(&self, item: &FileItem, block_index: u64) -> Result<Vec<u8>, Box<dyn Error>> {
+        let file_id = item.file_id as u64;
+        if let Some(data) = self.blocks.lock().unwrap().get(file_id, block_index) {
+            return Ok(data.to_vec());
+        }
+        let bounds = block_bounds(block_index, item.size, BLOCK_SIZE);
+        let len = bounds.end - bounds.start;
+        let url = self.download_url(file_id, false)?;
+        let data = match self
+            .runtime
+            .block_on(download_range(&url, bounds.start, len))
+        {
+            Ok(data) => data,
+            Err(e) => {
+                debug!("分段下载失败，刷新下载地址后重试: {}", e);
+                let url = self.download_url(file_id, true)?;
+                self.runtime
+                    .block_on(download_range(&url, bounds.start, len))?
+            }
+        };
+        self.blocks
+            .lock()
+            .unwrap()
+            .insert(file_id, block_index, data.clone());
+        Ok(data)
+    }
+
+    fn read_range(
+        &self,
+        item: &FileItem,
+        offset: u64,
+        size: u64,
+    ) -> Result<Vec<u8>, Box<dyn Error>> {
+        let end = offset.saturating_add(size).min(item.size);
+        let mut out = Vec::with_capacity(end.saturating_sub(offset) as usize);
+        for block_index in block_range(offset, size, item.size, BLOCK_SIZE) {
+            let data = self.block(item, block_index)?;
+            let slice = block_slice(block_index, offset, end, BLOCK_SIZE);
+            out.extend_from_slice(&data[slice.start.min(data.len())..slice.end.min(data.len())]);
+        }
+        Ok(out)
+    }
+}
+
+impl Filesystem for NetDiskFs {
+    fn lookup(&self, req: &Request, parent: INodeNo, name: &OsStr, reply: ReplyEntry) {
+        let dir_id = ino_to_file_id(parent.0, self.root_id);
+        let children = match self.children(dir_id) {
+            Ok(children) => children,
+            Err(e) => {
+                error!("读取目录 {} 失败: {}", dir_id, e);
+                return reply.error(Errno::EIO);
+            }
+        };
+        match children
+            .iter()
+            .find(|item| OsStr::new(&item.filename) == name)
+        {
+            Some(item) => {
+                let ino = file_id_to_ino(item.file_id as u64, self.root_id);
+                reply.entry(&ATTR_TTL, &self.attr(req, ino, Some(item)), Generation(0));
+            }
+            None => reply.error(Errno::ENOENT),
+        }
+    }
+
+    fn getattr(&self, req: &Request, ino: INodeNo, _fh: Option<FileHandle>, reply: ReplyAttr) {
+        if ino.0 == ROOT_INO {
+            return reply.attr(&ATTR_TTL, &self.attr(req, ino.0, None));
+        }
+        match self.item(ino.0) {
+            Some(item) => reply.attr(&ATTR_TTL, &self.attr(req, ino.0, Some(&item))),
+            None => reply.error(Errno::ENOENT),
+        }
+    }
+
+    fn open(&self, _req: &Request, ino: INodeNo, flags: OpenFlags, reply: ReplyOpen) {
+        if flags.acc_mode() != OpenAccMode::O_RDONLY {
+            return reply.error(Errno::EROFS);
+        }
+        match self.item(ino.0) {
+            Some(item) if item.r#type != 1 => {
+                reply.opened(FileHandle(0), FopenFlags::FOPEN_KEEP_CACHE)
+            }
+            Some(_) => reply.error(Errno::EISDIR),
+            None => reply.error(Errno::ENOENT),
+        }
+    }
+
+    fn read(
+        &self,
+        _req: &Request,
+        ino: INodeNo,
+        _fh: FileHandle,
+        offset: u64,
+        size: u32,
+        _flags: OpenFlags,
+        _lock_owner: Option<fuser::LockOwner>,
+        reply: ReplyData,
+    ) {
+        let item = match self.item(ino.0) {
+            Some(item) => item,
+            None => return reply.error(Errno::ENOENT),
+        };
+        match self.read_range(&item, offset, size as u64) {
+            Ok(data) => reply.data(&data),
+            Err(e) => {
+                error!("读取 {} 偏移 {} 失败: {}", item.filename, offset, e);
+                reply.error(Errno::EIO);
+            }
+        }
+    }
+
+    fn readdir(
+        &self,
+        _req: &Request,
+        ino: INodeNo,
+        _fh: FileHandle,
+        offset: u64,
+        mut reply: ReplyDirectory,
+    ) {
+        let dir_id = ino_to_file_id(ino.0, self.root_id);
+        let children = match self.children(dir_id) {
+            Ok(children) => children,
+            Err(e) => {
+                error!("读取目录 {} 失败: {}", dir_id, e);
+                return reply.error(Errno::EIO);
+            }
+        };
+        let parent_ino = match self.item(ino.0) {
+            Some(item) => file_id_to_ino(item.parent_file_id, self.root_id),
+            None => ROOT_INO,
+        };
+        let mut entries = vec![
+            (ino.0, FileType::Directory, ".".to_string()),
+            (parent_ino, FileType::Directory, "..".to_string()),
+        ];
+        entries.extend(children.into_iter().map(|item| {
+            let kind = if item.r#type == 1 {
+                FileType::Directory
+            } else {
+                FileType::RegularFile
+            };
+            (
+                file_id_to_ino(item.file_id as u64, self.root_id),
+                kind,
+                item.filename,
+            )
+        }));
+        for (index, (entry_ino, kind, name)) in
+            entries.into_iter().enumerate().skip(offset as usize)
+        {
+            if reply.add(INodeNo(entry_ino), index as u64 + 1, kind, name) {
+                break;
+            }
+        }
+        reply.ok();
+    }
+}
+
+/// 将 `root_id` 目录只读挂载到 `mountpoint`，阻塞直到被卸载（`fusermount -u`）
+pub fn mount(
+    token: AccessToken,
+    root_id: u64,
+    mountpoint: &Path,
+    cache_bytes: usize,
+) -> io::Result<()> {
+    let fs = NetDiskFs::new(token, root_id, cache_bytes)?;
+    let mut config = Config::default();
+    config.mount_options = vec![
+        MountOption::RO,
+        MountOption::FSName("netdisk".to_string()),
+        MountOption::Subtype("123pan".to_string()),
+    ];
+    config.n_threads = Some(FUSE_THREADS);
+    info!("挂载目录 {} 到 {}", root_id, mountpoint.display());
+    fuser::mount(fs, mountpoint, &config)
+}
@@ -1,11 +1,30 @@
 pub mod auth_api;
 pub mod base_api;
+pub mod batch_api;
+pub mod copy_api;
+pub mod direct_link_api;
 pub mod file_api;
 pub mod file_delete_api;
 pub mod file_list_api;
 pub mod file_move_api;
+pub mod image_host_api;
 pub mod limit;
+pub mod offline_api;
+pub mod openapi_api;
+pub mod paid_share_api;
+pub mod path_api;
 pub mod prelude;
+pub mod recycle_bin_api;
+pub mod s3_api;
+pub mod s3_auth;
 pub mod share_file_api;
+pub mod share_manage_api;
+pub mod share_stats_api;
+pub mod static_api;
+pub mod sync_api;
 pub mod user_info_api;
 pub mod file_upload_api;
+pub mod validated;
+pub mod video_api;
+pub mod web_upload_api;
+pub mod webdav_api;
@@ -1,51 +1,30 @@
+use super::base_api::api_send;
+use super::validated::ValidJson;
 use crate::io_basic::read_and_write::*;
 use crate::netdisk_auth::basic_env::NetDiskEnv;
 use crate::responses::prelude::*;
 use actix_web::web;
 use chrono::Utc;
-use log::{debug, error, info};
 use reqwest;
 use std::error::Error;
 use std::path::Path;
+use tracing::{debug, error};
 
 pub async fn access_token(
-    payload: web::Json<AuthConfig>,
+    payload: ValidJson<AuthConfig>,
 ) -> Result<AccessTokenResponse, Box<dyn Error>> {
-    let client = reqwest::Client::new();
     let platform = PlatformConfig::default();
     let api_url = format!("https://{}/api/v1/access_token", platform.platform_domain());
 
-    let response = client
+    let request = reqwest::Client::new()
         .post(&api_url) // 使用 &api_url 避免所有权问题
         .header("Platform", platform.platform())
-        .json(&payload)
-        .send()
-        .await?;
-
-    if response.status().is_success() {
-        match response.json::<AccessTokenResponse>().await {
-            Ok(body) => {
-                debug!("响应体: {:?}", &body);
-                Ok(body)
-            }
-            Err(e) => Err(Box::new(e)),
-        }
-    } else {
-        let status = response.status();
-        let error_body = response
-            .text()
-            .await
-            .unwrap_or_else(|_| "无法读取响应体".to_string());
-
-        debug!("请求失败，状态码: {}", status);
-        debug!("服务器错误详情: {}", error_body);
-
-        let reason = format!(
-            "Token API 调用失败。状态码: {}，服务器详情: {}",
-            status, error_body
-        );
-        Err(reason.into())
-    }
+        .json(&payload);
+    let body: AccessTokenResponse = api_send(request, "POST", "api/v1/access_token")
+        .await
+        .map_err(|e| format!("Token API 调用失败：{}", e))?;
+    debug!("响应体: {:?}", &body);
+    Ok(body)
 }
 
 pub async fn get_access_token_from_cache<T: AsRef<Path>>(
@@ -102,7 +81,7 @@ pub async fn get_access_token_from_cache<T: AsRef<Path>>(
 }
 /// 获取访问需要的access_token
 pub async fn access_token_and_cache(
-    payload: web::Json<AuthConfig>,
+    payload: ValidJson<AuthConfig>,
     env: web::Data<NetDiskEnv>,
 ) -> Result<AccessTokenResponse, Box<dyn Error>> {
     let file_path = env.config_dir.clone().join("config.toml");
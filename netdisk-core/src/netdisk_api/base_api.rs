@@ -1,19 +1,19 @@
-use crate::io_basic::read_and_write::*;
-use crate::netdisk_auth::basic_env::NetDiskEnv;
 use crate::responses::prelude::*;
-use actix_web::{get, post, web, HttpResponse, HttpServer, Responder};
-use chrono::Utc;
-use log::{debug, error, info};
+use crate::telemetry::{record_trace_id, upstream_span};
+use actix_web::http::StatusCode;
+use actix_web::{get, HttpResponse, Responder};
 use reqwest;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
 use std::error::Error;
-use std::path::Path;
+use std::time::Instant;
+use tracing::{debug, warn, Instrument, Span};
 
 #[get("/")]
 pub async fn hello() -> impl Responder {
     HttpResponse::Ok().body("Hello 123Pan!")
 }
 
-#[post("/echo")]
 pub async fn echo(req_body: String) -> impl Responder {
     HttpResponse::Ok().body(req_body)
 }
@@ -21,3 +21,130 @@ pub async fn echo(req_body: String) -> impl Responder {
 pub async fn manual_hello() -> impl Responder {
     HttpResponse::Ok().body("Hey there!")
 }
+
+/// 网关自身产生的错误响应，`code` 与 HTTP 状态码一致
+pub fn error_response(status: StatusCode, message: impl Into<String>) -> HttpResponse {
+    HttpResponse::build(status).json(ApiResponse::<()> {
+        code: status.as_u16() as i32,
+        message: message.into(),
+        data: None,
+        x_trace_id: String::new(),
+    })
+}
+
+/// 只读取响应中的 `x-traceID`，其余字段按调用方的类型解析
+#[derive(Deserialize)]
+struct TraceProbe {
+    #[serde(rename = "x-traceID", default)]
+    x_trace_id: String,
+}
+
+/// 解析上游响应：HTTP 状态码非 2xx 时返回包含响应体的错误。
+/// 响应中的 `x-traceID` 无论成功与否都会记录到当前 span
+async fn parse_response<R: DeserializeOwned>(
+    response: reqwest::Response,
+) -> Result<R, Box<dyn Error>> {
+    let status = response.status();
+    Span::current().record("status", status.as_u16());
+    let body = response
+        .bytes()
+        .await
+        .map_err(|e| format!("响应读取失败: {}", e))?;
+    if let Ok(probe) = serde_json::from_slice::<TraceProbe>(&body) {
+        record_trace_id(&probe.x_trace_id);
+    }
+    if !status.is_success() {
+        return Err(format!(
+            "API请求失败，状态码: {}，响应: {}",
+            status,
+            String::from_utf8_lossy(&body)
+        )
+        .into());
+    }
+    let api_response: R =
+        serde_json::from_slice(&body).map_err(|e| format!("响应解析失败: {}", e))?;
+    Ok(api_response)
+}
+
+/// 发送开放平台请求并解析响应，整个调用位于 `upstream` 子 span 中
+pub async fn api_send<R: DeserializeOwned>(
+    request: reqwest::RequestBuilder,
+    method: &str,
+    path: &str,
+) -> Result<R, Box<dyn Error>> {
+    async move {
+        let started = Instant::now();
+        let result = match request.send().await {
+            Ok(response) => parse_response(response).await,
+            Err(e) => Err(format!("请求发送失败: {}", e).into()),
+        };
+        let span = Span::current();
+        span.record("latency_ms", started.elapsed().as_millis() as u64);
+        match &result {
+            Ok(_) => debug!("上游调用完成"),
+            Err(e) => warn!(error = %e, "上游调用失败"),
+        }
+        result
+    }
+    .instrument(upstream_span(method, path))
+    .await
+}
+
+/// 以 GET 方式调用开放平台接口，`path` 不带前导 `/`，例如 `api/v2/file/list`
+pub async fn api_get<Q, R>(token: &AccessToken, path: &str, query: &Q) -> Result<R, Box<dyn Error>>
+where
+    Q: Serialize + ?Sized,
+    R: DeserializeOwned,
+{
+    let platform = PlatformConfig::default();
+    let api_url = format!("https://{}/{}", platform.platform_domain(), path);
+
+    debug!("GET {} 参数: {:?}", &api_url, serde_json::to_string(query));
+    let request = reqwest::Client::new()
+        .get(&api_url)
+        .query(query)
+        .header("Content-Type", "application/json")
+        .header("Platform", platform.platform())
+        .header("Authorization", format!("Bearer {}", token.access_token));
+    api_send(request, "GET", path).await
+}
+
+/// 以 POST 方式调用开放平台接口，请求体序列化为 JSON
+pub async fn api_post<B, R>(token: &AccessToken, path: &str, body: &B) -> Result<R, Box<dyn Error>>
+where
+    B: Serialize + ?Sized,
+    R: DeserializeOwned,
+{
+    let platform = PlatformConfig::default();
+    let api_url = format!("https://{}/{}", platform.platform_domain(), path);
+
+    debug!(
+        "POST {} 请求体: {:?}",
+        &api_url,
+        serde_json::to_string(body)
+    );
+    let request = reqwest::Client::new()
+        .post(&api_url)
+        .header("Authorization", format!("Bearer {}", token.access_token))
+        .header("Platform", platform.platform())
+        .json(body);
+    api_send(request, "POST", path).await
+}
+
+/// 以 PUT 方式调用开放平台接口，请求体序列化为 JSON
+pub async fn api_put<B, R>(token: &AccessToken, path: &str, body: &B) -> Result<R, Box<dyn Error>>
+where
+    B: Serialize + ?Sized,
+    R: DeserializeOwned,
+{
+    let platform = PlatformConfig::default();
+    let api_url = format!("https://{}/{}", platform.platform_domain(), path);
+
+    debug!("PUT {} 请求体: {:?}", &api_url, serde_json::to_string(body));
+    let request = reqwest::Client::new()
+        .put(&api_url)
+        .header("Authorization", format!("Bearer {}", token.access_token))
+        .header("Platform", platform.platform())
+        .json(body);
+    api_send(request, "PUT", path).await
+}
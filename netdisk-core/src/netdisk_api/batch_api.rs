@@ -0,0 +1,122 @@
+use super::file_delete_api::trash_files;
+use super::file_move_api::move_files;
+use super::limit::RateLimiter;
+use super::validated::ValidJson;
+use crate::cache::ResponseCache;
+use crate::responses::prelude::*;
+use actix_web::{web, HttpResponse};
+use std::collections::HashSet;
+use std::error::Error;
+use std::future::Future;
+use std::time::Duration;
+use tracing::{debug, warn};
+
+/// 开放平台批量接口单次最多接受的文件数量
+pub const API_BATCH_SIZE: usize = 100;
+/// 未指定时相邻两次上游请求的间隔（毫秒）
+pub const DEFAULT_BATCH_INTERVAL_MS: u64 = 500;
+
+/// 将任意数量的文件 ID 去重后按接口上限分批执行 `call`。
+///
+/// 某一批失败时逐个重试该批中的文件，以便在报告中定位具体失败的 ID。
+pub async fn run_batched<F, Fut>(
+    file_ids: &[u64],
+    limiter: &mut RateLimiter,
+    mut call: F,
+) -> BatchReport
+where
+    F: FnMut(Vec<u64>) -> Fut,
+    Fut: Future<Output = Result<(), Box<dyn Error>>>,
+{
+    let mut seen = HashSet::new();
+    let ids: Vec<u64> = file_ids
+        .iter()
+        .copied()
+        .filter(|id| seen.insert(*id))
+        .collect();
+    let mut report = BatchReport {
+        total: ids.len(),
+        ..Default::default()
+    };
+
+    for chunk in ids.chunks(API_BATCH_SIZE) {
+        limiter.wait().await;
+        match call(chunk.to_vec()).await {
+            Ok(()) => report.succeeded.extend_from_slice(chunk),
+            Err(e) if chunk.len() == 1 => report.failed.push(BatchFailure {
+                file_id: chunk[0],
+                message: e.to_string(),
+            }),
+            Err(e) => {
+                warn!("批量请求失败，逐个重试 {} 个文件: {}", chunk.len(), e);
+                for id in chunk {
+                    limiter.wait().await;
+                    match call(vec![*id]).await {
+                        Ok(()) => report.succeeded.push(*id),
+                        Err(e) => report.failed.push(BatchFailure {
+                            file_id: *id,
+                            message: e.to_string(),
+                        }),
+                    }
+                }
+            }
+        }
+    }
+    report
+}
+
+/// 按请求中的 `intervalMs` 构造限速器，间隔不超过 `MAX_INTERVAL_MS`
+pub fn limiter_for(interval_ms: Option<u64>) -> RateLimiter {
+    RateLimiter::new(Duration::from_millis(
+        interval_ms
+            .unwrap_or(DEFAULT_BATCH_INTERVAL_MS)
+            .min(MAX_INTERVAL_MS),
+    ))
+}
+
+/// 将批量结果包装为响应，有失败条目时在 message 中注明数量
+pub fn report_response(report: BatchReport) -> HttpResponse {
+    let message = if report.failed.is_empty() {
+        "ok".to_string()
+    } else {
+        format!("{} 个文件处理失败", report.failed.len())
+    };
+    HttpResponse::Ok().json(BatchReportResponse::new(0, message, report, String::new()))
+}
+
+/// # 批量移入回收站
+pub async fn batch_trash(
+    payload: ValidJson<BatchTrashItem>,
+    token: web::Data<AccessToken>,
+    cache: web::Data<ResponseCache>,
+) -> Result<HttpResponse, Box<dyn Error>> {
+    debug!("尝试发送信息:{:?}", &payload);
+    let mut limiter = limiter_for(payload.interval_ms);
+    let token = token.get_ref();
+    let report = run_batched(&payload.file_ids, &mut limiter, |ids| async move {
+        trash_files(token, &ids).await
+    })
+    .await;
+    // 部分失败时也整体失效，代价只是多一次上游查询
+    cache.invalidate_files(&payload.file_ids);
+    Ok(report_response(report))
+}
+
+/// # 批量移动文件
+pub async fn batch_move(
+    payload: ValidJson<BatchMoveItem>,
+    token: web::Data<AccessToken>,
+    cache: web::Data<ResponseCache>,
+) -> Result<HttpResponse, Box<dyn Error>> {
+    debug!("尝试发送信息:{:?}", &payload);
+    let mut limiter = limiter_for(payload.interval_ms);
+    let token = token.get_ref();
+    let to_parent_file_id = payload.to_parent_file_id;
+    let report = run_batched(&payload.file_ids, &mut limiter, |ids| async move {
+        move_files(token, &ids, to_parent_file_id).await
+    })
+    .await;
+    cache.invalidate_files(&payload.file_ids);
+    cache.invalidate_folders(&[to_parent_file_id]);
+    Ok(report_response(report))
+}
@@ -0,0 +1,186 @@
+use super::base_api::api_get;
+use super::file_api::{create_dir, download_to_path, fetch_download_url, list_folder_all};
+use super::file_upload_api::{complete_upload, create_file, upload_slices};
+use super::validated::ValidJson;
+use crate::cache::ResponseCache;
+use crate::responses::prelude::*;
+use actix_web::{web, HttpResponse};
+use std::collections::HashSet;
+use std::error::Error;
+use std::future::Future;
+use tracing::{debug, info, warn};
+
+/// 复制单个文件：优先按 etag 秒传，未命中时下载到临时文件再分片上传。
+///
+/// 返回 `Ok(None)` 表示秒传成功，`Ok(Some(n))` 表示重新上传了 n 字节。
+pub async fn copy_file(
+    token: &AccessToken,
+    source: &FileItem,
+    to_parent_file_id: u64,
+) -> Result<Option<u64>, Box<dyn Error>> {
+    let item = UploadFileItem {
+        parent_file_id: to_parent_file_id,
+        filename: source.filename.clone(),
+        etag: source.etag.clone(),
+        size: source.size,
+        duplicate: None,
+        contain_dir: None,
+    };
+    let data = create_file(token, &item).await?;
+    if data.reuse {
+        return Ok(None);
+    }
+
+    debug!("{} 未命中秒传，改为下载后重新上传", source.filename);
+    let url = fetch_download_url(token, source.file_id as u64).await?;
+    let tmp_file = tempfile::NamedTempFile::new()?;
+    let written = download_to_path(&url, tmp_file.path()).await?;
+    upload_slices(token, &data, tmp_file.path()).await?;
+    complete_upload(token, &data.preupload_id).await?;
+    Ok(Some(written))
+}
+
+/// 从 `dir_id` 沿父目录向上查找，判断它是否就是 `ancestor_id` 或位于其下；`parent_of` 返回目录的父目录 ID
+pub async fn is_same_or_descendant<F, Fut>(
+    dir_id: u64,
+    ancestor_id: u64,
+    mut parent_of: F,
+) -> Result<bool, Box<dyn Error>>
+where
+    F: FnMut(u64) -> Fut,
+    Fut: Future<Output = Result<u64, Box<dyn Error>>>,
+{
+    let mut visited = HashSet::new();
+    let mut current = dir_id;
+    // 根目录的 ID 为 0；`visited` 防止异常数据形成环
+    while current != 0 && visited.insert(current) {
+        if current == ancestor_id {
+            return Ok(true);
+        }
+        current = parent_of(current).await?;
+    }
+    Ok(current == ancestor_id)
+}
+
+async fn parent_dir_id(token: &AccessToken, dir_id: u64) -> Result<u64, Box<dyn Error>> {
+    let query = [("fileID", dir_id.to_string())];
+    let api_response: FileResponse = api_get(token, "api/v1/file/detail", &query).await?;
+    Ok(api_response.into_data()?.parent_file_id)
+}
+
+/// 递归复制目录：在 `to_parent_file_id` 下创建同名目录并重建其中的目录结构与文件。
+///
+/// 目标位于源目录自身或其子目录中时拒绝复制，否则新建的目录会再次出现在遍历中而无法结束。
+/// 单个文件或子目录失败不会中断整个复制，失败项记录在返回结果中。
+pub async fn copy_dir(
+    token: &AccessToken,
+    source_dir_id: u64,
+    source_name: &str,
+    to_parent_file_id: u64,
+) -> Result<CopyReport, Box<dyn Error>> {
+    if is_same_or_descendant(to_parent_file_id, source_dir_id, |id| {
+        parent_dir_id(token, id)
+    })
+    .await?
+    {
+        return Err(format!("不能将目录 {} 复制到自身或其子目录中", source_name).into());
+    }
+
+    let mut report = CopyReport {
+        dir_id: create_dir(token, source_name, to_parent_file_id).await?,
+        folders_created: 1,
+        ..Default::default()
+    };
+
+    // 本次复制新建的目录，遍历时跳过，避免复制刚生成的副本
+    let mut created = HashSet::from([report.dir_id]);
+    // (源目录 ID, 目标目录 ID, 目标路径)
+    let mut pending = vec![(source_dir_id, report.dir_id, format!("/{}", source_name))];
+    while let Some((src_id, dest_id, path)) = pending.pop() {
+        let items = match list_folder_all(token, src_id).await {
+            Ok(items) => items,
+            Err(e) => {
+                report.failed.push(CopyFailure {
+                    path,
+                    message: e.to_string(),
+                });
+                continue;
+            }
+        };
+
+        let items: Vec<FileItem> = items
+            .into_iter()
+            .filter(|item| item.trashed == 0 && !created.contains(&(item.file_id as u64)))
+            .collect();
+        for item in items {
+            let item_path = format!("{}/{}", path, item.filename);
+            if item.r#type == 1 {
+                match create_dir(token, &item.filename, dest_id).await {
+                    Ok(dir_id) => {
+                        created.insert(dir_id);
+                        report.folders_created += 1;
+                        pending.push((item.file_id as u64, dir_id, item_path));
+                    }
+                    Err(e) => report.failed.push(CopyFailure {
+                        path: item_path,
+                        message: e.to_string(),
+                    }),
+                }
+                continue;
+            }
+
+            match copy_file(token, &item, dest_id).await {
+                Ok(None) => report.reused += 1,
+                Ok(Some(bytes)) => {
+                    report.transferred += 1;
+                    report.transferred_bytes += bytes;
+                }
+                Err(e) => {
+                    warn!("复制 {} 失败: {}", item_path, e);
+                    report.failed.push(CopyFailure {
+                        path: item_path.clone(),
+                        message: e.to_string(),
+                    });
+                }
+            }
+            info!(
+                "复制进度: 秒传 {}，重新上传 {}，失败 {}，当前 {}",
+                report.reused,
+                report.transferred,
+                report.failed.len(),
+                item_path
+            );
+        }
+    }
+    Ok(report)
+}
+
+/// # 复制目录
+pub async fn copy_folder(
+    payload: ValidJson<CopyItem>,
+    token: web::Data<AccessToken>,
+    cache: web::Data<ResponseCache>,
+) -> Result<HttpResponse, Box<dyn Error>> {
+    debug!("尝试发送信息: {:?}", &payload);
+    let query = [("fileID", payload.source_file_id.to_string())];
+    let source: FileResponse = api_get(&token, "api/v1/file/detail", &query).await?;
+    let source = source.into_data()?;
+    if source.file_type != 1 {
+        return Err(format!("{} 不是目录", source.filename).into());
+    }
+
+    let report = copy_dir(
+        &token,
+        payload.source_file_id,
+        &source.filename,
+        payload.to_parent_file_id,
+    )
+    .await?;
+    cache.invalidate_folders(&[payload.to_parent_file_id]);
+    Ok(HttpResponse::Ok().json(CopyReportResponse::new(
+        0,
+        "ok".to_string(),
+        report,
+        String::new(),
+    )))
+}
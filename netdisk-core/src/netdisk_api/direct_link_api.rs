@@ -0,0 +1,211 @@
+use super::base_api::*;
+use super::file_api::split_dir_path;
+use super::path_api::resolve_path;
+use super::validated::{ValidJson, ValidQuery};
+use crate::io_basic::checksum::md5_hex;
+use crate::responses::prelude::*;
+use actix_web::http::{header, StatusCode};
+use actix_web::{web, HttpRequest, HttpResponse};
+use chrono::{DateTime, TimeZone, Utc};
+use percent_encoding::percent_decode_str;
+use reqwest::Url;
+use serde_json::json;
+use std::error::Error;
+use tracing::debug;
+
+/// 为直链生成鉴权参数并追加到链接上：
+/// `auth_key={timestamp}-{rand}-{uid}-{md5("{path}-{timestamp}-{rand}-{uid}-{key}")}`，
+/// 其中 `path` 为链接中解码后的路径，`timestamp` 为过期时间
+pub fn sign_direct_url(
+    url: &str,
+    uid: u64,
+    key: &str,
+    expires_at: DateTime<Utc>,
+    rand: &str,
+) -> Result<String, Box<dyn Error>> {
+    let mut url = Url::parse(url).map_err(|e| format!("直链格式错误: {}", e))?;
+    let path = percent_decode_str(url.path())
+        .decode_utf8_lossy()
+        .into_owned();
+    let timestamp = expires_at.timestamp();
+    let hash = md5_hex(format!("{}-{}-{}-{}-{}", path, timestamp, rand, uid, key).as_bytes());
+    url.query_pairs_mut().append_pair(
+        "auth_key",
+        &format!("{}-{}-{}-{}", timestamp, rand, uid, hash),
+    );
+    Ok(url.into())
+}
+
+/// 将 `/d/<路径>` 中的路径映射到网盘路径
+pub fn direct_link_path(raw_path: &str, prefix: &str, root: &str) -> Option<String> {
+    let rest = raw_path.strip_prefix(prefix)?;
+    if !rest.starts_with('/') {
+        return None;
+    }
+    let decoded = percent_decode_str(rest).decode_utf8_lossy();
+    let names = split_dir_path(&decoded);
+    if names.contains(&"..") {
+        return None;
+    }
+    let mut path = split_dir_path(root);
+    path.extend(names);
+    Some(format!("/{}", path.join("/")))
+}
+
+/// 启用或禁用目录的直链空间，返回目录名
+pub async fn toggle_direct_link(
+    token: &AccessToken,
+    file_id: u64,
+    enable: bool,
+) -> Result<String, Box<dyn Error>> {
+    let path = if enable {
+        "api/v1/direct-link/enable"
+    } else {
+        "api/v1/direct-link/disable"
+    };
+    let api_response: DirectLinkToggleResponse =
+        api_post(token, path, &DirectLinkItem { file_id }).await?;
+    Ok(api_response.into_data()?.filename)
+}
+
+/// 获取文件的直链，文件须位于已启用直链空间的目录中
+pub async fn direct_link_url(token: &AccessToken, file_id: u64) -> Result<String, Box<dyn Error>> {
+    let api_response: DirectLinkUrlResponse = api_get(
+        token,
+        "api/v1/direct-link/url",
+        &json!({ "fileID": file_id }),
+    )
+    .await?;
+    Ok(api_response.into_data()?.url)
+}
+
+/// 获取签名使用的 UID，未配置时从用户信息中读取
+async fn direct_link_uid(
+    token: &AccessToken,
+    config: &DirectLinkConfig,
+) -> Result<u64, Box<dyn Error>> {
+    if config.uid != 0 {
+        return Ok(config.uid);
+    }
+    let api_response: UserInfoResponse = api_get(token, "api/v1/user/info", &json!({})).await?;
+    Ok(api_response.into_data()?.uid)
+}
+
+/// 签名链接的失效时间，签名的时间戳只精确到秒；超出时间范围时返回 `None`
+pub fn direct_link_expires_at(now: DateTime<Utc>, expire_secs: u64) -> Option<DateTime<Utc>> {
+    let timestamp = now
+        .timestamp()
+        .checked_add(i64::try_from(expire_secs).ok()?)?;
+    Utc.timestamp_opt(timestamp, 0).single()
+}
+
+/// 获取文件的直链，配置了鉴权密钥时生成有效期为 `expire_secs` 的签名链接
+pub async fn build_direct_link(
+    token: &AccessToken,
+    config: &DirectLinkConfig,
+    file_id: u64,
+    expire_secs: Option<u64>,
+) -> Result<DirectLink, Box<dyn Error>> {
+    let url = direct_link_url(token, file_id).await?;
+    if config.auth_key.is_empty() {
+        return Ok(DirectLink {
+            file_id,
+            url,
+            signed: false,
+            expires_at: None,
+        });
+    }
+    let uid = direct_link_uid(token, config).await?;
+    let expire_secs = expire_secs.unwrap_or(config.expire_secs);
+    let expires_at = direct_link_expires_at(Utc::now(), expire_secs).ok_or("直链有效期超出范围")?;
+    let nanos = Utc::now().timestamp_nanos_opt().unwrap_or_default() as u128;
+    let rand = base62::encode(nanos);
+    Ok(DirectLink {
+        file_id,
+        url: sign_direct_url(&url, uid, &config.auth_key, expires_at, &rand)?,
+        signed: true,
+        expires_at: Some(expires_at),
+    })
+}
+
+/// # 启用目录的直链空间
+pub async fn direct_link_enable(
+    payload: ValidJson<DirectLinkItem>,
+    token: web::Data<AccessToken>,
+) -> Result<HttpResponse, Box<dyn Error>> {
+    debug!("尝试发送信息: {:?}", &payload);
+    let filename = toggle_direct_link(&token, payload.file_id, true).await?;
+    Ok(HttpResponse::Ok().json(DirectLinkToggleResponse::new(
+        0,
+        "ok".to_string(),
+        DirectLinkToggleData { filename },
+        String::new(),
+    )))
+}
+
+/// # 禁用目录的直链空间
+pub async fn direct_link_disable(
+    payload: ValidJson<DirectLinkItem>,
+    token: web::Data<AccessToken>,
+) -> Result<HttpResponse, Box<dyn Error>> {
+    debug!("尝试发送信息: {:?}", &payload);
+    let filename = toggle_direct_link(&token, payload.file_id, false).await?;
+    Ok(HttpResponse::Ok().json(DirectLinkToggleResponse::new(
+        0,
+        "ok".to_string(),
+        DirectLinkToggleData { filename },
+        String::new(),
+    )))
+}
+
+/// # 获取文件直链
+pub async fn direct_link_get(
+    query: ValidQuery<DirectLinkUrlQuery>,
+    token: web::Data<AccessToken>,
+    gateway: web::Data<GatewayConfig>,
+) -> Result<HttpResponse, Box<dyn Error>> {
+    let link = build_direct_link(
+        &token,
+        &gateway.direct_link,
+        query.file_id,
+        query.expire_secs,
+    )
+    .await?;
+    Ok(HttpResponse::Ok().json(DirectLinkResponse::new(
+        0,
+        "ok".to_string(),
+        link,
+        String::new(),
+    )))
+}
+
+/// # 按网盘路径 302 跳转到文件直链
+pub async fn direct_link_redirect(
+    req: HttpRequest,
+    token: web::Data<AccessToken>,
+    gateway: web::Data<GatewayConfig>,
+) -> Result<HttpResponse, Box<dyn Error>> {
+    let config = &gateway.direct_link;
+    let path = match direct_link_path(req.path(), &config.normalized_prefix(), &config.root) {
+        Some(path) => path,
+        None => return Ok(error_response(StatusCode::NOT_FOUND, "路径不存在")),
+    };
+    debug!("直链跳转 {}", path);
+    let node = match resolve_path(&token, &path).await? {
+        Some(node) if !node.is_dir() => node,
+        _ => return Ok(error_response(StatusCode::NOT_FOUND, "文件不存在")),
+    };
+    let link = build_direct_link(&token, config, node.file_id(), None).await?;
+    Ok(HttpResponse::Found()
+        .insert_header((header::LOCATION, link.url))
+        .insert_header((header::CACHE_CONTROL, "no-store"))
+        .finish())
+}
+
+pub fn direct_link_config(cfg: &mut web::ServiceConfig, config: &DirectLinkConfig) {
+    if config.enabled {
+        cfg.service(
+            web::scope(&config.normalized_prefix()).default_service(web::to(direct_link_redirect)),
+        );
+    }
+}
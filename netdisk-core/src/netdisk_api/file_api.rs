@@ -1,227 +1,343 @@
+use super::base_api::*;
+use super::file_move_api::rename_file;
+use super::validated::{ValidJson, ValidQuery};
+use crate::cache::{
+    cached_get, cached_json, cached_post, list_tags, CachePolicy, CacheTag, ResponseCache,
+};
 use crate::responses::prelude::*;
-use actix_web::{get, post, web, HttpResponse};
-use log::{debug, error, info};
+use actix_web::{web, HttpRequest, HttpResponse};
 use reqwest;
 use std::error::Error;
-pub async fn file_lists_query(
-    query: web::Query<FileListQuery>, // 假设 FileListQuery 包含所有参数
-    token: web::Data<AccessToken>,
-) -> Result<HttpResponse, Box<dyn Error>> {
-    let client = reqwest::Client::new();
-    let platform = PlatformConfig::default();
-    let api_url = format!("https://{}/api/v2/file/list", platform.platform_domain());
-
-    let authorization_header = format!("Bearer {}", token.get_ref().access_token);
-
-    // --- 修正 3: 构建包含所有可选参数的查询参数列表 ---
-    let mut query_params = Vec::new();
-    query_params.push(("parentFileId", query.parent_file_id.to_string()));
-    query_params.push(("limit", query.limit.to_string()));
-
-    // 动态添加可选参数
-    if let Some(search_data) = &query.search_data {
+use std::path::Path;
+use tokio::io::AsyncWriteExt;
+use tracing::debug;
+/// 文件列表的上游查询参数，可选参数缺失时不传
+pub(crate) fn list_query_params(
+    parent_file_id: i64,
+    limit: u8,
+    search_data: Option<&String>,
+    search_mode: Option<String>,
+    last_file_id: Option<i64>,
+) -> Vec<(&'static str, String)> {
+    let mut query_params = vec![
+        ("parentFileId", parent_file_id.to_string()),
+        ("limit", limit.to_string()),
+    ];
+    if let Some(search_data) = search_data {
         query_params.push(("searchData", search_data.clone()));
     }
-    if let Some(search_mode) = query.search_mode {
-        query_params.push(("searchMode", search_mode.to_string()));
+    if let Some(search_mode) = search_mode {
+        query_params.push(("searchMode", search_mode));
     }
-    if let Some(last_file_id) = query.last_file_id {
+    if let Some(last_file_id) = last_file_id {
         query_params.push(("lastFileId", last_file_id.to_string()));
     }
+    query_params
+}
 
-    // 1. 发送 GET 请求
-    // debug!("尝试发送信息:{}", &query_params);
-    let response = client
-        .get(api_url)
-        .query(&query_params) // 使用包含所有参数的 Vec
-        .header("Content-Type", "application/json")
-        .header("Platform", platform.platform())
-        .header("Authorization", &authorization_header)
-        .send()
-        .await?;
-
-    // 2. 检查 HTTP 状态码
-    if !response.status().is_success() {
-        // ... (错误处理逻辑不变)
-        let status = response.status();
-        let body = response.text().await.unwrap_or_default();
-        return Err(format!("API 请求失败，HTTP 状态码: {}，响应: {}", status, body).into());
-    }
-    let api_response: FileListResponse = response.json().await?;
+pub async fn file_lists_query(
+    req: HttpRequest,
+    query: ValidQuery<FileListQuery>, // 假设 FileListQuery 包含所有参数
+    token: web::Data<AccessToken>,
+    cache: web::Data<ResponseCache>,
+) -> Result<HttpResponse, Box<dyn Error>> {
+    let query_params = list_query_params(
+        query.parent_file_id,
+        query.limit,
+        query.search_data.as_ref(),
+        query.search_mode.map(|mode| mode.to_string()),
+        query.last_file_id,
+    );
+    let parent_file_id = query.parent_file_id as u64;
+    let (api_response, status) = cached_get(
+        &cache,
+        &token,
+        "api/v2/file/list",
+        &query_params,
+        CachePolicy::from_request(&req),
+        |response: &FileListResponse| match &response.data {
+            Some(data) => list_tags(parent_file_id, &data.file_list),
+            None => list_tags(parent_file_id, &[]),
+        },
+    )
+    .await?;
 
     // 返回 Actix 响应
-    Ok(HttpResponse::Ok().json(api_response))
+    Ok(cached_json(&api_response, status))
 }
 
 pub async fn file_query(
-    query: web::Query<FileQuery>,
+    req: HttpRequest,
+    query: ValidQuery<FileQuery>,
     token: web::Data<AccessToken>,
+    cache: web::Data<ResponseCache>,
 ) -> Result<HttpResponse, Box<dyn Error>> {
-    let client = reqwest::Client::new();
-    let platform = PlatformConfig::default();
-    let api_url = format!("https://{}/api/v1/file/detail", platform.platform_domain());
-
-    let authorization_header = format!("Bearer {}", token.access_token);
-
     // 关键修复：使用与API匹配的参数名fileID
-    let mut query_params = Vec::new();
-    query_params.push(("fileID", query.file_id.to_string())); // 这里改为fileID
+    let query_params = vec![("fileID", query.file_id.to_string())];
+    let file_id = query.file_id as u64;
 
     debug!("尝试发送信息: {:?}", &query_params);
-    let response = client
-        .get(api_url)
-        .query(&query_params)
-        .header("Platform", platform.platform())
-        .header("Authorization", &authorization_header)
-        .send()
-        .await
-        .map_err(|e| format!("请求发送失败: {}", e))?;
+    let (api_response, status): (FileResponse, _) = cached_get(
+        &cache,
+        &token,
+        "api/v1/file/detail",
+        &query_params,
+        CachePolicy::from_request(&req),
+        |_| vec![CacheTag::File(file_id)],
+    )
+    .await?;
+
+    Ok(cached_json(&api_response, status))
+}
 
-    // 检查HTTP状态码
-    let status = response.status();
-    if !status.is_success() {
-        let body = response.text().await.unwrap_or_default();
-        return Err(format!("API请求失败，状态码: {}，响应: {}", status, body).into());
-    }
+pub async fn files_info(
+    req: HttpRequest,
+    payload: ValidJson<FilesQuery>,
+    token: web::Data<AccessToken>,
+    cache: web::Data<ResponseCache>,
+) -> Result<HttpResponse, Box<dyn Error>> {
+    debug!("尝试发送信息: {:?}", &payload);
+    let tags: Vec<CacheTag> = payload
+        .file_ids
+        .iter()
+        .map(|id| CacheTag::File(*id))
+        .collect();
+    let (api_response, status): (FilesInfoResponse, _) = cached_post(
+        &cache,
+        &token,
+        "api/v1/file/infos",
+        &payload.0,
+        CachePolicy::from_request(&req),
+        |_| tags,
+    )
+    .await?;
+
+    Ok(cached_json(&api_response, status))
+}
 
-    // 解析响应
-    let api_response: FileResponse = response
-        .json()
-        .await
-        .map_err(|e| format!("响应解析失败: {}", e))?;
+pub async fn mkdir(
+    payload: ValidJson<EntryItem>,
+    token: web::Data<AccessToken>,
+    cache: web::Data<ResponseCache>,
+) -> Result<HttpResponse, Box<dyn Error>> {
+    debug!("尝试发送信息: {:?}", &payload);
+    let api_response: PathInfoResponse = api_post(&token, "upload/v1/file/mkdir", &payload).await?;
 
+    debug!("响应内容: {:?}", &api_response);
+    if api_response.is_ok() {
+        cache.invalidate_folders(&[payload.parentID]);
+    }
     Ok(HttpResponse::Ok().json(api_response))
 }
 
-pub async fn files_info(
-    payload: web::Json<FilesQuery>,
+pub async fn download(
+    query: ValidQuery<FileQuery>,
     token: web::Data<AccessToken>,
-) -> Result<HttpResponse, Box<dyn Error>> {
-    let client = reqwest::Client::new();
-    let platform = PlatformConfig::default();
-    let api_url = format!("https://{}/api/v1/file/infos", platform.platform_domain());
+) -> Result<HttpResponse, actix_web::Error> {
+    // <- 注意返回类型
+    let file_query_data: FileQuery = query.into_inner();
 
-    let authorization_header = format!("Bearer {}", token.access_token);
+    debug!("尝试发送信息: {:?}", &file_query_data);
+    let api_response: DownloadUrlResponse =
+        api_get(&token, "api/v1/file/download_info", &file_query_data)
+            .await
+            .map_err(|e| actix_web::error::ErrorInternalServerError(e.to_string()))?; // <- 转 actix_web::Error
 
-    debug!("尝试发送信息: {:?}", &payload);
+    debug!("响应内容: {:?}", &api_response);
+    Ok(HttpResponse::Ok().json(api_response))
+}
 
-    let response = client
-        .post(&api_url)
-        .header("Authorization", &authorization_header)
-        .header("Platform", platform.platform())
-        .json(&payload)
-        .send()
-        .await
-        .map_err(|e| format!("请求发送失败: {}", e))?;
+/// 分页拉取目录下的全部条目，结果包含回收站中的条目（`trashed == 1`）
+pub async fn list_folder_all(
+    token: &AccessToken,
+    parent_file_id: u64,
+) -> Result<Vec<FileItem>, Box<dyn Error>> {
+    let mut items = Vec::new();
+    let mut last_file_id: Option<i32> = None;
+    loop {
+        let mut query_params = vec![
+            ("parentFileId", parent_file_id.to_string()),
+            ("limit", "100".to_string()),
+        ];
+        if let Some(last_file_id) = last_file_id {
+            query_params.push(("lastFileId", last_file_id.to_string()));
+        }
+        let api_response: FileListResponse =
+            api_get(token, "api/v2/file/list", &query_params).await?;
+        let body = api_response.into_data()?;
+        items.extend(body.file_list);
+
+        // lastFileId 为 -1 表示已经是最后一页
+        if body.last_file_id == -1 {
+            break;
+        }
+        last_file_id = Some(body.last_file_id);
+    }
+    Ok(items)
+}
 
-    let status = response.status();
-    if !status.is_success() {
-        let body = response.text().await.unwrap_or_default();
-        return Err(format!("API请求失败，状态码: {}，响应: {}", status, body).into());
+/// 在 `parent_id` 下创建目录，返回新目录的 `dirID`
+pub async fn create_dir(
+    token: &AccessToken,
+    name: &str,
+    parent_id: u64,
+) -> Result<u64, Box<dyn Error>> {
+    let payload = EntryItem {
+        name: name.to_string(),
+        parentID: parent_id,
+    };
+    let api_response: PathInfoResponse = api_post(token, "upload/v1/file/mkdir", &payload).await?;
+    Ok(api_response.into_data()?.dirID)
+}
+
+/// 在 `parent_id` 下查找未删除的同名目录
+pub async fn find_dir(
+    token: &AccessToken,
+    name: &str,
+    parent_id: u64,
+) -> Result<Option<u64>, Box<dyn Error>> {
+    let found = list_folder_all(token, parent_id)
+        .await?
+        .into_iter()
+        .find(|item| item.trashed == 0 && item.r#type == 1 && item.filename == name);
+    Ok(found.map(|item| item.file_id as u64))
+}
+
+/// 幂等地创建目录：已存在时直接返回其 `dirID`。
+///
+/// 创建失败时重新查找一次，以兼容其他客户端同时创建同名目录的情况。
+pub async fn ensure_dir(
+    token: &AccessToken,
+    name: &str,
+    parent_id: u64,
+) -> Result<u64, Box<dyn Error>> {
+    if let Some(dir_id) = find_dir(token, name, parent_id).await? {
+        return Ok(dir_id);
     }
+    match create_dir(token, name, parent_id).await {
+        Ok(dir_id) => Ok(dir_id),
+        Err(e) => match find_dir(token, name, parent_id).await? {
+            Some(dir_id) => {
+                debug!("目录 {} 已被并发创建，复用 {}", name, dir_id);
+                Ok(dir_id)
+            }
+            None => Err(e),
+        },
+    }
+}
 
-    // 解析响应
-    let api_response: FilesInfoResponse = response
-        .json()
-        .await
-        .map_err(|e| format!("响应解析失败: {}", e))?;
+/// 将 `a/b/c` 形式的路径拆分为目录名，忽略空段与 `.`
+pub fn split_dir_path(path: &str) -> Vec<&str> {
+    path.split('/')
+        .map(str::trim)
+        .filter(|name| !name.is_empty() && *name != ".")
+        .collect()
+}
 
-    Ok(HttpResponse::Ok().json(api_response))
+/// 类似 `mkdir -p`：从 `root_id` 开始逐级确保路径中的每个目录存在，返回最后一级的 `dirID`
+pub async fn ensure_dir_path(
+    token: &AccessToken,
+    path: &str,
+    root_id: u64,
+) -> Result<u64, Box<dyn Error>> {
+    let mut dir_id = root_id;
+    for name in split_dir_path(path) {
+        dir_id = ensure_dir(token, name, dir_id).await?;
+    }
+    Ok(dir_id)
 }
 
-pub async fn mkdir(
-    payload: web::Json<EntryItem>,
+/// # 幂等创建多级目录
+pub async fn mkdir_p(
+    payload: ValidJson<MkdirPathItem>,
     token: web::Data<AccessToken>,
+    cache: web::Data<ResponseCache>,
 ) -> Result<HttpResponse, Box<dyn Error>> {
-    let client = reqwest::Client::new();
-    let platform = PlatformConfig::default();
-    let api_url = format!(
-        "https://{}/upload/v1/file/mkdir",
-        platform.platform_domain()
-    );
-
-    let authorization_header = format!("Bearer {}", token.access_token);
-
     debug!("尝试发送信息: {:?}", &payload);
+    let dir_id = ensure_dir_path(&token, &payload.path, payload.parent_id).await?;
+    // 只知道起点与终点，中间已存在目录的列表依靠有效期过期
+    cache.invalidate_folders(&[payload.parent_id, dir_id]);
+    Ok(HttpResponse::Ok().json(PathInfoResponse::new(
+        0,
+        "ok".to_string(),
+        EntryInfo { dirID: dir_id },
+        String::new(),
+    )))
+}
+
+/// 获取文件的下载地址
+pub async fn fetch_download_url(
+    token: &AccessToken,
+    file_id: u64,
+) -> Result<String, Box<dyn Error>> {
+    let query = [("fileId", file_id.to_string())];
+    let api_response: DownloadUrlResponse =
+        api_get(token, "api/v1/file/download_info", &query).await?;
+    Ok(api_response.into_data()?.download_url)
+}
 
-    let response = client
-        .post(&api_url)
-        .header("Authorization", &authorization_header)
-        .header("Platform", platform.platform())
-        .json(&payload)
+/// 将下载地址的内容流式写入本地文件，返回写入的字节数
+pub async fn download_to_path(url: &str, path: &Path) -> Result<u64, Box<dyn Error>> {
+    let mut response = reqwest::Client::new()
+        .get(url)
         .send()
         .await
         .map_err(|e| format!("请求发送失败: {}", e))?;
-
     let status = response.status();
     if !status.is_success() {
-        let body = response.text().await.unwrap_or_default();
-        Err(format!("API请求失败，状态码: {}，响应: {}", status, body).into())
-    } else {
-        // 解析响应
-        let api_response: PathInfoResponse = response
-            .json()
-            .await
-            .map_err(|e| format!("响应解析失败: {}", e))?;
+        return Err(format!("下载失败，状态码: {}", status).into());
+    }
 
-        debug!("响应内容: {:?}", &api_response);
-        Ok(HttpResponse::Ok().json(api_response))
+    let mut file = tokio::fs::File::create(path).await?;
+    let mut written = 0u64;
+    while let Some(chunk) = response.chunk().await? {
+        file.write_all(&chunk).await?;
+        written += chunk.len() as u64;
     }
+    file.flush().await?;
+    Ok(written)
 }
 
-pub async fn download(
-    query: web::Query<FileQuery>,
-    token: web::Data<AccessToken>,
-) -> Result<HttpResponse, actix_web::Error> {
-    // <- 注意返回类型
-    let file_query_data: FileQuery = query.into_inner();
-    let client = reqwest::Client::new();
-    let platform = PlatformConfig::default();
-    let api_url = format!(
-        "https://{}/{}",
-        platform.platform_domain(),
-        "api/v1/file/download_info"
-    );
-
-    let authorization_header = format!("Bearer {}", token.access_token);
-
-    debug!("尝试发送信息: {:?}", &file_query_data);
-
-    let response = client
-        .get(api_url)
-        .query(&file_query_data)
-        .header("Content-Type", "application/json")
-        .header("Platform", platform.platform())
-        .header("Authorization", &authorization_header)
+/// 按 `Range: bytes=start-(start+len-1)` 下载一段内容，返回的字节数与请求长度不一致时视为失败
+pub async fn download_range(url: &str, start: u64, len: u64) -> Result<Vec<u8>, Box<dyn Error>> {
+    if len == 0 {
+        return Ok(Vec::new());
+    }
+    let response = reqwest::Client::new()
+        .get(url)
+        .header("Range", format!("bytes={}-{}", start, start + len - 1))
         .send()
         .await
-        .map_err(actix_web::error::ErrorInternalServerError)?; // <- 转 actix_web::Error
-
+        .map_err(|e| format!("请求发送失败: {}", e))?;
     let status = response.status();
-    if !status.is_success() {
-        let body = response.text().await.unwrap_or_default();
-        Err(actix_web::error::ErrorInternalServerError(format!(
-            "API请求失败，状态码: {}，响应: {}",
-            status, body
-        )))
-    } else {
-        let api_response: DownloadUrlResponse = response
-            .json()
-            .await
-            .map_err(actix_web::error::ErrorInternalServerError)?;
-
-        debug!("响应内容: {:?}", &api_response);
-        Ok(HttpResponse::Ok().json(api_response))
+    let partial = status == reqwest::StatusCode::PARTIAL_CONTENT;
+    if !partial && (!status.is_success() || start != 0) {
+        return Err(format!("分段下载失败，状态码: {}", status).into());
+    }
+    let mut data = response.bytes().await?.to_vec();
+    // 不支持 Range 的服务器会返回完整内容，只截取需要的部分
+    if !partial {
+        data.truncate(len as usize);
     }
+    if data.len() as u64 != len {
+        return Err(format!(
+            "分段下载长度不符: 期望 {} 字节，实际 {} 字节",
+            len,
+            data.len()
+        )
+        .into());
+    }
+    Ok(data)
 }
 
-pub fn file_config(cfg: &mut web::ServiceConfig) {
-    cfg.service(
-        web::scope("/file") // 所有路由都以 /share 为前缀
-            .route("/download", web::get().to(download))
-            .route("/mkdir", web::post().to(mkdir))
-            .route("/file_lists_query", web::get().to(file_lists_query))
-            .route("/file_query", web::get().to(file_query))
-            .route("/files_info", web::post().to(files_info)),
-    );
+/// # 修改单个文件名称
+pub async fn rename(
+    payload: ValidJson<FileRenameItem>,
+    token: web::Data<AccessToken>,
+    cache: web::Data<ResponseCache>,
+) -> Result<HttpResponse, Box<dyn Error>> {
+    debug!("尝试发送信息: {:?}", &payload);
+    rename_file(&token, payload.file_id, &payload.file_name).await?;
+    cache.invalidate_files(&[payload.file_id]);
+    Ok(HttpResponse::Ok().json(ApiResponse::new(0, "ok".to_string(), (), String::new())))
 }
@@ -1,81 +1,385 @@
+use super::base_api::*;
+use super::validated::ValidJson;
+use crate::cache::ResponseCache;
+use crate::netdisk_auth::basic_env::NetDiskEnv;
 use crate::responses::prelude::*;
-use actix_web::{get, post, web, HttpResponse, HttpServer, Responder};
-use log::{debug, error, info};
-use reqwest;
+use actix_web::{web, HttpResponse};
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use chrono::Utc;
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use std::collections::{HashMap, HashSet};
 use std::error::Error;
+use std::io;
+use tokio::fs::OpenOptions;
+use tokio::io::AsyncWriteExt;
+use tracing::{debug, info};
+
+type HmacSha256 = Hmac<Sha256>;
 
-#[post("/trash")]
 pub async fn trash(
-    payload: web::Json<FilesQuery>,
+    payload: ValidJson<FilesQuery>,
     token: web::Data<AccessToken>,
+    cache: web::Data<ResponseCache>,
 ) -> Result<HttpResponse, Box<dyn Error>> {
-    let client = reqwest::Client::new();
-    let platform = PlatformConfig::default();
-    let api_url = format!("https://{}/api/v1/file/trash", platform.platform_domain());
+    debug!("尝试发送信息: {:?}", &payload);
+    let api_response: ApiResponse<()> = api_post(&token, "api/v1/file/trash", &payload).await?;
 
-    let authorization_header = format!("Bearer {}", token.access_token);
+    debug!("响应内容: {:?}", &api_response);
+    if api_response.is_ok() {
+        cache.invalidate_files(&payload.file_ids);
+    }
+    Ok(HttpResponse::Ok().json(api_response))
+}
 
-    debug!("尝试发送信息: {:?}", &payload);
+/// 单批将文件移入回收站，调用方负责保证 `file_ids` 不超过接口上限
+pub async fn trash_files(token: &AccessToken, file_ids: &[u64]) -> Result<(), Box<dyn Error>> {
+    let payload = FilesQuery {
+        file_ids: file_ids.to_vec(),
+    };
+    let api_response: ApiResponse<()> = api_post(token, "api/v1/file/trash", &payload).await?;
+    if !api_response.is_ok() {
+        return Err(format!(
+            "移入回收站失败，code: {}，信息: {}",
+            api_response.code, api_response.message
+        )
+        .into());
+    }
+    Ok(())
+}
+
+/// 彻底删除接口单次最多接受的文件数量
+const DELETE_BATCH_SIZE: usize = 100;
+/// 预检下发的确认令牌有效期（秒）
+const CONFIRM_TOKEN_TTL: i64 = 300;
+/// 彻底删除审计日志文件名，位于配置目录下
+pub const DELETE_AUDIT_LOG: &str = "delete_audit.log";
+/// 确认令牌的签名密钥文件名，位于配置目录下
+pub const DELETE_SECRET_FILE: &str = "delete_token.key";
+
+/// 分批获取多个文件的详情
+pub async fn fetch_files_info(
+    token: &AccessToken,
+    file_ids: &[u64],
+) -> Result<Vec<FileInfo>, Box<dyn Error>> {
+    let mut file_list = Vec::new();
+    for chunk in file_ids.chunks(DELETE_BATCH_SIZE) {
+        let payload = FilesQuery {
+            file_ids: chunk.to_vec(),
+        };
+        let api_response: FilesInfoResponse =
+            api_post(token, "api/v1/file/infos", &payload).await?;
+        file_list.extend(api_response.into_data()?.fileList);
+    }
+    Ok(file_list)
+}
+
+/// 沿父目录向上查询，拼出目录的完整路径；`cache` 在同一次预检内复用已查询的目录
+pub async fn resolve_dir_path(
+    token: &AccessToken,
+    dir_id: u64,
+    cache: &mut HashMap<u64, String>,
+) -> Result<String, Box<dyn Error>> {
+    let mut names = Vec::new();
+    let mut current = dir_id;
+    let mut prefix = String::new();
+    while current != 0 {
+        if let Some(path) = cache.get(&current) {
+            prefix = path.clone();
+            break;
+        }
+        let query = [("fileID", current.to_string())];
+        let api_response: FileResponse = api_get(token, "api/v1/file/detail", &query).await?;
+        let dir = api_response.into_data()?;
+        names.push((current, dir.filename));
+        current = dir.parent_file_id;
+    }
+
+    // 自顶向下补全缓存
+    let mut path = prefix;
+    for (id, name) in names.into_iter().rev() {
+        path = format!("{}/{}", path, name);
+        cache.insert(id, path.clone());
+    }
+    Ok(path)
+}
+
+/// 读取确认令牌的签名密钥，不存在时生成 32 字节随机密钥并写入配置目录（Unix 下权限为 0600）；
+/// 多个进程同时生成时只有一个能创建文件，其余读取它写入的密钥
+pub fn load_delete_secret(env: &NetDiskEnv) -> Result<Vec<u8>, io::Error> {
+    let path = env.config_dir.join(DELETE_SECRET_FILE);
+    if let Some(secret) = read_delete_secret(&path)? {
+        return Ok(secret);
+    }
+    std::fs::create_dir_all(&env.config_dir)?;
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    match options.open(&path) {
+        Ok(mut file) => {
+            use std::io::Write;
+            let secret: [u8; 32] = rand::random();
+            file.write_all(&secret)?;
+            file.sync_all()?;
+            Ok(secret.to_vec())
+        }
+        Err(e) if e.kind() == io::ErrorKind::AlreadyExists => {
+            // 其他进程刚创建了文件，等待它写完密钥
+            for _ in 0..50 {
+                if let Some(secret) = read_delete_secret(&path)? {
+                    return Ok(secret);
+                }
+                std::thread::sleep(std::time::Duration::from_millis(10));
+            }
+            Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("签名密钥文件 {} 不完整，请删除后重试", path.display()),
+            ))
+        }
+        Err(e) => Err(e),
+    }
+}
+
+/// 读取已有的签名密钥，文件不存在或长度不足时返回 `None`
+fn read_delete_secret(path: &std::path::Path) -> Result<Option<Vec<u8>>, io::Error> {
+    match std::fs::read(path) {
+        Ok(secret) if secret.len() >= 32 => Ok(Some(secret)),
+        Ok(_) => Ok(None),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+fn confirm_token_mac(
+    secret: &[u8],
+    token: &AccessToken,
+    file_ids: &[u64],
+    expires_at: i64,
+) -> HmacSha256 {
+    let mut ids = file_ids.to_vec();
+    ids.sort_unstable();
+    ids.dedup();
+    let ids: Vec<String> = ids.iter().map(u64::to_string).collect();
+    let mut mac = HmacSha256::new_from_slice(secret).expect("HMAC 支持任意长度的密钥");
+    mac.update(format!("{}\n{}\n{}", ids.join(","), expires_at, token.access_token).as_bytes());
+    mac
+}
 
-    let response = client
-        .post(&api_url)
-        .header("Authorization", &authorization_header)
-        .header("Platform", platform.platform())
-        .json(&payload)
-        .send()
-        .await
-        .map_err(|e| format!("请求发送失败: {}", e))?;
-
-    let status = response.status();
-    if !status.is_success() {
-        let body = response.text().await.unwrap_or_default();
-        Err(format!("API请求失败，状态码: {}，响应: {}", status, body).into())
+/// 计算确认令牌：`<过期时间戳>-<签名>`，签名为 HMAC-SHA256，覆盖排序后的文件 ID、
+/// 过期时间与当前 access_token
+pub fn delete_confirm_token(
+    secret: &[u8],
+    token: &AccessToken,
+    file_ids: &[u64],
+    expires_at: i64,
+) -> String {
+    let signature = confirm_token_mac(secret, token, file_ids, expires_at)
+        .finalize()
+        .into_bytes();
+    format!("{}-{}", expires_at, URL_SAFE_NO_PAD.encode(signature))
+}
+
+/// 校验确认令牌是否与文件 ID 匹配且未过期，签名以常量时间比较
+pub fn verify_delete_token(
+    secret: &[u8],
+    token: &AccessToken,
+    file_ids: &[u64],
+    confirm_token: &str,
+) -> bool {
+    let (expires_at, signature) = match confirm_token
+        .split_once('-')
+        .and_then(|(ts, signature)| Some((ts.parse::<i64>().ok()?, signature)))
+    {
+        Some(parts) => parts,
+        None => return false,
+    };
+    let signature = match URL_SAFE_NO_PAD.decode(signature) {
+        Ok(signature) => signature,
+        Err(_) => return false,
+    };
+    expires_at >= Utc::now().timestamp()
+        && confirm_token_mac(secret, token, file_ids, expires_at)
+            .verify_slice(&signature)
+            .is_ok()
+}
+
+/// 预检：只允许彻底删除已经在回收站中的文件，并汇总数量、大小与路径
+pub async fn preflight_delete(
+    token: &AccessToken,
+    env: &NetDiskEnv,
+    file_ids: &[u64],
+) -> Result<DeletePreflight, Box<dyn Error>> {
+    let infos = fetch_files_info(token, file_ids).await?;
+    let mut dir_cache = HashMap::new();
+    let mut file_list = Vec::new();
+    for info in infos.into_iter().filter(|info| info.trashed == 1) {
+        let dir = resolve_dir_path(token, info.parent_file_id, &mut dir_cache).await?;
+        file_list.push(DeleteCandidate {
+            file_id: info.file_id,
+            path: format!("{}/{}", dir, info.filename),
+            filename: info.filename,
+            size: info.size,
+            etag: info.etag,
+        });
+    }
+
+    let accepted: HashSet<u64> = file_list.iter().map(|f| f.file_id).collect();
+    let mut rejected: Vec<u64> = file_ids
+        .iter()
+        .copied()
+        .filter(|id| !accepted.contains(id))
+        .collect();
+    rejected.sort_unstable();
+    rejected.dedup();
+
+    let confirm_token = if rejected.is_empty() {
+        let expires_at = Utc::now().timestamp() + CONFIRM_TOKEN_TTL;
+        let secret = load_delete_secret(env)?;
+        Some(delete_confirm_token(&secret, token, file_ids, expires_at))
     } else {
-        // 解析响应
-        let api_response: ApiResponse<()> = response
-            .json()
-            .await
-            .map_err(|e| format!("响应解析失败: {}", e))?;
-
-        debug!("响应内容: {:?}", &api_response);
-        Ok(HttpResponse::Ok().json(api_response))
+        None
+    };
+    Ok(DeletePreflight {
+        count: file_list.len(),
+        total_size: file_list.iter().map(|f| f.size).sum(),
+        file_list,
+        rejected,
+        confirm_token,
+    })
+}
+
+/// 追加写入彻底删除审计日志，每行一条 JSON 记录
+pub async fn append_delete_audit(
+    env: &NetDiskEnv,
+    source: &str,
+    files: &[DeleteCandidate],
+) -> Result<(), io::Error> {
+    let mut lines = String::new();
+    for file in files {
+        let entry = DeleteAuditEntry {
+            deleted_at: Utc::now(),
+            source: source.to_string(),
+            file: file.clone(),
+        };
+        let line = serde_json::to_string(&entry)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        lines.push_str(&line);
+        lines.push('\n');
+    }
+    let mut log_file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(env.config_dir.join(DELETE_AUDIT_LOG))
+        .await?;
+    log_file.write_all(lines.as_bytes()).await?;
+    log_file.flush().await
+}
+
+/// 分批彻底删除预检通过的文件，每批成功后立即写入审计日志
+pub async fn delete_permanently(
+    token: &AccessToken,
+    env: &NetDiskEnv,
+    source: &str,
+    files: &[DeleteCandidate],
+) -> Result<(), Box<dyn Error>> {
+    for chunk in files.chunks(DELETE_BATCH_SIZE) {
+        let payload = FilesQuery {
+            file_ids: chunk.iter().map(|f| f.file_id).collect(),
+        };
+        let api_response: ApiResponse<()> = api_post(token, "api/v1/file/delete", &payload).await?;
+        if !api_response.is_ok() {
+            return Err(format!(
+                "彻底删除失败，code: {}，信息: {}",
+                api_response.code, api_response.message
+            )
+            .into());
+        }
+        append_delete_audit(env, source, chunk).await?;
+        info!("已彻底删除 {} 个文件", chunk.len());
     }
+    Ok(())
 }
 
-#[post("/delete")]
+/// # 彻底删除预检
+pub async fn delete_preflight(
+    payload: ValidJson<GuardedDeleteItem>,
+    token: web::Data<AccessToken>,
+    env: web::Data<NetDiskEnv>,
+) -> Result<HttpResponse, Box<dyn Error>> {
+    debug!("尝试发送信息: {:?}", &payload);
+    let preflight = preflight_delete(&token, &env, &payload.file_ids).await?;
+    Ok(HttpResponse::Ok().json(DeletePreflightResponse::new(
+        0,
+        "ok".to_string(),
+        preflight,
+        String::new(),
+    )))
+}
+
+/// # 彻底删除文件
+///
+/// 只接受回收站中的文件，且必须携带 `/delete/preflight` 返回的 `confirmToken`。
 pub async fn delete(
-    payload: web::Json<FilesQuery>,
+    payload: ValidJson<GuardedDeleteItem>,
     token: web::Data<AccessToken>,
+    env: web::Data<NetDiskEnv>,
+    cache: web::Data<ResponseCache>,
 ) -> Result<HttpResponse, Box<dyn Error>> {
-    let client = reqwest::Client::new();
-    let platform = PlatformConfig::default();
-    let api_url = format!("https://{}/api/v1/file/delete", platform.platform_domain());
+    debug!("尝试发送信息: {:?}", &payload);
+    let secret = load_delete_secret(&env)?;
+    let confirmed = payload
+        .confirm_token
+        .as_deref()
+        .map(|t| verify_delete_token(&secret, &token, &payload.file_ids, t))
+        .unwrap_or(false);
+    if !confirmed {
+        return Ok(HttpResponse::BadRequest().json(ApiResponse::<()> {
+            code: 400,
+            message: "缺少或无效的 confirmToken，请先调用 /delete/preflight".to_string(),
+            data: None,
+            x_trace_id: String::new(),
+        }));
+    }
 
-    let authorization_header = format!("Bearer {}", token.access_token);
+    // 令牌签发后文件状态可能变化，删除前重新预检
+    let preflight = preflight_delete(&token, &env, &payload.file_ids).await?;
+    if !preflight.rejected.is_empty() {
+        return Ok(
+            HttpResponse::BadRequest().json(DeletePreflightResponse::new(
+                400,
+                "存在不在回收站中的文件，拒绝彻底删除".to_string(),
+                preflight,
+                String::new(),
+            )),
+        );
+    }
+
+    delete_permanently(&token, &env, "delete", &preflight.file_list).await?;
+    cache.invalidate_files(&payload.file_ids);
+    Ok(HttpResponse::Ok().json(DeletePreflightResponse::new(
+        0,
+        "ok".to_string(),
+        preflight,
+        String::new(),
+    )))
+}
 
+pub async fn recover(
+    payload: ValidJson<FilesQuery>,
+    token: web::Data<AccessToken>,
+    cache: web::Data<ResponseCache>,
+) -> Result<HttpResponse, Box<dyn Error>> {
     debug!("尝试发送信息: {:?}", &payload);
+    let api_response: ApiResponse<()> = api_post(&token, "api/v1/file/recover", &payload).await?;
 
-    let response = client
-        .post(&api_url)
-        .header("Authorization", &authorization_header)
-        .header("Platform", platform.platform())
-        .json(&payload)
-        .send()
-        .await
-        .map_err(|e| format!("请求发送失败: {}", e))?;
-
-    let status = response.status();
-    if !status.is_success() {
-        let body = response.text().await.unwrap_or_default();
-        Err(format!("API请求失败，状态码: {}，响应: {}", status, body).into())
-    } else {
-        // 解析响应
-        let api_response: ApiResponse<()> = response
-            .json()
-            .await
-            .map_err(|e| format!("响应解析失败: {}", e))?;
-
-        debug!("响应内容: {:?}", &api_response);
-        Ok(HttpResponse::Ok().json(api_response))
+    debug!("响应内容: {:?}", &api_response);
+    if api_response.is_ok() {
+        cache.invalidate_files(&payload.file_ids);
     }
+    Ok(HttpResponse::Ok().json(api_response))
 }
@@ -1,58 +1,38 @@
+use super::file_api::list_query_params;
+use super::validated::ValidQuery;
+use crate::cache::{cached_get, cached_json, list_tags, CachePolicy, ResponseCache};
 use crate::responses::prelude::*;
-use actix_web::{get, post, web, HttpResponse, HttpServer, Responder};
-use log::{debug, error, info};
-use reqwest;
-use serde_json::Value;
-use std::collections::HashMap;
+use actix_web::{web, HttpRequest, HttpResponse};
 use std::error::Error;
+use tracing::debug;
 
-#[get("/file_search")]
 pub async fn file_search(
-    query: web::Query<FileSearchItem>,
+    req: HttpRequest,
+    query: ValidQuery<FileSearchItem>,
     token: web::Data<AccessToken>,
+    cache: web::Data<ResponseCache>,
 ) -> Result<HttpResponse, Box<dyn Error>> {
-    let client = reqwest::Client::new();
-    let platform = PlatformConfig::default();
-    let api_url = format!("https://{}/api/v2/file/list", platform.platform_domain());
+    let query_params = list_query_params(
+        query.parent_file_id as i64,
+        query.limit,
+        query.search_data.as_ref(),
+        query.search_mode.clone(),
+        query.last_file_id,
+    );
+    let parent_file_id = query.parent_file_id;
+    let (api_response, status) = cached_get(
+        &cache,
+        &token,
+        "api/v2/file/list",
+        &query_params,
+        CachePolicy::from_request(&req),
+        |response: &FileSearchResponse| match &response.data {
+            Some(data) => list_tags(parent_file_id, &data.file_list),
+            None => list_tags(parent_file_id, &[]),
+        },
+    )
+    .await?;
 
-    let authorization_header = format!("Bearer {}", token.access_token);
-
-    let mut query_params = Vec::new();
-    query_params.push(("parentFileId", query.parent_file_id.to_string()));
-    query_params.push(("limit", query.limit.to_string()));
-
-    // 动态添加可选参数
-    if let Some(search_data) = &query.search_data {
-        query_params.push(("searchData", search_data.clone()));
-    }
-    if let Some(search_mode) = &query.search_mode {
-        query_params.push(("searchMode", search_mode.to_string()));
-    }
-    if let Some(last_file_id) = &query.last_file_id {
-        query_params.push(("lastFileId", last_file_id.to_string()));
-    }
-
-    let response = client
-        .get(api_url)
-        .query(&query_params) // 使用包含所有参数的 Vec
-        .header("Content-Type", "application/json")
-        .header("Platform", platform.platform())
-        .header("Authorization", &authorization_header)
-        .send()
-        .await?;
-
-    let status = response.status();
-    if !status.is_success() {
-        let body = response.text().await.unwrap_or_default();
-        Err(format!("API请求失败，状态码: {}，响应: {}", status, body).into())
-    } else {
-        // 解析响应
-        let api_response: FileSearchResponse = response
-            .json()
-            .await
-            .map_err(|e| format!("响应解析失败: {}", e))?;
-
-        debug!("响应内容: {:?}", &api_response);
-        Ok(HttpResponse::Ok().json(api_response))
-    }
+    debug!("响应内容: {:?}", &api_response);
+    Ok(cached_json(&api_response, status))
 }
@@ -1,51 +1,71 @@
+use super::base_api::*;
+use super::validated::ValidJson;
+use crate::cache::ResponseCache;
 use crate::responses::prelude::*;
-use actix_web::{self, error, post, web, HttpResponse};
-use log::debug;
-use reqwest;
-#[actix_web::route("/file/move", method = "POST")]
+use actix_web::{self, error, web, HttpResponse};
+use std::error::Error;
+use tracing::debug;
 pub async fn move_file(
-    payload: web::Json<FileMoveInfo>,
+    payload: ValidJson<FileMoveInfo>,
     token: web::Data<AccessToken>,
+    cache: web::Data<ResponseCache>,
 ) -> Result<HttpResponse, actix_web::Error> {
-    debug!("目标 API URL = ");
-
-    let client = reqwest::Client::new();
-    let platform = PlatformConfig::default();
-    let api_url = format!("https://{}/api/v1/file/move", platform.platform_domain());
-
-    let authorization_header = format!("Bearer {}", token.access_token);
-
     debug!("尝试发送信息: {:?}", &payload);
-
-    let response = client
-        .post(&api_url)
-        .header("Authorization", &authorization_header)
-        .header("Platform", platform.platform())
-        .json(&payload)
-        .send()
+    let api_response: ApiResponse<()> = api_post(&token, "api/v1/file/move", &payload)
         .await
-        .map_err(|e| {
-            let error_message = format!("请求发送失败: {}", e);
-            error::ErrorInternalServerError(error_message)
-        })?;
-
-    let status = response.status();
-    if !status.is_success() {
-        let body = response.text().await.unwrap_or_default();
-        let error_message = format!("API请求失败，状态码: {}，响应: {}", status, body);
-        return Err(error::ErrorInternalServerError(error_message));
-    } else {
-        let api_response: ApiResponse<()> = response.json().await.map_err(|e| {
-            let error_message = format!("响应解析失败: {}", e);
-            error::ErrorInternalServerError(error_message)
-        })?;
+        .map_err(|e| error::ErrorInternalServerError(e.to_string()))?;
 
-        debug!("响应内容: {:?}", &api_response);
-        Ok(HttpResponse::Ok().json(api_response))
+    debug!("响应内容: {:?}", &api_response);
+    if api_response.is_ok() {
+        cache.invalidate_files(&payload.fileIDs);
+        cache.invalidate_folders(&[payload.toParentFileID]);
     }
+    Ok(HttpResponse::Ok().json(api_response))
 }
 // pub fn move_config(cfg: &mut web::ServiceConfig) {
 //     println!("✅ move_config 被调用，注册 /file/move");
 
 //     cfg.service(web::scope("/file").route("/move", web::post().to(move_file)));
 // }
+
+/// 单批移动文件，调用方负责保证 `file_ids` 不超过接口上限
+pub async fn move_files(
+    token: &AccessToken,
+    file_ids: &[u64],
+    to_parent_file_id: u64,
+) -> Result<(), Box<dyn Error>> {
+    let payload = FileMoveInfo {
+        fileIDs: file_ids.to_vec(),
+        toParentFileID: to_parent_file_id,
+    };
+    let api_response: ApiResponse<()> = api_post(token, "api/v1/file/move", &payload).await?;
+    if !api_response.is_ok() {
+        return Err(format!(
+            "移动失败，code: {}，信息: {}",
+            api_response.code, api_response.message
+        )
+        .into());
+    }
+    Ok(())
+}
+
+/// 修改单个文件的名称
+pub async fn rename_file(
+    token: &AccessToken,
+    file_id: u64,
+    file_name: &str,
+) -> Result<(), Box<dyn Error>> {
+    let payload = FileRenameItem {
+        file_id,
+        file_name: file_name.to_string(),
+    };
+    let api_response: ApiResponse<()> = api_put(token, "api/v1/file/name", &payload).await?;
+    if !api_response.is_ok() {
+        return Err(format!(
+            "重命名失败，code: {}，信息: {}",
+            api_response.code, api_response.message
+        )
+        .into());
+    }
+    Ok(())
+}
@@ -1,51 +1,171 @@
+use super::base_api::*;
+use super::validated::ValidJson;
+use crate::cache::ResponseCache;
+use crate::io_basic::checksum::{async_file_md5, md5_hex};
 use crate::responses::prelude::*;
-use actix_web::{self, get, post, web, HttpResponse, HttpServer, Responder};
-use log::{debug, error, info};
-use reqwest;
+use actix_web::{self, web, HttpResponse};
+use reqwest::{self, multipart};
 use std::error::Error;
+use std::path::Path;
+use std::time::Duration;
+use tokio::io::AsyncReadExt;
+use tracing::{debug, info};
+
+/// 上传完毕接口轮询的最大次数，每次间隔 1 秒
+const COMPLETE_POLL_LIMIT: u32 = 60;
+
+/// 本地文件上传结果
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UploadOutcome {
+    pub file_id: u64,
+    /// 是否命中秒传（未发送文件内容）
+    pub reused: bool,
+}
 
-#[post("/file/upload")]
 pub async fn file_upload(
-    payload: web::Json<UploadFileItem>,
+    payload: ValidJson<UploadFileItem>,
     token: web::Data<AccessToken>,
+    cache: web::Data<ResponseCache>,
 ) -> Result<HttpResponse, actix_web::Error> {
-    // **注意：理想情况下，client 和 platform 应该通过 web::Data 传入**
-    let client = reqwest::Client::new();
-    let platform = PlatformConfig::default();
+    debug!("尝试发送信息: {:?}", &payload);
+    let api_response: UploadFileResponse = api_post(&token, "upload/v2/file/create", &payload)
+        .await
+        .map_err(|e| actix_web::error::ErrorInternalServerError(e.to_string()))?;
+    if api_response.is_ok() {
+        cache.invalidate_folders(&[payload.parent_file_id]);
+    }
+    Ok(HttpResponse::Ok().json(api_response))
+}
 
-    let api_url = format!(
-        "https://{}/upload/v2/file/create",
-        platform.platform_domain()
-    );
+/// 创建文件；命中秒传时 `reuse` 为 true 并直接返回 `fileID`
+pub async fn create_file(
+    token: &AccessToken,
+    item: &UploadFileItem,
+) -> Result<UploadFileData, Box<dyn Error>> {
+    let api_response: UploadFileResponse = api_post(token, "upload/v2/file/create", item).await?;
+    api_response.into_data()
+}
+
+/// 按 `sliceSize` 依次上传本地文件的全部分片
+pub async fn upload_slices(
+    token: &AccessToken,
+    data: &UploadFileData,
+    path: &Path,
+) -> Result<(), Box<dyn Error>> {
+    let server = data.servers.first().ok_or("创建文件接口未返回上传域名")?;
+    let server = if server.starts_with("http") {
+        server.trim_end_matches('/').to_string()
+    } else {
+        format!("https://{}", server.trim_end_matches('/'))
+    };
+    let api_url = format!("{}/upload/v2/file/slice", server);
+    let slice_size = data.slice_size.max(1);
 
+    let client = reqwest::Client::new();
+    let platform = PlatformConfig::default();
     let authorization_header = format!("Bearer {}", token.access_token);
+    let mut file = tokio::fs::File::open(path).await?;
+    let mut slice_no = 1u64;
+    loop {
+        let mut buffer = Vec::new();
+        (&mut file)
+            .take(slice_size)
+            .read_to_end(&mut buffer)
+            .await?;
+        if buffer.is_empty() {
+            break;
+        }
 
-    debug!("尝试发送信息: {:?}", &payload);
-    let response = client
-        .post(&api_url)
-        .header("Authorization", &authorization_header)
-        .header("Platform", platform.platform())
-        .json(&payload)
-        .send()
-        .await
-        .map_err(|e| actix_web::error::ErrorInternalServerError(format!("请求发送失败: {}", e)))?;
+        let form = multipart::Form::new()
+            .text("preuploadID", data.preupload_id.clone())
+            .text("sliceNo", slice_no.to_string())
+            .text("sliceMD5", md5_hex(&buffer))
+            .part(
+                "slice",
+                multipart::Part::bytes(buffer).file_name(format!("slice{}", slice_no)),
+            );
+        debug!("上传分片 {} 到 {}", slice_no, &api_url);
+        let request = client
+            .post(&api_url)
+            .header("Authorization", &authorization_header)
+            .header("Platform", platform.platform())
+            .multipart(form);
+        let api_response: ApiResponse<()> =
+            api_send(request, "POST", "upload/v2/file/slice").await?;
+        if !api_response.is_ok() {
+            return Err(format!(
+                "分片 {} 上传失败，code: {}，信息: {}",
+                slice_no, api_response.code, api_response.message
+            )
+            .into());
+        }
+        slice_no += 1;
+    }
+    Ok(())
+}
 
-    let status = response.status();
+/// 通知分片上传完毕，服务端校验未完成时轮询等待，返回最终的 `fileID`
+pub async fn complete_upload(
+    token: &AccessToken,
+    preupload_id: &str,
+) -> Result<u64, Box<dyn Error>> {
+    let payload = UploadCompleteItem {
+        preupload_id: preupload_id.to_string(),
+    };
+    for _ in 0..COMPLETE_POLL_LIMIT {
+        let api_response: UploadCompleteResponse =
+            api_post(token, "upload/v2/file/upload_complete", &payload).await?;
+        let data = api_response.into_data()?;
+        if data.completed && data.file_id != 0 {
+            return Ok(data.file_id);
+        }
+        tokio::time::sleep(Duration::from_secs(1)).await;
+    }
+    Err(format!("等待上传完毕超时，preuploadID: {}", preupload_id).into())
+}
 
-    if !status.is_success() {
-        let body = response
-            .text()
-            .await
-            .unwrap_or_else(|e| format!("无法读取失败响应体: {}", e)); // 确保总是有一个字符串
+/// 上传本地文件：先按 MD5 尝试秒传，未命中时分片上传
+pub async fn upload_local_file(
+    token: &AccessToken,
+    path: &Path,
+    parent_file_id: u64,
+    filename: &str,
+    duplicate: Option<u8>,
+) -> Result<UploadOutcome, Box<dyn Error>> {
+    let size = tokio::fs::metadata(path).await?.len();
+    let etag = async_file_md5(path).await?;
+    let item = UploadFileItem {
+        parent_file_id,
+        filename: filename.to_string(),
+        etag,
+        size,
+        duplicate,
+        contain_dir: None,
+    };
+    upload_with_item(token, &item, path).await
+}
 
-        Err(actix_web::error::ErrorInternalServerError(format!(
-            "API请求失败，状态码: {}，响应: {}",
-            status, body
-        )))
-    } else {
-        let api_response: UploadFileResponse = response.json().await.map_err(|e| {
-            actix_web::error::ErrorInternalServerError(format!("响应解析失败: {}", e))
-        })?;
-        Ok(HttpResponse::Ok().json(api_response))
+/// 使用已知 etag 与大小的 `item` 上传本地文件 `path`
+pub async fn upload_with_item(
+    token: &AccessToken,
+    item: &UploadFileItem,
+    path: &Path,
+) -> Result<UploadOutcome, Box<dyn Error>> {
+    let data = create_file(token, item).await?;
+    if data.reuse {
+        let file_id = data.file_id.ok_or("秒传成功但未返回 fileID")?;
+        info!("秒传成功: {} -> {}", item.filename, file_id);
+        return Ok(UploadOutcome {
+            file_id,
+            reused: true,
+        });
     }
+
+    upload_slices(token, &data, path).await?;
+    let file_id = complete_upload(token, &data.preupload_id).await?;
+    info!("分片上传完成: {} -> {}", item.filename, file_id);
+    Ok(UploadOutcome {
+        file_id,
+        reused: false,
+    })
 }
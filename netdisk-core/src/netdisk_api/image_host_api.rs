@@ -0,0 +1,83 @@
+use super::base_api::error_response;
+use super::validated::ValidQuery;
+use crate::cache::ResponseCache;
+use crate::image_host::{host_image, prepare_image};
+use crate::responses::prelude::*;
+use actix_multipart::Multipart;
+use actix_web::http::StatusCode;
+use actix_web::{web, HttpResponse};
+use futures_util::StreamExt;
+use std::error::Error;
+use tracing::debug;
+
+/// 读取表单中的第一个文件字段，失败时返回对应的状态码：超过 `max_bytes` 为 413，表单格式错误为 400
+async fn read_image_field(
+    payload: &mut Multipart,
+    max_bytes: u64,
+) -> Result<Option<(Vec<u8>, Option<String>)>, (StatusCode, String)> {
+    let bad_form = |e: actix_multipart::MultipartError| {
+        (StatusCode::BAD_REQUEST, format!("表单格式错误: {}", e))
+    };
+    while let Some(field) = payload.next().await {
+        let mut field = field.map_err(bad_form)?;
+        let filename = match field.content_disposition() {
+            Some(disposition) if disposition.get_filename().is_some() => {
+                disposition.get_filename().map(str::to_string)
+            }
+            _ => continue,
+        };
+        let mut data = Vec::new();
+        while let Some(chunk) = field.next().await {
+            data.extend_from_slice(&chunk.map_err(bad_form)?);
+            if data.len() as u64 > max_bytes {
+                return Err((
+                    StatusCode::PAYLOAD_TOO_LARGE,
+                    format!("图片超过大小上限 {} 字节", max_bytes),
+                ));
+            }
+        }
+        return Ok(Some((data, filename)));
+    }
+    Ok(None)
+}
+
+/// # 图床上传
+///
+/// 接收 `multipart/form-data` 中的第一个文件，按文件头校验格式，放入按日期划分的目录，
+/// 返回链接与 Markdown/HTML 片段。
+pub async fn image_upload(
+    mut payload: Multipart,
+    query: ValidQuery<ImageUploadQuery>,
+    token: web::Data<AccessToken>,
+    gateway: web::Data<GatewayConfig>,
+    cache: web::Data<ResponseCache>,
+) -> Result<HttpResponse, Box<dyn Error>> {
+    let config = &gateway.image_host;
+    let (data, filename) = match read_image_field(&mut payload, config.max_bytes).await {
+        Ok(Some(field)) => field,
+        Ok(None) => {
+            return Ok(error_response(
+                StatusCode::BAD_REQUEST,
+                "表单中没有文件字段",
+            ))
+        }
+        Err((status, message)) => return Ok(error_response(status, message)),
+    };
+    debug!("图床上传: {:?}, {} 字节", filename, data.len());
+    let max_width = query.max_width.unwrap_or(config.max_width);
+    let image = match prepare_image(data, config.max_bytes, max_width, config.max_pixels) {
+        Ok(image) => image,
+        Err(message) => return Ok(error_response(StatusCode::BAD_REQUEST, message)),
+    };
+    let name = query.name.as_deref().or(filename.as_deref());
+    let result = host_image(&token, &gateway, image, name).await?;
+    // 日期目录可能是新建的，其上级目录的列表依靠有效期过期
+    cache.invalidate_folders(&[result.parent_file_id]);
+    cache.invalidate_files(&[result.file_id]);
+    Ok(HttpResponse::Ok().json(ImageHostResponse::new(
+        0,
+        "ok".to_string(),
+        result,
+        String::new(),
+    )))
+}
@@ -1,4 +1,5 @@
 pub mod limit_deserializer {
+    use serde::de::Error;
     use serde::{Deserialize, Deserializer};
 
     // 定义最大限制常量
@@ -12,16 +13,41 @@ pub mod limit_deserializer {
         T: Deserialize<'de>,
     {
         // 尝试反序列化完整的 Vec
-        let mut vec_data = Vec::<T>::deserialize(deserializer)?;
+        let vec_data = Vec::<T>::deserialize(deserializer)?;
 
-        // 检查长度并截断
+        // 超过上限时直接拒绝，不再静默截断
         if vec_data.len() > MAX_LIMIT {
-            // 注意：这里我们使用 .truncate() 进行切片
-            vec_data.truncate(MAX_LIMIT);
-            // 可以在此处添加日志警告
-            // log::warn!("List was automatically truncated to {} elements.", MAX_LIMIT);
+            return Err(D::Error::custom(format!(
+                "列表长度 {} 超过上限 {}；批量操作请使用 /batch 接口",
+                vec_data.len(),
+                MAX_LIMIT
+            )));
         }
 
         Ok(vec_data)
     }
 }
+
+/// 简单的请求限速器：保证相邻两次调用之间至少间隔 `interval`
+#[derive(Debug)]
+pub struct RateLimiter {
+    interval: std::time::Duration,
+    last_call: Option<tokio::time::Instant>,
+}
+
+impl RateLimiter {
+    pub fn new(interval: std::time::Duration) -> Self {
+        RateLimiter {
+            interval,
+            last_call: None,
+        }
+    }
+
+    /// 等待到允许发起下一次请求
+    pub async fn wait(&mut self) {
+        if let Some(last_call) = self.last_call {
+            tokio::time::sleep_until(last_call + self.interval).await;
+        }
+        self.last_call = Some(tokio::time::Instant::now());
+    }
+}
@@ -0,0 +1,152 @@
+use super::base_api::*;
+use super::batch_api::limiter_for;
+use super::validated::{ValidJson, ValidQuery};
+use crate::local_db::open_local_db;
+use crate::netdisk_auth::basic_env::NetDiskEnv;
+use crate::offline::{load_tasks, poll_once, submit_links};
+use crate::responses::prelude::*;
+use actix_web::http::StatusCode;
+use actix_web::{web, HttpResponse};
+use serde_json::json;
+use std::error::Error;
+use tracing::debug;
+
+/// 创建离线下载任务，返回 taskID
+pub async fn create_offline_task(
+    token: &AccessToken,
+    item: &OfflineDownloadItem,
+) -> Result<u64, Box<dyn Error>> {
+    let api_response: OfflineTaskResponse =
+        api_post(token, "api/v1/offline/download", item).await?;
+    Ok(api_response.into_data()?.task_id)
+}
+
+/// 查询离线下载任务的进度
+pub async fn offline_task_process(
+    token: &AccessToken,
+    task_id: u64,
+) -> Result<OfflineProcessData, Box<dyn Error>> {
+    let api_response: OfflineProcessResponse = api_get(
+        token,
+        "api/v1/offline/download/process",
+        &json!({ "taskID": task_id }),
+    )
+    .await?;
+    api_response.into_data()
+}
+
+/// # 创建单个离线下载任务
+///
+/// 直接下载到 `dirID`；链接被跳过或提交失败时返回错误码与原因。
+pub async fn offline_download(
+    payload: ValidJson<OfflineDownloadItem>,
+    token: web::Data<AccessToken>,
+    env: web::Data<NetDiskEnv>,
+) -> Result<HttpResponse, Box<dyn Error>> {
+    debug!("尝试发送信息: {:?}", &payload);
+    let payload = payload.into_inner();
+    let link = OfflineLink {
+        url: payload.url,
+        file_name: payload.file_name,
+        source: None,
+    };
+    let mut limiter = limiter_for(Some(0));
+    let report = submit_links(
+        &token,
+        &env,
+        vec![link],
+        payload.dir_id,
+        false,
+        &mut limiter,
+    )
+    .await?;
+    if report.submitted.is_empty() {
+        let (status, reason) = match (report.skipped.first(), report.failed.first()) {
+            (Some(skipped), _) => (StatusCode::BAD_REQUEST, skipped.reason.clone()),
+            (None, Some(failed)) => (StatusCode::BAD_GATEWAY, failed.reason.clone()),
+            (None, None) => (StatusCode::BAD_GATEWAY, "提交失败".to_string()),
+        };
+        return Ok(HttpResponse::build(status).json(OfflineBulkResponse::new(
+            status.as_u16() as i32,
+            reason,
+            report,
+            String::new(),
+        )));
+    }
+    Ok(HttpResponse::Ok().json(OfflineBulkResponse::new(
+        0,
+        "ok".to_string(),
+        report,
+        String::new(),
+    )))
+}
+
+/// # 批量提交离线下载任务，重复的链接会被跳过
+pub async fn offline_bulk(
+    payload: ValidJson<OfflineBulkItem>,
+    token: web::Data<AccessToken>,
+    env: web::Data<NetDiskEnv>,
+) -> Result<HttpResponse, Box<dyn Error>> {
+    debug!("尝试发送信息: {:?}", &payload);
+    let payload = payload.into_inner();
+    let links = payload
+        .urls
+        .into_iter()
+        .map(|url| OfflineLink {
+            url: url.trim().to_string(),
+            file_name: None,
+            source: None,
+        })
+        .collect();
+    let mut limiter = limiter_for(payload.interval_ms);
+    let report = submit_links(&token, &env, links, payload.dir_id, true, &mut limiter).await?;
+    Ok(HttpResponse::Ok().json(OfflineBulkResponse::new(
+        0,
+        "ok".to_string(),
+        report,
+        String::new(),
+    )))
+}
+
+/// # 查询离线下载进度（直接转发上游结果）
+pub async fn offline_process(
+    query: ValidQuery<OfflineProcessQuery>,
+    token: web::Data<AccessToken>,
+) -> Result<HttpResponse, Box<dyn Error>> {
+    let api_response: OfflineProcessResponse = api_get(
+        &token,
+        "api/v1/offline/download/process",
+        &json!({ "taskID": query.task_id }),
+    )
+    .await?;
+    Ok(HttpResponse::Ok().json(api_response))
+}
+
+/// # 本地记录的离线下载任务
+pub async fn offline_tasks(
+    query: ValidQuery<OfflineTasksQuery>,
+    env: web::Data<NetDiskEnv>,
+) -> Result<HttpResponse, Box<dyn Error>> {
+    let conn = open_local_db(&env)?;
+    let tasks = load_tasks(&conn, query.pending)?;
+    Ok(HttpResponse::Ok().json(OfflineTasksResponse::new(
+        0,
+        "ok".to_string(),
+        tasks,
+        String::new(),
+    )))
+}
+
+/// # 立即轮询一次未结束的任务，返回状态有变化的任务
+pub async fn offline_poll(
+    token: web::Data<AccessToken>,
+    env: web::Data<NetDiskEnv>,
+) -> Result<HttpResponse, Box<dyn Error>> {
+    let changed = poll_once(&token, &env).await?;
+    Ok(HttpResponse::Ok().json(OfflineTasksResponse::new(
+        0,
+        "ok".to_string(),
+        changed,
+        String::new(),
+    )))
+}
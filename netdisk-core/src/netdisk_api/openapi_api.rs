@@ -0,0 +1,17 @@
+use crate::openapi::gateway_openapi;
+use actix_web::HttpResponse;
+
+/// Swagger UI 页面，脚本与样式从 CDN 加载
+const DOCS_HTML: &str = include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/static/docs.html"));
+
+/// # OpenAPI 3 文档
+pub async fn openapi_json() -> HttpResponse {
+    HttpResponse::Ok().json(gateway_openapi())
+}
+
+/// # 交互式接口文档
+pub async fn docs() -> HttpResponse {
+    HttpResponse::Ok()
+        .content_type("text/html; charset=utf-8")
+        .body(DOCS_HTML)
+}
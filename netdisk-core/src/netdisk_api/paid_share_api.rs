@@ -0,0 +1,193 @@
+use super::base_api::*;
+use super::batch_api::{limiter_for, report_response, run_batched};
+use super::share_manage_api::{parse_file_id_list, record_share};
+use super::validated::{ValidJson, ValidQuery};
+use crate::netdisk_auth::basic_env::NetDiskEnv;
+use crate::responses::prelude::*;
+use actix_web::{web, HttpResponse};
+use chrono::Utc;
+use std::error::Error;
+use tracing::{debug, warn};
+
+/// 付费分享列表接口单页的最大数量
+const PAID_SHARE_PAGE_LIMIT: u8 = 100;
+
+/// 按 `lastShareId` 翻页拉取全部付费分享
+pub async fn list_all_paid_shares(
+    token: &AccessToken,
+) -> Result<Vec<PayShareItem>, Box<dyn Error>> {
+    let mut shares = Vec::new();
+    let mut last_share_id: Option<i64> = None;
+    loop {
+        let mut query = vec![("limit", PAID_SHARE_PAGE_LIMIT.to_string())];
+        if let Some(last_share_id) = last_share_id {
+            query.push(("lastShareId", last_share_id.to_string()));
+        }
+        let api_response: PayShareDataResponse =
+            api_get(token, "api/v1/share/payment/list", &query).await?;
+        let data = api_response.into_data()?;
+        let page = data.share_list.unwrap_or_default();
+        let done = data.last_share_id == -1 || page.is_empty();
+        shares.extend(page);
+        if done {
+            return Ok(shares);
+        }
+        last_share_id = Some(data.last_share_id);
+    }
+}
+
+/// 修改付费分享的流量包设置，调用方负责保证 `shareIdList` 不超过接口上限
+pub async fn update_paid_share_traffic(
+    token: &AccessToken,
+    item: &ShareLinkItem,
+) -> Result<(), Box<dyn Error>> {
+    let api_response: ApiResponse<()> =
+        api_put(token, "api/v1/share/list/payment/info", item).await?;
+    if !api_response.is_ok() {
+        return Err(format!(
+            "修改付费分享失败，code: {}，信息: {}",
+            api_response.code, api_response.message
+        )
+        .into());
+    }
+    Ok(())
+}
+
+/// 汇总付费分享的收益，按收益降序排列
+pub fn paid_share_earnings(shares: &[PayShareItem]) -> PaidShareEarnings {
+    let mut summaries: Vec<PaidShareSummary> = shares
+        .iter()
+        .map(|share| PaidShareSummary {
+            share_id: share.share_id as u64,
+            share_key: share.share_key.clone(),
+            share_name: share.share_name.clone(),
+            url: share_url(&share.share_key),
+            pay_amount: share.pay_amount,
+            amount: i64::from(share.amount),
+            download_count: share.download_count,
+            save_count: share.save_count,
+            expired: share.expired != 0,
+        })
+        .collect();
+    summaries.sort_by(|a, b| b.amount.cmp(&a.amount).then(a.share_id.cmp(&b.share_id)));
+    PaidShareEarnings {
+        total_amount: summaries.iter().map(|share| share.amount).sum(),
+        share_count: summaries.len(),
+        active_count: summaries.iter().filter(|share| !share.expired).count(),
+        shares: summaries,
+    }
+}
+
+/// # 创建付费分享链接
+///
+/// 先校验价格、打赏模式与描述长度，成功后同样记录分享包含的文件。
+pub async fn pay_link(
+    payload: ValidJson<PayLinkItem>,
+    token: web::Data<AccessToken>,
+    env: web::Data<NetDiskEnv>,
+) -> Result<HttpResponse, Box<dyn Error>> {
+    debug!("尝试发送信息:{:?}", &payload);
+    let api_response: SharedDataResponse =
+        api_post(&token, "api/v1/share/content-payment/create", &payload).await?;
+    if let Some(data) = api_response.data.as_ref().filter(|_| api_response.is_ok()) {
+        let record = ShareRecord {
+            share_id: data.share_id,
+            share_key: data.share_key.clone(),
+            share_name: payload.share_name.clone(),
+            file_ids: parse_file_id_list(&payload.file_id_list),
+            share_pwd: None,
+            // 付费分享没有有效期
+            share_expire: ShareExpireDays::Permanent,
+            created_at: Utc::now(),
+            recreated_from: None,
+        };
+        if let Err(e) = record_share(&env, record).await {
+            warn!("保存分享记录失败: {}", e);
+        }
+    }
+    Ok(HttpResponse::Ok().json(api_response))
+}
+
+/// # 获取付费分享链接列表（单页）
+pub async fn payment_list(
+    query: ValidQuery<ShareQuery>,
+    token: web::Data<AccessToken>,
+) -> Result<HttpResponse, Box<dyn Error>> {
+    debug!("尝试发送信息:{:?}", &query);
+    let api_response: PayShareDataResponse =
+        api_get(&token, "api/v1/share/payment/list", &query.into_inner()).await?;
+    Ok(HttpResponse::Ok().json(api_response))
+}
+
+/// # 获取全部付费分享（自动翻页）
+pub async fn payment_all(token: web::Data<AccessToken>) -> Result<HttpResponse, Box<dyn Error>> {
+    let shares = list_all_paid_shares(&token).await?;
+    Ok(HttpResponse::Ok().json(PayShareItemsResponse::new(
+        0,
+        "ok".to_string(),
+        shares,
+        String::new(),
+    )))
+}
+
+/// # 修改付费分享文件信息
+pub async fn change_share_list_info(
+    payload: ValidJson<ShareLinkItem>,
+    token: web::Data<AccessToken>,
+) -> Result<HttpResponse, Box<dyn Error>> {
+    debug!("尝试发送信息:{:?}", &payload);
+    let api_response: ApiResponse<()> =
+        api_put(&token, "api/v1/share/list/payment/info", &payload).await?;
+    Ok(HttpResponse::Ok().json(api_response))
+}
+
+/// 未失效的付费分享；付费分享没有有效期，只看 `expired` 标记
+pub fn active_paid_share_ids(shares: &[PayShareItem]) -> Vec<u64> {
+    shares
+        .iter()
+        .filter(|share| share.expired == 0)
+        .map(|share| share.share_id as u64)
+        .collect()
+}
+
+/// # 批量修改付费分享的流量包设置
+///
+/// `shareIdList` 为空时修改全部未失效的付费分享。
+pub async fn payment_traffic(
+    payload: ValidJson<ShareTrafficItem>,
+    token: web::Data<AccessToken>,
+) -> Result<HttpResponse, Box<dyn Error>> {
+    debug!("尝试发送信息:{:?}", &payload);
+    let share_ids = if payload.share_id_list.is_empty() {
+        active_paid_share_ids(&list_all_paid_shares(&token).await?)
+    } else {
+        payload.share_id_list.clone()
+    };
+    let mut limiter = limiter_for(payload.interval_ms);
+    let token = token.get_ref();
+    let payload = payload.into_inner();
+    let report = run_batched(&share_ids, &mut limiter, |ids| {
+        let item = ShareLinkItem {
+            share_id_list: ids,
+            traffic_switch: payload.traffic_switch,
+            traffic_limit_switch: payload.traffic_limit_switch,
+            traffic_limit: payload.traffic_limit,
+        };
+        async move { update_paid_share_traffic(token, &item).await }
+    })
+    .await;
+    Ok(report_response(report))
+}
+
+/// # 付费分享收益汇总
+pub async fn payment_earnings(
+    token: web::Data<AccessToken>,
+) -> Result<HttpResponse, Box<dyn Error>> {
+    let shares = list_all_paid_shares(&token).await?;
+    Ok(HttpResponse::Ok().json(PaidShareEarningsResponse::new(
+        0,
+        "ok".to_string(),
+        paid_share_earnings(&shares),
+        String::new(),
+    )))
+}
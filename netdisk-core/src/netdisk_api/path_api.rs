@@ -0,0 +1,104 @@
+use super::file_api::{list_folder_all, split_dir_path};
+use crate::responses::prelude::*;
+use chrono::{DateTime, Local};
+use std::error::Error;
+
+/// 按路径解析到的远端条目，根目录没有对应的 `FileItem`
+#[derive(Debug, Clone)]
+pub enum RemoteNode {
+    Root,
+    Item(FileItem),
+}
+
+impl RemoteNode {
+    pub fn file_id(&self) -> u64 {
+        match self {
+            RemoteNode::Root => 0,
+            RemoteNode::Item(item) => item.file_id as u64,
+        }
+    }
+
+    pub fn is_dir(&self) -> bool {
+        match self {
+            RemoteNode::Root => true,
+            RemoteNode::Item(item) => item.r#type == 1,
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        match self {
+            RemoteNode::Root => "",
+            RemoteNode::Item(item) => &item.filename,
+        }
+    }
+
+    pub fn size(&self) -> u64 {
+        match self {
+            RemoteNode::Root => 0,
+            RemoteNode::Item(item) => item.size,
+        }
+    }
+
+    pub fn etag(&self) -> &str {
+        match self {
+            RemoteNode::Root => "",
+            RemoteNode::Item(item) => &item.etag,
+        }
+    }
+
+    pub fn update_at(&self) -> Option<DateTime<Local>> {
+        match self {
+            RemoteNode::Root => None,
+            RemoteNode::Item(item) => Some(item.update_at),
+        }
+    }
+}
+
+/// 列出目录下未删除的条目
+pub async fn list_dir(token: &AccessToken, dir_id: u64) -> Result<Vec<FileItem>, Box<dyn Error>> {
+    let mut items = list_folder_all(token, dir_id).await?;
+    items.retain(|item| item.trashed == 0);
+    Ok(items)
+}
+
+/// 从 `root_id` 开始逐级查找 `a/b/c` 形式的路径，路径为空或不存在时返回 `None`
+pub async fn find_entry(
+    token: &AccessToken,
+    root_id: u64,
+    path: &str,
+) -> Result<Option<FileItem>, Box<dyn Error>> {
+    let mut found: Option<FileItem> = None;
+    let mut dir_id = root_id;
+    for name in split_dir_path(path) {
+        if found.as_ref().map_or(false, |item| item.r#type != 1) {
+            return Ok(None);
+        }
+        found = list_dir(token, dir_id)
+            .await?
+            .into_iter()
+            .find(|item| item.filename == name);
+        match &found {
+            Some(item) => dir_id = item.file_id as u64,
+            None => return Ok(None),
+        }
+    }
+    Ok(found)
+}
+
+/// 将 `/a/b/c` 形式的路径逐级解析为远端条目，不存在时返回 `None`
+pub async fn resolve_path(
+    token: &AccessToken,
+    path: &str,
+) -> Result<Option<RemoteNode>, Box<dyn Error>> {
+    if split_dir_path(path).is_empty() {
+        return Ok(Some(RemoteNode::Root));
+    }
+    Ok(find_entry(token, 0, path).await?.map(RemoteNode::Item))
+}
+
+/// 拆分为上级目录路径与最后一级名称，`/a/b/c` 返回 `("a/b", "c")`
+pub fn split_parent(path: &str) -> Option<(String, &str)> {
+    let mut names = split_dir_path(path);
+    let name = names.pop()?;
+    Some((names.join("/"), name))
+}
@@ -1,10 +1,29 @@
 pub use super::auth_api::*;
 pub use super::base_api::*;
+pub use super::batch_api::*;
+pub use super::copy_api::*;
+pub use super::direct_link_api::*;
 pub use super::file_api::*;
 pub use super::file_delete_api::*;
 pub use super::file_list_api::*;
 pub use super::file_move_api::*;
+pub use super::image_host_api::*;
 pub use super::limit::*;
+pub use super::offline_api::*;
+pub use super::openapi_api::*;
+pub use super::paid_share_api::*;
+pub use super::path_api::*;
+pub use super::recycle_bin_api::*;
+pub use super::s3_api::*;
+pub use super::s3_auth::*;
 pub use super::share_file_api::*;
+pub use super::share_manage_api::*;
+pub use super::share_stats_api::*;
+pub use super::static_api::*;
+pub use super::sync_api::*;
 pub use super::user_info_api::*;
 pub use super::file_upload_api::*;
+pub use super::validated::*;
+pub use super::video_api::*;
+pub use super::web_upload_api::*;
+pub use super::webdav_api::*;
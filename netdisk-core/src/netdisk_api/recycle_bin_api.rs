@@ -0,0 +1,141 @@
+use super::file_api::list_folder_all;
+use super::file_delete_api::{
+    delete_permanently, load_delete_secret, preflight_delete, verify_delete_token,
+};
+use super::validated::{ValidJson, ValidQuery};
+use crate::cache::ResponseCache;
+use crate::netdisk_auth::basic_env::NetDiskEnv;
+use crate::responses::prelude::*;
+use actix_web::{web, HttpResponse};
+use chrono::{Duration, Local};
+use std::collections::VecDeque;
+use std::error::Error;
+use tracing::{debug, info};
+
+/// 从 `parent_file_id` 开始收集回收站中的条目；`recursive` 为 true 时继续进入未删除的子目录
+pub async fn collect_trashed(
+    token: &AccessToken,
+    parent_file_id: u64,
+    recursive: bool,
+) -> Result<Vec<FileItem>, Box<dyn Error>> {
+    let mut trashed = Vec::new();
+    let mut pending = VecDeque::from([parent_file_id]);
+    while let Some(dir_id) = pending.pop_front() {
+        for item in list_folder_all(token, dir_id).await? {
+            if item.trashed == 1 {
+                trashed.push(item);
+            } else if recursive && item.r#type == 1 {
+                pending.push_back(item.file_id as u64);
+            }
+        }
+    }
+    Ok(trashed)
+}
+
+/// 判断回收站条目是否满足清理条件
+pub fn matches_purge_filter(item: &FileItem, filter: &PurgeItem) -> bool {
+    if let Some(days) = filter.older_than_days {
+        if Local::now() - item.update_at < Duration::days(i64::from(days)) {
+            return false;
+        }
+    }
+    if let Some(pattern) = &filter.filter {
+        if !item.filename.contains(pattern.as_str()) {
+            return false;
+        }
+    }
+    true
+}
+
+/// # 获取回收站中的文件列表
+pub async fn trash_list(
+    query: ValidQuery<TrashQuery>,
+    token: web::Data<AccessToken>,
+) -> Result<HttpResponse, Box<dyn Error>> {
+    debug!("尝试发送信息:{:?}", &query);
+    let file_list = collect_trashed(&token, query.parent_file_id, query.recursive).await?;
+    let api_response = TrashListResponse::new(
+        0,
+        "ok".to_string(),
+        TrashListData { file_list },
+        String::new(),
+    );
+    Ok(HttpResponse::Ok().json(api_response))
+}
+
+/// # 彻底清理回收站
+///
+/// 默认 `dryRun` 为 true，只预检符合条件的条目并返回 `confirmToken`；确认无误后携带该令牌
+/// 并传入 `"dryRun": false` 才会真正删除。期间符合条件的条目有变化时令牌失效，需重新预览。
+pub async fn purge(
+    payload: ValidJson<PurgeItem>,
+    token: web::Data<AccessToken>,
+    env: web::Data<NetDiskEnv>,
+    cache: web::Data<ResponseCache>,
+) -> Result<HttpResponse, Box<dyn Error>> {
+    debug!("尝试发送信息:{:?}", &payload);
+    let file_list: Vec<FileItem> =
+        collect_trashed(&token, payload.parent_file_id, payload.recursive)
+            .await?
+            .into_iter()
+            .filter(|item| matches_purge_filter(item, &payload))
+            .collect();
+    let file_ids: Vec<u64> = file_list.iter().map(|item| item.file_id as u64).collect();
+
+    if !payload.dry_run && !file_ids.is_empty() {
+        let secret = load_delete_secret(&env)?;
+        let confirmed = payload
+            .confirm_token
+            .as_deref()
+            .map(|t| verify_delete_token(&secret, &token, &file_ids, t))
+            .unwrap_or(false);
+        if !confirmed {
+            return Ok(HttpResponse::BadRequest().json(ApiResponse::<()> {
+                code: 400,
+                message: "缺少或无效的 confirmToken，请先以 dryRun 预览".to_string(),
+                data: None,
+                x_trace_id: String::new(),
+            }));
+        }
+    }
+
+    // 与 /delete 相同，只处理预检时仍在回收站中的条目
+    let preflight = if file_ids.is_empty() {
+        None
+    } else {
+        Some(preflight_delete(&token, &env, &file_ids).await?)
+    };
+    let (count, total_size) = preflight
+        .as_ref()
+        .map(|p| (p.count, p.total_size))
+        .unwrap_or((0, 0));
+    let confirm_token = match &preflight {
+        Some(preflight) if !payload.dry_run => {
+            delete_permanently(&token, &env, "purge", &preflight.file_list).await?;
+            let deleted: Vec<u64> = preflight
+                .file_list
+                .iter()
+                .map(|item| item.file_id)
+                .collect();
+            cache.invalidate_files(&deleted);
+            info!("已彻底删除回收站中的 {} 个条目", preflight.count);
+            None
+        }
+        Some(preflight) => preflight.confirm_token.clone(),
+        None => None,
+    };
+
+    let report = PurgeReport {
+        dry_run: payload.dry_run,
+        count,
+        total_size,
+        file_list,
+        confirm_token,
+    };
+    Ok(HttpResponse::Ok().json(PurgeResponse::new(
+        0,
+        "ok".to_string(),
+        report,
+        String::new(),
+    )))
+}
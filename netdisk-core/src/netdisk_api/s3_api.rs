@@ -0,0 +1,874 @@
+use super::file_api::ensure_dir_path;
+use super::file_delete_api::trash_files;
+use super::file_upload_api::upload_with_item;
+use super::path_api::{find_entry, list_dir};
+use super::s3_auth::*;
+use super::webdav_api::{proxy_download, xml_escape};
+use crate::cache::ResponseCache;
+use crate::io_basic::checksum::async_file_md5;
+use crate::netdisk_auth::basic_env::NetDiskEnv;
+use crate::responses::prelude::*;
+use actix_web::http::{header, StatusCode};
+use actix_web::{web, HttpRequest, HttpResponse};
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use chrono::{DateTime, Utc};
+use futures_util::StreamExt;
+use md5::Md5;
+use percent_encoding::percent_decode_str;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, HashMap};
+use std::error::Error;
+use std::fmt;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+use tokio::io::AsyncWriteExt;
+use tracing::{info, warn};
+
+/// 分片上传的暂存目录，位于配置目录下
+pub const S3_MULTIPART_DIR: &str = "s3-multipart";
+const DEFAULT_MAX_KEYS: usize = 1000;
+/// CompleteMultipartUpload 等 XML 请求体的大小上限
+const MAX_XML_BODY: usize = 1024 * 1024;
+const S3_XMLNS: &str = "http://s3.amazonaws.com/doc/2006-03-01/";
+const EMPTY_MD5: &str = "d41d8cd98f00b204e9800998ecf8427e";
+
+/// 以 S3 XML 错误格式返回给客户端的错误
+#[derive(Debug)]
+pub struct S3Error {
+    pub status: StatusCode,
+    pub code: &'static str,
+    pub message: String,
+}
+
+impl S3Error {
+    pub fn new(status: StatusCode, code: &'static str, message: impl Into<String>) -> Self {
+        S3Error {
+            status,
+            code,
+            message: message.into(),
+        }
+    }
+
+    pub fn response(&self, resource: &str) -> HttpResponse {
+        HttpResponse::build(self.status)
+            .content_type("application/xml")
+            .body(format!(
+                "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<Error><Code>{}</Code><Message>{}</Message><Resource>{}</Resource></Error>",
+                self.code,
+                xml_escape(&self.message),
+                xml_escape(resource)
+            ))
+    }
+}
+
+impl fmt::Display for S3Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.code, self.message)
+    }
+}
+
+impl Error for S3Error {}
+
+impl From<SigV4Error> for S3Error {
+    fn from(e: SigV4Error) -> Self {
+        let status = match e.code {
+            "AuthorizationHeaderMalformed" | "InvalidRequest" => StatusCode::BAD_REQUEST,
+            "NotImplemented" => StatusCode::NOT_IMPLEMENTED,
+            _ => StatusCode::FORBIDDEN,
+        };
+        S3Error::new(status, e.code, e.message)
+    }
+}
+
+/// 列表中的单个对象；`is_dir` 为 true 的条目只会以 CommonPrefixes 的形式出现
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct S3Object {
+    pub key: String,
+    pub size: u64,
+    pub etag: String,
+    pub last_modified: DateTime<Utc>,
+    pub is_dir: bool,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ListPage {
+    pub contents: Vec<S3Object>,
+    pub common_prefixes: Vec<String>,
+    pub is_truncated: bool,
+    /// 本页最后一个 key 或公共前缀，用于生成下一页的标记
+    pub next_marker: Option<String>,
+}
+
+/// 按 S3 语义对候选对象过滤、合并公共前缀并分页
+pub fn paginate_objects(
+    entries: Vec<S3Object>,
+    prefix: &str,
+    delimiter: Option<&str>,
+    start_after: Option<&str>,
+    max_keys: usize,
+) -> ListPage {
+    let delimiter = delimiter.filter(|d| !d.is_empty());
+    // None 表示公共前缀
+    let mut items: BTreeMap<String, Option<S3Object>> = BTreeMap::new();
+    for entry in entries {
+        if !entry.key.starts_with(prefix) {
+            continue;
+        }
+        if let Some(delimiter) = delimiter {
+            if let Some(i) = entry.key[prefix.len()..].find(delimiter) {
+                let common = entry.key[..prefix.len() + i + delimiter.len()].to_string();
+                items.entry(common).or_insert(None);
+                continue;
+            }
+        }
+        if !entry.is_dir {
+            items.insert(entry.key.clone(), Some(entry));
+        }
+    }
+
+    let mut page = ListPage::default();
+    let mut remaining = items
+        .into_iter()
+        .filter(|(key, _)| start_after.map_or(true, |after| key.as_str() > after));
+    for (key, item) in remaining.by_ref().take(max_keys) {
+        page.next_marker = Some(key.clone());
+        match item {
+            Some(object) => page.contents.push(object),
+            None => page.common_prefixes.push(key),
+        }
+    }
+    page.is_truncated = remaining.next().is_some();
+    page
+}
+
+/// 从 CompleteMultipartUpload 请求体中取出 `(PartNumber, ETag)` 列表
+pub fn parse_complete_parts(body: &str) -> Option<Vec<(u32, String)>> {
+    fn tag<'a>(text: &'a str, name: &str) -> Option<&'a str> {
+        let start = text.find(&format!("<{}>", name))? + name.len() + 2;
+        let end = text[start..].find(&format!("</{}>", name))? + start;
+        Some(text[start..end].trim())
+    }
+
+    let mut parts = Vec::new();
+    for block in body.split("<Part>").skip(1) {
+        let number = tag(block, "PartNumber")?.parse().ok()?;
+        let etag = tag(block, "ETag")?
+            .replace("&quot;", "")
+            .replace('"', "")
+            .to_lowercase();
+        parts.push((number, etag));
+    }
+    Some(parts)
+}
+
+fn s3_time(time: &DateTime<Utc>) -> String {
+    time.format("%Y-%m-%dT%H:%M:%S%.3fZ").to_string()
+}
+
+fn xml_response(body: String) -> HttpResponse {
+    HttpResponse::Ok()
+        .content_type("application/xml")
+        .body(format!(
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n{}",
+            body
+        ))
+}
+
+fn no_such_key(key: &str) -> S3Error {
+    S3Error::new(
+        StatusCode::NOT_FOUND,
+        "NoSuchKey",
+        format!("对象 {} 不存在", key),
+    )
+}
+
+fn not_implemented() -> S3Error {
+    S3Error::new(
+        StatusCode::NOT_IMPLEMENTED,
+        "NotImplemented",
+        "不支持该操作",
+    )
+}
+
+fn content_type_of(key: &str) -> String {
+    let ext = key.rsplit_once('.').map(|(_, ext)| ext).unwrap_or_default();
+    actix_files::file_extension_to_mime(ext).to_string()
+}
+
+fn list_buckets(config: &S3Config) -> HttpResponse {
+    let buckets: String = config
+        .buckets
+        .keys()
+        .map(|name| {
+            format!(
+                "<Bucket><Name>{}</Name><CreationDate>{}</CreationDate></Bucket>",
+                xml_escape(name),
+                s3_time(&DateTime::<Utc>::UNIX_EPOCH)
+            )
+        })
+        .collect();
+    xml_response(format!(
+        "<ListAllMyBucketsResult xmlns=\"{}\"><Owner><ID>netdisk</ID><DisplayName>netdisk</DisplayName></Owner><Buckets>{}</Buckets></ListAllMyBucketsResult>",
+        S3_XMLNS, buckets
+    ))
+}
+
+/// 收集 `prefix` 所在目录下的候选对象；分隔符为 `/` 时只列一层，否则递归遍历
+async fn collect_objects(
+    token: &AccessToken,
+    root_id: u64,
+    prefix: &str,
+    delimiter: Option<&str>,
+) -> Result<Vec<S3Object>, Box<dyn Error>> {
+    let dir_key = &prefix[..prefix.rfind('/').map_or(0, |i| i + 1)];
+    let dir_id = if dir_key.is_empty() {
+        root_id
+    } else {
+        match find_entry(token, root_id, dir_key).await? {
+            Some(item) if item.r#type == 1 => item.file_id as u64,
+            _ => return Ok(Vec::new()),
+        }
+    };
+
+    let recursive = delimiter != Some("/");
+    let mut objects = Vec::new();
+    let mut pending = vec![(dir_id, dir_key.to_string())];
+    while let Some((dir_id, dir_key)) = pending.pop() {
+        for item in list_dir(token, dir_id).await? {
+            let is_dir = item.r#type == 1;
+            let mut key = format!("{}{}", dir_key, item.filename);
+            if is_dir {
+                key.push('/');
+                if recursive && (key.starts_with(prefix) || prefix.starts_with(&key)) {
+                    pending.push((item.file_id as u64, key.clone()));
+                }
+            }
+            objects.push(S3Object {
+                key,
+                size: item.size,
+                etag: item.etag.to_lowercase(),
+                last_modified: item.update_at.with_timezone(&Utc),
+                is_dir,
+            });
+        }
+    }
+    Ok(objects)
+}
+
+/// ListObjectsV2，未指定 `list-type=2` 时按 ListObjects（V1）的 marker 语义返回
+async fn list_objects(
+    token: &AccessToken,
+    bucket: &str,
+    root_id: u64,
+    query: &HashMap<String, String>,
+) -> Result<HttpResponse, Box<dyn Error>> {
+    let param = |name: &str| query.get(name).map(String::as_str);
+    let v2 = param("list-type") == Some("2");
+    let prefix = param("prefix").unwrap_or_default();
+    let delimiter = param("delimiter");
+    let max_keys = param("max-keys")
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(DEFAULT_MAX_KEYS)
+        .min(DEFAULT_MAX_KEYS);
+    let url_encoding = param("encoding-type") == Some("url");
+
+    let token_key = param("continuation-token")
+        .and_then(|t| URL_SAFE_NO_PAD.decode(t).ok())
+        .and_then(|t| String::from_utf8(t).ok());
+    let start_after = if v2 {
+        token_key.as_deref().or_else(|| param("start-after"))
+    } else {
+        param("marker")
+    };
+
+    let entries = collect_objects(token, root_id, prefix, delimiter).await?;
+    let page = paginate_objects(entries, prefix, delimiter, start_after, max_keys);
+
+    let encode = |key: &str| {
+        if url_encoding {
+            uri_encode(key, false)
+        } else {
+            xml_escape(key)
+        }
+    };
+    let mut body = format!(
+        "<ListBucketResult xmlns=\"{}\"><Name>{}</Name><Prefix>{}</Prefix><MaxKeys>{}</MaxKeys><IsTruncated>{}</IsTruncated>",
+        S3_XMLNS,
+        xml_escape(bucket),
+        encode(prefix),
+        max_keys,
+        page.is_truncated
+    );
+    if let Some(delimiter) = delimiter {
+        body.push_str(&format!("<Delimiter>{}</Delimiter>", encode(delimiter)));
+    }
+    if url_encoding {
+        body.push_str("<EncodingType>url</EncodingType>");
+    }
+    if v2 {
+        body.push_str(&format!(
+            "<KeyCount>{}</KeyCount>",
+            page.contents.len() + page.common_prefixes.len()
+        ));
+        if let Some(token) = param("continuation-token") {
+            body.push_str(&format!(
+                "<ContinuationToken>{}</ContinuationToken>",
+                xml_escape(token)
+            ));
+        }
+        if let Some(start_after) = param("start-after") {
+            body.push_str(&format!("<StartAfter>{}</StartAfter>", encode(start_after)));
+        }
+        if let (true, Some(marker)) = (page.is_truncated, &page.next_marker) {
+            body.push_str(&format!(
+                "<NextContinuationToken>{}</NextContinuationToken>",
+                URL_SAFE_NO_PAD.encode(marker)
+            ));
+        }
+    } else {
+        body.push_str(&format!(
+            "<Marker>{}</Marker>",
+            encode(param("marker").unwrap_or_default())
+        ));
+        if let (true, Some(marker)) = (page.is_truncated, &page.next_marker) {
+            body.push_str(&format!("<NextMarker>{}</NextMarker>", encode(marker)));
+        }
+    }
+    for object in &page.contents {
+        body.push_str(&format!(
+            "<Contents><Key>{}</Key><LastModified>{}</LastModified><ETag>&quot;{}&quot;</ETag><Size>{}</Size><StorageClass>STANDARD</StorageClass></Contents>",
+            encode(&object.key),
+            s3_time(&object.last_modified),
+            object.etag,
+            object.size
+        ));
+    }
+    for common in &page.common_prefixes {
+        body.push_str(&format!(
+            "<CommonPrefixes><Prefix>{}</Prefix></CommonPrefixes>",
+            encode(common)
+        ));
+    }
+    body.push_str("</ListBucketResult>");
+    Ok(xml_response(body))
+}
+
+/// GetObject / HeadObject
+async fn get_object(
+    req: &HttpRequest,
+    token: &AccessToken,
+    root_id: u64,
+    key: &str,
+    with_body: bool,
+) -> Result<HttpResponse, Box<dyn Error>> {
+    let item = match find_entry(token, root_id, key).await? {
+        Some(item) if item.r#type != 1 && !key.ends_with('/') => item,
+        _ => return Err(no_such_key(key).into()),
+    };
+    let mut builder = HttpResponse::Ok();
+    builder
+        .content_type(content_type_of(key))
+        .insert_header((header::ACCEPT_RANGES, "bytes"))
+        .insert_header((header::ETAG, format!("\"{}\"", item.etag.to_lowercase())))
+        .insert_header((
+            header::LAST_MODIFIED,
+            item.update_at
+                .with_timezone(&Utc)
+                .format("%a, %d %b %Y %H:%M:%S GMT")
+                .to_string(),
+        ));
+    if !with_body {
+        return Ok(builder.no_chunking(item.size).finish());
+    }
+    proxy_download(req, token, item.file_id as u64, builder).await
+}
+
+/// 将请求体写入 `path`，同时计算 MD5 与 SHA-256；已签名的请求体会校验 SHA-256
+async fn receive_to_file(
+    payload: &mut web::Payload,
+    path: &Path,
+    payload_hash: &str,
+) -> Result<(u64, String), Box<dyn Error>> {
+    let mut file = tokio::fs::File::create(path).await?;
+    let mut md5 = Md5::new();
+    let mut sha256 = Sha256::new();
+    let mut size = 0u64;
+    while let Some(chunk) = payload.next().await {
+        let chunk = chunk?;
+        md5.update(&chunk);
+        sha256.update(&chunk);
+        file.write_all(&chunk).await?;
+        size += chunk.len() as u64;
+    }
+    file.flush().await?;
+    if payload_hash != UNSIGNED_PAYLOAD && to_hex(&sha256.finalize()) != payload_hash {
+        return Err(S3Error::new(
+            StatusCode::BAD_REQUEST,
+            "XAmzContentSHA256Mismatch",
+            "请求体的 SHA-256 与 x-amz-content-sha256 不一致",
+        )
+        .into());
+    }
+    Ok((size, to_hex(&md5.finalize())))
+}
+
+/// 读取较小的 XML 请求体并校验 SHA-256
+async fn receive_xml(
+    payload: &mut web::Payload,
+    payload_hash: &str,
+) -> Result<String, Box<dyn Error>> {
+    let mut body = Vec::new();
+    while let Some(chunk) = payload.next().await {
+        body.extend_from_slice(&chunk?);
+        if body.len() > MAX_XML_BODY {
+            return Err(S3Error::new(
+                StatusCode::BAD_REQUEST,
+                "MaxMessageLengthExceeded",
+                "请求体过大",
+            )
+            .into());
+        }
+    }
+    if payload_hash != UNSIGNED_PAYLOAD && sha256_hex(&body) != payload_hash {
+        return Err(S3Error::new(
+            StatusCode::BAD_REQUEST,
+            "XAmzContentSHA256Mismatch",
+            "请求体的 SHA-256 与 x-amz-content-sha256 不一致",
+        )
+        .into());
+    }
+    Ok(String::from_utf8(body)?)
+}
+
+/// 在 bucket 目录下按 key 创建上级目录并上传本地文件，覆盖同名文件，返回 fileID
+async fn upload_key(
+    token: &AccessToken,
+    cache: &ResponseCache,
+    root_id: u64,
+    key: &str,
+    path: &Path,
+    size: u64,
+    etag: String,
+) -> Result<u64, Box<dyn Error>> {
+    let (dir, name) = key.rsplit_once('/').unwrap_or(("", key));
+    if name.is_empty() {
+        return Err(S3Error::new(StatusCode::BAD_REQUEST, "InvalidArgument", "无效的 key").into());
+    }
+    let parent_file_id = ensure_dir_path(token, dir, root_id).await?;
+    let item = UploadFileItem {
+        parent_file_id,
+        filename: name.to_string(),
+        etag,
+        size,
+        duplicate: Some(2),
+        contain_dir: None,
+    };
+    let file_id = upload_with_item(token, &item, path).await?.file_id;
+    // 只知道起点与终点，中间新建目录的列表依靠有效期过期
+    cache.invalidate_folders(&[root_id, parent_file_id]);
+    cache.invalidate_files(&[file_id]);
+    Ok(file_id)
+}
+
+/// PutObject；以 `/` 结尾的空对象视为创建目录
+async fn put_object(
+    req: &HttpRequest,
+    mut payload: web::Payload,
+    token: &AccessToken,
+    cache: &ResponseCache,
+    root_id: u64,
+    key: &str,
+    payload_hash: &str,
+) -> Result<HttpResponse, Box<dyn Error>> {
+    if req.headers().contains_key("x-amz-copy-source") {
+        return Err(not_implemented().into());
+    }
+    let temp = tempfile::Builder::new().prefix("netdisk-s3-").tempfile()?;
+    let (size, md5) = receive_to_file(&mut payload, temp.path(), payload_hash).await?;
+    if key.ends_with('/') && size == 0 {
+        let dir_id = ensure_dir_path(token, key, root_id).await?;
+        cache.invalidate_folders(&[root_id, dir_id]);
+        return Ok(HttpResponse::Ok()
+            .insert_header((header::ETAG, format!("\"{}\"", EMPTY_MD5)))
+            .finish());
+    }
+    let file_id = upload_key(token, cache, root_id, key, temp.path(), size, md5.clone()).await?;
+    info!("S3 PutObject {} ({} 字节) -> {}", key, size, file_id);
+    Ok(HttpResponse::Ok()
+        .insert_header((header::ETAG, format!("\"{}\"", md5)))
+        .finish())
+}
+
+/// DeleteObject 只会将条目移入回收站，对象不存在时同样返回 204
+async fn delete_object(
+    token: &AccessToken,
+    cache: &ResponseCache,
+    root_id: u64,
+    key: &str,
+) -> Result<HttpResponse, Box<dyn Error>> {
+    if let Some(item) = find_entry(token, root_id, key).await? {
+        // 目录只在以 `/` 结尾的 key 上删除，对应 S3 的目录占位对象
+        if item.r#type != 1 || key.ends_with('/') {
+            trash_files(token, &[item.file_id as u64]).await?;
+            cache.invalidate_files(&[item.file_id as u64]);
+            cache.invalidate_folders(&[item.parent_file_id]);
+        }
+    }
+    Ok(HttpResponse::NoContent().finish())
+}
+
+/// 分片上传的目标，保存在暂存目录的 `upload.json` 中
+#[derive(Debug, Serialize, Deserialize)]
+struct MultipartUpload {
+    bucket: String,
+    key: String,
+    created_at: DateTime<Utc>,
+}
+
+/// uploadId 只包含字母与数字，防止拼接出暂存目录以外的路径
+fn upload_dir(env: &NetDiskEnv, upload_id: &str) -> Result<PathBuf, S3Error> {
+    let dir = env.config_dir.join(S3_MULTIPART_DIR).join(upload_id);
+    if upload_id.is_empty()
+        || !upload_id.chars().all(|c| c.is_ascii_alphanumeric())
+        || !dir.is_dir()
+    {
+        return Err(S3Error::new(
+            StatusCode::NOT_FOUND,
+            "NoSuchUpload",
+            "分片上传不存在或已结束",
+        ));
+    }
+    Ok(dir)
+}
+
+/// 打开分片上传的暂存目录，并确认其 bucket/key 与请求一致
+async fn open_upload(
+    env: &NetDiskEnv,
+    upload_id: &str,
+    bucket: &str,
+    key: &str,
+) -> Result<PathBuf, Box<dyn Error>> {
+    let dir = upload_dir(env, upload_id)?;
+    let upload: MultipartUpload =
+        serde_json::from_slice(&tokio::fs::read(dir.join("upload.json")).await?)?;
+    if upload.bucket != bucket || upload.key != key {
+        return Err(S3Error::new(
+            StatusCode::NOT_FOUND,
+            "NoSuchUpload",
+            "uploadId 与 bucket/key 不匹配",
+        )
+        .into());
+    }
+    Ok(dir)
+}
+
+/// 删除创建时间早于 `ttl` 的分片上传暂存目录，返回删除的数量；
+/// 缺少 `upload.json` 的目录按目录的修改时间计算
+pub async fn cleanup_multipart_uploads(
+    env: &NetDiskEnv,
+    ttl: std::time::Duration,
+) -> Result<usize, io::Error> {
+    let root = env.config_dir.join(S3_MULTIPART_DIR);
+    let mut entries = match tokio::fs::read_dir(&root).await {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(0),
+        Err(e) => return Err(e),
+    };
+    let now = SystemTime::now();
+    let mut removed = 0;
+    while let Some(entry) = entries.next_entry().await? {
+        let dir = entry.path();
+        if !entry.file_type().await?.is_dir() {
+            continue;
+        }
+        let created_at = match tokio::fs::read(dir.join("upload.json"))
+            .await
+            .ok()
+            .and_then(|data| serde_json::from_slice::<MultipartUpload>(&data).ok())
+        {
+            Some(upload) => SystemTime::from(upload.created_at),
+            None => entry.metadata().await?.modified()?,
+        };
+        let expired = now
+            .duration_since(created_at)
+            .map_or(false, |age| age > ttl);
+        if expired {
+            tokio::fs::remove_dir_all(&dir).await?;
+            removed += 1;
+        }
+    }
+    Ok(removed)
+}
+
+/// 网关运行期间定时清理过期的分片上传，启动时先清理一次
+pub async fn run_multipart_cleanup_loop(env: NetDiskEnv, ttl: std::time::Duration) {
+    let mut ticker = tokio::time::interval(ttl.min(std::time::Duration::from_secs(3600)));
+    loop {
+        ticker.tick().await;
+        match cleanup_multipart_uploads(&env, ttl).await {
+            Ok(0) => {}
+            Ok(removed) => info!("已清理 {} 个过期的 S3 分片上传", removed),
+            Err(e) => warn!("清理 S3 分片上传失败: {}", e),
+        }
+    }
+}
+
+fn part_path(dir: &Path, part_number: u32) -> PathBuf {
+    dir.join(format!("part-{:05}", part_number))
+}
+
+async fn create_multipart(
+    env: &NetDiskEnv,
+    bucket: &str,
+    key: &str,
+) -> Result<HttpResponse, Box<dyn Error>> {
+    let now = Utc::now();
+    let nanos = now.timestamp_nanos_opt().unwrap_or_default() as u128;
+    let upload_id = format!(
+        "{}{}",
+        base62::encode(nanos),
+        base62::encode(u128::from(std::process::id()))
+    );
+    let dir = env.config_dir.join(S3_MULTIPART_DIR).join(&upload_id);
+    tokio::fs::create_dir_all(&dir).await?;
+    let upload = MultipartUpload {
+        bucket: bucket.to_string(),
+        key: key.to_string(),
+        created_at: now,
+    };
+    tokio::fs::write(dir.join("upload.json"), serde_json::to_vec(&upload)?).await?;
+    Ok(xml_response(format!(
+        "<InitiateMultipartUploadResult xmlns=\"{}\"><Bucket>{}</Bucket><Key>{}</Key><UploadId>{}</UploadId></InitiateMultipartUploadResult>",
+        S3_XMLNS,
+        xml_escape(bucket),
+        xml_escape(key),
+        upload_id
+    )))
+}
+
+/// UploadPart：分片暂存在本地，完成时合并后再走秒传/分片上传流程
+async fn upload_part(
+    mut payload: web::Payload,
+    env: &NetDiskEnv,
+    bucket: &str,
+    key: &str,
+    upload_id: &str,
+    part_number: Option<&String>,
+    payload_hash: &str,
+) -> Result<HttpResponse, Box<dyn Error>> {
+    let dir = open_upload(env, upload_id, bucket, key).await?;
+    let part_number: u32 = part_number
+        .and_then(|n| n.parse().ok())
+        .filter(|n| (1..=10000).contains(n))
+        .ok_or_else(|| {
+            S3Error::new(
+                StatusCode::BAD_REQUEST,
+                "InvalidArgument",
+                "partNumber 必须在 1 到 10000 之间",
+            )
+        })?;
+    let path = part_path(&dir, part_number);
+    let (_, md5) = receive_to_file(&mut payload, &path, payload_hash).await?;
+    tokio::fs::write(path.with_extension("md5"), &md5).await?;
+    Ok(HttpResponse::Ok()
+        .insert_header((header::ETAG, format!("\"{}\"", md5)))
+        .finish())
+}
+
+#[allow(clippy::too_many_arguments)]
+async fn complete_multipart(
+    mut payload: web::Payload,
+    env: &NetDiskEnv,
+    token: &AccessToken,
+    cache: &ResponseCache,
+    root_id: u64,
+    bucket: &str,
+    key: &str,
+    upload_id: &str,
+    payload_hash: &str,
+) -> Result<HttpResponse, Box<dyn Error>> {
+    let dir = open_upload(env, upload_id, bucket, key).await?;
+    let body = receive_xml(&mut payload, payload_hash).await?;
+    let parts = parse_complete_parts(&body)
+        .filter(|parts| !parts.is_empty())
+        .ok_or_else(|| S3Error::new(StatusCode::BAD_REQUEST, "MalformedXML", "无法解析分片列表"))?;
+    if parts.windows(2).any(|w| w[0].0 >= w[1].0) {
+        return Err(S3Error::new(
+            StatusCode::BAD_REQUEST,
+            "InvalidPartOrder",
+            "分片必须按 PartNumber 升序排列",
+        )
+        .into());
+    }
+
+    let object_path = dir.join("object");
+    let mut object = tokio::fs::File::create(&object_path).await?;
+    for (part_number, etag) in &parts {
+        let path = part_path(&dir, *part_number);
+        let stored = tokio::fs::read_to_string(path.with_extension("md5"))
+            .await
+            .unwrap_or_default();
+        if stored.is_empty() || &stored != etag {
+            return Err(S3Error::new(
+                StatusCode::BAD_REQUEST,
+                "InvalidPart",
+                format!("分片 {} 不存在或 ETag 不匹配", part_number),
+            )
+            .into());
+        }
+        let mut part = tokio::fs::File::open(&path).await?;
+        tokio::io::copy(&mut part, &mut object).await?;
+    }
+    object.flush().await?;
+    drop(object);
+
+    let size = tokio::fs::metadata(&object_path).await?.len();
+    let md5 = async_file_md5(&object_path).await?;
+    let file_id = upload_key(token, cache, root_id, key, &object_path, size, md5.clone()).await?;
+    info!(
+        "S3 分片上传完成 {} ({} 个分片，{} 字节) -> {}",
+        key,
+        parts.len(),
+        size,
+        file_id
+    );
+    tokio::fs::remove_dir_all(&dir).await?;
+    Ok(xml_response(format!(
+        "<CompleteMultipartUploadResult xmlns=\"{}\"><Bucket>{}</Bucket><Key>{}</Key><ETag>&quot;{}&quot;</ETag></CompleteMultipartUploadResult>",
+        S3_XMLNS,
+        xml_escape(bucket),
+        xml_escape(key),
+        md5
+    )))
+}
+
+async fn abort_multipart(
+    env: &NetDiskEnv,
+    bucket: &str,
+    key: &str,
+    upload_id: &str,
+) -> Result<HttpResponse, Box<dyn Error>> {
+    let dir = open_upload(env, upload_id, bucket, key).await?;
+    tokio::fs::remove_dir_all(dir).await?;
+    Ok(HttpResponse::NoContent().finish())
+}
+
+async fn handle(
+    req: &HttpRequest,
+    payload: web::Payload,
+    token: &AccessToken,
+    env: &NetDiskEnv,
+    cache: &ResponseCache,
+    config: &S3Config,
+) -> Result<HttpResponse, Box<dyn Error>> {
+    let payload_hash = verify_sigv4(req, config).map_err(S3Error::from)?;
+    let prefix = config.normalized_prefix();
+    let rest = req.path().strip_prefix(&prefix).unwrap_or_default();
+    let rest = percent_decode_str(rest.trim_start_matches('/'))
+        .decode_utf8_lossy()
+        .into_owned();
+    let (bucket, key) = rest.split_once('/').unwrap_or((rest.as_str(), ""));
+    let query: HashMap<String, String> = web::Query::from_query(req.query_string())
+        .map(web::Query::into_inner)
+        .unwrap_or_default();
+    let method = req.method().as_str();
+
+    if bucket.is_empty() {
+        return match method {
+            "GET" => Ok(list_buckets(config)),
+            _ => Err(not_implemented().into()),
+        };
+    }
+    let root_id = *config.buckets.get(bucket).ok_or_else(|| {
+        S3Error::new(
+            StatusCode::NOT_FOUND,
+            "NoSuchBucket",
+            format!("bucket {} 未配置", bucket),
+        )
+    })?;
+
+    if key.is_empty() {
+        return match method {
+            "GET" if query.contains_key("location") => Ok(xml_response(format!(
+                "<LocationConstraint xmlns=\"{}\">{}</LocationConstraint>",
+                S3_XMLNS, config.region
+            ))),
+            "GET" => list_objects(token, bucket, root_id, &query).await,
+            "HEAD" => Ok(HttpResponse::Ok().finish()),
+            _ => Err(not_implemented().into()),
+        };
+    }
+
+    match (method, query.get("uploadId")) {
+        ("GET", None) => get_object(req, token, root_id, key, true).await,
+        ("HEAD", None) => get_object(req, token, root_id, key, false).await,
+        ("PUT", None) => put_object(req, payload, token, cache, root_id, key, &payload_hash).await,
+        ("DELETE", None) => delete_object(token, cache, root_id, key).await,
+        ("POST", None) if query.contains_key("uploads") => create_multipart(env, bucket, key).await,
+        ("PUT", Some(upload_id)) => {
+            upload_part(
+                payload,
+                env,
+                bucket,
+                key,
+                upload_id,
+                query.get("partNumber"),
+                &payload_hash,
+            )
+            .await
+        }
+        ("POST", Some(upload_id)) => {
+            complete_multipart(
+                payload,
+                env,
+                token,
+                cache,
+                root_id,
+                bucket,
+                key,
+                upload_id,
+                &payload_hash,
+            )
+            .await
+        }
+        ("DELETE", Some(upload_id)) => abort_multipart(env, bucket, key, upload_id).await,
+        _ => Err(not_implemented().into()),
+    }
+}
+
+/// # S3 兼容接口入口
+///
+/// 使用 path-style 访问：`<前缀>/<bucket>/<key>`，请求需携带 SigV4 签名。
+pub async fn s3(
+    req: HttpRequest,
+    payload: web::Payload,
+    token: web::Data<AccessToken>,
+    env: web::Data<NetDiskEnv>,
+    gateway: web::Data<GatewayConfig>,
+    cache: web::Data<ResponseCache>,
+) -> HttpResponse {
+    match handle(&req, payload, &token, &env, &cache, &gateway.s3).await {
+        Ok(response) => response,
+        Err(e) => match e.downcast::<S3Error>() {
+            Ok(s3_error) => s3_error.response(req.path()),
+            Err(e) => {
+                warn!("S3 请求 {} {} 失败: {}", req.method(), req.path(), e);
+                S3Error::new(
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    "InternalError",
+                    e.to_string(),
+                )
+                .response(req.path())
+            }
+        },
+    }
+}
+
+pub fn s3_config(cfg: &mut web::ServiceConfig, config: &S3Config) {
+    if config.enabled {
+        cfg.service(web::scope(&config.normalized_prefix()).default_service(web::to(s3)));
+    }
+}
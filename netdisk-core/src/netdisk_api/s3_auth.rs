@@ -0,0 +1,274 @@
+use crate::responses::prelude::*;
+use actix_web::HttpRequest;
+use chrono::{Duration, NaiveDateTime, Utc};
+use hmac::{Hmac, Mac};
+use percent_encoding::percent_decode_str;
+use sha2::{Digest, Sha256};
+
+type HmacSha256 = Hmac<Sha256>;
+
+pub const SIGV4_ALGORITHM: &str = "AWS4-HMAC-SHA256";
+/// 不对请求体签名时 `x-amz-content-sha256` 的取值
+pub const UNSIGNED_PAYLOAD: &str = "UNSIGNED-PAYLOAD";
+/// 请求时间与服务器时间允许的最大偏差（分钟）
+const MAX_CLOCK_SKEW_MINUTES: i64 = 15;
+
+/// SigV4 校验失败的原因，对应 S3 的错误码
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SigV4Error {
+    pub code: &'static str,
+    pub message: String,
+}
+
+impl SigV4Error {
+    fn new(code: &'static str, message: impl Into<String>) -> Self {
+        SigV4Error {
+            code,
+            message: message.into(),
+        }
+    }
+}
+
+/// 解析后的 `Authorization` 头
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SigV4Authorization {
+    pub access_key_id: String,
+    /// `yyyymmdd`
+    pub date: String,
+    pub region: String,
+    pub service: String,
+    pub signed_headers: Vec<String>,
+    pub signature: String,
+}
+
+impl SigV4Authorization {
+    pub fn scope(&self) -> String {
+        format!(
+            "{}/{}/{}/aws4_request",
+            self.date, self.region, self.service
+        )
+    }
+}
+
+pub fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+pub fn sha256_hex(data: &[u8]) -> String {
+    to_hex(&Sha256::digest(data))
+}
+
+fn hmac_sha256(key: &[u8], data: &[u8]) -> Vec<u8> {
+    let mut mac = HmacSha256::new_from_slice(key).expect("HMAC 支持任意长度的密钥");
+    mac.update(data);
+    mac.finalize().into_bytes().to_vec()
+}
+
+/// 解析 `AWS4-HMAC-SHA256 Credential=.../20130524/us-east-1/s3/aws4_request, SignedHeaders=..., Signature=...`
+pub fn parse_authorization(header: &str) -> Option<SigV4Authorization> {
+    let rest = header.strip_prefix(SIGV4_ALGORITHM)?.trim();
+    let mut credential = None;
+    let mut signed_headers = None;
+    let mut signature = None;
+    for part in rest.split(',') {
+        let (key, value) = part.trim().split_once('=')?;
+        match key {
+            "Credential" => credential = Some(value),
+            "SignedHeaders" => signed_headers = Some(value),
+            "Signature" => signature = Some(value),
+            _ => {}
+        }
+    }
+    let mut scope = credential?.split('/');
+    let auth = SigV4Authorization {
+        access_key_id: scope.next()?.to_string(),
+        date: scope.next()?.to_string(),
+        region: scope.next()?.to_string(),
+        service: scope.next()?.to_string(),
+        signed_headers: signed_headers?.split(';').map(str::to_string).collect(),
+        signature: signature?.to_string(),
+    };
+    if scope.next() != Some("aws4_request") {
+        return None;
+    }
+    Some(auth)
+}
+
+/// SigV4 的 URI 编码：只保留 RFC 3986 非保留字符
+pub fn uri_encode(input: &str, encode_slash: bool) -> String {
+    let mut encoded = String::with_capacity(input.len());
+    for byte in input.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                encoded.push(byte as char)
+            }
+            b'/' if !encode_slash => encoded.push('/'),
+            _ => encoded.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    encoded
+}
+
+/// 规范化查询字符串：解码后重新编码，并按参数名、参数值排序
+pub fn canonical_query(raw_query: &str) -> String {
+    let mut params: Vec<(String, String)> = raw_query
+        .split('&')
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| {
+            let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+            let decode = |s: &str| percent_decode_str(s).decode_utf8_lossy().into_owned();
+            (
+                uri_encode(&decode(key), true),
+                uri_encode(&decode(value), true),
+            )
+        })
+        .collect();
+    params.sort();
+    params
+        .iter()
+        .map(|(key, value)| format!("{}={}", key, value))
+        .collect::<Vec<_>>()
+        .join("&")
+}
+
+/// 构造规范请求；`headers` 为参与签名的头（名称小写）及其原始值。
+///
+/// S3 的规范 URI 直接使用请求中已编码一次的路径，不再二次编码。
+pub fn canonical_request(
+    method: &str,
+    path: &str,
+    raw_query: &str,
+    headers: &[(String, String)],
+    payload_hash: &str,
+) -> String {
+    let canonical_headers: String = headers
+        .iter()
+        .map(|(name, value)| {
+            let value = value.split_whitespace().collect::<Vec<_>>().join(" ");
+            format!("{}:{}\n", name, value)
+        })
+        .collect();
+    let signed_headers = headers
+        .iter()
+        .map(|(name, _)| name.as_str())
+        .collect::<Vec<_>>()
+        .join(";");
+    format!(
+        "{}\n{}\n{}\n{}\n{}\n{}",
+        method,
+        path,
+        canonical_query(raw_query),
+        canonical_headers,
+        signed_headers,
+        payload_hash
+    )
+}
+
+/// 根据规范请求计算签名
+pub fn compute_signature(
+    secret_access_key: &str,
+    auth: &SigV4Authorization,
+    amz_date: &str,
+    canonical_request: &str,
+) -> String {
+    let string_to_sign = format!(
+        "{}\n{}\n{}\n{}",
+        SIGV4_ALGORITHM,
+        amz_date,
+        auth.scope(),
+        sha256_hex(canonical_request.as_bytes())
+    );
+    let key = hmac_sha256(
+        format!("AWS4{}", secret_access_key).as_bytes(),
+        auth.date.as_bytes(),
+    );
+    let key = hmac_sha256(&key, auth.region.as_bytes());
+    let key = hmac_sha256(&key, auth.service.as_bytes());
+    let key = hmac_sha256(&key, b"aws4_request");
+    to_hex(&hmac_sha256(&key, string_to_sign.as_bytes()))
+}
+
+/// 逐字节比较，避免签名校验的耗时泄露匹配长度
+fn constant_time_eq(a: &str, b: &str) -> bool {
+    a.len() == b.len()
+        && a.bytes()
+            .zip(b.bytes())
+            .fold(0u8, |acc, (x, y)| acc | (x ^ y))
+            == 0
+}
+
+/// 校验请求的 SigV4 签名，通过时返回 `x-amz-content-sha256` 的取值
+pub fn verify_sigv4(req: &HttpRequest, config: &S3Config) -> Result<String, SigV4Error> {
+    let header_str = |name: &str| req.headers().get(name).and_then(|v| v.to_str().ok());
+    let auth = header_str("authorization")
+        .and_then(parse_authorization)
+        .ok_or_else(|| SigV4Error::new("AccessDenied", "缺少或无法解析 SigV4 Authorization 头"))?;
+    if config.access_key_id.is_empty() || auth.access_key_id != config.access_key_id {
+        return Err(SigV4Error::new("InvalidAccessKeyId", "AccessKeyId 不存在"));
+    }
+    if auth.service != "s3" || auth.region != config.region {
+        return Err(SigV4Error::new(
+            "AuthorizationHeaderMalformed",
+            format!("签名范围应为 {}/s3", config.region),
+        ));
+    }
+
+    let amz_date = header_str("x-amz-date")
+        .ok_or_else(|| SigV4Error::new("AccessDenied", "缺少 x-amz-date 头"))?;
+    let signed_at = NaiveDateTime::parse_from_str(amz_date, "%Y%m%dT%H%M%SZ")
+        .map_err(|_| SigV4Error::new("AccessDenied", "x-amz-date 格式错误"))?
+        .and_utc();
+    if (Utc::now() - signed_at).abs() > Duration::minutes(MAX_CLOCK_SKEW_MINUTES) {
+        return Err(SigV4Error::new(
+            "RequestTimeTooSkewed",
+            "请求时间与服务器时间相差过大",
+        ));
+    }
+    if !amz_date.starts_with(&auth.date) {
+        return Err(SigV4Error::new(
+            "AuthorizationHeaderMalformed",
+            "签名日期与 x-amz-date 不一致",
+        ));
+    }
+
+    let payload_hash = header_str("x-amz-content-sha256")
+        .ok_or_else(|| SigV4Error::new("InvalidRequest", "缺少 x-amz-content-sha256 头"))?
+        .to_string();
+    if payload_hash.starts_with("STREAMING-") {
+        return Err(SigV4Error::new(
+            "NotImplemented",
+            "不支持 aws-chunked 分块签名上传，请关闭 payload signing 或使用 UNSIGNED-PAYLOAD",
+        ));
+    }
+
+    let mut headers = Vec::with_capacity(auth.signed_headers.len());
+    for name in &auth.signed_headers {
+        let values: Vec<&str> = req
+            .headers()
+            .get_all(name.as_str())
+            .filter_map(|v| v.to_str().ok())
+            .collect();
+        if values.is_empty() {
+            return Err(SigV4Error::new(
+                "AccessDenied",
+                format!("缺少已签名的头 {}", name),
+            ));
+        }
+        headers.push((name.clone(), values.join(",")));
+    }
+    let canonical = canonical_request(
+        req.method().as_str(),
+        req.uri().path(),
+        req.query_string(),
+        &headers,
+        &payload_hash,
+    );
+    let expected = compute_signature(&config.secret_access_key, &auth, amz_date, &canonical);
+    if !constant_time_eq(&expected, &auth.signature) {
+        return Err(SigV4Error::new(
+            "SignatureDoesNotMatch",
+            "签名不匹配，请检查密钥与签名方式",
+        ));
+    }
+    Ok(payload_hash)
+}
@@ -0,0 +1,486 @@
+use super::base_api::*;
+use super::batch_api::{limiter_for, report_response, run_batched};
+use super::validated::{ValidJson, ValidQuery};
+use crate::netdisk_auth::basic_env::NetDiskEnv;
+use crate::responses::prelude::*;
+use actix_web::http::StatusCode;
+use actix_web::{web, HttpResponse};
+use chrono::{DateTime, Local, TimeDelta, Utc};
+use serde_json::json;
+use std::error::Error;
+use std::io::{self, ErrorKind};
+use std::path::PathBuf;
+use tokio::sync::Mutex;
+use tracing::{debug, info, warn};
+
+/// 本地分享记录文件，位于配置目录下
+pub const SHARE_RECORDS_FILE: &str = "shares.json";
+/// 分享记录文件的写锁，避免并发请求互相覆盖对方的修改
+static SHARE_RECORDS_LOCK: Mutex<()> = Mutex::const_new(());
+/// 分享列表接口单页的最大数量
+const SHARE_PAGE_LIMIT: u8 = 100;
+
+fn share_records_path(env: &NetDiskEnv) -> PathBuf {
+    env.config_dir.join(SHARE_RECORDS_FILE)
+}
+
+/// 读取本地分享记录，文件不存在时返回空记录
+pub async fn load_share_records(env: &NetDiskEnv) -> Result<ShareRecords, io::Error> {
+    match tokio::fs::read(share_records_path(env)).await {
+        Ok(content) => {
+            serde_json::from_slice(&content).map_err(|e| io::Error::new(ErrorKind::InvalidData, e))
+        }
+        Err(e) if e.kind() == ErrorKind::NotFound => Ok(ShareRecords::default()),
+        Err(e) => Err(e),
+    }
+}
+
+/// 先写临时文件再改名，避免中途退出留下损坏的记录
+pub async fn save_share_records(env: &NetDiskEnv, records: &ShareRecords) -> Result<(), io::Error> {
+    tokio::fs::create_dir_all(&env.config_dir).await?;
+    let path = share_records_path(env);
+    let content = serde_json::to_vec_pretty(records)
+        .map_err(|e| io::Error::new(ErrorKind::InvalidData, e))?;
+    let tmp_path = path.with_extension("json.tmp");
+    tokio::fs::write(&tmp_path, content).await?;
+    tokio::fs::rename(&tmp_path, &path).await
+}
+
+/// 在进程内串行化分享记录的读取-修改-写回，`update` 返回 `false` 时不写回
+pub async fn update_share_records<F>(env: &NetDiskEnv, update: F) -> Result<(), io::Error>
+where
+    F: FnOnce(&mut ShareRecords) -> bool,
+{
+    let _guard = SHARE_RECORDS_LOCK.lock().await;
+    let mut records = load_share_records(env).await?;
+    if update(&mut records) {
+        save_share_records(env, &records).await?;
+    }
+    Ok(())
+}
+
+/// 把一条分享记录写入本地，已有同 ID 的记录时覆盖
+pub async fn record_share(env: &NetDiskEnv, record: ShareRecord) -> Result<(), io::Error> {
+    update_share_records(env, |records| {
+        records.shares.insert(record.share_id, record);
+        true
+    })
+    .await
+}
+
+/// 包含指定文件的全部分享，按分享 ID 排序
+pub fn shares_containing(records: &ShareRecords, file_id: u64) -> Vec<ShareLink> {
+    records
+        .shares
+        .values()
+        .filter(|record| record.file_ids.contains(&file_id))
+        .map(ShareLink::from)
+        .collect()
+}
+
+/// 解析 `fileIDList` 形式的 `"1,2,3"`
+pub fn parse_file_id_list(file_id_list: &str) -> Vec<u64> {
+    file_id_list
+        .split(',')
+        .filter_map(|id| id.trim().parse().ok())
+        .collect()
+}
+
+/// 由创建分享的请求与返回结果构造本地记录
+pub fn share_record(item: &ShareItem, data: &SharedData) -> ShareRecord {
+    ShareRecord {
+        share_id: data.share_id,
+        share_key: data.share_key.clone(),
+        share_name: item.share_name.clone(),
+        file_ids: parse_file_id_list(&item.file_id_list),
+        share_pwd: item.share_pwd.clone().filter(|pwd| !pwd.is_empty()),
+        share_expire: item.share_expire,
+        created_at: Utc::now(),
+        recreated_from: None,
+    }
+}
+
+/// 创建分享链接
+pub async fn create_share(
+    token: &AccessToken,
+    item: &ShareItem,
+) -> Result<SharedData, Box<dyn Error>> {
+    let api_response: SharedDataResponse = api_post(token, "api/v1/share/create", item).await?;
+    api_response.into_data()
+}
+
+/// 按 `lastShareId` 翻页拉取全部分享
+pub async fn list_all_shares(token: &AccessToken) -> Result<Vec<ShareItemData>, Box<dyn Error>> {
+    let mut shares = Vec::new();
+    let mut last_share_id: Option<i64> = None;
+    loop {
+        let mut query = vec![("limit", SHARE_PAGE_LIMIT.to_string())];
+        if let Some(last_share_id) = last_share_id {
+            query.push(("lastShareId", last_share_id.to_string()));
+        }
+        let api_response: SharedListDataResponse =
+            api_get(token, "api/v1/share/list", &query).await?;
+        let data = api_response.into_data()?;
+        let done = data.last_share_id == -1 || data.share_list.is_empty();
+        shares.extend(data.share_list);
+        if done {
+            return Ok(shares);
+        }
+        last_share_id = Some(data.last_share_id);
+    }
+}
+
+/// 修改分享的流量包设置，调用方负责保证 `shareIdList` 不超过接口上限
+pub async fn update_share_traffic(
+    token: &AccessToken,
+    item: &ShareLinkItem,
+) -> Result<(), Box<dyn Error>> {
+    let api_response: ApiResponse<()> = api_put(token, "api/v1/share/list/info", item).await?;
+    if !api_response.is_ok() {
+        return Err(format!(
+            "修改分享失败，code: {}，信息: {}",
+            api_response.code, api_response.message
+        )
+        .into());
+    }
+    Ok(())
+}
+
+/// 取消分享（`api/v1/share/delete`），调用方负责保证数量不超过接口上限
+pub async fn cancel_shares(token: &AccessToken, share_ids: &[u64]) -> Result<(), Box<dyn Error>> {
+    let payload = json!({ "shareIdList": share_ids });
+    let api_response: ApiResponse<()> = api_post(token, "api/v1/share/delete", &payload).await?;
+    if !api_response.is_ok() {
+        return Err(format!(
+            "取消分享失败，code: {}，信息: {}",
+            api_response.code, api_response.message
+        )
+        .into());
+    }
+    Ok(())
+}
+
+fn is_expired(share: &ShareItemData, now: DateTime<Local>) -> bool {
+    share.expired != 0 || share.expiration <= now
+}
+
+/// 尚未过期、但会在 `days` 天内到期的分享，按到期时间排序；截止时间溢出时返回 `None`
+pub fn expiring_within(
+    shares: &[ShareItemData],
+    now: DateTime<Local>,
+    days: i64,
+) -> Option<Vec<ShareItemData>> {
+    let deadline = now.checked_add_signed(TimeDelta::try_days(days)?)?;
+    let mut expiring: Vec<ShareItemData> = shares
+        .iter()
+        .filter(|share| !is_expired(share, now) && share.expiration <= deadline)
+        .cloned()
+        .collect();
+    expiring.sort_by_key(|share| share.expiration);
+    Some(expiring)
+}
+
+/// 找出需要重建的过期分享及其本地记录；没有记录或已重建过的分享列入 `skipped`
+pub fn plan_recreate(
+    shares: &[ShareItemData],
+    records: &ShareRecords,
+    only: Option<&[u64]>,
+    now: DateTime<Local>,
+) -> (Vec<(ShareItemData, ShareRecord)>, Vec<SkippedShare>) {
+    let mut planned = Vec::new();
+    let mut skipped = Vec::new();
+    if let Some(only) = only {
+        for id in only {
+            if !shares.iter().any(|share| share.share_id as u64 == *id) {
+                skipped.push(SkippedShare {
+                    share_id: *id,
+                    reason: "分享不存在".to_string(),
+                });
+            }
+        }
+    }
+
+    for share in shares {
+        let share_id = share.share_id as u64;
+        if only.map_or(false, |only| !only.contains(&share_id)) {
+            continue;
+        }
+        if !is_expired(share, now) {
+            if only.is_some() {
+                skipped.push(SkippedShare {
+                    share_id,
+                    reason: "分享未过期".to_string(),
+                });
+            }
+            continue;
+        }
+        if let Some(newer) = records
+            .shares
+            .values()
+            .find(|record| record.recreated_from == Some(share_id))
+        {
+            skipped.push(SkippedShare {
+                share_id,
+                reason: format!("已重建为分享 {}", newer.share_id),
+            });
+            continue;
+        }
+        match records.shares.get(&share_id) {
+            Some(record) if !record.file_ids.is_empty() => {
+                planned.push((share.clone(), record.clone()))
+            }
+            _ => skipped.push(SkippedShare {
+                share_id,
+                reason: "没有本地分享记录，无法确定分享的文件".to_string(),
+            }),
+        }
+    }
+    (planned, skipped)
+}
+
+/// 用原分享的文件、提取码与流量包设置重建过期分享
+pub async fn run_recreate(
+    token: &AccessToken,
+    env: &NetDiskEnv,
+    item: &ShareRecreateItem,
+) -> Result<ShareRecreateReport, Box<dyn Error>> {
+    let shares = list_all_shares(token).await?;
+    let records = load_share_records(env).await?;
+    let (planned, skipped) = plan_recreate(
+        &shares,
+        &records,
+        item.share_id_list.as_deref(),
+        Local::now(),
+    );
+    let mut report = ShareRecreateReport {
+        dry_run: item.dry_run,
+        skipped,
+        ..Default::default()
+    };
+
+    for (share, record) in planned {
+        let mut recreated = RecreatedShare {
+            old_share_id: record.share_id,
+            share_id: None,
+            share_key: None,
+            share_name: record.share_name.clone(),
+            file_ids: record.file_ids.clone(),
+        };
+        if item.dry_run {
+            report.recreated.push(recreated);
+            continue;
+        }
+        let share_pwd = record
+            .share_pwd
+            .clone()
+            .or_else(|| Some(share.share_pwd.clone()).filter(|pwd| !pwd.is_empty()));
+        let new_item = ShareItem {
+            share_name: record.share_name.clone(),
+            share_expire: item.share_expire.unwrap_or(record.share_expire),
+            file_id_list: record
+                .file_ids
+                .iter()
+                .map(u64::to_string)
+                .collect::<Vec<_>>()
+                .join(","),
+            share_pwd,
+            traffic_switch: Some(share.traffic_switch),
+            traffic_limit_switch: Some(share.traffic_limit_switch),
+            traffic_limit: Some(share.traffic_limit),
+        };
+        match create_share(token, &new_item).await {
+            Ok(data) => {
+                info!("分享 {} 已重建为 {}", record.share_id, data.share_id);
+                let mut new_record = share_record(&new_item, &data);
+                new_record.recreated_from = Some(record.share_id);
+                // 每重建一个就保存，避免中途失败后重复创建
+                update_share_records(env, |records| {
+                    records.shares.remove(&record.share_id);
+                    records.shares.insert(data.share_id, new_record);
+                    true
+                })
+                .await?;
+                recreated.share_id = Some(data.share_id);
+                recreated.share_key = Some(data.share_key);
+                report.recreated.push(recreated);
+            }
+            Err(e) => report.failed.push(SkippedShare {
+                share_id: record.share_id,
+                reason: e.to_string(),
+            }),
+        }
+    }
+    Ok(report)
+}
+
+/// # 以文件 ID 数组创建分享
+///
+/// 返回完整链接与可直接发送的分享文案，并记录分享包含的文件。
+pub async fn share_link_create(
+    payload: ValidJson<ShareCreateItem>,
+    token: web::Data<AccessToken>,
+    env: web::Data<NetDiskEnv>,
+) -> Result<HttpResponse, Box<dyn Error>> {
+    debug!("尝试发送信息: {:?}", &payload);
+    let item = match payload.to_share_item() {
+        Ok(item) => item,
+        Err(message) => return Ok(error_response(StatusCode::BAD_REQUEST, message)),
+    };
+    let data = create_share(&token, &item).await?;
+    let record = share_record(&item, &data);
+    let link = ShareLink::from(&record);
+    if let Err(e) = record_share(&env, record).await {
+        warn!("保存分享记录失败: {}", e);
+    }
+    Ok(HttpResponse::Ok().json(ShareLinkResponse::new(
+        0,
+        "ok".to_string(),
+        link,
+        String::new(),
+    )))
+}
+
+/// # 由 shareKey 查询分享的文件与完整链接（仅限本地有记录的分享）
+pub async fn share_link_resolve(
+    path: web::Path<String>,
+    env: web::Data<NetDiskEnv>,
+) -> Result<HttpResponse, Box<dyn Error>> {
+    let records = load_share_records(&env).await?;
+    match records
+        .shares
+        .values()
+        .find(|record| record.share_key == *path)
+    {
+        Some(record) => Ok(HttpResponse::Ok().json(ShareLinkResponse::new(
+            0,
+            "ok".to_string(),
+            ShareLink::from(record),
+            String::new(),
+        ))),
+        None => Ok(error_response(
+            StatusCode::NOT_FOUND,
+            format!("没有分享 {} 的本地记录", path),
+        )),
+    }
+}
+
+/// # 查询哪些分享包含指定文件
+pub async fn share_by_file(
+    query: ValidQuery<ShareByFileQuery>,
+    env: web::Data<NetDiskEnv>,
+) -> Result<HttpResponse, Box<dyn Error>> {
+    let records = load_share_records(&env).await?;
+    Ok(HttpResponse::Ok().json(ShareLinksResponse::new(
+        0,
+        "ok".to_string(),
+        shares_containing(&records, query.file_id),
+        String::new(),
+    )))
+}
+
+/// # 获取全部分享（自动翻页）
+pub async fn share_all(token: web::Data<AccessToken>) -> Result<HttpResponse, Box<dyn Error>> {
+    let shares = list_all_shares(&token).await?;
+    Ok(HttpResponse::Ok().json(ShareItemsResponse::new(
+        0,
+        "ok".to_string(),
+        shares,
+        String::new(),
+    )))
+}
+
+/// # 查找 N 天内到期的分享
+pub async fn share_expiring(
+    query: ValidQuery<ShareExpiringQuery>,
+    token: web::Data<AccessToken>,
+) -> Result<HttpResponse, Box<dyn Error>> {
+    let shares = list_all_shares(&token).await?;
+    let expiring = match expiring_within(&shares, Local::now(), query.days) {
+        Some(expiring) => expiring,
+        None => return Ok(error_response(StatusCode::BAD_REQUEST, "days 超出范围")),
+    };
+    Ok(HttpResponse::Ok().json(ShareItemsResponse::new(
+        0,
+        "ok".to_string(),
+        expiring,
+        String::new(),
+    )))
+}
+
+/// # 批量取消分享
+pub async fn share_cancel(
+    payload: ValidJson<ShareCancelItem>,
+    token: web::Data<AccessToken>,
+    env: web::Data<NetDiskEnv>,
+) -> Result<HttpResponse, Box<dyn Error>> {
+    debug!("尝试发送信息:{:?}", &payload);
+    let mut limiter = limiter_for(payload.interval_ms);
+    let token = token.get_ref();
+    let report = run_batched(&payload.share_id_list, &mut limiter, |ids| async move {
+        cancel_shares(token, &ids).await
+    })
+    .await;
+
+    update_share_records(&env, |records| {
+        let before = records.shares.len();
+        for share_id in &report.succeeded {
+            records.shares.remove(share_id);
+        }
+        records.shares.len() != before
+    })
+    .await?;
+    Ok(report_response(report))
+}
+
+/// # 批量修改分享的流量包设置
+///
+/// `shareIdList` 为空时修改全部未过期的分享。
+pub async fn share_traffic(
+    payload: ValidJson<ShareTrafficItem>,
+    token: web::Data<AccessToken>,
+) -> Result<HttpResponse, Box<dyn Error>> {
+    debug!("尝试发送信息:{:?}", &payload);
+    let share_ids = if payload.share_id_list.is_empty() {
+        let now = Local::now();
+        list_all_shares(&token)
+            .await?
+            .iter()
+            .filter(|share| !is_expired(share, now))
+            .map(|share| share.share_id as u64)
+            .collect()
+    } else {
+        payload.share_id_list.clone()
+    };
+    let mut limiter = limiter_for(payload.interval_ms);
+    let token = token.get_ref();
+    let payload = payload.into_inner();
+    let report = run_batched(&share_ids, &mut limiter, |ids| {
+        let item = ShareLinkItem {
+            share_id_list: ids,
+            traffic_switch: payload.traffic_switch,
+            traffic_limit_switch: payload.traffic_limit_switch,
+            traffic_limit: payload.traffic_limit,
+        };
+        async move { update_share_traffic(token, &item).await }
+    })
+    .await;
+    Ok(report_response(report))
+}
+
+/// # 重建已过期的分享
+///
+/// 默认 `dryRun` 为 true，只返回重建计划；只有通过网关创建、留有本地记录的分享才能重建。
+pub async fn share_recreate(
+    payload: ValidJson<ShareRecreateItem>,
+    token: web::Data<AccessToken>,
+    env: web::Data<NetDiskEnv>,
+) -> Result<HttpResponse, Box<dyn Error>> {
+    debug!("尝试发送信息:{:?}", &payload);
+    let report = run_recreate(&token, &env, &payload).await?;
+    Ok(HttpResponse::Ok().json(ShareRecreateResponse::new(
+        0,
+        "ok".to_string(),
+        report,
+        String::new(),
+    )))
+}
@@ -0,0 +1,45 @@
+use super::validated::ValidQuery;
+use crate::netdisk_auth::basic_env::NetDiskEnv;
+use crate::responses::prelude::*;
+use crate::share_stats::{report_to_csv, share_stats_report, take_snapshot};
+use actix_web::{web, HttpResponse};
+use std::error::Error;
+
+/// # 立即记录一次分享统计快照
+pub async fn share_stats_snapshot(
+    token: web::Data<AccessToken>,
+    env: web::Data<NetDiskEnv>,
+) -> Result<HttpResponse, Box<dyn Error>> {
+    let result = take_snapshot(&token, &env).await?;
+    Ok(HttpResponse::Ok().json(ShareSnapshotResponse::new(
+        0,
+        "ok".to_string(),
+        result,
+        String::new(),
+    )))
+}
+
+/// # 分享统计报告
+///
+/// `format=csv` 时直接返回 CSV 文本，便于导入表格。
+pub async fn share_stats(
+    query: ValidQuery<ShareStatsQuery>,
+    env: web::Data<NetDiskEnv>,
+) -> Result<HttpResponse, Box<dyn Error>> {
+    let report = share_stats_report(&env, &query)?;
+    match query.format {
+        ReportFormat::Json => Ok(HttpResponse::Ok().json(ShareStatsReportResponse::new(
+            0,
+            "ok".to_string(),
+            report,
+            String::new(),
+        ))),
+        ReportFormat::Csv => Ok(HttpResponse::Ok()
+            .content_type("text/csv; charset=utf-8")
+            .insert_header((
+                "Content-Disposition",
+                "attachment; filename=\"share-stats.csv\"",
+            ))
+            .body(report_to_csv(&report))),
+    }
+}
@@ -0,0 +1,50 @@
+use actix_web::http::header;
+use actix_web::{web, HttpResponse};
+
+/// 编译时嵌入的网页文件管理器资源：(相对 `/static/` 的路径, Content-Type, 内容)
+const ASSETS: &[(&str, &str, &[u8])] = &[
+    (
+        "index.html",
+        "text/html; charset=utf-8",
+        include_bytes!(concat!(env!("CARGO_MANIFEST_DIR"), "/static/index.html")),
+    ),
+    (
+        "style.css",
+        "text/css; charset=utf-8",
+        include_bytes!(concat!(env!("CARGO_MANIFEST_DIR"), "/static/style.css")),
+    ),
+    (
+        "js/main.js",
+        "text/javascript; charset=utf-8",
+        include_bytes!(concat!(env!("CARGO_MANIFEST_DIR"), "/static/js/main.js")),
+    ),
+];
+
+/// 按路径查找嵌入的资源，空路径对应 `index.html`
+pub fn embedded_asset(path: &str) -> Option<(&'static str, &'static [u8])> {
+    let path = match path.trim_start_matches('/') {
+        "" => "index.html",
+        path => path,
+    };
+    ASSETS
+        .iter()
+        .find(|(name, _, _)| *name == path)
+        .map(|(_, content_type, body)| (*content_type, *body))
+}
+
+pub async fn asset(path: web::Path<String>) -> HttpResponse {
+    match embedded_asset(&path) {
+        Some((content_type, body)) => HttpResponse::Ok()
+            .content_type(content_type)
+            // 资源随二进制一起发布，升级后需重新校验
+            .insert_header((header::CACHE_CONTROL, "no-cache"))
+            .body(body),
+        None => HttpResponse::NotFound().finish(),
+    }
+}
+
+pub async fn index() -> HttpResponse {
+    HttpResponse::Found()
+        .insert_header((header::LOCATION, "/static/"))
+        .finish()
+}
@@ -0,0 +1,62 @@
+use super::validated::ValidJson;
+use crate::cache::ResponseCache;
+use crate::netdisk_auth::basic_env::NetDiskEnv;
+use crate::responses::prelude::*;
+use crate::sync::engine::run_sync;
+use crate::sync::mirror::run_mirror;
+use actix_web::{web, HttpResponse};
+use std::error::Error;
+use tracing::debug;
+
+/// # 本地目录与远端目录双向同步
+///
+/// 默认 `dryRun` 为 true，只返回同步计划；传入 `"dryRun": false` 才会执行并更新同步状态。
+pub async fn sync_folder(
+    payload: ValidJson<SyncItem>,
+    token: web::Data<AccessToken>,
+    env: web::Data<NetDiskEnv>,
+    cache: web::Data<ResponseCache>,
+) -> Result<HttpResponse, Box<dyn Error>> {
+    debug!("尝试发送信息:{:?}", &payload);
+    let report = run_sync(
+        &token,
+        &env,
+        &payload.local_path,
+        payload.remote_dir_id,
+        payload.dry_run,
+    )
+    .await?;
+    if !report.dry_run {
+        cache.invalidate_folders(&report.remote_dirs);
+        cache.invalidate_files(&report.remote_file_ids());
+    }
+    Ok(HttpResponse::Ok().json(SyncReportResponse::new(
+        0,
+        "ok".to_string(),
+        report,
+        String::new(),
+    )))
+}
+
+/// # 本地目录单向镜像（备份）到远端目录
+///
+/// 默认 `dryRun` 为 true，只返回镜像计划。
+pub async fn mirror_folder(
+    payload: ValidJson<MirrorItem>,
+    token: web::Data<AccessToken>,
+    env: web::Data<NetDiskEnv>,
+    cache: web::Data<ResponseCache>,
+) -> Result<HttpResponse, Box<dyn Error>> {
+    debug!("尝试发送信息:{:?}", &payload);
+    let report = run_mirror(&token, &env, &payload).await?;
+    if !report.dry_run {
+        cache.invalidate_folders(&report.remote_dirs);
+        cache.invalidate_files(&report.remote_file_ids());
+    }
+    Ok(HttpResponse::Ok().json(MirrorReportResponse::new(
+        0,
+        "ok".to_string(),
+        report,
+        String::new(),
+    )))
+}
@@ -0,0 +1,83 @@
+use crate::responses::prelude::*;
+use actix_web::error::InternalError;
+use actix_web::{dev::Payload, web, FromRequest, HttpRequest, HttpResponse};
+use futures_util::future::LocalBoxFuture;
+use serde::de::DeserializeOwned;
+use serde::{Serialize, Serializer};
+use std::fmt;
+use std::ops::Deref;
+
+/// 参数校验失败的 400 响应，`data` 为每个不合法字段的说明
+pub fn validation_error_response(errors: &FieldErrors) -> HttpResponse {
+    HttpResponse::BadRequest().json(ApiResponse::new(
+        400,
+        format!("请求参数校验失败：{}", errors),
+        errors.clone(),
+        String::new(),
+    ))
+}
+
+fn validation_error(errors: FieldErrors) -> actix_web::Error {
+    let response = validation_error_response(&errors);
+    InternalError::from_response(errors, response).into()
+}
+
+/// 反序列化后执行 [`Validate`] 的 JSON 请求体，格式错误或校验失败时返回 400
+pub struct ValidJson<T>(pub T);
+
+/// 反序列化后执行 [`Validate`] 的查询参数，格式错误或校验失败时返回 400
+pub struct ValidQuery<T>(pub T);
+
+macro_rules! validated_extractor {
+    ($name:ident, $inner:ident, $source:literal) => {
+        impl<T> $name<T> {
+            pub fn into_inner(self) -> T {
+                self.0
+            }
+        }
+
+        impl<T> Deref for $name<T> {
+            type Target = T;
+
+            fn deref(&self) -> &T {
+                &self.0
+            }
+        }
+
+        impl<T: fmt::Debug> fmt::Debug for $name<T> {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                self.0.fmt(f)
+            }
+        }
+
+        /// 与 `web::Json` 一样透明序列化，可直接转发给上游
+        impl<T: Serialize> Serialize for $name<T> {
+            fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                self.0.serialize(serializer)
+            }
+        }
+
+        impl<T> FromRequest for $name<T>
+        where
+            T: DeserializeOwned + Validate + 'static,
+        {
+            type Error = actix_web::Error;
+            type Future = LocalBoxFuture<'static, Result<Self, Self::Error>>;
+
+            fn from_request(req: &HttpRequest, payload: &mut Payload) -> Self::Future {
+                let extract = web::$inner::<T>::from_request(req, payload);
+                Box::pin(async move {
+                    let value = extract
+                        .await
+                        .map_err(|e| validation_error(FieldErrors::single($source, e.to_string())))?
+                        .into_inner();
+                    value.validate().map_err(validation_error)?;
+                    Ok($name(value))
+                })
+            }
+        }
+    };
+}
+
+validated_extractor!(ValidJson, Json, "body");
+validated_extractor!(ValidQuery, Query, "query");
@@ -0,0 +1,257 @@
+use super::base_api::*;
+use super::validated::{ValidJson, ValidQuery};
+use crate::local_db::open_local_db;
+use crate::netdisk_auth::basic_env::NetDiskEnv;
+use crate::responses::prelude::*;
+use crate::video::{
+    apply_result, choose_resolutions, load_transcode, load_transcodes, master_playlist,
+    rewrite_playlist, save_transcode, DEFAULT_CODEC,
+};
+use actix_web::http::StatusCode;
+use actix_web::{web, HttpRequest, HttpResponse};
+use chrono::Utc;
+use reqwest::Url;
+use serde_json::json;
+use std::error::Error;
+use tracing::{debug, info};
+
+/// m3u8 播放列表的 Content-Type
+const PLAYLIST_CONTENT_TYPE: &str = "application/vnd.apple.mpegurl";
+
+/// 将云盘中的视频上传到转码空间，转码前需先执行
+pub async fn upload_to_transcode_space(
+    token: &AccessToken,
+    file_id: u64,
+) -> Result<(), Box<dyn Error>> {
+    let item = TranscodeUploadItem {
+        file_ids: vec![TranscodeFileId { file_id }],
+    };
+    let api_response: ApiResponse<serde_json::Value> =
+        api_post(token, "api/v1/transcode/upload/from_cloud_disk", &item).await?;
+    if !api_response.is_ok() {
+        return Err(format!(
+            "上传到转码空间失败，code: {}，信息: {}",
+            api_response.code, api_response.message
+        )
+        .into());
+    }
+    Ok(())
+}
+
+/// 查询视频可转码的分辨率
+pub async fn video_resolutions(
+    token: &AccessToken,
+    file_id: u64,
+) -> Result<VideoResolutionsData, Box<dyn Error>> {
+    let api_response: VideoResolutionsResponse = api_post(
+        token,
+        "api/v1/transcode/video/resolutions",
+        &json!({ "fileId": file_id }),
+    )
+    .await?;
+    api_response.into_data()
+}
+
+/// 提交转码任务
+pub async fn submit_transcode(
+    token: &AccessToken,
+    item: &TranscodeVideoItem,
+) -> Result<(), Box<dyn Error>> {
+    let api_response: ApiResponse<serde_json::Value> =
+        api_post(token, "api/v1/transcode/video", item).await?;
+    if !api_response.is_ok() {
+        return Err(format!(
+            "提交转码失败，code: {}，信息: {}",
+            api_response.code, api_response.message
+        )
+        .into());
+    }
+    Ok(())
+}
+
+/// 查询各分辨率的转码结果
+pub async fn transcode_result(
+    token: &AccessToken,
+    file_id: u64,
+) -> Result<Vec<TranscodedVideo>, Box<dyn Error>> {
+    let api_response: TranscodeResultResponse = api_post(
+        token,
+        "api/v1/transcode/video/result",
+        &json!({ "fileId": file_id }),
+    )
+    .await?;
+    Ok(api_response.into_data()?.videos)
+}
+
+/// 查询上游转码结果并更新本地记录，没有记录时返回 `None`
+pub async fn refresh_transcode(
+    token: &AccessToken,
+    env: &NetDiskEnv,
+    file_id: u64,
+) -> Result<Option<VideoTranscode>, Box<dyn Error>> {
+    let conn = open_local_db(env)?;
+    let mut record = match load_transcode(&conn, file_id)? {
+        Some(record) => record,
+        None => return Ok(None),
+    };
+    if record.status != VideoTranscodeStatus::Transcoding {
+        return Ok(Some(record));
+    }
+    let videos = transcode_result(token, file_id).await?;
+    apply_result(&mut record, &videos, Utc::now());
+    save_transcode(&conn, &record)?;
+    Ok(Some(record))
+}
+
+fn transcode_response(record: VideoTranscode) -> HttpResponse {
+    HttpResponse::Ok().json(VideoTranscodeResponse::new(
+        0,
+        "ok".to_string(),
+        record,
+        String::new(),
+    ))
+}
+
+/// # 提交视频转码
+///
+/// 上游首次查询分辨率时才开始解析视频，尚未解析完成时返回 409，稍后重试即可。
+pub async fn video_transcode(
+    payload: ValidJson<VideoTranscodeItem>,
+    token: web::Data<AccessToken>,
+    env: web::Data<NetDiskEnv>,
+) -> Result<HttpResponse, Box<dyn Error>> {
+    debug!("尝试发送信息: {:?}", &payload);
+    let file_id = payload.file_id;
+    upload_to_transcode_space(&token, file_id).await?;
+    let info = video_resolutions(&token, file_id).await?;
+    if !info.is_get_resolution {
+        return Ok(error_response(
+            StatusCode::CONFLICT,
+            "正在解析视频分辨率，请稍后重试",
+        ));
+    }
+    let resolutions = match choose_resolutions(&info.resolutions, payload.resolutions.as_deref()) {
+        Ok(resolutions) => resolutions,
+        Err(message) => return Ok(error_response(StatusCode::BAD_REQUEST, message)),
+    };
+    let item = TranscodeVideoItem {
+        file_id,
+        codec_name: payload
+            .codec_name
+            .clone()
+            .unwrap_or_else(|| DEFAULT_CODEC.to_string()),
+        video_time: info.video_time,
+        resolutions: resolutions.join(","),
+    };
+    submit_transcode(&token, &item).await?;
+    info!("已提交转码 {}: {}", file_id, item.resolutions);
+
+    let now = Utc::now();
+    let record = VideoTranscode {
+        file_id,
+        status: VideoTranscodeStatus::Transcoding,
+        resolutions,
+        ready: Vec::new(),
+        failed: Vec::new(),
+        submitted_at: now,
+        updated_at: now,
+    };
+    save_transcode(&open_local_db(&env)?, &record)?;
+    Ok(transcode_response(record))
+}
+
+/// # 查询视频可转码的分辨率
+pub async fn video_resolutions_get(
+    path: web::Path<u64>,
+    token: web::Data<AccessToken>,
+) -> Result<HttpResponse, Box<dyn Error>> {
+    let info = video_resolutions(&token, path.into_inner()).await?;
+    Ok(HttpResponse::Ok().json(VideoResolutionsResponse::new(
+        0,
+        "ok".to_string(),
+        info,
+        String::new(),
+    )))
+}
+
+/// # 查询转码状态
+///
+/// 转码中的记录会先向上游查询一次结果再返回。
+pub async fn video_status(
+    path: web::Path<u64>,
+    token: web::Data<AccessToken>,
+    env: web::Data<NetDiskEnv>,
+) -> Result<HttpResponse, Box<dyn Error>> {
+    match refresh_transcode(&token, &env, path.into_inner()).await? {
+        Some(record) => Ok(transcode_response(record)),
+        None => Ok(error_response(StatusCode::NOT_FOUND, "该视频没有转码记录")),
+    }
+}
+
+/// # 本地记录的全部转码
+pub async fn video_transcodes(env: web::Data<NetDiskEnv>) -> Result<HttpResponse, Box<dyn Error>> {
+    let records = load_transcodes(&open_local_db(&env)?)?;
+    Ok(HttpResponse::Ok().json(VideoTranscodesResponse::new(
+        0,
+        "ok".to_string(),
+        records,
+        String::new(),
+    )))
+}
+
+/// # 视频播放列表
+///
+/// 不带 `resolution` 时返回包含全部已转码分辨率的主播放列表；
+/// 带 `resolution` 时返回该分辨率的 m3u8，分片地址改写为上游的绝对地址。
+pub async fn video_play(
+    req: HttpRequest,
+    path: web::Path<u64>,
+    query: ValidQuery<VideoPlayQuery>,
+    token: web::Data<AccessToken>,
+    env: web::Data<NetDiskEnv>,
+) -> Result<HttpResponse, Box<dyn Error>> {
+    let file_id = path.into_inner();
+    let record = match refresh_transcode(&token, &env, file_id).await? {
+        Some(record) => record,
+        None => return Ok(error_response(StatusCode::NOT_FOUND, "该视频没有转码记录")),
+    };
+    if record.ready.is_empty() {
+        return Ok(error_response(StatusCode::CONFLICT, "该视频尚未转码完成"));
+    }
+    let resolution = match &query.resolution {
+        Some(resolution) => resolution,
+        None => {
+            return Ok(HttpResponse::Ok()
+                .content_type(PLAYLIST_CONTENT_TYPE)
+                .body(master_playlist(req.path(), &record.ready)))
+        }
+    };
+
+    let videos = transcode_result(&token, file_id).await?;
+    let playlist_url = videos
+        .iter()
+        .filter(|video| video.resolution.eq_ignore_ascii_case(resolution))
+        .find_map(|video| video.playlist_url());
+    let playlist_url = match playlist_url {
+        Some(url) => Url::parse(url).map_err(|e| format!("播放地址格式错误: {}", e))?,
+        None => {
+            return Ok(error_response(
+                StatusCode::NOT_FOUND,
+                format!("分辨率 {} 没有可播放的转码结果", resolution),
+            ))
+        }
+    };
+    let response = reqwest::Client::new()
+        .get(playlist_url.clone())
+        .send()
+        .await
+        .map_err(|e| format!("请求发送失败: {}", e))?;
+    let status = response.status();
+    if !status.is_success() {
+        return Err(format!("获取播放列表失败，状态码: {}", status).into());
+    }
+    let content = response.text().await?;
+    Ok(HttpResponse::Ok()
+        .content_type(PLAYLIST_CONTENT_TYPE)
+        .body(rewrite_playlist(&content, &playlist_url)))
+}
@@ -0,0 +1,304 @@
+use super::base_api::error_response;
+use super::file_upload_api::upload_local_file;
+use super::validated::{ValidJson, ValidQuery};
+use crate::cache::ResponseCache;
+use crate::netdisk_auth::basic_env::NetDiskEnv;
+use crate::responses::prelude::*;
+use actix_web::http::StatusCode;
+use actix_web::{web, HttpResponse};
+use chrono::Utc;
+use futures_util::StreamExt;
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::SystemTime;
+use tokio::io::AsyncWriteExt;
+use tokio::sync::Mutex;
+use tracing::{debug, info, warn};
+
+/// 浏览器分块上传的暂存目录，位于配置目录下
+pub const WEB_UPLOAD_DIR: &str = "web-upload";
+const SESSION_FILE: &str = "session.json";
+const DATA_FILE: &str = "data";
+/// 各上传会话的锁，校验偏移量与写入分块须在同一把锁内完成，避免重试的分块被追加两次
+static SESSION_LOCKS: std::sync::Mutex<Option<BTreeMap<String, Arc<Mutex<()>>>>> =
+    std::sync::Mutex::new(None);
+
+fn session_response(session: WebUploadSession) -> HttpResponse {
+    HttpResponse::Ok().json(WebUploadSessionResponse::new(
+        0,
+        "ok".to_string(),
+        session,
+        String::new(),
+    ))
+}
+
+/// 会话 ID 只允许字母数字，避免拼接出配置目录之外的路径
+fn session_dir(env: &NetDiskEnv, upload_id: &str) -> Option<PathBuf> {
+    if upload_id.is_empty() || !upload_id.chars().all(|c| c.is_ascii_alphanumeric()) {
+        return None;
+    }
+    let dir = env.config_dir.join(WEB_UPLOAD_DIR).join(upload_id);
+    dir.is_dir().then_some(dir)
+}
+
+fn session_lock(upload_id: &str) -> Arc<Mutex<()>> {
+    SESSION_LOCKS
+        .lock()
+        .unwrap()
+        .get_or_insert_with(BTreeMap::new)
+        .entry(upload_id.to_string())
+        .or_default()
+        .clone()
+}
+
+/// 会话目录删除后移除对应的锁
+fn forget_session_lock(upload_id: &str) {
+    if let Some(locks) = SESSION_LOCKS.lock().unwrap().as_mut() {
+        locks.remove(upload_id);
+    }
+}
+
+async fn load_session(dir: &Path) -> Result<WebUploadSession, Box<dyn Error>> {
+    let mut session: WebUploadSession =
+        serde_json::from_slice(&tokio::fs::read(dir.join(SESSION_FILE)).await?)?;
+    session.received = tokio::fs::metadata(dir.join(DATA_FILE)).await?.len();
+    Ok(session)
+}
+
+/// # 创建分块上传会话
+pub async fn create_session(
+    payload: ValidJson<WebUploadItem>,
+    env: web::Data<NetDiskEnv>,
+) -> Result<HttpResponse, Box<dyn Error>> {
+    debug!("尝试发送信息: {:?}", &payload);
+    let nanos = Utc::now().timestamp_nanos_opt().unwrap_or_default() as u128;
+    let upload_id = format!(
+        "{}{}",
+        base62::encode(nanos),
+        base62::encode(u128::from(std::process::id()))
+    );
+    let dir = env.config_dir.join(WEB_UPLOAD_DIR).join(&upload_id);
+    tokio::fs::create_dir_all(&dir).await?;
+    let session = WebUploadSession {
+        upload_id,
+        parent_file_id: payload.parent_file_id,
+        filename: payload.filename.clone(),
+        size: payload.size,
+        duplicate: payload.duplicate,
+        received: 0,
+    };
+    tokio::fs::write(dir.join(SESSION_FILE), serde_json::to_vec(&session)?).await?;
+    tokio::fs::File::create(dir.join(DATA_FILE)).await?;
+    Ok(session_response(session))
+}
+
+/// # 查询上传会话，用于断点续传
+pub async fn session_status(
+    path: web::Path<String>,
+    env: web::Data<NetDiskEnv>,
+) -> Result<HttpResponse, Box<dyn Error>> {
+    match session_dir(&env, &path) {
+        Some(dir) => Ok(session_response(load_session(&dir).await?)),
+        None => Ok(error_response(
+            StatusCode::NOT_FOUND,
+            "上传会话不存在或已结束",
+        )),
+    }
+}
+
+/// # 追加一个分块
+///
+/// `offset` 必须等于已接收的字节数，不一致时返回 409 与当前会话状态。
+pub async fn append_chunk(
+    path: web::Path<String>,
+    query: ValidQuery<WebUploadChunkQuery>,
+    mut payload: web::Payload,
+    env: web::Data<NetDiskEnv>,
+) -> Result<HttpResponse, Box<dyn Error>> {
+    let dir = match session_dir(&env, &path) {
+        Some(dir) => dir,
+        None => {
+            return Ok(error_response(
+                StatusCode::NOT_FOUND,
+                "上传会话不存在或已结束",
+            ))
+        }
+    };
+    let lock = session_lock(&path);
+    let _guard = lock.lock().await;
+    if !dir.is_dir() {
+        return Ok(error_response(
+            StatusCode::NOT_FOUND,
+            "上传会话不存在或已结束",
+        ));
+    }
+    let session = load_session(&dir).await?;
+    if query.offset != session.received {
+        return Ok(HttpResponse::Conflict().json(WebUploadSessionResponse::new(
+            409,
+            format!("偏移量应为 {}", session.received),
+            session,
+            String::new(),
+        )));
+    }
+
+    let mut file = tokio::fs::OpenOptions::new()
+        .append(true)
+        .open(dir.join(DATA_FILE))
+        .await?;
+    let mut received = session.received;
+    while let Some(chunk) = payload.next().await {
+        let chunk = chunk?;
+        received += chunk.len() as u64;
+        if received > session.size {
+            file.flush().await?;
+            drop(file);
+            // 丢弃本次写入的部分，保留之前已确认的数据
+            tokio::fs::OpenOptions::new()
+                .write(true)
+                .open(dir.join(DATA_FILE))
+                .await?
+                .set_len(session.received)
+                .await?;
+            return Ok(error_response(
+                StatusCode::BAD_REQUEST,
+                format!("数据超出声明的文件大小 {}", session.size),
+            ));
+        }
+        file.write_all(&chunk).await?;
+    }
+    file.flush().await?;
+    Ok(session_response(WebUploadSession {
+        received,
+        ..session
+    }))
+}
+
+/// # 完成上传：合并后的文件走秒传/分片上传流程
+pub async fn complete_session(
+    path: web::Path<String>,
+    token: web::Data<AccessToken>,
+    env: web::Data<NetDiskEnv>,
+    cache: web::Data<ResponseCache>,
+) -> Result<HttpResponse, Box<dyn Error>> {
+    let dir = match session_dir(&env, &path) {
+        Some(dir) => dir,
+        None => {
+            return Ok(error_response(
+                StatusCode::NOT_FOUND,
+                "上传会话不存在或已结束",
+            ))
+        }
+    };
+    let lock = session_lock(&path);
+    let _guard = lock.lock().await;
+    if !dir.is_dir() {
+        return Ok(error_response(
+            StatusCode::NOT_FOUND,
+            "上传会话不存在或已结束",
+        ));
+    }
+    let session = load_session(&dir).await?;
+    if session.received != session.size {
+        return Ok(error_response(
+            StatusCode::CONFLICT,
+            format!(
+                "已接收 {} 字节，文件大小为 {}",
+                session.received, session.size
+            ),
+        ));
+    }
+    let outcome = upload_local_file(
+        &token,
+        &dir.join(DATA_FILE),
+        session.parent_file_id,
+        &session.filename,
+        Some(session.duplicate.unwrap_or(1)),
+    )
+    .await?;
+    info!(
+        "浏览器上传完成: {} ({} 字节) -> {}",
+        session.filename, session.size, outcome.file_id
+    );
+    cache.invalidate_folders(&[session.parent_file_id]);
+    tokio::fs::remove_dir_all(&dir).await?;
+    forget_session_lock(&path);
+    Ok(HttpResponse::Ok().json(WebUploadResultResponse::new(
+        0,
+        "ok".to_string(),
+        WebUploadResult {
+            file_id: outcome.file_id,
+            reused: outcome.reused,
+        },
+        String::new(),
+    )))
+}
+
+/// # 取消上传并清理暂存数据
+pub async fn abort_session(
+    path: web::Path<String>,
+    env: web::Data<NetDiskEnv>,
+) -> Result<HttpResponse, Box<dyn Error>> {
+    if let Some(dir) = session_dir(&env, &path) {
+        let lock = session_lock(&path);
+        let _guard = lock.lock().await;
+        if dir.is_dir() {
+            tokio::fs::remove_dir_all(&dir).await?;
+        }
+        forget_session_lock(&path);
+    }
+    Ok(HttpResponse::NoContent().finish())
+}
+
+/// 清理超过 `ttl` 未收到分块的上传会话，返回清理的数量
+pub async fn cleanup_web_uploads(
+    env: &NetDiskEnv,
+    ttl: std::time::Duration,
+) -> Result<usize, io::Error> {
+    let root = env.config_dir.join(WEB_UPLOAD_DIR);
+    let mut entries = match tokio::fs::read_dir(&root).await {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(0),
+        Err(e) => return Err(e),
+    };
+    let now = SystemTime::now();
+    let mut removed = 0;
+    while let Some(entry) = entries.next_entry().await? {
+        let dir = entry.path();
+        if !entry.file_type().await?.is_dir() {
+            continue;
+        }
+        let upload_id = entry.file_name().to_string_lossy().into_owned();
+        let lock = session_lock(&upload_id);
+        let guard = lock.lock().await;
+        // 每次追加分块都会更新数据文件的修改时间
+        let active_at = match tokio::fs::metadata(dir.join(DATA_FILE)).await {
+            Ok(metadata) => metadata.modified()?,
+            Err(_) => entry.metadata().await?.modified()?,
+        };
+        let expired = now.duration_since(active_at).map_or(false, |age| age > ttl);
+        if expired {
+            tokio::fs::remove_dir_all(&dir).await?;
+            drop(guard);
+            forget_session_lock(&upload_id);
+            removed += 1;
+        }
+    }
+    Ok(removed)
+}
+
+/// 网关运行期间定时清理过期的上传会话，启动时先清理一次
+pub async fn run_web_upload_cleanup_loop(env: NetDiskEnv, ttl: std::time::Duration) {
+    let mut ticker = tokio::time::interval(ttl.min(std::time::Duration::from_secs(3600)));
+    loop {
+        ticker.tick().await;
+        match cleanup_web_uploads(&env, ttl).await {
+            Ok(0) => {}
+            Ok(removed) => info!("已清理 {} 个过期的浏览器上传会话", removed),
+            Err(e) => warn!("清理浏览器上传会话失败: {}", e),
+        }
+    }
+}
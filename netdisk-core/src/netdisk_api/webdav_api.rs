@@ -0,0 +1,430 @@
+use super::file_api::{create_dir, fetch_download_url, split_dir_path};
+use super::file_delete_api::trash_files;
+use super::file_move_api::{move_files, rename_file};
+use super::file_upload_api::upload_local_file;
+use super::path_api::*;
+use crate::cache::ResponseCache;
+use crate::responses::prelude::*;
+use actix_web::http::{header, StatusCode};
+use actix_web::{web, HttpRequest, HttpResponse, HttpResponseBuilder};
+use chrono::Utc;
+use futures_util::StreamExt;
+use percent_encoding::{percent_decode_str, utf8_percent_encode, AsciiSet, CONTROLS};
+use std::error::Error;
+use tokio::io::AsyncWriteExt;
+use tracing::{debug, info};
+
+const ALLOWED_METHODS: &str = "OPTIONS, PROPFIND, GET, HEAD, PUT, MKCOL, MOVE, DELETE";
+
+/// href 中单个路径段需要编码的字符
+const PATH_SEGMENT: &AsciiSet = &CONTROLS
+    .add(b' ')
+    .add(b'"')
+    .add(b'#')
+    .add(b'%')
+    .add(b'&')
+    .add(b'<')
+    .add(b'>')
+    .add(b'?')
+    .add(b'`')
+    .add(b'{')
+    .add(b'}')
+    .add(b'/');
+
+/// 将请求路径去掉前缀并解码，返回 `/a/b` 形式的网盘路径；不在前缀下时返回 `None`
+pub fn dav_path(raw_path: &str, prefix: &str) -> Option<String> {
+    let rest = raw_path.strip_prefix(prefix)?;
+    if !rest.is_empty() && !rest.starts_with('/') {
+        return None;
+    }
+    let decoded = percent_decode_str(rest).decode_utf8_lossy();
+    Some(format!("/{}", split_dir_path(&decoded).join("/")))
+}
+
+/// 生成条目的 href，目录以 `/` 结尾
+pub fn dav_href(prefix: &str, path: &str, is_dir: bool) -> String {
+    let mut href = prefix.to_string();
+    for name in split_dir_path(path) {
+        href.push('/');
+        href.extend(utf8_percent_encode(name, PATH_SEGMENT));
+    }
+    if is_dir {
+        href.push('/');
+    }
+    href
+}
+
+pub fn xml_escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn content_type_of(name: &str) -> String {
+    let ext = name
+        .rsplit_once('.')
+        .map(|(_, ext)| ext)
+        .unwrap_or_default();
+    actix_files::file_extension_to_mime(ext).to_string()
+}
+
+fn http_date(node: &RemoteNode) -> Option<String> {
+    node.update_at().map(|t| {
+        t.with_timezone(&Utc)
+            .format("%a, %d %b %Y %H:%M:%S GMT")
+            .to_string()
+    })
+}
+
+/// 单个条目的 `<D:response>` 片段
+pub fn propfind_entry(href: &str, node: &RemoteNode) -> String {
+    let mut props = format!("<D:displayname>{}</D:displayname>", xml_escape(node.name()));
+    if node.is_dir() {
+        props.push_str("<D:resourcetype><D:collection/></D:resourcetype>");
+    } else {
+        props.push_str("<D:resourcetype/>");
+        props.push_str(&format!(
+            "<D:getcontentlength>{}</D:getcontentlength><D:getcontenttype>{}</D:getcontenttype><D:getetag>\"{}\"</D:getetag>",
+            node.size(),
+            content_type_of(node.name()),
+            xml_escape(node.etag())
+        ));
+    }
+    if let Some(date) = http_date(node) {
+        props.push_str(&format!("<D:getlastmodified>{}</D:getlastmodified>", date));
+    }
+    if let RemoteNode::Item(item) = node {
+        props.push_str(&format!(
+            "<D:creationdate>{}</D:creationdate>",
+            item.create_at.to_rfc3339()
+        ));
+    }
+    format!(
+        "<D:response><D:href>{}</D:href><D:propstat><D:prop>{}</D:prop><D:status>HTTP/1.1 200 OK</D:status></D:propstat></D:response>",
+        xml_escape(href),
+        props
+    )
+}
+
+/// 由多个 `<D:response>` 片段组成的 207 响应体
+pub fn multistatus(entries: &[String]) -> String {
+    format!(
+        "<?xml version=\"1.0\" encoding=\"utf-8\"?>\n<D:multistatus xmlns:D=\"DAV:\">{}</D:multistatus>",
+        entries.concat()
+    )
+}
+
+fn status(code: StatusCode) -> HttpResponse {
+    HttpResponse::build(code).finish()
+}
+
+fn options() -> HttpResponse {
+    HttpResponse::Ok()
+        .insert_header(("DAV", "1"))
+        .insert_header(("MS-Author-Via", "DAV"))
+        .insert_header((header::ALLOW, ALLOWED_METHODS))
+        .finish()
+}
+
+async fn propfind(
+    req: &HttpRequest,
+    token: &AccessToken,
+    prefix: &str,
+    path: &str,
+) -> Result<HttpResponse, Box<dyn Error>> {
+    let node = match resolve_path(token, path).await? {
+        Some(node) => node,
+        None => return Ok(status(StatusCode::NOT_FOUND)),
+    };
+    let mut entries = vec![propfind_entry(
+        &dav_href(prefix, path, node.is_dir()),
+        &node,
+    )];
+    // Depth: infinity 按 1 处理，避免一次请求遍历整个网盘
+    let depth = req
+        .headers()
+        .get("Depth")
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or("1");
+    if node.is_dir() && depth != "0" {
+        for item in list_dir(token, node.file_id()).await? {
+            let child_path = format!("{}/{}", path.trim_end_matches('/'), item.filename);
+            let child = RemoteNode::Item(item);
+            entries.push(propfind_entry(
+                &dav_href(prefix, &child_path, child.is_dir()),
+                &child,
+            ));
+        }
+    }
+    Ok(HttpResponse::build(StatusCode::MULTI_STATUS)
+        .content_type("application/xml; charset=utf-8")
+        .body(multistatus(&entries)))
+}
+
+/// GET 通过下载地址代理文件内容，并透传 `Range`；HEAD 只返回元数据
+async fn get(
+    req: &HttpRequest,
+    token: &AccessToken,
+    path: &str,
+    with_body: bool,
+) -> Result<HttpResponse, Box<dyn Error>> {
+    let node = match resolve_path(token, path).await? {
+        Some(node) => node,
+        None => return Ok(status(StatusCode::NOT_FOUND)),
+    };
+    if node.is_dir() {
+        return Ok(HttpResponse::MethodNotAllowed()
+            .insert_header((header::ALLOW, "OPTIONS, PROPFIND, MKCOL, MOVE, DELETE"))
+            .finish());
+    }
+
+    let mut builder = HttpResponse::Ok();
+    builder
+        .content_type(content_type_of(node.name()))
+        .insert_header((header::ACCEPT_RANGES, "bytes"))
+        .insert_header((header::ETAG, format!("\"{}\"", node.etag())));
+    if let Some(date) = http_date(&node) {
+        builder.insert_header((header::LAST_MODIFIED, date));
+    }
+    if !with_body {
+        return Ok(builder.no_chunking(node.size()).finish());
+    }
+    proxy_download(req, token, node.file_id(), builder).await
+}
+
+/// 通过下载地址代理文件内容，透传请求中的 `Range` 以及上游返回的状态码与 `Content-Range`
+pub async fn proxy_download(
+    req: &HttpRequest,
+    token: &AccessToken,
+    file_id: u64,
+    mut builder: HttpResponseBuilder,
+) -> Result<HttpResponse, Box<dyn Error>> {
+    let url = fetch_download_url(token, file_id).await?;
+    let mut upstream = reqwest::Client::new().get(&url);
+    if let Some(range) = req.headers().get(header::RANGE) {
+        upstream = upstream.header("Range", range.to_str()?);
+    }
+    let response = upstream.send().await?;
+    builder.status(StatusCode::from_u16(response.status().as_u16())?);
+    if let Some(content_range) = response.headers().get("Content-Range") {
+        builder.insert_header((header::CONTENT_RANGE, content_range.to_str()?));
+    }
+    if let Some(len) = response.content_length() {
+        builder.no_chunking(len);
+    }
+    Ok(builder.streaming(response.bytes_stream()))
+}
+
+/// PUT 先将请求体写入临时文件，再走秒传/分片上传流程，覆盖同名文件
+async fn put(
+    mut payload: web::Payload,
+    token: &AccessToken,
+    cache: &ResponseCache,
+    path: &str,
+) -> Result<HttpResponse, Box<dyn Error>> {
+    let (parent_path, name) = match split_parent(path) {
+        Some(parts) => parts,
+        None => return Ok(status(StatusCode::METHOD_NOT_ALLOWED)),
+    };
+    let parent = match resolve_path(token, &parent_path).await? {
+        Some(node) if node.is_dir() => node,
+        _ => return Ok(status(StatusCode::CONFLICT)),
+    };
+    let existing = list_dir(token, parent.file_id())
+        .await?
+        .into_iter()
+        .find(|item| item.filename == name);
+    if existing.as_ref().map_or(false, |item| item.r#type == 1) {
+        return Ok(status(StatusCode::METHOD_NOT_ALLOWED));
+    }
+
+    let temp = tempfile::Builder::new().prefix("netdisk-dav-").tempfile()?;
+    let mut file = tokio::fs::File::create(temp.path()).await?;
+    let mut written = 0u64;
+    while let Some(chunk) = payload.next().await {
+        let chunk = chunk?;
+        file.write_all(&chunk).await?;
+        written += chunk.len() as u64;
+    }
+    file.flush().await?;
+    drop(file);
+
+    // duplicate = 2：覆盖同名文件
+    let outcome = upload_local_file(token, temp.path(), parent.file_id(), name, Some(2)).await?;
+    info!(
+        "WebDAV 上传 {} ({} 字节) -> {}",
+        path, written, outcome.file_id
+    );
+    cache.invalidate_folders(&[parent.file_id()]);
+    cache.invalidate_files(&[outcome.file_id]);
+    if let Some(existing) = &existing {
+        cache.invalidate_files(&[existing.file_id as u64]);
+    }
+    Ok(status(if existing.is_some() {
+        StatusCode::NO_CONTENT
+    } else {
+        StatusCode::CREATED
+    }))
+}
+
+async fn mkcol(
+    token: &AccessToken,
+    cache: &ResponseCache,
+    path: &str,
+) -> Result<HttpResponse, Box<dyn Error>> {
+    let (parent_path, name) = match split_parent(path) {
+        Some(parts) => parts,
+        None => return Ok(status(StatusCode::METHOD_NOT_ALLOWED)),
+    };
+    let parent = match resolve_path(token, &parent_path).await? {
+        Some(node) if node.is_dir() => node,
+        _ => return Ok(status(StatusCode::CONFLICT)),
+    };
+    let exists = list_dir(token, parent.file_id())
+        .await?
+        .iter()
+        .any(|item| item.filename == name);
+    if exists {
+        return Ok(status(StatusCode::METHOD_NOT_ALLOWED));
+    }
+    create_dir(token, name, parent.file_id()).await?;
+    cache.invalidate_folders(&[parent.file_id()]);
+    Ok(status(StatusCode::CREATED))
+}
+
+/// `path` 是否为 `ancestor` 或位于其下
+pub fn is_same_or_within(path: &str, ancestor: &str) -> bool {
+    ancestor == "/" || path == ancestor || path.starts_with(&format!("{}/", ancestor))
+}
+
+/// 从 `Destination` 头中取出路径部分，兼容完整 URL 与绝对路径两种写法
+fn destination_path(destination: &str) -> &str {
+    match destination.split_once("://") {
+        Some((_, rest)) => rest.find('/').map_or("/", |i| &rest[i..]),
+        None => destination,
+    }
+}
+
+/// MOVE：跨目录时调用移动接口，名称不同时再调用重命名接口
+async fn move_entry(
+    req: &HttpRequest,
+    token: &AccessToken,
+    cache: &ResponseCache,
+    prefix: &str,
+    path: &str,
+) -> Result<HttpResponse, Box<dyn Error>> {
+    let destination = match req.headers().get("Destination") {
+        Some(value) => value.to_str()?,
+        None => return Ok(status(StatusCode::BAD_REQUEST)),
+    };
+    let dest = match dav_path(destination_path(destination), prefix) {
+        Some(dest) => dest,
+        None => return Ok(status(StatusCode::BAD_GATEWAY)),
+    };
+    let overwrite = req
+        .headers()
+        .get("Overwrite")
+        .map_or(true, |v| !v.as_bytes().eq_ignore_ascii_case(b"F"));
+
+    let source = match resolve_path(token, path).await? {
+        Some(RemoteNode::Item(item)) => item,
+        Some(RemoteNode::Root) => return Ok(status(StatusCode::FORBIDDEN)),
+        None => return Ok(status(StatusCode::NOT_FOUND)),
+    };
+    // 目录不能移动到自身的子目录中
+    if source.r#type == 1 && dest != path && is_same_or_within(&dest, path) {
+        return Ok(status(StatusCode::CONFLICT));
+    }
+    let (parent_path, name) = match split_parent(&dest) {
+        Some(parts) => parts,
+        None => return Ok(status(StatusCode::FORBIDDEN)),
+    };
+    let parent = match resolve_path(token, &parent_path).await? {
+        Some(node) if node.is_dir() => node,
+        _ => return Ok(status(StatusCode::CONFLICT)),
+    };
+    let existing = list_dir(token, parent.file_id())
+        .await?
+        .into_iter()
+        .find(|item| item.filename == name);
+    if let Some(existing) = &existing {
+        if existing.file_id == source.file_id {
+            return Ok(status(StatusCode::FORBIDDEN));
+        }
+        if !overwrite {
+            return Ok(status(StatusCode::PRECONDITION_FAILED));
+        }
+        trash_files(token, &[existing.file_id as u64]).await?;
+        cache.invalidate_files(&[existing.file_id as u64]);
+    }
+
+    let file_id = source.file_id as u64;
+    if source.parent_file_id != parent.file_id() {
+        move_files(token, &[file_id], parent.file_id()).await?;
+    }
+    if source.filename != name {
+        rename_file(token, file_id, name).await?;
+    }
+    cache.invalidate_files(&[file_id]);
+    cache.invalidate_folders(&[source.parent_file_id, parent.file_id()]);
+    Ok(status(if existing.is_some() {
+        StatusCode::NO_CONTENT
+    } else {
+        StatusCode::CREATED
+    }))
+}
+
+async fn delete_entry(
+    token: &AccessToken,
+    cache: &ResponseCache,
+    path: &str,
+) -> Result<HttpResponse, Box<dyn Error>> {
+    match resolve_path(token, path).await? {
+        Some(RemoteNode::Item(item)) => {
+            trash_files(token, &[item.file_id as u64]).await?;
+            cache.invalidate_files(&[item.file_id as u64]);
+            cache.invalidate_folders(&[item.parent_file_id]);
+            Ok(status(StatusCode::NO_CONTENT))
+        }
+        Some(RemoteNode::Root) => Ok(status(StatusCode::FORBIDDEN)),
+        None => Ok(status(StatusCode::NOT_FOUND)),
+    }
+}
+
+/// # WebDAV 入口
+///
+/// 按路径访问网盘，DELETE 只会将条目移入回收站。
+pub async fn webdav(
+    req: HttpRequest,
+    payload: web::Payload,
+    token: web::Data<AccessToken>,
+    gateway: web::Data<GatewayConfig>,
+    cache: web::Data<ResponseCache>,
+) -> Result<HttpResponse, Box<dyn Error>> {
+    let prefix = gateway.webdav.normalized_prefix();
+    let path = match dav_path(req.path(), &prefix) {
+        Some(path) => path,
+        None => return Ok(status(StatusCode::NOT_FOUND)),
+    };
+    debug!("WebDAV {} {}", req.method(), path);
+    match req.method().as_str() {
+        "OPTIONS" => Ok(options()),
+        "PROPFIND" => propfind(&req, &token, &prefix, &path).await,
+        "GET" => get(&req, &token, &path, true).await,
+        "HEAD" => get(&req, &token, &path, false).await,
+        "PUT" => put(payload, &token, &cache, &path).await,
+        "MKCOL" => mkcol(&token, &cache, &path).await,
+        "MOVE" => move_entry(&req, &token, &cache, &prefix, &path).await,
+        "DELETE" => delete_entry(&token, &cache, &path).await,
+        _ => Ok(HttpResponse::MethodNotAllowed()
+            .insert_header((header::ALLOW, ALLOWED_METHODS))
+            .finish()),
+    }
+}
+
+pub fn webdav_config(cfg: &mut web::ServiceConfig, config: &WebDavConfig) {
+    if config.enabled {
+        cfg.service(web::scope(&config.normalized_prefix()).default_service(web::to(webdav)));
+    }
+}
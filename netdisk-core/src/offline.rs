@@ -0,0 +1,286 @@
+use crate::io_basic::checksum::md5_hex;
+use crate::local_db::open_local_db;
+use crate::netdisk_api::file_api::ensure_dir;
+use crate::netdisk_api::limit::RateLimiter;
+use crate::netdisk_api::offline_api::{create_offline_task, offline_task_process};
+use crate::netdisk_api::path_api::list_dir;
+use crate::netdisk_auth::basic_env::NetDiskEnv;
+use crate::responses::prelude::*;
+use chrono::Utc;
+use log::{info, warn};
+use rusqlite::{params, Connection, OptionalExtension, Row};
+use std::collections::HashSet;
+use std::error::Error;
+use std::path::Path;
+
+/// 上游支持的链接协议
+const SUPPORTED_SCHEMES: [&str; 3] = ["http://", "https://", "magnet:?"];
+
+/// 是否为上游支持的离线下载链接
+pub fn is_supported_url(url: &str) -> bool {
+    let lower = url.to_ascii_lowercase();
+    SUPPORTED_SCHEMES
+        .iter()
+        .any(|scheme| lower.starts_with(scheme))
+}
+
+/// 读取链接文件：每行一个链接，忽略空行与 `#` 开头的注释
+pub fn read_links_file(path: &Path) -> Result<Vec<OfflineLink>, std::io::Error> {
+    let content = std::fs::read_to_string(path)?;
+    let source = format!("file:{}", path.display());
+    Ok(content
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(|line| OfflineLink {
+            url: line.to_string(),
+            file_name: None,
+            source: Some(source.clone()),
+        })
+        .collect())
+}
+
+/// 从 python 流水线生成的 SQLite 目录（`movies` 表）读取磁力链接
+pub fn catalog_links(db_path: &Path, limit: Option<usize>) -> rusqlite::Result<Vec<OfflineLink>> {
+    let conn = Connection::open_with_flags(db_path, rusqlite::OpenFlags::SQLITE_OPEN_READ_ONLY)?;
+    let mut stmt = conn.prepare(
+        "SELECT id, magnetlink FROM movies
+         WHERE magnetlink IS NOT NULL AND magnetlink != ''
+         ORDER BY id LIMIT ?1",
+    )?;
+    let limit = limit.map_or(-1, |limit| limit as i64);
+    let rows = stmt.query_map(params![limit], |row| {
+        Ok(OfflineLink {
+            url: row.get::<_, String>(1)?.trim().to_string(),
+            file_name: None,
+            source: Some(format!("catalog:{}", row.get::<_, i64>(0)?)),
+        })
+    })?;
+    rows.collect()
+}
+
+fn task_from_row(row: &Row) -> rusqlite::Result<OfflineTask> {
+    let status: String = row.get(6)?;
+    let file_ids: String = row.get(9)?;
+    Ok(OfflineTask {
+        task_id: row.get::<_, i64>(0)? as u64,
+        url: row.get(1)?,
+        file_name: row.get(2)?,
+        dir_id: row.get::<_, Option<i64>>(3)?.map(|id| id as u64),
+        source: row.get(4)?,
+        submitted_at: row.get(5)?,
+        status: OfflineTaskStatus::parse(&status).unwrap_or(OfflineTaskStatus::Downloading),
+        process: row.get(7)?,
+        updated_at: row.get(8)?,
+        file_ids: file_ids
+            .split(',')
+            .filter_map(|id| id.parse().ok())
+            .collect(),
+    })
+}
+
+const TASK_COLUMNS: &str = "task_id, url, file_name, dir_id, source, submitted_at, status, \
+                            process, updated_at, file_ids";
+
+/// 写入或覆盖一条任务记录
+pub fn save_task(conn: &Connection, task: &OfflineTask) -> rusqlite::Result<()> {
+    let file_ids: Vec<String> = task.file_ids.iter().map(u64::to_string).collect();
+    conn.execute(
+        &format!(
+            "INSERT OR REPLACE INTO offline_tasks ({}) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+            TASK_COLUMNS
+        ),
+        params![
+            task.task_id as i64,
+            task.url,
+            task.file_name,
+            task.dir_id.map(|id| id as i64),
+            task.source,
+            task.submitted_at,
+            task.status.as_str(),
+            task.process,
+            task.updated_at,
+            file_ids.join(","),
+        ],
+    )?;
+    Ok(())
+}
+
+/// 读取任务记录，`pending` 为 true 时只返回未结束的任务
+pub fn load_tasks(conn: &Connection, pending: bool) -> rusqlite::Result<Vec<OfflineTask>> {
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {} FROM offline_tasks
+         WHERE ?1 = 0 OR status NOT IN ('failed', 'succeeded')
+         ORDER BY submitted_at, task_id",
+        TASK_COLUMNS
+    ))?;
+    let rows = stmt.query_map(params![pending], task_from_row)?;
+    rows.collect()
+}
+
+/// 该链接是否已经提交过（失败的任务允许重新提交）
+pub fn already_submitted(conn: &Connection, url: &str) -> rusqlite::Result<bool> {
+    conn.query_row(
+        "SELECT 1 FROM offline_tasks WHERE url = ?1 AND status != 'failed' LIMIT 1",
+        params![url],
+        |_| Ok(()),
+    )
+    .optional()
+    .map(|found| found.is_some())
+}
+
+/// 每个任务专属的下载目录名，由链接决定，重新提交失败的链接时复用同一目录
+pub fn task_dir_name(url: &str) -> String {
+    format!("offline-{}", &md5_hex(url.as_bytes())[..12])
+}
+
+/// 任务下载得到的文件：任务目录中的全部条目
+pub fn task_result_files(items: &[FileItem]) -> Vec<u64> {
+    items.iter().map(|item| item.file_id as u64).collect()
+}
+
+/// 逐个提交链接并写入本地记录，已提交过或协议不支持的链接会被跳过；
+/// `task_dirs` 为 true 时每个任务下载到 `dir_id`（默认根目录）下的专属目录中，完成后据此确定下载得到的文件，
+/// 否则直接下载到 `dir_id`，不记录下载得到的文件
+pub async fn submit_links(
+    token: &AccessToken,
+    env: &NetDiskEnv,
+    links: Vec<OfflineLink>,
+    dir_id: Option<u64>,
+    task_dirs: bool,
+    limiter: &mut RateLimiter,
+) -> Result<OfflineBulkReport, Box<dyn Error>> {
+    let conn = open_local_db(env)?;
+    let mut report = OfflineBulkReport::default();
+    let mut seen = HashSet::new();
+    for link in links {
+        if !is_supported_url(&link.url) {
+            report.skipped.push(OfflineSkipped {
+                url: link.url,
+                reason: "只支持 http(s) 与磁力链接".to_string(),
+            });
+            continue;
+        }
+        if !seen.insert(link.url.clone()) || already_submitted(&conn, &link.url)? {
+            report.skipped.push(OfflineSkipped {
+                url: link.url,
+                reason: "链接已提交过".to_string(),
+            });
+            continue;
+        }
+
+        limiter.wait().await;
+        let task_dir_id = if task_dirs {
+            match ensure_dir(token, &task_dir_name(&link.url), dir_id.unwrap_or(0)).await {
+                Ok(task_dir_id) => Some(task_dir_id),
+                Err(e) => {
+                    report.failed.push(OfflineSkipped {
+                        url: link.url,
+                        reason: format!("创建任务目录失败: {}", e),
+                    });
+                    continue;
+                }
+            }
+        } else {
+            None
+        };
+        let item = OfflineDownloadItem {
+            url: link.url.clone(),
+            file_name: link.file_name.clone(),
+            dir_id: task_dir_id.or(dir_id),
+            call_back_url: None,
+        };
+        match create_offline_task(token, &item).await {
+            Ok(task_id) => {
+                let now = Utc::now();
+                let task = OfflineTask {
+                    task_id,
+                    url: link.url,
+                    file_name: link.file_name,
+                    dir_id: task_dir_id,
+                    source: link.source,
+                    submitted_at: now,
+                    status: OfflineTaskStatus::Downloading,
+                    process: 0.0,
+                    updated_at: now,
+                    file_ids: Vec::new(),
+                };
+                save_task(&conn, &task)?;
+                info!("已提交离线下载任务 {}: {}", task.task_id, task.url);
+                report.submitted.push(task);
+            }
+            Err(e) => report.failed.push(OfflineSkipped {
+                url: link.url,
+                reason: e.to_string(),
+            }),
+        }
+    }
+    Ok(report)
+}
+
+/// 查询一次全部未结束任务的进度，返回本次状态有变化的任务
+pub async fn poll_once(
+    token: &AccessToken,
+    env: &NetDiskEnv,
+) -> Result<Vec<OfflineTask>, Box<dyn Error>> {
+    let conn = open_local_db(env)?;
+    let pending = load_tasks(&conn, true)?;
+    let mut changed = Vec::new();
+    for mut task in pending {
+        let progress = match offline_task_process(token, task.task_id).await {
+            Ok(progress) => progress,
+            Err(e) => {
+                warn!("查询离线下载任务 {} 失败: {}", task.task_id, e);
+                continue;
+            }
+        };
+        let status = OfflineTaskStatus::from_code(progress.status);
+        if status == task.status && (progress.process - task.process).abs() < f64::EPSILON {
+            continue;
+        }
+        task.status = status;
+        task.process = progress.process;
+        task.updated_at = Utc::now();
+        if status == OfflineTaskStatus::Succeeded {
+            match task.dir_id {
+                Some(dir_id) => match list_dir(token, dir_id).await {
+                    Ok(items) => task.file_ids = task_result_files(&items),
+                    Err(e) => {
+                        // 保持未完成状态，下次轮询时重新读取任务目录
+                        warn!("读取离线下载任务 {} 的目录失败: {}", task.task_id, e);
+                        continue;
+                    }
+                },
+                None => warn!(
+                    "离线下载任务 {} 没有专属目录，无法确定下载得到的文件",
+                    task.task_id
+                ),
+            }
+            info!("离线下载完成 {}: {:?}", task.url, task.file_ids);
+        } else if status == OfflineTaskStatus::Failed {
+            warn!("离线下载失败 {}", task.url);
+        }
+        save_task(&conn, &task)?;
+        changed.push(task);
+    }
+    Ok(changed)
+}
+
+/// 持续轮询直到没有未结束的任务
+pub async fn run_poller(
+    token: &AccessToken,
+    env: &NetDiskEnv,
+    interval: std::time::Duration,
+) -> Result<Vec<OfflineTask>, Box<dyn Error>> {
+    let mut finished = Vec::new();
+    loop {
+        let changed = poll_once(token, env).await?;
+        finished.extend(changed.into_iter().filter(|task| task.status.is_finished()));
+        let conn = open_local_db(env)?;
+        if load_tasks(&conn, true)?.is_empty() {
+            return Ok(finished);
+        }
+        drop(conn);
+        tokio::time::sleep(interval).await;
+    }
+}
@@ -0,0 +1,718 @@
+use crate::netdisk_api::prelude::*;
+use crate::responses::prelude::*;
+use actix_web::http::Method;
+use actix_web::{web, FromRequest, Handler, Responder};
+use utoipa::openapi::path::{HttpMethod, OperationBuilder, ParameterBuilder, ParameterIn};
+use utoipa::openapi::request_body::RequestBodyBuilder;
+use utoipa::openapi::schema::{ComponentsBuilder, Schema};
+use utoipa::openapi::{
+    Content, InfoBuilder, OpenApi, OpenApiBuilder, Paths, RefOr, Required, ResponseBuilder,
+};
+use utoipa::{PartialSchema, ToSchema};
+
+/// OpenAPI 文档的地址
+pub const OPENAPI_PATH: &str = "/openapi.json";
+
+/// 交互式接口文档页面的地址
+pub const DOCS_PATH: &str = "/docs";
+
+/// actix 路由模板转换为 OpenAPI 路径：`/static/{path:.*}` 变为 `/static/{path}`
+pub fn openapi_path(template: &str) -> String {
+    template
+        .split('/')
+        .map(
+            |segment| match (segment.strip_prefix('{'), segment.find(':')) {
+                (Some(name), Some(colon)) if segment.ends_with('}') => {
+                    format!("{{{}}}", &name[..colon - 1])
+                }
+                _ => segment.to_string(),
+            },
+        )
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// 路径中的 `{name}` 参数名
+fn path_parameters(path: &str) -> Vec<&str> {
+    path.split('/')
+        .filter_map(|segment| segment.strip_prefix('{')?.strip_suffix('}'))
+        .collect()
+}
+
+/// 逐个登记接口，同时收集请求与响应中引用的类型；给定 `cfg` 时也把处理函数注册到应用
+#[derive(Default)]
+struct SpecBuilder<'a> {
+    cfg: Option<&'a mut web::ServiceConfig>,
+    paths: Paths,
+    schemas: Vec<(String, RefOr<Schema>)>,
+}
+
+impl SpecBuilder<'_> {
+    fn schema<T: ToSchema>(&mut self) -> RefOr<Schema> {
+        T::schemas(&mut self.schemas);
+        T::schema()
+    }
+
+    fn json_content<T: ToSchema>(&mut self) -> Content {
+        Content::new(Some(self.schema::<T>()))
+    }
+
+    /// 登记接口；带 `{name}` 的路径自动加上路径参数，`fileID` 类参数为整数。
+    /// `template` 为 actix 路由模板，返回值须以 `to` 绑定处理函数
+    fn operation(
+        &mut self,
+        method: HttpMethod,
+        template: &str,
+        tag: &str,
+        summary: &str,
+        operation: OperationBuilder,
+    ) -> Endpoint<'_> {
+        let path = openapi_path(template);
+        let mut operation = operation.tag(tag).summary(Some(summary));
+        for name in path_parameters(&path) {
+            let schema = if name.ends_with("_id") && name != "upload_id" {
+                u64::schema()
+            } else {
+                String::schema()
+            };
+            operation = operation.parameter(
+                ParameterBuilder::new()
+                    .name(name)
+                    .parameter_in(ParameterIn::Path)
+                    .required(Required::True)
+                    .schema(Some(schema)),
+            );
+        }
+        self.paths
+            .add_path_operation(path, vec![method.clone()], operation.build());
+        Endpoint {
+            cfg: self.cfg.as_deref_mut(),
+            method,
+            template: template.to_string(),
+        }
+    }
+
+    /// 返回 `R` 的 JSON 接口
+    fn returns<R: ToSchema>(&mut self, operation: OperationBuilder) -> OperationBuilder {
+        let content = self.json_content::<R>();
+        operation.response(
+            "200",
+            ResponseBuilder::new()
+                .description("`code` 为 0 表示成功")
+                .content("application/json", content),
+        )
+    }
+
+    /// 请求参数校验失败时的 400 响应
+    fn rejects(&mut self, operation: OperationBuilder) -> OperationBuilder {
+        let content = self.json_content::<ApiResponse<FieldErrors>>();
+        operation.response(
+            "400",
+            ResponseBuilder::new()
+                .description("请求参数校验失败，`data` 列出每个不合法的字段")
+                .content("application/json", content),
+        )
+    }
+
+    /// 以 `B` 为 JSON 请求体的接口
+    fn json<B: ToSchema, R: ToSchema>(
+        &mut self,
+        method: HttpMethod,
+        path: &str,
+        tag: &str,
+        summary: &str,
+    ) -> Endpoint<'_> {
+        let operation = self.with_body::<B>(OperationBuilder::new());
+        let operation = self.returns::<R>(operation);
+        let operation = self.rejects(operation);
+        self.operation(method, path, tag, summary, operation)
+    }
+
+    /// 以 `Q` 的字段为查询参数的接口
+    fn query<Q: ToSchema, R: ToSchema>(
+        &mut self,
+        method: HttpMethod,
+        path: &str,
+        tag: &str,
+        summary: &str,
+    ) -> Endpoint<'_> {
+        let operation = self.with_query::<Q>(OperationBuilder::new());
+        let operation = self.returns::<R>(operation);
+        let operation = self.rejects(operation);
+        self.operation(method, path, tag, summary, operation)
+    }
+
+    /// 除路径参数外没有其他参数的接口
+    fn plain<R: ToSchema>(
+        &mut self,
+        method: HttpMethod,
+        path: &str,
+        tag: &str,
+        summary: &str,
+    ) -> Endpoint<'_> {
+        let operation = self.returns::<R>(OperationBuilder::new());
+        self.operation(method, path, tag, summary, operation)
+    }
+
+    fn with_body<B: ToSchema>(&mut self, operation: OperationBuilder) -> OperationBuilder {
+        let content = self.json_content::<B>();
+        operation.request_body(Some(
+            RequestBodyBuilder::new()
+                .content("application/json", content)
+                .required(Some(Required::True))
+                .build(),
+        ))
+    }
+
+    fn with_query<Q: ToSchema>(&mut self, mut operation: OperationBuilder) -> OperationBuilder {
+        if let RefOr::T(Schema::Object(object)) = self.schema::<Q>() {
+            for (name, schema) in object.properties {
+                let required = if object.required.contains(&name) {
+                    Required::True
+                } else {
+                    Required::False
+                };
+                operation = operation.parameter(
+                    ParameterBuilder::new()
+                        .name(name)
+                        .parameter_in(ParameterIn::Query)
+                        .required(required)
+                        .schema(Some(schema)),
+                );
+            }
+        }
+        operation
+    }
+
+    fn build(self) -> OpenApi {
+        OpenApiBuilder::new()
+            .info(
+                InfoBuilder::new()
+                    .title("123 云盘网关")
+                    .version(env!("CARGO_PKG_VERSION"))
+                    .description(Some(
+                        "WebDAV、S3 兼容接口与 `/d/<路径>` 直链跳转的前缀可在 `gateway.toml` 中配置，不在本文档中列出。",
+                    ))
+                    .build(),
+            )
+            .paths(self.paths)
+            .components(Some(
+                ComponentsBuilder::new()
+                    .schemas_from_iter(self.schemas)
+                    .build(),
+            ))
+            .build()
+    }
+}
+
+/// 已写入文档、尚未绑定处理函数的接口
+#[must_use = "接口须以 `to` 绑定处理函数"]
+struct Endpoint<'a> {
+    cfg: Option<&'a mut web::ServiceConfig>,
+    method: HttpMethod,
+    template: String,
+}
+
+impl Endpoint<'_> {
+    /// 绑定处理函数；只生成文档时忽略
+    fn to<F, Args>(self, handler: F)
+    where
+        F: Handler<Args>,
+        Args: FromRequest + 'static,
+        F::Output: Responder + 'static,
+    {
+        if let Some(cfg) = self.cfg {
+            let method = match self.method {
+                HttpMethod::Get => Method::GET,
+                HttpMethod::Post => Method::POST,
+                HttpMethod::Put => Method::PUT,
+                HttpMethod::Delete => Method::DELETE,
+                HttpMethod::Options => Method::OPTIONS,
+                HttpMethod::Head => Method::HEAD,
+                HttpMethod::Patch => Method::PATCH,
+                HttpMethod::Trace => Method::TRACE,
+            };
+            cfg.route(&self.template, web::method(method).to(handler));
+        }
+    }
+}
+
+/// 描述网关全部接口的 OpenAPI 3 文档，与 `gateway_routes` 注册的路由出自同一张路由表
+pub fn gateway_openapi() -> OpenApi {
+    let mut spec = SpecBuilder::default();
+    route_table(&mut spec);
+    spec.build()
+}
+
+/// 注册路由表中的全部接口；WebDAV、S3 与直链跳转的前缀可配置，另行注册
+pub fn gateway_routes(cfg: &mut web::ServiceConfig) {
+    route_table(&mut SpecBuilder {
+        cfg: Some(cfg),
+        ..SpecBuilder::default()
+    });
+}
+
+/// 网关的路由表：每个接口的方法、路径、文档与处理函数
+fn route_table(spec: &mut SpecBuilder) {
+    use HttpMethod::{Delete, Get, Post, Put};
+
+    spec.json::<AuthConfig, AccessTokenResponse>(
+        Post,
+        "/access_token",
+        "认证",
+        "获取 access_token 并写入配置目录",
+    )
+    .to(access_token_and_cache);
+    spec.plain::<UserInfoResponse>(Get, "/user_info", "认证", "用户信息")
+        .to(user_info);
+
+    spec.query::<FileListQuery, FileListResponse>(
+        Get,
+        "/file/file_lists_query",
+        "文件",
+        "获取目录下的文件列表",
+    )
+    .to(file_lists_query);
+    spec.query::<FileQuery, FileResponse>(Get, "/file/file_query", "文件", "获取单个文件详情")
+        .to(file_query);
+    spec.json::<FilesQuery, FilesInfoResponse>(
+        Post,
+        "/file/files_info",
+        "文件",
+        "获取多个文件详情",
+    )
+    .to(files_info);
+    spec.query::<FileSearchItem, FileSearchResponse>(Get, "/file_search", "文件", "搜索文件")
+        .to(file_search);
+    spec.json::<EntryItem, PathInfoResponse>(Post, "/file/mkdir", "文件", "创建目录")
+        .to(mkdir);
+    spec.json::<MkdirPathItem, PathInfoResponse>(Post, "/file/mkdir_p", "文件", "幂等创建多级目录")
+        .to(mkdir_p);
+    spec.json::<FileRenameItem, ApiResponse<()>>(Put, "/file/rename", "文件", "修改单个文件名称")
+        .to(rename);
+    spec.json::<FileMoveInfo, ApiResponse<()>>(Post, "/file/move", "文件", "移动文件")
+        .to(move_file);
+    spec.json::<CopyItem, CopyReportResponse>(Post, "/file/copy", "文件", "复制目录")
+        .to(copy_folder);
+    spec.json::<UploadFileItem, UploadFileResponse>(
+        Post,
+        "/file/upload",
+        "文件",
+        "上传网关所在机器上的本地文件",
+    )
+    .to(file_upload);
+    let fetch = spec
+        .with_query::<FileQuery>(OperationBuilder::new())
+        .response(
+            "302",
+            ResponseBuilder::new().description("跳转到文件的下载地址"),
+        );
+    let fetch = spec.rejects(fetch);
+    spec.operation(Get, "/file/download", "文件", "下载文件", fetch)
+        .to(download);
+
+    spec.json::<FilesQuery, ApiResponse<()>>(Post, "/trash", "回收站", "移入回收站")
+        .to(trash);
+    spec.json::<FilesQuery, ApiResponse<()>>(Post, "/recover", "回收站", "从回收站恢复")
+        .to(recover);
+    spec.json::<GuardedDeleteItem, DeletePreflightResponse>(
+        Post,
+        "/delete/preflight",
+        "回收站",
+        "彻底删除预检",
+    )
+    .to(delete_preflight);
+    spec.json::<GuardedDeleteItem, DeletePreflightResponse>(
+        Post,
+        "/delete",
+        "回收站",
+        "彻底删除文件",
+    )
+    .to(delete);
+    spec.query::<TrashQuery, TrashListResponse>(
+        Get,
+        "/recycle/list",
+        "回收站",
+        "获取回收站中的文件列表",
+    )
+    .to(trash_list);
+    spec.json::<PurgeItem, PurgeResponse>(Post, "/recycle/purge", "回收站", "彻底清理回收站")
+        .to(purge);
+
+    spec.json::<BatchTrashItem, BatchReportResponse>(
+        Post,
+        "/batch/trash",
+        "批量操作",
+        "批量移入回收站",
+    )
+    .to(batch_trash);
+    spec.json::<BatchMoveItem, BatchReportResponse>(
+        Post,
+        "/batch/move",
+        "批量操作",
+        "批量移动文件",
+    )
+    .to(batch_move);
+
+    spec.json::<ShareItem, SharedDataResponse>(Post, "/share/create", "分享", "创建分享链接")
+        .to(share_create);
+    spec.query::<ShareQuery, SharedListDataResponse>(
+        Get,
+        "/share/list",
+        "分享",
+        "获取分享链接列表（单页）",
+    )
+    .to(share_list);
+    spec.json::<ShareLinkItem, ApiResponse<()>>(
+        Put,
+        "/share/list/info",
+        "分享",
+        "修改分享链接信息",
+    )
+    .to(share_list_info);
+    spec.json::<ShareCreateItem, ShareLinkResponse>(
+        Post,
+        "/share/link",
+        "分享",
+        "以文件 ID 数组创建分享",
+    )
+    .to(share_link_create);
+    spec.plain::<ShareLinkResponse>(
+        Get,
+        "/share/link/{share_key}",
+        "分享",
+        "由 shareKey 查询分享的文件与完整链接",
+    )
+    .to(share_link_resolve);
+    spec.query::<ShareByFileQuery, ShareLinksResponse>(
+        Get,
+        "/share/by-file",
+        "分享",
+        "查询哪些分享包含指定文件",
+    )
+    .to(share_by_file);
+    spec.plain::<ShareItemsResponse>(Get, "/share/all", "分享", "获取全部分享（自动翻页）")
+        .to(share_all);
+    spec.query::<ShareExpiringQuery, ShareItemsResponse>(
+        Get,
+        "/share/expiring",
+        "分享",
+        "查找 N 天内到期的分享",
+    )
+    .to(share_expiring);
+    spec.json::<ShareCancelItem, BatchReportResponse>(
+        Post,
+        "/share/cancel",
+        "分享",
+        "批量取消分享",
+    )
+    .to(share_cancel);
+    spec.json::<ShareTrafficItem, BatchReportResponse>(
+        Post,
+        "/share/traffic",
+        "分享",
+        "批量修改分享的流量包设置",
+    )
+    .to(share_traffic);
+    spec.json::<ShareRecreateItem, ShareRecreateResponse>(
+        Post,
+        "/share/recreate",
+        "分享",
+        "重建已过期的分享",
+    )
+    .to(share_recreate);
+    spec.plain::<ShareSnapshotResponse>(
+        Post,
+        "/share/stats/snapshot",
+        "分享",
+        "立即记录一次分享统计快照",
+    )
+    .to(share_stats_snapshot);
+    spec.query::<ShareStatsQuery, ShareStatsReportResponse>(
+        Get,
+        "/share/stats/report",
+        "分享",
+        "分享统计报告（`format=csv` 时返回 CSV）",
+    )
+    .to(share_stats);
+
+    spec.json::<PayLinkItem, SharedDataResponse>(
+        Post,
+        "/share/content-payment/create",
+        "付费分享",
+        "创建付费分享链接",
+    )
+    .to(pay_link);
+    spec.json::<PayLinkItem, SharedDataResponse>(
+        Put,
+        "/share/content-payment/creat",
+        "付费分享",
+        "创建付费分享链接（兼容旧的路径与方法）",
+    )
+    .to(pay_link);
+    spec.json::<ShareLinkItem, ApiResponse<()>>(
+        Put,
+        "/share/list/payment/info",
+        "付费分享",
+        "修改付费分享文件信息",
+    )
+    .to(change_share_list_info);
+    spec.query::<ShareQuery, PayShareDataResponse>(
+        Get,
+        "/share/payment/list",
+        "付费分享",
+        "获取付费分享链接列表（单页）",
+    )
+    .to(payment_list);
+    spec.plain::<PayShareItemsResponse>(
+        Get,
+        "/share/payment/all",
+        "付费分享",
+        "获取全部付费分享（自动翻页）",
+    )
+    .to(payment_all);
+    spec.json::<ShareTrafficItem, BatchReportResponse>(
+        Post,
+        "/share/payment/traffic",
+        "付费分享",
+        "批量修改付费分享的流量包设置",
+    )
+    .to(payment_traffic);
+    spec.plain::<PaidShareEarningsResponse>(
+        Get,
+        "/share/payment/earnings",
+        "付费分享",
+        "付费分享收益汇总",
+    )
+    .to(payment_earnings);
+
+    spec.json::<SyncItem, SyncReportResponse>(Post, "/sync", "同步", "本地目录与远端目录双向同步")
+        .to(sync_folder);
+    spec.json::<MirrorItem, MirrorReportResponse>(
+        Post,
+        "/sync/mirror",
+        "同步",
+        "本地目录单向镜像（备份）到远端目录",
+    )
+    .to(mirror_folder);
+
+    spec.json::<OfflineDownloadItem, OfflineBulkResponse>(
+        Post,
+        "/offline/download",
+        "离线下载",
+        "创建单个离线下载任务",
+    )
+    .to(offline_download);
+    spec.json::<OfflineBulkItem, OfflineBulkResponse>(
+        Post,
+        "/offline/bulk",
+        "离线下载",
+        "批量提交离线下载任务",
+    )
+    .to(offline_bulk);
+    spec.query::<OfflineProcessQuery, OfflineProcessResponse>(
+        Get,
+        "/offline/process",
+        "离线下载",
+        "查询离线下载进度",
+    )
+    .to(offline_process);
+    spec.query::<OfflineTasksQuery, OfflineTasksResponse>(
+        Get,
+        "/offline/tasks",
+        "离线下载",
+        "本地记录的离线下载任务",
+    )
+    .to(offline_tasks);
+    spec.plain::<OfflineTasksResponse>(
+        Post,
+        "/offline/poll",
+        "离线下载",
+        "立即轮询一次未结束的任务",
+    )
+    .to(offline_poll);
+
+    spec.json::<DirectLinkItem, DirectLinkToggleResponse>(
+        Post,
+        "/direct-link/enable",
+        "直链",
+        "启用目录的直链空间",
+    )
+    .to(direct_link_enable);
+    spec.json::<DirectLinkItem, DirectLinkToggleResponse>(
+        Post,
+        "/direct-link/disable",
+        "直链",
+        "禁用目录的直链空间",
+    )
+    .to(direct_link_disable);
+    spec.query::<DirectLinkUrlQuery, DirectLinkResponse>(
+        Get,
+        "/direct-link/url",
+        "直链",
+        "获取文件直链",
+    )
+    .to(direct_link_get);
+
+    let image = spec
+        .with_query::<ImageUploadQuery>(OperationBuilder::new())
+        .request_body(Some(
+            RequestBodyBuilder::new()
+                .description(Some("`multipart/form-data`，图片放在任意一个文件字段中"))
+                .content("multipart/form-data", Content::new(None::<Schema>))
+                .required(Some(Required::True))
+                .build(),
+        ));
+    let image = spec.returns::<ImageHostResponse>(image);
+    let image = spec.rejects(image);
+    spec.operation(Post, "/image/upload", "图床", "图床上传", image)
+        .to(image_upload);
+
+    spec.json::<VideoTranscodeItem, VideoTranscodeResponse>(
+        Post,
+        "/video/transcode",
+        "视频",
+        "提交视频转码",
+    )
+    .to(video_transcode);
+    spec.plain::<VideoTranscodesResponse>(Get, "/video/transcodes", "视频", "本地记录的全部转码")
+        .to(video_transcodes);
+    spec.plain::<VideoResolutionsResponse>(
+        Get,
+        "/video/{file_id}/resolutions",
+        "视频",
+        "查询视频可转码的分辨率",
+    )
+    .to(video_resolutions_get);
+    spec.plain::<VideoTranscodeResponse>(Get, "/video/{file_id}/status", "视频", "查询转码状态")
+        .to(video_status);
+    let play = spec
+        .with_query::<VideoPlayQuery>(OperationBuilder::new())
+        .response(
+            "200",
+            ResponseBuilder::new().description("HLS 播放列表").content(
+                "application/vnd.apple.mpegurl",
+                Content::new(None::<Schema>),
+            ),
+        );
+    spec.operation(Get, "/video/{file_id}/play", "视频", "视频播放列表", play)
+        .to(video_play);
+
+    spec.json::<WebUploadItem, WebUploadSessionResponse>(
+        Post,
+        "/upload/session",
+        "网页上传",
+        "创建分块上传会话",
+    )
+    .to(create_session);
+    spec.plain::<WebUploadSessionResponse>(
+        Get,
+        "/upload/session/{upload_id}",
+        "网页上传",
+        "查询上传会话，用于断点续传",
+    )
+    .to(session_status);
+    let chunk = spec
+        .with_query::<WebUploadChunkQuery>(OperationBuilder::new())
+        .request_body(Some(
+            RequestBodyBuilder::new()
+                .content("application/octet-stream", Content::new(None::<Schema>))
+                .required(Some(Required::True))
+                .build(),
+        ));
+    let chunk = spec.returns::<WebUploadSessionResponse>(chunk);
+    let chunk = spec.rejects(chunk);
+    spec.operation(
+        Put,
+        "/upload/session/{upload_id}",
+        "网页上传",
+        "追加一个分块，`offset` 须等于已接收的字节数",
+        chunk,
+    )
+    .to(append_chunk);
+    spec.plain::<WebUploadResultResponse>(
+        Post,
+        "/upload/session/{upload_id}/complete",
+        "网页上传",
+        "完成上传",
+    )
+    .to(complete_session);
+    let abort =
+        OperationBuilder::new().response("204", ResponseBuilder::new().description("已取消"));
+    spec.operation(
+        Delete,
+        "/upload/session/{upload_id}",
+        "网页上传",
+        "取消上传并清理暂存数据",
+        abort,
+    )
+    .to(abort_session);
+
+    let text = |description: &str| {
+        OperationBuilder::new().response(
+            "200",
+            ResponseBuilder::new()
+                .description(description)
+                .content("text/plain", Content::new(Some(String::schema()))),
+        )
+    };
+    spec.operation(
+        Post,
+        "/echo",
+        "其他",
+        "原样返回请求体",
+        text("请求体").request_body(Some(
+            RequestBodyBuilder::new()
+                .content("text/plain", Content::new(Some(String::schema())))
+                .build(),
+        )),
+    )
+    .to(echo);
+    spec.operation(Get, "/hey", "其他", "连通性检查", text("固定的问候语"))
+        .to(manual_hello);
+
+    let redirect = || {
+        OperationBuilder::new().response(
+            "302",
+            ResponseBuilder::new().description("跳转到 `/static/`"),
+        )
+    };
+    spec.operation(Get, "/", "网页", "网页文件管理器", redirect())
+        .to(index);
+    spec.operation(Get, "/static", "网页", "网页文件管理器", redirect())
+        .to(index);
+    spec.operation(
+        Get,
+        "/static/{path:.*}",
+        "网页",
+        "网页文件管理器的静态资源",
+        OperationBuilder::new().response("200", ResponseBuilder::new().description("资源内容")),
+    )
+    .to(asset);
+    spec.operation(
+        Get,
+        OPENAPI_PATH,
+        "网页",
+        "本文档",
+        OperationBuilder::new().response(
+            "200",
+            ResponseBuilder::new()
+                .description("OpenAPI 3 文档")
+                .content("application/json", Content::new(None::<Schema>)),
+        ),
+    )
+    .to(openapi_json);
+    spec.operation(
+        Get,
+        DOCS_PATH,
+        "网页",
+        "交互式接口文档",
+        OperationBuilder::new().response(
+            "200",
+            ResponseBuilder::new()
+                .description("Swagger UI 页面")
+                .content("text/html", Content::new(None::<Schema>)),
+        ),
+    )
+    .to(docs);
+}
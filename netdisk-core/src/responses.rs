@@ -1,5 +1,14 @@
 pub mod auth_config;
 pub mod base_config;
+pub mod direct_link_config;
 pub mod file_info;
+pub mod gateway_config;
+pub mod image_host_config;
+pub mod offline_config;
 pub mod prelude;
 pub mod share_file_config;
+pub mod share_stats_config;
+pub mod sync_config;
+pub mod validation;
+pub mod video_config;
+pub mod web_upload_config;
@@ -1,18 +1,27 @@
 use actix_web::{body::BoxBody, http::header::ContentType, HttpRequest, HttpResponse, Responder};
 
+use super::validation::{FieldErrors, Validate};
 use chrono::{DateTime, Local, NaiveDateTime, TimeZone, Utc};
 use serde::{self, Deserialize, Deserializer, Serialize, Serializer};
 use std::env;
 use std::fs;
 use std::path::PathBuf;
+use utoipa::ToSchema;
 
 /// 授权信息
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, ToSchema)]
 pub struct AuthConfig {
     client_id: String,
     client_secret: String,
 }
 
+impl Validate for AuthConfig {
+    fn check(&self, errors: &mut FieldErrors) {
+        errors.not_blank("client_id", &self.client_id);
+        errors.not_blank("client_secret", &self.client_secret);
+    }
+}
+
 impl AuthConfig {
     pub fn client_id(&self) -> &str {
         &self.client_id
@@ -5,10 +5,13 @@ use serde::{self, Deserialize, Deserializer, Serialize, Serializer};
 use std::env;
 use std::fs;
 use std::path::PathBuf;
-#[derive(Debug, Serialize, Deserialize, Clone)]
+use utoipa::ToSchema;
+#[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
 pub struct ApiResponse<T> {
     pub code: i32,
     pub message: String,
+    /// 业务数据，失败时可能为空
+    #[schema(inline)]
     pub data: Option<T>,
     #[serde(rename = "x-traceID")]
     pub x_trace_id: String,
@@ -22,6 +25,22 @@ impl<T> ApiResponse<T> {
             x_trace_id: x_trace_id,
         }
     }
+
+    /// 开放平台以 `code == 0` 表示业务成功
+    pub fn is_ok(&self) -> bool {
+        self.code == 0
+    }
+
+    /// 业务失败或缺少 `data` 时返回错误，否则取出数据
+    pub fn into_data(self) -> Result<T, Box<dyn std::error::Error>> {
+        if !self.is_ok() {
+            return Err(
+                format!("接口返回错误，code: {}，信息: {}", self.code, self.message).into(),
+            );
+        }
+        self.data
+            .ok_or_else(|| format!("接口未返回数据，x-traceID: {}", self.x_trace_id).into())
+    }
 }
 
 /// 序列化配置文件
@@ -153,25 +172,139 @@ impl<T: serde::Serialize> Responder for ApiResponse<T> {
     }
 }
 
+/// 网关输出时间的格式，由 `gateway.toml` 的 `time_format` 指定
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum TimeFormat {
+    /// RFC 3339，带 `+08:00` 时区，例如 `2024-06-01T12:00:00+08:00`
+    #[default]
+    Rfc3339,
+    /// 与开放平台一致的 `2024-06-01 12:00:00`（北京时间）
+    Legacy,
+}
+
+/// 开放平台的时间字段：平台返回北京时间（Asia/Shanghai）且不带时区，
+/// 解析结果与服务器所在时区无关，`DateTime<Local>` 只作为存储类型。
 pub mod standard_format {
     // 将顶层 use 引入到模块内部作用域
+    use super::TimeFormat;
     use super::{DateTime, Deserialize, Deserializer, Local, NaiveDateTime, Serializer, TimeZone};
+    use chrono::{Duration, NaiveDate, SecondsFormat};
+    use chrono_tz::Asia::Shanghai;
+    use std::sync::atomic::{AtomicBool, Ordering};
 
     // API 要求的日期时间格式
     const FORMAT: &str = "%Y-%m-%d %H:%M:%S";
 
+    /// 平台偶尔返回的其他不带时区的格式
+    const NAIVE_FORMATS: &[&str] = &[
+        FORMAT,
+        "%Y-%m-%d %H:%M:%S%.f",
+        "%Y-%m-%dT%H:%M:%S",
+        "%Y-%m-%dT%H:%M:%S%.f",
+        "%Y/%m/%d %H:%M:%S",
+        "%Y-%m-%d %H:%M",
+    ];
+
+    /// 序列化是否使用旧格式，进程内所有响应共用，启动网关时按配置设置一次
+    static LEGACY_OUTPUT: AtomicBool = AtomicBool::new(false);
+
+    pub fn set_output_format(format: TimeFormat) {
+        LEGACY_OUTPUT.store(format == TimeFormat::Legacy, Ordering::Relaxed);
+    }
+
+    pub fn output_format() -> TimeFormat {
+        if LEGACY_OUTPUT.load(Ordering::Relaxed) {
+            TimeFormat::Legacy
+        } else {
+            TimeFormat::Rfc3339
+        }
+    }
+
+    /// 按北京时间解释不带时区的时间；夏令时（1986-1991）切换造成的重复时刻取较早者，
+    /// 不存在的时刻按 UTC+8 处理
+    pub fn from_platform_time(naive: &NaiveDateTime) -> DateTime<Local> {
+        Shanghai
+            .from_local_datetime(naive)
+            .earliest()
+            .unwrap_or_else(|| Shanghai.from_utc_datetime(&(*naive - Duration::hours(8))))
+            .with_timezone(&Local)
+    }
+
+    /// 解析平台返回的时间，依次尝试 RFC 3339、不带时区的常见格式、纯日期与 Unix 时间戳；
+    /// 无法识别时返回 `None`
+    pub fn parse_platform_time(s: &str) -> Option<DateTime<Local>> {
+        let s = s.trim();
+        if let Ok(dt) = DateTime::parse_from_rfc3339(s) {
+            return Some(dt.with_timezone(&Local));
+        }
+        if let Some(naive) = NAIVE_FORMATS
+            .iter()
+            .find_map(|format| NaiveDateTime::parse_from_str(s, format).ok())
+        {
+            return Some(from_platform_time(&naive));
+        }
+        if let Ok(date) = NaiveDate::parse_from_str(s, "%Y-%m-%d") {
+            return date
+                .and_hms_opt(0, 0, 0)
+                .map(|naive| from_platform_time(&naive));
+        }
+        let secs: i64 = s.parse().ok()?;
+        // 13 位时间戳为毫秒
+        let secs = if secs.abs() >= 100_000_000_000 {
+            secs / 1000
+        } else {
+            secs
+        };
+        Local.timestamp_opt(secs, 0).single()
+    }
+
+    /// 按当前输出格式格式化，时区固定为北京时间
+    pub fn format_platform_time(date: &DateTime<Local>) -> String {
+        let date = date.with_timezone(&Shanghai);
+        match output_format() {
+            TimeFormat::Rfc3339 => date.to_rfc3339_opts(SecondsFormat::Secs, false),
+            TimeFormat::Legacy => date.format(FORMAT).to_string(),
+        }
+    }
+
+    /// 字段可能是字符串或数字时间戳
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum RawTime {
+        Text(String),
+        Number(i64),
+    }
+
+    impl RawTime {
+        fn parse<E: serde::de::Error>(self) -> Result<Option<DateTime<Local>>, E> {
+            match self {
+                RawTime::Text(s) if is_empty_time(&s) => Ok(None),
+                RawTime::Text(s) => parse_platform_time(&s)
+                    .map(Some)
+                    .ok_or_else(|| E::custom(format!("无效日期格式：{}", s))),
+                RawTime::Number(n) => parse_platform_time(&n.to_string())
+                    .map(Some)
+                    .ok_or_else(|| E::custom(format!("无效时间戳：{}", n))),
+            }
+        }
+    }
+
+    /// 平台用空字符串或全零时间表示未设置
+    fn is_empty_time(s: &str) -> bool {
+        let s = s.trim();
+        s.is_empty() || s.starts_with("0000-00-00")
+    }
+
     // --- 1. 反序列化 (JSON String -> Rust DateTime<Local>) ---
     pub fn deserialize_option<'de, D>(deserializer: D) -> Result<Option<DateTime<Local>>, D::Error>
     where
         D: Deserializer<'de>,
     {
-        let option: Option<String> = Option::deserialize(deserializer)?;
+        let option: Option<RawTime> = Option::deserialize(deserializer)?;
 
         match option {
-            Some(s) => match NaiveDateTime::parse_from_str(&s, FORMAT) {
-                Ok(dt) => Ok(Some(Local.from_local_datetime(&dt).unwrap())),
-                Err(_) => Err(serde::de::Error::custom(format!("无效日期格式：{}", s))),
-            },
+            Some(raw) => raw.parse(),
             None => Ok(None), // null or missing field will be parsed as None
         }
     }
@@ -181,11 +314,9 @@ pub mod standard_format {
     where
         D: Deserializer<'de>,
     {
-        let s = String::deserialize(deserializer)?;
-        match NaiveDateTime::parse_from_str(&s, FORMAT) {
-            Ok(dt) => Ok(Local.from_local_datetime(&dt).unwrap()),
-            Err(_) => Err(serde::de::Error::custom(format!("无效日期格式：{}", s))),
-        }
+        RawTime::deserialize(deserializer)?
+            .parse()?
+            .ok_or_else(|| serde::de::Error::custom("缺少日期"))
     }
 
     // 序列化为字符串
@@ -193,8 +324,7 @@ pub mod standard_format {
     where
         S: Serializer,
     {
-        let s = date.format(FORMAT).to_string();
-        serializer.serialize_str(&s)
+        serializer.serialize_str(&format_platform_time(date))
     }
 
     // 序列化为 Option<DateTime<Local>>，直接转为字符串
@@ -206,7 +336,7 @@ pub mod standard_format {
         S: Serializer,
     {
         match date {
-            Some(d) => serializer.serialize_str(&d.format(FORMAT).to_string()),
+            Some(d) => serializer.serialize_str(&format_platform_time(d)),
             None => serializer.serialize_none(),
         }
     }
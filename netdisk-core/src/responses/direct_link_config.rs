@@ -0,0 +1,64 @@
+use super::base_config::*;
+use super::validation::*;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+/// 启用/禁用直链空间，`fileID` 须为目录
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, ToSchema)]
+pub struct DirectLinkItem {
+    #[serde(rename = "fileID")]
+    pub file_id: u64,
+}
+
+impl Validate for DirectLinkItem {}
+
+#[derive(Debug, Deserialize, Serialize, Clone, ToSchema)]
+pub struct DirectLinkToggleData {
+    pub filename: String,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct DirectLinkUrlData {
+    pub url: String,
+}
+
+/// 签名直链有效期的上限（秒），即 365 天
+pub const MAX_DIRECT_LINK_EXPIRE_SECS: u64 = 365 * 24 * 3600;
+
+#[derive(Debug, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct DirectLinkUrlQuery {
+    #[serde(rename = "fileID")]
+    pub file_id: u64,
+    /// 签名链接的有效期（秒），不传时使用配置中的 `expire_secs`
+    pub expire_secs: Option<u64>,
+}
+
+impl Validate for DirectLinkUrlQuery {
+    fn check(&self, errors: &mut FieldErrors) {
+        match self.expire_secs {
+            Some(0) => errors.add("expireSecs", "至少为 1"),
+            Some(secs) if secs > MAX_DIRECT_LINK_EXPIRE_SECS => errors.add(
+                "expireSecs",
+                format!("不能超过 {}", MAX_DIRECT_LINK_EXPIRE_SECS),
+            ),
+            _ => {}
+        }
+    }
+}
+
+/// 文件的直链；配置了鉴权密钥时为带 `auth_key` 的签名链接
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct DirectLink {
+    #[serde(rename = "fileID")]
+    pub file_id: u64,
+    pub url: String,
+    pub signed: bool,
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+pub type DirectLinkToggleResponse = ApiResponse<DirectLinkToggleData>;
+pub type DirectLinkUrlResponse = ApiResponse<DirectLinkUrlData>;
+pub type DirectLinkResponse = ApiResponse<DirectLink>;
@@ -1,7 +1,9 @@
 use super::base_config::*;
+use super::validation::*;
 use crate::netdisk_api::prelude::*;
 use chrono::{DateTime, Local, NaiveDateTime, TimeZone, Utc};
 use serde::{self, Deserialize, Serialize, Serializer};
+use utoipa::ToSchema;
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct RequestConfig<T> {
@@ -20,7 +22,7 @@ impl<T> RequestConfig<T> {
 }
 
 /// 返回授权信息
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct AccessToken {
     pub access_token: String,
@@ -52,7 +54,7 @@ struct FileDetailQuery {
     access_token: String,
 }
 
-#[derive(Debug, Deserialize, Serialize, Clone)]
+#[derive(Debug, Deserialize, Serialize, Clone, ToSchema)]
 #[serde(rename_all = "camelCase")] // 关键！将 Rust 的 snake_case 映射到 JSON 的 camelCase
 pub struct FileItem {
     // 整数类型
@@ -77,7 +79,7 @@ pub struct FileItem {
     pub update_at: DateTime<Local>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, ToSchema)]
 pub struct FileListQuery {
     // 1. parentFileId (number, 必填)
     // 根目录传 0。这里假设 API 接受 i64 或 u64
@@ -96,28 +98,53 @@ pub struct FileListQuery {
 
     pub last_file_id: Option<i64>,
 }
-#[derive(Debug, Deserialize, Serialize)]
+
+impl Validate for FileListQuery {
+    fn check(&self, errors: &mut FieldErrors) {
+        errors.range("limit", self.limit as usize, 1, MAX_API_ITEMS);
+        if self.parent_file_id < 0 {
+            errors.add("parentFileId", "不能为负数");
+        }
+        errors.one_of("search_mode", self.search_mode, &[0, 1]);
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct FileListBody {
     pub last_file_id: i32,
     pub file_list: Vec<FileItem>,
 }
 
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
 pub struct FileQuery {
     #[serde(rename = "fileID")]
     #[serde(alias = "fileId")]
     pub file_id: i64,
 }
 
-#[derive(Debug, Deserialize, Serialize)]
+impl Validate for FileQuery {
+    fn check(&self, errors: &mut FieldErrors) {
+        if self.file_id <= 0 {
+            errors.add("fileID", "须为正整数");
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct FilesQuery {
     #[serde(rename = "fileIds", deserialize_with = "limit_deserializer::limit_vec")]
     pub file_ids: Vec<u64>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+impl Validate for FilesQuery {
+    fn check(&self, errors: &mut FieldErrors) {
+        errors.count("fileIds", self.file_ids.len(), 1, MAX_API_ITEMS);
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct FileData {
     #[serde(rename = "fileID")]
@@ -137,7 +164,7 @@ pub struct FileData {
     pub trashed: i32,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct FileInfo {
     pub file_id: u64,
@@ -159,26 +186,58 @@ pub struct FileInfo {
     pub update_at: DateTime<Local>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct FilesInfoData {
     pub fileList: Vec<FileInfo>,
 }
 
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct EntryItem {
     pub name: String,
     pub parentID: u64,
 }
 
-#[derive(Debug, Deserialize, Serialize)]
+impl Validate for EntryItem {
+    fn check(&self, errors: &mut FieldErrors) {
+        errors.file_name("name", &self.name);
+    }
+}
+
+/// 多级目录创建请求，`path` 形如 `a/b/c`，相对于 `parentID`
+#[derive(Debug, Deserialize, Serialize, Clone, ToSchema)]
+pub struct MkdirPathItem {
+    pub path: String,
+    #[serde(rename = "parentID", alias = "parentId", default)]
+    pub parent_id: u64,
+}
+
+impl Validate for MkdirPathItem {
+    fn check(&self, errors: &mut FieldErrors) {
+        let names: Vec<&str> = self
+            .path
+            .split('/')
+            .filter(|name| !name.is_empty())
+            .collect();
+        if names.is_empty() {
+            errors.add("path", "不能为空");
+        }
+        for name in names {
+            if let Err(message) = check_file_name(name) {
+                errors.add("path", format!("{}：{}", name, message));
+            }
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct EntryInfo {
     pub dirID: u64,
 }
 
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct FileMoveInfo {
     // #[rename = "fileIDs"]
@@ -186,7 +245,27 @@ pub struct FileMoveInfo {
     pub toParentFileID: u64,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+impl Validate for FileMoveInfo {
+    fn check(&self, errors: &mut FieldErrors) {
+        errors.count("fileIDs", self.fileIDs.len(), 1, MAX_API_ITEMS);
+    }
+}
+
+/// 修改单个文件名称的请求体
+#[derive(Debug, Deserialize, Serialize, Clone, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct FileRenameItem {
+    pub file_id: u64,
+    pub file_name: String,
+}
+
+impl Validate for FileRenameItem {
+    fn check(&self, errors: &mut FieldErrors) {
+        errors.file_name("fileName", &self.file_name);
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
 #[serde(rename_all = "camelCase")] // 确保字段名与API返回的 camelCase 匹配
 pub struct VipInfo {
     pub vip_level: u32,
@@ -200,7 +279,7 @@ pub struct VipInfo {
 }
 
 /// 开发者信息
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct DeveloperInfo {
     #[serde(
@@ -216,7 +295,7 @@ pub struct DeveloperInfo {
     pub end_time: Option<DateTime<Local>>, // 或使用 chrono::DateTime<Utc>
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
 #[serde(rename_all = "camelCase")] // 确保所有字段名与API返回的 camelCase 匹配
 pub struct UserInfo {
     pub uid: u64, // 使用 u64 或 i64 (取决于 number 的实际范围)
@@ -246,7 +325,7 @@ pub struct UserInfo {
     pub developer_info: Option<DeveloperInfo>,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct FileSearchItem {
     #[serde(rename = "parentFileId")]
@@ -257,23 +336,30 @@ pub struct FileSearchItem {
     pub last_file_id: Option<i64>,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+impl Validate for FileSearchItem {
+    fn check(&self, errors: &mut FieldErrors) {
+        errors.range("limit", self.limit as usize, 1, MAX_API_ITEMS);
+        errors.one_of("searchMode", self.search_mode.as_deref(), &["0", "1"]);
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct FileSearchedData {
     pub last_file_id: i64,
     pub file_list: Vec<FileItem>,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct DownloadUrlData {
     pub download_url: String,
 }
 
-#[derive(Debug, Deserialize, Serialize, Clone)]
+#[derive(Debug, Deserialize, Serialize, Clone, ToSchema)]
 #[serde(rename_all = "camelCase")] // 关键！将 Rust 的 snake_case 映射到 JSON 的 camelCase
 pub struct UploadFileItem {
-    #[serde(alias = "parentFileID")]
+    #[serde(rename = "parentFileID", alias = "parentFileId")]
     pub parent_file_id: u64,
     pub filename: String,
     pub etag: String,
@@ -282,16 +368,44 @@ pub struct UploadFileItem {
     pub contain_dir: Option<bool>,
 }
 
+impl Validate for UploadFileItem {
+    fn check(&self, errors: &mut FieldErrors) {
+        if self.contain_dir == Some(true) {
+            // 文件名可以带路径，逐级检查
+            let names: Vec<&str> = self
+                .filename
+                .split('/')
+                .filter(|name| !name.is_empty())
+                .collect();
+            if names.is_empty() {
+                errors.add("filename", "不能为空");
+            }
+            if let Some(message) = names.iter().find_map(|name| check_file_name(name).err()) {
+                errors.add("filename", message);
+            }
+        } else {
+            errors.file_name("filename", &self.filename);
+        }
+        if self.etag.len() != 32 || !self.etag.chars().all(|c| c.is_ascii_hexdigit()) {
+            errors.add("etag", "须为 32 位十六进制 MD5");
+        }
+        errors.one_of("duplicate", self.duplicate, &[1, 2]);
+    }
+}
+
 /// 创建文件接口的返回内容
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct UploadFileData {
     #[serde(alias = "fileID")]
     pub file_id: Option<u64>,
     pub reuse: bool,
-    #[serde(alias = "preuploadID")]
+    // 秒传成功时以下字段可能缺失
+    #[serde(alias = "preuploadID", default)]
     pub preupload_id: String,
+    #[serde(default)]
     pub slice_size: u64,
+    #[serde(default)]
     pub servers: Vec<String>,
 }
 impl UploadFileData {
@@ -312,6 +426,213 @@ impl UploadFileData {
     }
 }
 
+/// 回收站列表查询参数
+#[derive(Debug, Deserialize, Serialize, Clone, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct TrashQuery {
+    /// 从该目录开始查找，根目录为 0
+    #[serde(rename = "parentFileId", default)]
+    pub parent_file_id: u64,
+    /// 是否递归查找未删除的子目录
+    #[serde(default)]
+    pub recursive: bool,
+}
+
+impl Validate for TrashQuery {}
+
+#[derive(Debug, Deserialize, Serialize, Clone, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct TrashListData {
+    pub file_list: Vec<FileItem>,
+}
+
+/// 彻底清理回收站的筛选条件，默认只预览不删除
+#[derive(Debug, Deserialize, Serialize, Clone, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct PurgeItem {
+    #[serde(rename = "parentFileId", default)]
+    pub parent_file_id: u64,
+    #[serde(default)]
+    pub recursive: bool,
+    /// 只清理最后修改时间早于 N 天前的条目
+    pub older_than_days: Option<u32>,
+    /// 只清理文件名包含该字符串的条目
+    pub filter: Option<String>,
+    /// 为 true（默认）时只返回待删除列表与 `confirmToken`，不调用删除接口
+    #[serde(default = "default_dry_run")]
+    pub dry_run: bool,
+    /// 预览时返回的确认令牌，`dryRun` 为 false 时必填
+    pub confirm_token: Option<String>,
+}
+
+impl Validate for PurgeItem {}
+
+fn default_dry_run() -> bool {
+    true
+}
+
+/// 清理回收站的结果
+#[derive(Debug, Deserialize, Serialize, Clone, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct PurgeReport {
+    pub dry_run: bool,
+    /// 通过预检（仍在回收站中）的条目数，实际删除时即删除的数量
+    pub count: usize,
+    pub total_size: u64,
+    pub file_list: Vec<FileItem>,
+    /// 预览时下发，携带该令牌并传入 `"dryRun": false` 才会真正删除
+    pub confirm_token: Option<String>,
+}
+
+/// 彻底删除请求：先调用预检获得 `confirmToken`，再携带该令牌执行删除
+#[derive(Debug, Deserialize, Serialize, Clone, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct GuardedDeleteItem {
+    #[serde(rename = "fileIds")]
+    pub file_ids: Vec<u64>,
+    pub confirm_token: Option<String>,
+}
+
+impl Validate for GuardedDeleteItem {
+    fn check(&self, errors: &mut FieldErrors) {
+        errors.count("fileIds", self.file_ids.len(), 1, usize::MAX);
+    }
+}
+
+/// 预检通过、允许彻底删除的条目
+#[derive(Debug, Deserialize, Serialize, Clone, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct DeleteCandidate {
+    pub file_id: u64,
+    pub filename: String,
+    /// 从根目录开始的完整路径
+    pub path: String,
+    pub size: u64,
+    pub etag: String,
+}
+
+/// 彻底删除前的预检结果
+#[derive(Debug, Deserialize, Serialize, Clone, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct DeletePreflight {
+    pub count: usize,
+    pub total_size: u64,
+    pub file_list: Vec<DeleteCandidate>,
+    /// 不存在或不在回收站中的文件 ID，存在时拒绝删除
+    pub rejected: Vec<u64>,
+    /// 仅当没有被拒绝的文件时下发
+    pub confirm_token: Option<String>,
+}
+
+/// 审计日志中的一条彻底删除记录
+#[derive(Debug, Deserialize, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct DeleteAuditEntry {
+    pub deleted_at: DateTime<Utc>,
+    /// 触发删除的入口，例如 `delete` 或 `purge`
+    pub source: String,
+    #[serde(flatten)]
+    pub file: DeleteCandidate,
+}
+
+/// 批量移入回收站，文件数量不受单次接口 100 个的限制
+#[derive(Debug, Deserialize, Serialize, Clone, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct BatchTrashItem {
+    #[serde(rename = "fileIds")]
+    pub file_ids: Vec<u64>,
+    /// 相邻两次上游请求的最小间隔（毫秒）
+    pub interval_ms: Option<u64>,
+}
+
+impl Validate for BatchTrashItem {
+    fn check(&self, errors: &mut FieldErrors) {
+        errors.count("fileIds", self.file_ids.len(), 1, usize::MAX);
+        errors.interval_ms(self.interval_ms);
+    }
+}
+
+/// 批量移动文件，文件数量不受单次接口 100 个的限制
+#[derive(Debug, Deserialize, Serialize, Clone, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct BatchMoveItem {
+    #[serde(rename = "fileIDs", alias = "fileIds")]
+    pub file_ids: Vec<u64>,
+    #[serde(rename = "toParentFileID")]
+    pub to_parent_file_id: u64,
+    pub interval_ms: Option<u64>,
+}
+
+impl Validate for BatchMoveItem {
+    fn check(&self, errors: &mut FieldErrors) {
+        errors.count("fileIDs", self.file_ids.len(), 1, usize::MAX);
+        errors.interval_ms(self.interval_ms);
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct BatchFailure {
+    pub file_id: u64,
+    pub message: String,
+}
+
+/// 批量操作结果，逐个列出成功与失败的文件
+#[derive(Debug, Deserialize, Serialize, Clone, Default, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct BatchReport {
+    pub total: usize,
+    pub succeeded: Vec<u64>,
+    pub failed: Vec<BatchFailure>,
+}
+
+/// 分片上传完毕的请求体
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct UploadCompleteItem {
+    #[serde(rename = "preuploadID")]
+    pub preupload_id: String,
+}
+
+/// 分片上传完毕接口的返回内容，`completed` 为 false 时需要稍后重试
+#[derive(Debug, Deserialize, Serialize, Clone, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct UploadCompleteData {
+    pub completed: bool,
+    #[serde(rename = "fileID", alias = "fileId", default)]
+    pub file_id: u64,
+}
+
+/// 复制目录：在 `toParentFileID` 下重建源目录及其全部内容
+#[derive(Debug, Deserialize, Serialize, Clone, ToSchema)]
+pub struct CopyItem {
+    #[serde(rename = "sourceFileID", alias = "sourceFileId")]
+    pub source_file_id: u64,
+    #[serde(rename = "toParentFileID", alias = "toParentFileId")]
+    pub to_parent_file_id: u64,
+}
+
+impl Validate for CopyItem {}
+
+#[derive(Debug, Deserialize, Serialize, Clone, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct CopyFailure {
+    pub path: String,
+    pub message: String,
+}
+
+/// 复制结果：秒传成功的文件计入 `reused`，下载后重新上传的计入 `transferred`
+#[derive(Debug, Deserialize, Serialize, Clone, Default, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct CopyReport {
+    #[serde(rename = "dirID")]
+    pub dir_id: u64,
+    pub folders_created: usize,
+    pub reused: usize,
+    pub transferred: usize,
+    pub transferred_bytes: u64,
+    pub failed: Vec<CopyFailure>,
+}
+
 pub type AccessTokenResponse = ApiResponse<AccessToken>;
 pub type FileListResponse = ApiResponse<FileListBody>;
 pub type FileResponse = ApiResponse<FileData>;
@@ -321,3 +642,9 @@ pub type UserInfoResponse = ApiResponse<UserInfo>;
 pub type FileSearchResponse = ApiResponse<FileSearchedData>;
 pub type DownloadUrlResponse = ApiResponse<DownloadUrlData>;
 pub type UploadFileResponse = ApiResponse<UploadFileData>;
+pub type TrashListResponse = ApiResponse<TrashListData>;
+pub type PurgeResponse = ApiResponse<PurgeReport>;
+pub type DeletePreflightResponse = ApiResponse<DeletePreflight>;
+pub type BatchReportResponse = ApiResponse<BatchReport>;
+pub type UploadCompleteResponse = ApiResponse<UploadCompleteData>;
+pub type CopyReportResponse = ApiResponse<CopyReport>;
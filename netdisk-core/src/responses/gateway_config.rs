@@ -0,0 +1,450 @@
+use crate::io_basic::read_and_write::async_read_and_deserialize;
+use crate::netdisk_auth::basic_env::NetDiskEnv;
+use crate::responses::base_config::TimeFormat;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::io;
+
+/// 网关配置文件名，位于配置目录下（`config.toml` 用于缓存 access_token）
+pub const GATEWAY_CONFIG_FILE: &str = "gateway.toml";
+
+/// 网关的可选功能配置，文件不存在时全部使用默认值
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct GatewayConfig {
+    /// 响应中时间字段的格式：`rfc3339`（默认）或 `legacy`
+    #[serde(default)]
+    pub time_format: TimeFormat,
+    #[serde(default)]
+    pub webdav: WebDavConfig,
+    #[serde(default)]
+    pub s3: S3Config,
+    #[serde(default)]
+    pub web_upload: WebUploadConfig,
+    #[serde(default)]
+    pub share_stats: ShareStatsConfig,
+    #[serde(default)]
+    pub direct_link: DirectLinkConfig,
+    #[serde(default)]
+    pub image_host: ImageHostConfig,
+    #[serde(default)]
+    pub cache: CacheConfig,
+    #[serde(default)]
+    pub log: LogConfig,
+}
+
+impl GatewayConfig {
+    /// 读取 `<配置目录>/gateway.toml`
+    pub async fn load(env: &NetDiskEnv) -> Result<Self, io::Error> {
+        let path = env.config_dir.join(GATEWAY_CONFIG_FILE);
+        if tokio::fs::metadata(&path).await.is_err() {
+            return Ok(GatewayConfig::default());
+        }
+        let config: GatewayConfig = async_read_and_deserialize(path).await?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// 检查各挂载前缀：为空或为 `/` 时会覆盖网关的全部路由
+    pub fn validate(&self) -> Result<(), io::Error> {
+        let prefixes = [
+            ("webdav", &self.webdav.prefix),
+            ("s3", &self.s3.prefix),
+            ("direct_link", &self.direct_link.prefix),
+        ];
+        for (section, prefix) in prefixes {
+            if prefix.trim_matches('/').is_empty() {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("[{}] 的 prefix 不能为空或 `/`", section),
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// ```toml
+/// [webdav]
+/// enabled = true
+/// prefix = "/dav"
+/// ```
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct WebDavConfig {
+    /// 不需要认证即可读写网盘，默认关闭
+    #[serde(default)]
+    pub enabled: bool,
+    /// WebDAV 挂载的路径前缀，以 `/` 开头且不以 `/` 结尾
+    #[serde(default = "default_webdav_prefix")]
+    pub prefix: String,
+}
+
+impl Default for WebDavConfig {
+    fn default() -> Self {
+        WebDavConfig {
+            enabled: false,
+            prefix: default_webdav_prefix(),
+        }
+    }
+}
+
+impl WebDavConfig {
+    /// 规范化后的前缀，例如 `dav/` 会变为 `/dav`
+    pub fn normalized_prefix(&self) -> String {
+        format!("/{}", self.prefix.trim_matches('/'))
+    }
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+fn default_webdav_prefix() -> String {
+    "/dav".to_string()
+}
+
+/// S3 兼容接口配置，每个 bucket 对应网盘中的一个目录：
+///
+/// ```toml
+/// [s3]
+/// enabled = true
+/// prefix = "/s3"
+/// region = "us-east-1"
+/// access_key_id = "AKIDEXAMPLE"
+/// secret_access_key = "wJalrXUtnFEMI/K7MDENG+bPxRfiCYEXAMPLEKEY"
+/// multipart_ttl_hours = 24
+///
+/// [s3.buckets]
+/// renders = 18529409
+/// ```
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct S3Config {
+    /// 需要配置密钥，默认关闭
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default = "default_s3_prefix")]
+    pub prefix: String,
+    #[serde(default = "default_s3_region")]
+    pub region: String,
+    #[serde(default)]
+    pub access_key_id: String,
+    #[serde(default)]
+    pub secret_access_key: String,
+    /// bucket 名称到网盘目录 ID 的映射
+    #[serde(default)]
+    pub buckets: BTreeMap<String, u64>,
+    /// 未完成的分片上传保留的时长（小时），超时后清理暂存目录，为 0 时不清理
+    #[serde(default = "default_s3_multipart_ttl_hours")]
+    pub multipart_ttl_hours: u64,
+}
+
+impl Default for S3Config {
+    fn default() -> Self {
+        S3Config {
+            enabled: false,
+            prefix: default_s3_prefix(),
+            region: default_s3_region(),
+            access_key_id: String::new(),
+            secret_access_key: String::new(),
+            buckets: BTreeMap::new(),
+            multipart_ttl_hours: default_s3_multipart_ttl_hours(),
+        }
+    }
+}
+
+impl S3Config {
+    pub fn normalized_prefix(&self) -> String {
+        format!("/{}", self.prefix.trim_matches('/'))
+    }
+}
+
+fn default_s3_prefix() -> String {
+    "/s3".to_string()
+}
+
+fn default_s3_region() -> String {
+    "us-east-1".to_string()
+}
+
+fn default_s3_multipart_ttl_hours() -> u64 {
+    24
+}
+
+/// 浏览器分块上传配置：
+///
+/// ```toml
+/// [web_upload]
+/// session_ttl_hours = 24
+/// ```
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct WebUploadConfig {
+    /// 上传会话最后一次收到分块后保留的时长（小时），超时后清理暂存目录，为 0 时不清理
+    #[serde(default = "default_web_upload_session_ttl_hours")]
+    pub session_ttl_hours: u64,
+}
+
+impl Default for WebUploadConfig {
+    fn default() -> Self {
+        WebUploadConfig {
+            session_ttl_hours: default_web_upload_session_ttl_hours(),
+        }
+    }
+}
+
+fn default_web_upload_session_ttl_hours() -> u64 {
+    24
+}
+
+/// 分享统计快照，写入 `<配置目录>/netdisk.db`：
+///
+/// ```toml
+/// [share_stats]
+/// snapshot_interval_mins = 60
+/// ```
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct ShareStatsConfig {
+    /// 网关运行时自动记录快照的间隔（分钟），0 表示不自动记录
+    #[serde(default)]
+    pub snapshot_interval_mins: u64,
+}
+
+/// 直链配置，`/d/<路径>` 重定向到文件的直链：
+///
+/// ```toml
+/// [direct_link]
+/// enabled = true
+/// auth_key = "直链鉴权密钥"
+/// uid = 1815309870
+/// expire_secs = 3600
+/// root = "/static"
+/// ```
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct DirectLinkConfig {
+    /// 是否挂载 `/d/<路径>` 跳转；跳转不需要认证即可读取 `root` 下的任何文件，默认关闭
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default = "default_direct_link_prefix")]
+    pub prefix: String,
+    /// `/d/<路径>` 中的路径相对于该网盘目录
+    #[serde(default = "default_direct_link_root")]
+    pub root: String,
+    /// 开放平台中设置的直链鉴权密钥，为空时返回不签名的直链
+    #[serde(default)]
+    pub auth_key: String,
+    /// 签名使用的账号 UID，为 0 时从用户信息中获取
+    #[serde(default)]
+    pub uid: u64,
+    /// 签名链接的默认有效期（秒）
+    #[serde(default = "default_direct_link_expire_secs")]
+    pub expire_secs: u64,
+}
+
+impl Default for DirectLinkConfig {
+    fn default() -> Self {
+        DirectLinkConfig {
+            enabled: false,
+            prefix: default_direct_link_prefix(),
+            root: default_direct_link_root(),
+            auth_key: String::new(),
+            uid: 0,
+            expire_secs: default_direct_link_expire_secs(),
+        }
+    }
+}
+
+impl DirectLinkConfig {
+    pub fn normalized_prefix(&self) -> String {
+        format!("/{}", self.prefix.trim_matches('/'))
+    }
+}
+
+fn default_direct_link_prefix() -> String {
+    "/d".to_string()
+}
+
+fn default_direct_link_root() -> String {
+    "/".to_string()
+}
+
+fn default_direct_link_expire_secs() -> u64 {
+    3600
+}
+
+/// 图床配置，图片按上传日期放在 `root` 下的子目录中：
+///
+/// ```toml
+/// [image_host]
+/// root = "/static/images"
+/// date_format = "%Y/%m"
+/// max_width = 1920
+/// max_pixels = 40000000
+/// public_base_url = "https://pan.example.com"
+/// ```
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ImageHostConfig {
+    #[serde(default = "default_image_host_root")]
+    pub root: String,
+    /// 日期子目录的格式，`/` 分隔多级目录
+    #[serde(default = "default_image_host_date_format")]
+    pub date_format: String,
+    /// 单张图片的大小上限（字节）
+    #[serde(default = "default_image_host_max_bytes")]
+    pub max_bytes: u64,
+    /// 宽度超过该值的图片等比缩小，为 0 时不缩放
+    #[serde(default)]
+    pub max_width: u32,
+    /// 单张图片的像素数上限（宽 × 高），在解码前按图片头中的尺寸检查
+    #[serde(default = "default_image_host_max_pixels")]
+    pub max_pixels: u64,
+    /// 网关对外的地址；设置后返回经 `/d/<路径>` 跳转的永久链接，
+    /// 此时须启用 `[direct_link]` 且 `root` 位于其 `root` 之下，否则返回直链
+    #[serde(default)]
+    pub public_base_url: String,
+}
+
+impl Default for ImageHostConfig {
+    fn default() -> Self {
+        ImageHostConfig {
+            root: default_image_host_root(),
+            date_format: default_image_host_date_format(),
+            max_bytes: default_image_host_max_bytes(),
+            max_width: 0,
+            max_pixels: default_image_host_max_pixels(),
+            public_base_url: String::new(),
+        }
+    }
+}
+
+fn default_image_host_root() -> String {
+    "/images".to_string()
+}
+
+fn default_image_host_date_format() -> String {
+    "%Y/%m/%d".to_string()
+}
+
+fn default_image_host_max_bytes() -> u64 {
+    20 * 1024 * 1024
+}
+
+fn default_image_host_max_pixels() -> u64 {
+    40_000_000
+}
+
+/// 上游响应缓存配置，`endpoint_ttl_secs` 按接口路径覆盖默认有效期，为 0 时该接口不缓存：
+///
+/// ```toml
+/// [cache]
+/// capacity = 2000
+/// disk = true
+///
+/// [cache.endpoint_ttl_secs]
+/// "api/v2/file/list" = 10
+/// "api/v1/user/info" = 600
+/// ```
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct CacheConfig {
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    /// 内存中最多保留的响应数
+    #[serde(default = "default_cache_capacity")]
+    pub capacity: usize,
+    /// 同时写入配置目录下的 `cache/`，重启后仍可命中；失效记录也保存在该目录
+    #[serde(default)]
+    pub disk: bool,
+    /// 未单独配置的接口使用的有效期（秒）
+    #[serde(default = "default_cache_ttl_secs")]
+    pub ttl_secs: u64,
+    #[serde(default = "default_endpoint_ttl_secs")]
+    pub endpoint_ttl_secs: BTreeMap<String, u64>,
+}
+
+impl Default for CacheConfig {
+    fn default() -> Self {
+        CacheConfig {
+            enabled: default_enabled(),
+            capacity: default_cache_capacity(),
+            disk: false,
+            ttl_secs: default_cache_ttl_secs(),
+            endpoint_ttl_secs: default_endpoint_ttl_secs(),
+        }
+    }
+}
+
+impl CacheConfig {
+    /// 接口的缓存有效期（秒），为 0 表示不缓存
+    pub fn ttl_for(&self, endpoint: &str) -> u64 {
+        if !self.enabled {
+            return 0;
+        }
+        self.endpoint_ttl_secs
+            .get(endpoint)
+            .copied()
+            .unwrap_or(self.ttl_secs)
+    }
+
+    /// 所有接口中最长的有效期（秒）
+    pub fn max_ttl(&self) -> u64 {
+        self.endpoint_ttl_secs
+            .values()
+            .copied()
+            .fold(self.ttl_secs, u64::max)
+    }
+}
+
+fn default_cache_capacity() -> usize {
+    1000
+}
+
+fn default_cache_ttl_secs() -> u64 {
+    30
+}
+
+fn default_endpoint_ttl_secs() -> BTreeMap<String, u64> {
+    [
+        ("api/v1/user/info", 300),
+        ("api/v1/file/detail", 60),
+        ("api/v1/file/infos", 60),
+        ("api/v2/file/list", 30),
+    ]
+    .iter()
+    .map(|(endpoint, ttl)| (endpoint.to_string(), *ttl))
+    .collect()
+}
+
+/// 日志输出配置，设置了环境变量 `RUST_LOG` 时以其为准：
+///
+/// ```toml
+/// [log]
+/// format = "json"
+/// filter = "info,netdisk_core=debug"
+/// ```
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct LogConfig {
+    #[serde(default)]
+    pub format: LogFormat,
+    /// `tracing_subscriber::EnvFilter` 语法的过滤规则
+    #[serde(default = "default_log_filter")]
+    pub filter: String,
+}
+
+impl Default for LogConfig {
+    fn default() -> Self {
+        LogConfig {
+            format: LogFormat::default(),
+            filter: default_log_filter(),
+        }
+    }
+}
+
+/// 日志格式：`text`（默认）为便于阅读的单行文本，`json` 每行一个 JSON 对象
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum LogFormat {
+    #[default]
+    Text,
+    Json,
+}
+
+fn default_log_filter() -> String {
+    "info".to_string()
+}
@@ -0,0 +1,89 @@
+use super::base_config::*;
+use super::validation::*;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+/// 图床支持的图片格式，按文件头识别
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum ImageFormat {
+    Png,
+    Jpeg,
+    Gif,
+    Webp,
+}
+
+impl ImageFormat {
+    /// 按文件头识别格式，不认识的内容返回 `None`
+    pub fn sniff(data: &[u8]) -> Option<Self> {
+        if data.starts_with(b"\x89PNG\r\n\x1a\n") {
+            Some(ImageFormat::Png)
+        } else if data.starts_with(&[0xff, 0xd8, 0xff]) {
+            Some(ImageFormat::Jpeg)
+        } else if data.starts_with(b"GIF87a") || data.starts_with(b"GIF89a") {
+            Some(ImageFormat::Gif)
+        } else if data.len() >= 12 && &data[..4] == b"RIFF" && &data[8..12] == b"WEBP" {
+            Some(ImageFormat::Webp)
+        } else {
+            None
+        }
+    }
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            ImageFormat::Png => "png",
+            ImageFormat::Jpeg => "jpg",
+            ImageFormat::Gif => "gif",
+            ImageFormat::Webp => "webp",
+        }
+    }
+
+    pub fn mime(self) -> &'static str {
+        match self {
+            ImageFormat::Png => "image/png",
+            ImageFormat::Jpeg => "image/jpeg",
+            ImageFormat::Gif => "image/gif",
+            ImageFormat::Webp => "image/webp",
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Default, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ImageUploadQuery {
+    /// 图片宽度超过该值时等比缩小，为 0 时不缩放；不传时使用配置中的 `max_width`
+    pub max_width: Option<u32>,
+    /// 代替上传文件名使用的名称，同时作为 Markdown/HTML 的替代文字
+    pub name: Option<String>,
+}
+
+impl Validate for ImageUploadQuery {
+    fn check(&self, errors: &mut FieldErrors) {
+        if let Some(name) = &self.name {
+            errors.file_name("name", name);
+        }
+    }
+}
+
+/// 图床上传结果
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ImageHostResult {
+    #[serde(rename = "fileID")]
+    pub file_id: u64,
+    /// 存放图片的日期目录
+    #[serde(rename = "parentFileID")]
+    pub parent_file_id: u64,
+    /// 网盘中的完整路径
+    pub path: String,
+    pub url: String,
+    pub markdown: String,
+    pub html: String,
+    pub format: ImageFormat,
+    pub width: Option<u32>,
+    pub height: Option<u32>,
+    pub size: u64,
+    pub resized: bool,
+}
+
+pub type ImageHostResponse = ApiResponse<ImageHostResult>;
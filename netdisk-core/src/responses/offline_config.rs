@@ -0,0 +1,185 @@
+use super::base_config::*;
+use super::validation::*;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+/// 创建离线下载任务的请求体，支持 http(s) 与磁力链接
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct OfflineDownloadItem {
+    pub url: String,
+    /// 自定义文件名，不传时使用链接中的文件名
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub file_name: Option<String>,
+    /// 保存到的目录 ID，不传时保存到上游默认的离线下载目录
+    #[serde(rename = "dirID", skip_serializing_if = "Option::is_none")]
+    pub dir_id: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub call_back_url: Option<String>,
+}
+
+impl Validate for OfflineDownloadItem {
+    fn check(&self, errors: &mut FieldErrors) {
+        if !crate::offline::is_supported_url(&self.url) {
+            errors.add("url", "只支持 http、https 与 magnet 链接");
+        }
+        if let Some(file_name) = &self.file_name {
+            errors.file_name("fileName", file_name);
+        }
+        if let Some(call_back_url) = &self.call_back_url {
+            let lower = call_back_url.to_ascii_lowercase();
+            if !lower.starts_with("http://") && !lower.starts_with("https://") {
+                errors.add("callBackUrl", "只支持 http 与 https 地址");
+            }
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq)]
+pub struct OfflineTaskData {
+    #[serde(rename = "taskID")]
+    pub task_id: u64,
+}
+
+/// 离线下载进度，`status`：0 下载中，1 失败，2 成功，3 重试中
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, ToSchema)]
+pub struct OfflineProcessData {
+    pub process: f64,
+    pub status: u8,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct OfflineProcessQuery {
+    #[serde(rename = "taskID")]
+    pub task_id: u64,
+}
+
+impl Validate for OfflineProcessQuery {}
+
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum OfflineTaskStatus {
+    Downloading,
+    Failed,
+    Succeeded,
+    Retrying,
+}
+
+impl OfflineTaskStatus {
+    pub fn from_code(code: u8) -> Self {
+        match code {
+            1 => OfflineTaskStatus::Failed,
+            2 => OfflineTaskStatus::Succeeded,
+            3 => OfflineTaskStatus::Retrying,
+            _ => OfflineTaskStatus::Downloading,
+        }
+    }
+
+    /// 是否已结束，结束后轮询不再查询
+    pub fn is_finished(self) -> bool {
+        matches!(
+            self,
+            OfflineTaskStatus::Failed | OfflineTaskStatus::Succeeded
+        )
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            OfflineTaskStatus::Downloading => "downloading",
+            OfflineTaskStatus::Failed => "failed",
+            OfflineTaskStatus::Succeeded => "succeeded",
+            OfflineTaskStatus::Retrying => "retrying",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "downloading" => Some(OfflineTaskStatus::Downloading),
+            "failed" => Some(OfflineTaskStatus::Failed),
+            "succeeded" => Some(OfflineTaskStatus::Succeeded),
+            "retrying" => Some(OfflineTaskStatus::Retrying),
+            _ => None,
+        }
+    }
+}
+
+/// 本地数据库中记录的离线下载任务
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct OfflineTask {
+    #[serde(rename = "taskID")]
+    pub task_id: u64,
+    pub url: String,
+    pub file_name: Option<String>,
+    /// 任务专属的下载目录，位于提交时指定的目录下；单个提交的任务直接下载到指定目录，此处为空
+    #[serde(rename = "dirID")]
+    pub dir_id: Option<u64>,
+    /// 链接来源，例如 `file:links.txt` 或 `catalog:12`
+    pub source: Option<String>,
+    pub submitted_at: DateTime<Utc>,
+    pub status: OfflineTaskStatus,
+    /// 下载进度（0~100）
+    pub process: f64,
+    pub updated_at: DateTime<Utc>,
+    /// 下载完成后任务目录中的文件
+    #[serde(rename = "fileIDs")]
+    pub file_ids: Vec<u64>,
+}
+
+/// 待提交的链接
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct OfflineLink {
+    pub url: String,
+    pub file_name: Option<String>,
+    pub source: Option<String>,
+}
+
+/// 批量提交离线下载任务
+#[derive(Debug, Deserialize, Serialize, Clone, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct OfflineBulkItem {
+    pub urls: Vec<String>,
+    #[serde(rename = "dirID")]
+    pub dir_id: Option<u64>,
+    /// 相邻两次提交的最小间隔（毫秒）
+    pub interval_ms: Option<u64>,
+}
+
+impl Validate for OfflineBulkItem {
+    fn check(&self, errors: &mut FieldErrors) {
+        errors.count("urls", self.urls.len(), 1, usize::MAX);
+        errors.interval_ms(self.interval_ms);
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct OfflineSkipped {
+    pub url: String,
+    pub reason: String,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, Default, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct OfflineBulkReport {
+    pub submitted: Vec<OfflineTask>,
+    /// 重复或格式不支持的链接
+    pub skipped: Vec<OfflineSkipped>,
+    pub failed: Vec<OfflineSkipped>,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct OfflineTasksQuery {
+    /// 只返回未结束的任务
+    #[serde(default)]
+    pub pending: bool,
+}
+
+impl Validate for OfflineTasksQuery {}
+
+pub type OfflineTaskResponse = ApiResponse<OfflineTaskData>;
+pub type OfflineProcessResponse = ApiResponse<OfflineProcessData>;
+pub type OfflineTasksResponse = ApiResponse<Vec<OfflineTask>>;
+pub type OfflineBulkResponse = ApiResponse<OfflineBulkReport>;
@@ -1,4 +1,13 @@
 pub use super::auth_config::*;
 pub use super::base_config::*;
+pub use super::direct_link_config::*;
 pub use super::file_info::*;
+pub use super::gateway_config::*;
+pub use super::image_host_config::*;
+pub use super::offline_config::*;
 pub use super::share_file_config::*;
+pub use super::share_stats_config::*;
+pub use super::sync_config::*;
+pub use super::validation::*;
+pub use super::video_config::*;
+pub use super::web_upload_config::*;
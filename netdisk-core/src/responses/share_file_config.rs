@@ -1,9 +1,12 @@
 use super::base_config::*;
+use super::validation::*;
 use crate::netdisk_api::prelude::*;
 use chrono::{DateTime, Local, NaiveDateTime, TimeZone, Utc};
 use serde::{self, Deserialize, Deserializer, Serialize, Serializer};
+use std::collections::BTreeMap;
+use utoipa::ToSchema;
 
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct ShareItem {
     /// 必填：分享链接名称
@@ -33,7 +36,142 @@ pub struct ShareItem {
     pub traffic_limit: Option<u64>, // 使用 u64 来匹配 int64 的要求，确保足够的容量
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+impl Validate for ShareItem {
+    fn check(&self, errors: &mut FieldErrors) {
+        errors.not_blank("shareName", &self.share_name);
+        check_file_id_list(errors, "fileIDList", &self.file_id_list);
+        check_traffic(
+            errors,
+            self.traffic_switch.map(i64::from),
+            self.traffic_limit_switch.map(i64::from),
+            None,
+        );
+    }
+}
+
+/// 单个分享最多包含的文件数
+pub const MAX_SHARE_FILES: usize = 100;
+/// 分享链接的公开地址前缀
+pub const SHARE_URL_PREFIX: &str = "https://www.123pan.com/s/";
+
+/// 分享的完整公开地址
+pub fn share_url(share_key: &str) -> String {
+    format!("{}{}", SHARE_URL_PREFIX, share_key)
+}
+
+/// 以文件 ID 数组创建分享，网关负责拼接 `fileIDList`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ShareCreateItem {
+    pub share_name: String,
+    pub share_expire: ShareExpireDays,
+    #[serde(rename = "fileIDs")]
+    pub file_ids: Vec<u64>,
+    pub share_pwd: Option<String>,
+    pub traffic_switch: Option<u8>,
+    pub traffic_limit_switch: Option<u8>,
+    pub traffic_limit: Option<u64>,
+}
+
+impl Validate for ShareCreateItem {
+    fn check(&self, errors: &mut FieldErrors) {
+        errors.not_blank("shareName", &self.share_name);
+        let mut file_ids = self.file_ids.clone();
+        file_ids.sort_unstable();
+        file_ids.dedup();
+        errors.count("fileIDs", file_ids.len(), 1, MAX_SHARE_FILES);
+        check_traffic(
+            errors,
+            self.traffic_switch.map(i64::from),
+            self.traffic_limit_switch.map(i64::from),
+            None,
+        );
+    }
+}
+
+impl ShareCreateItem {
+    /// 校验并转换为上游的请求体：文件 ID 去重后须为 1 到 100 个
+    pub fn to_share_item(&self) -> Result<ShareItem, String> {
+        if self.share_name.trim().is_empty() {
+            return Err("分享名称不能为空".to_string());
+        }
+        let mut file_ids: Vec<u64> = Vec::with_capacity(self.file_ids.len());
+        for file_id in &self.file_ids {
+            if !file_ids.contains(file_id) {
+                file_ids.push(*file_id);
+            }
+        }
+        if file_ids.is_empty() {
+            return Err("至少需要分享一个文件".to_string());
+        }
+        if file_ids.len() > MAX_SHARE_FILES {
+            return Err(format!(
+                "单个分享最多包含 {} 个文件，当前为 {} 个",
+                MAX_SHARE_FILES,
+                file_ids.len()
+            ));
+        }
+        Ok(ShareItem {
+            share_name: self.share_name.clone(),
+            share_expire: self.share_expire,
+            file_id_list: file_ids
+                .iter()
+                .map(u64::to_string)
+                .collect::<Vec<_>>()
+                .join(","),
+            share_pwd: self.share_pwd.clone().filter(|pwd| !pwd.is_empty()),
+            traffic_switch: self.traffic_switch,
+            traffic_limit_switch: self.traffic_limit_switch,
+            traffic_limit: self.traffic_limit,
+        })
+    }
+}
+
+/// 分享链接的完整信息，`message` 可直接粘贴发送
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ShareLink {
+    #[serde(rename = "shareID")]
+    pub share_id: u64,
+    pub share_key: String,
+    pub share_name: String,
+    pub url: String,
+    pub share_pwd: Option<String>,
+    pub share_expire: ShareExpireDays,
+    #[serde(rename = "fileIDs")]
+    pub file_ids: Vec<u64>,
+    pub message: String,
+}
+
+impl From<&ShareRecord> for ShareLink {
+    fn from(record: &ShareRecord) -> Self {
+        let url = share_url(&record.share_key);
+        let mut message = format!("分享「{}」\n链接：{}", record.share_name, url);
+        if let Some(pwd) = &record.share_pwd {
+            message.push_str(&format!("\n提取码：{}", pwd));
+        }
+        ShareLink {
+            share_id: record.share_id,
+            share_key: record.share_key.clone(),
+            share_name: record.share_name.clone(),
+            url,
+            share_pwd: record.share_pwd.clone(),
+            share_expire: record.share_expire,
+            file_ids: record.file_ids.clone(),
+            message,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ShareByFileQuery {
+    pub file_id: u64,
+}
+
+impl Validate for ShareByFileQuery {}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
 pub enum ShareExpireDays {
     #[serde(rename = "1")] // 在序列化/反序列化时，将这个成员映射为数字 1
     OneDay = 1,
@@ -48,7 +186,7 @@ pub enum ShareExpireDays {
     #[serde(rename = "0")]
     Permanent = 0,
 }
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct SharedData {
     #[serde(rename = "shareID")]
@@ -56,14 +194,20 @@ pub struct SharedData {
     pub share_key: String,
 }
 
-#[derive(Debug, Deserialize, Clone, Serialize)]
+#[derive(Debug, Deserialize, Clone, Serialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct ShareQuery {
     pub last_share_id: Option<i64>,
     pub limit: u8,
 }
 
-#[derive(Debug, Deserialize, Clone, Serialize)]
+impl Validate for ShareQuery {
+    fn check(&self, errors: &mut FieldErrors) {
+        errors.range("limit", self.limit as usize, 1, MAX_API_ITEMS);
+    }
+}
+
+#[derive(Debug, Deserialize, Clone, Serialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct ShareItemData {
     #[serde(rename = "shareId")]
@@ -85,14 +229,15 @@ pub struct ShareItemData {
     pub save_count: u32,
 }
 
-#[derive(Debug, Deserialize, Clone, Serialize)]
+#[derive(Debug, Deserialize, Clone, Serialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct ShareListData {
-    pub last_share_id: u64,
+    /// 为 -1 时表示已是最后一页
+    pub last_share_id: i64,
     pub share_list: Vec<ShareItemData>,
 }
 
-#[derive(Debug, Deserialize, Clone, Serialize)]
+#[derive(Debug, Deserialize, Clone, Serialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct ShareLinkItem {
     pub share_id_list: Vec<u64>,
@@ -101,7 +246,24 @@ pub struct ShareLinkItem {
     pub traffic_limit: Option<i64>,
 }
 
-#[derive(Debug, Deserialize, Clone, Serialize)]
+impl Validate for ShareLinkItem {
+    fn check(&self, errors: &mut FieldErrors) {
+        errors.count("shareIdList", self.share_id_list.len(), 1, MAX_API_ITEMS);
+        check_traffic(
+            errors,
+            self.traffic_switch.map(i64::from),
+            self.traffic_limit_switch.map(i64::from),
+            self.traffic_limit,
+        );
+    }
+}
+
+/// 付费分享的价格区间（元）
+pub const PAY_AMOUNT_RANGE: std::ops::RangeInclusive<u32> = 1..=99;
+/// 付费分享资源描述的最大字符数
+pub const RESOURCE_DESC_MAX_CHARS: usize = 200;
+
+#[derive(Debug, Deserialize, Clone, Serialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct PayLinkItem {
     pub share_name: String,
@@ -115,7 +277,44 @@ pub struct PayLinkItem {
     pub traffic_limit: Option<i64>,
 }
 
-#[derive(Debug, Deserialize, Clone, Serialize)]
+impl Validate for PayLinkItem {
+    fn check(&self, errors: &mut FieldErrors) {
+        errors.not_blank("shareName", &self.share_name);
+        if !PAY_AMOUNT_RANGE.contains(&self.pay_amount) {
+            errors.add(
+                "payAmount",
+                format!(
+                    "付费金额须为 {} 到 {} 元的整数，当前为 {}",
+                    PAY_AMOUNT_RANGE.start(),
+                    PAY_AMOUNT_RANGE.end(),
+                    self.pay_amount
+                ),
+            );
+        }
+        errors.one_of("isReward", self.is_reward, &[0, 1]);
+        if let Some(desc) = &self.resource_desc {
+            let chars = desc.chars().count();
+            if chars > RESOURCE_DESC_MAX_CHARS {
+                errors.add(
+                    "resourceDesc",
+                    format!(
+                        "资源描述最多 {} 个字符，当前为 {}",
+                        RESOURCE_DESC_MAX_CHARS, chars
+                    ),
+                );
+            }
+        }
+        check_file_id_list(errors, "fileIDList", &self.file_id_list);
+        check_traffic(
+            errors,
+            self.traffic_switch.map(i64::from),
+            self.traffic_limit_switch.map(i64::from),
+            self.traffic_limit,
+        );
+    }
+}
+
+#[derive(Debug, Deserialize, Clone, Serialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct PayShareItem {
     pub share_id: i64,
@@ -135,13 +334,226 @@ pub struct PayShareItem {
     pub save_count: u32,
 }
 
-#[derive(Debug, Deserialize, Clone, Serialize)]
+#[derive(Debug, Deserialize, Clone, Serialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct PayListItem {
-    pub last_share_id: i8,
+    /// 为 -1 时表示已是最后一页
+    pub last_share_id: i64,
     pub share_list: Option<Vec<PayShareItem>>,
 }
 
+/// 付费分享汇总中的单个分享
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct PaidShareSummary {
+    #[serde(rename = "shareID")]
+    pub share_id: u64,
+    pub share_key: String,
+    pub share_name: String,
+    pub url: String,
+    pub pay_amount: u32,
+    /// 累计收益
+    pub amount: i64,
+    pub download_count: u32,
+    pub save_count: u32,
+    pub expired: bool,
+}
+
+/// 全部付费分享及收益合计，分享按收益降序排列
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Default, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct PaidShareEarnings {
+    pub total_amount: i64,
+    pub share_count: usize,
+    pub active_count: usize,
+    pub shares: Vec<PaidShareSummary>,
+}
+
+/// 本地记录的分享：分享列表接口不返回文件 ID，重建过期分享时依赖该记录
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ShareRecord {
+    #[serde(rename = "shareID")]
+    pub share_id: u64,
+    pub share_key: String,
+    pub share_name: String,
+    #[serde(rename = "fileIDs")]
+    pub file_ids: Vec<u64>,
+    pub share_pwd: Option<String>,
+    pub share_expire: ShareExpireDays,
+    pub created_at: DateTime<Utc>,
+    /// 由哪个过期分享重建而来
+    pub recreated_from: Option<u64>,
+}
+
+/// `<配置目录>/shares.json` 的内容，以 shareID 为键
+#[derive(Debug, Deserialize, Serialize, Clone, Default)]
+pub struct ShareRecords {
+    pub shares: BTreeMap<u64, ShareRecord>,
+}
+
+/// 批量取消分享
+#[derive(Debug, Deserialize, Serialize, Clone, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ShareCancelItem {
+    pub share_id_list: Vec<u64>,
+    /// 相邻两次上游请求的最小间隔（毫秒）
+    pub interval_ms: Option<u64>,
+}
+
+impl Validate for ShareCancelItem {
+    fn check(&self, errors: &mut FieldErrors) {
+        errors.count("shareIdList", self.share_id_list.len(), 1, usize::MAX);
+        errors.interval_ms(self.interval_ms);
+    }
+}
+
+/// 批量修改分享的流量包设置，`shareIdList` 为空时修改全部未过期的分享
+#[derive(Debug, Deserialize, Serialize, Clone, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ShareTrafficItem {
+    #[serde(default)]
+    pub share_id_list: Vec<u64>,
+    pub traffic_switch: Option<i32>,
+    pub traffic_limit_switch: Option<i32>,
+    pub traffic_limit: Option<i64>,
+    pub interval_ms: Option<u64>,
+}
+
+impl Validate for ShareTrafficItem {
+    fn check(&self, errors: &mut FieldErrors) {
+        check_traffic(
+            errors,
+            self.traffic_switch.map(i64::from),
+            self.traffic_limit_switch.map(i64::from),
+            self.traffic_limit,
+        );
+        errors.interval_ms(self.interval_ms);
+    }
+}
+
+/// 查找到期分享时 `days` 的上限，即 100 年
+pub const MAX_EXPIRING_DAYS: i64 = 36500;
+
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, ToSchema)]
+pub struct ShareExpiringQuery {
+    /// 查找 N 天内到期的分享
+    #[serde(default = "default_expiring_days")]
+    pub days: i64,
+}
+
+impl Validate for ShareExpiringQuery {
+    fn check(&self, errors: &mut FieldErrors) {
+        if self.days < 0 {
+            errors.add("days", "不能为负数");
+        } else if self.days > MAX_EXPIRING_DAYS {
+            errors.add("days", format!("不能超过 {}", MAX_EXPIRING_DAYS));
+        }
+    }
+}
+
+fn default_expiring_days() -> i64 {
+    7
+}
+
+/// 用相同的文件与提取码重建已过期的分享
+#[derive(Debug, Deserialize, Serialize, Clone, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ShareRecreateItem {
+    /// 只重建这些分享，不传时重建全部已过期的分享
+    pub share_id_list: Option<Vec<u64>>,
+    /// 新分享的有效期，不传时沿用原分享创建时的设置
+    pub share_expire: Option<ShareExpireDays>,
+    /// 为 true（默认）时只返回重建计划
+    #[serde(default = "default_recreate_dry_run")]
+    pub dry_run: bool,
+}
+
+impl Validate for ShareRecreateItem {
+    fn check(&self, errors: &mut FieldErrors) {
+        if let Some(share_id_list) = &self.share_id_list {
+            errors.count("shareIdList", share_id_list.len(), 1, usize::MAX);
+        }
+    }
+}
+
+fn default_recreate_dry_run() -> bool {
+    true
+}
+
+/// 单个分享的重建结果，预览时 `shareID`/`shareKey` 为空
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct RecreatedShare {
+    #[serde(rename = "oldShareID")]
+    pub old_share_id: u64,
+    #[serde(rename = "shareID")]
+    pub share_id: Option<u64>,
+    pub share_key: Option<String>,
+    pub share_name: String,
+    #[serde(rename = "fileIDs")]
+    pub file_ids: Vec<u64>,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct SkippedShare {
+    #[serde(rename = "shareID")]
+    pub share_id: u64,
+    pub reason: String,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, Default, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ShareRecreateReport {
+    pub dry_run: bool,
+    pub recreated: Vec<RecreatedShare>,
+    /// 没有本地记录等无法重建的分享
+    pub skipped: Vec<SkippedShare>,
+    pub failed: Vec<SkippedShare>,
+}
+
 pub type SharedDataResponse = ApiResponse<SharedData>;
 pub type SharedListDataResponse = ApiResponse<ShareListData>;
 pub type PayShareDataResponse = ApiResponse<PayListItem>;
+pub type ShareItemsResponse = ApiResponse<Vec<ShareItemData>>;
+pub type ShareRecreateResponse = ApiResponse<ShareRecreateReport>;
+pub type ShareLinkResponse = ApiResponse<ShareLink>;
+pub type PayShareItemsResponse = ApiResponse<Vec<PayShareItem>>;
+pub type PaidShareEarningsResponse = ApiResponse<PaidShareEarnings>;
+pub type ShareLinksResponse = ApiResponse<Vec<ShareLink>>;
+
+/// 逗号分隔的文件 ID 列表须为 1 到 100 个数字
+fn check_file_id_list(errors: &mut FieldErrors, field: &str, file_id_list: &str) {
+    let file_ids: Vec<&str> = file_id_list
+        .split(',')
+        .map(str::trim)
+        .filter(|id| !id.is_empty())
+        .collect();
+    if file_ids.iter().any(|id| id.parse::<u64>().is_err()) {
+        errors.add(field, format!("文件 ID 列表格式错误: {}", file_id_list));
+    } else if file_ids.is_empty() || file_ids.len() > MAX_SHARE_FILES {
+        errors.add(
+            field,
+            format!(
+                "分享文件数须为 1 到 {} 个，当前为 {}",
+                MAX_SHARE_FILES,
+                file_ids.len()
+            ),
+        );
+    }
+}
+
+/// 流量包设置：`trafficSwitch` 为 1~4，`trafficLimitSwitch` 为 1~2，限制流量不能为负数
+fn check_traffic(
+    errors: &mut FieldErrors,
+    traffic_switch: Option<i64>,
+    traffic_limit_switch: Option<i64>,
+    traffic_limit: Option<i64>,
+) {
+    errors.one_of("trafficSwitch", traffic_switch, &[1, 2, 3, 4]);
+    errors.one_of("trafficLimitSwitch", traffic_limit_switch, &[1, 2]);
+    if traffic_limit.map_or(false, |limit| limit < 0) {
+        errors.add("trafficLimit", "不能为负数");
+    }
+}
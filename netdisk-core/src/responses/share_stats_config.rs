@@ -0,0 +1,161 @@
+use super::base_config::*;
+use super::validation::*;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+use utoipa::ToSchema;
+
+/// 某一时刻单个分享的统计数据，普通分享的 `amount` 为 0
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ShareStatsSnapshot {
+    pub taken_at: DateTime<Utc>,
+    #[serde(rename = "shareID")]
+    pub share_id: u64,
+    pub share_key: String,
+    pub share_name: String,
+    /// 是否为付费分享
+    pub paid: bool,
+    pub preview_count: u64,
+    pub download_count: u64,
+    pub save_count: u64,
+    /// 已消耗的流量包流量（字节）
+    pub bytes_charge: u64,
+    /// 付费分享的累计收益
+    pub amount: i64,
+}
+
+/// 报告的排序方式，均按统计区间内的增量降序
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq, Default, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum ShareStatsSort {
+    #[default]
+    Downloads,
+    Traffic,
+    Revenue,
+}
+
+impl FromStr for ShareStatsSort {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "downloads" => Ok(ShareStatsSort::Downloads),
+            "traffic" => Ok(ShareStatsSort::Traffic),
+            "revenue" => Ok(ShareStatsSort::Revenue),
+            _ => Err(format!(
+                "未知的排序方式 {}，可选 downloads/traffic/revenue",
+                s
+            )),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq, Default, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum ReportFormat {
+    #[default]
+    Json,
+    Csv,
+}
+
+impl FromStr for ReportFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "json" => Ok(ReportFormat::Json),
+            "csv" => Ok(ReportFormat::Csv),
+            _ => Err(format!("未知的报告格式 {}，可选 json/csv", s)),
+        }
+    }
+}
+
+impl fmt::Display for ReportFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ReportFormat::Json => write!(f, "json"),
+            ReportFormat::Csv => write!(f, "csv"),
+        }
+    }
+}
+
+/// 报告统计区间 `sinceDays` 的上限，即 100 年
+pub const MAX_SINCE_DAYS: i64 = 36500;
+
+/// `GET /share/stats/report` 的查询参数
+#[derive(Debug, Deserialize, Serialize, Clone, Default, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ShareStatsQuery {
+    /// 只统计最近 N 天的快照，不传时统计全部快照
+    pub since_days: Option<i64>,
+    #[serde(default)]
+    pub sort: ShareStatsSort,
+    /// 只保留排序后的前 N 个分享
+    pub top: Option<usize>,
+    #[serde(default)]
+    pub format: ReportFormat,
+}
+
+impl Validate for ShareStatsQuery {
+    fn check(&self, errors: &mut FieldErrors) {
+        match self.since_days {
+            Some(days) if days < 0 => errors.add("sinceDays", "不能为负数"),
+            Some(days) if days > MAX_SINCE_DAYS => {
+                errors.add("sinceDays", format!("不能超过 {}", MAX_SINCE_DAYS))
+            }
+            _ => {}
+        }
+        if self.top == Some(0) {
+            errors.add("top", "至少为 1");
+        }
+    }
+}
+
+/// 单个分享在统计区间内的最新数据与增量（区间内最后一次快照减去第一次快照）
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ShareStatsRow {
+    #[serde(rename = "shareID")]
+    pub share_id: u64,
+    pub share_key: String,
+    pub share_name: String,
+    pub paid: bool,
+    pub first_at: DateTime<Utc>,
+    pub last_at: DateTime<Utc>,
+    pub preview_count: u64,
+    pub download_count: u64,
+    pub save_count: u64,
+    pub bytes_charge: u64,
+    pub amount: i64,
+    pub preview_delta: u64,
+    pub download_delta: u64,
+    pub save_delta: u64,
+    pub bytes_delta: u64,
+    pub revenue_delta: i64,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ShareStatsReport {
+    pub generated_at: DateTime<Utc>,
+    pub since: Option<DateTime<Utc>>,
+    pub sort: ShareStatsSort,
+    pub download_delta: u64,
+    pub bytes_delta: u64,
+    pub revenue_delta: i64,
+    pub rows: Vec<ShareStatsRow>,
+}
+
+/// 一次快照写入的分享数量
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ShareSnapshotResult {
+    pub taken_at: DateTime<Utc>,
+    pub shares: usize,
+    pub paid: usize,
+}
+
+pub type ShareStatsReportResponse = ApiResponse<ShareStatsReport>;
+pub type ShareSnapshotResponse = ApiResponse<ShareSnapshotResult>;
@@ -0,0 +1,273 @@
+use super::base_config::*;
+use super::validation::*;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use utoipa::ToSchema;
+
+/// 同步状态中记录的单个文件，`md5` 同时也是远端的 etag
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct SyncedFile {
+    pub size: u64,
+    pub md5: String,
+    /// 本地文件的修改时间（Unix 秒），未变化时可复用 `md5` 而不必重新计算
+    pub mtime: i64,
+}
+
+/// 上一次同步完成时两端一致的文件集合，用于判断是哪一侧发生了修改
+#[derive(Debug, Deserialize, Serialize, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct SyncState {
+    pub local_root: PathBuf,
+    #[serde(rename = "remoteDirID")]
+    pub remote_dir_id: u64,
+    pub synced_at: Option<DateTime<Utc>>,
+    /// 以 `/` 分隔的相对路径为键
+    pub files: BTreeMap<String, SyncedFile>,
+}
+
+/// 同步计划中的单个动作，路径均为相对同步根目录、以 `/` 分隔的路径
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Eq, ToSchema)]
+#[serde(tag = "action", rename_all = "camelCase")]
+pub enum SyncAction {
+    /// 本地新增或修改，上传到远端
+    #[serde(rename_all = "camelCase")]
+    Upload {
+        path: String,
+        size: u64,
+        md5: String,
+    },
+    /// 远端新增或修改，下载到本地
+    #[serde(rename_all = "camelCase")]
+    Download {
+        path: String,
+        file_id: u64,
+        size: u64,
+        etag: String,
+    },
+    /// 本地发生了重命名/移动，在远端执行相同操作
+    #[serde(rename_all = "camelCase")]
+    RenameRemote {
+        from: String,
+        to: String,
+        file_id: u64,
+    },
+    /// 远端发生了重命名/移动，在本地执行相同操作
+    #[serde(rename_all = "camelCase")]
+    RenameLocal { from: String, to: String },
+    /// 远端已删除且本地未修改
+    #[serde(rename_all = "camelCase")]
+    DeleteLocal { path: String },
+    /// 本地已删除且远端未修改，远端文件移入回收站
+    #[serde(rename_all = "camelCase")]
+    DeleteRemote { path: String, file_id: u64 },
+    /// 两端自上次同步后都发生了修改，需要人工处理
+    #[serde(rename_all = "camelCase")]
+    Conflict { path: String, reason: String },
+}
+
+impl SyncAction {
+    /// 动作作用的（目标）路径
+    pub fn path(&self) -> &str {
+        match self {
+            SyncAction::Upload { path, .. }
+            | SyncAction::Download { path, .. }
+            | SyncAction::DeleteLocal { path }
+            | SyncAction::DeleteRemote { path, .. }
+            | SyncAction::Conflict { path, .. } => path,
+            SyncAction::RenameRemote { to, .. } | SyncAction::RenameLocal { to, .. } => to,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, Default, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct SyncPlan {
+    pub actions: Vec<SyncAction>,
+    /// 两端内容一致、无需处理的文件
+    pub in_sync: Vec<String>,
+}
+
+impl SyncPlan {
+    pub fn conflicts(&self) -> usize {
+        self.actions
+            .iter()
+            .filter(|action| matches!(action, SyncAction::Conflict { .. }))
+            .count()
+    }
+}
+
+/// 同步请求，默认只预览同步计划
+#[derive(Debug, Deserialize, Serialize, Clone, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct SyncItem {
+    #[schema(value_type = String)]
+    pub local_path: PathBuf,
+    #[serde(rename = "remoteDirID", alias = "remoteDirId")]
+    pub remote_dir_id: u64,
+    #[serde(default = "default_sync_dry_run")]
+    pub dry_run: bool,
+}
+
+impl Validate for SyncItem {
+    fn check(&self, errors: &mut FieldErrors) {
+        if self.local_path.as_os_str().is_empty() {
+            errors.add("localPath", "不能为空");
+        }
+    }
+}
+
+fn default_sync_dry_run() -> bool {
+    true
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct SyncFailure {
+    pub path: String,
+    pub message: String,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, Default, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct SyncReport {
+    pub dry_run: bool,
+    pub plan: SyncPlan,
+    pub applied: usize,
+    pub failed: Vec<SyncFailure>,
+    /// 执行后远端同步目录下的全部目录（含新建的），网关据此使缓存失效
+    #[serde(skip)]
+    pub remote_dirs: Vec<u64>,
+}
+
+impl SyncReport {
+    /// 被移动、改名或移入回收站的远端文件
+    pub fn remote_file_ids(&self) -> Vec<u64> {
+        self.plan
+            .actions
+            .iter()
+            .filter_map(|action| match action {
+                SyncAction::RenameRemote { file_id, .. }
+                | SyncAction::DeleteRemote { file_id, .. } => Some(*file_id),
+                _ => None,
+            })
+            .collect()
+    }
+}
+
+/// 单向镜像时远端多余文件（本地已不存在）的处理方式
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq, Default, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub enum OrphanPolicy {
+    /// 移动到远端根目录下按日期命名的归档目录
+    #[default]
+    Archive,
+    /// 移入回收站
+    Trash,
+}
+
+/// 单向镜像（备份）请求：只上传，不下载
+#[derive(Debug, Deserialize, Serialize, Clone, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct MirrorItem {
+    #[schema(value_type = String)]
+    pub local_path: PathBuf,
+    #[serde(rename = "remoteDirID", alias = "remoteDirId")]
+    pub remote_dir_id: u64,
+    #[serde(default)]
+    pub orphan_policy: OrphanPolicy,
+    /// 归档目录名，位于远端根目录下，默认为 `_archive`
+    #[serde(default = "default_archive_dir")]
+    pub archive_dir: String,
+    #[serde(default = "default_sync_dry_run")]
+    pub dry_run: bool,
+}
+
+impl Validate for MirrorItem {
+    fn check(&self, errors: &mut FieldErrors) {
+        if self.local_path.as_os_str().is_empty() {
+            errors.add("localPath", "不能为空");
+        }
+        errors.file_name("archiveDir", &self.archive_dir);
+    }
+}
+
+pub fn default_archive_dir() -> String {
+    "_archive".to_string()
+}
+
+/// 单向镜像计划中的单个动作
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Eq, ToSchema)]
+#[serde(tag = "action", rename_all = "camelCase")]
+pub enum MirrorAction {
+    /// 本地新增或修改，上传并覆盖远端
+    #[serde(rename_all = "camelCase")]
+    Upload {
+        path: String,
+        size: u64,
+        md5: String,
+    },
+    /// 远端多余的文件移动到归档目录下的 `to`
+    #[serde(rename_all = "camelCase")]
+    Archive {
+        path: String,
+        to: String,
+        file_id: u64,
+    },
+    /// 远端多余的文件移入回收站
+    #[serde(rename_all = "camelCase")]
+    Trash { path: String, file_id: u64 },
+}
+
+impl MirrorAction {
+    pub fn path(&self) -> &str {
+        match self {
+            MirrorAction::Upload { path, .. }
+            | MirrorAction::Archive { path, .. }
+            | MirrorAction::Trash { path, .. } => path,
+        }
+    }
+}
+
+/// 单向镜像每次运行的汇总报告
+#[derive(Debug, Deserialize, Serialize, Clone, Default, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct MirrorReport {
+    pub dry_run: bool,
+    pub started_at: Option<DateTime<Utc>>,
+    pub finished_at: Option<DateTime<Utc>>,
+    pub actions: Vec<MirrorAction>,
+    /// 内容未变化、无需上传的文件数
+    pub unchanged: usize,
+    /// 命中秒传、未发送内容的上传数
+    pub reused: usize,
+    /// 实际发送了内容的上传数与字节数
+    pub uploaded: usize,
+    pub uploaded_bytes: u64,
+    pub archived: usize,
+    pub trashed: usize,
+    pub failed: Vec<SyncFailure>,
+    /// 执行后远端镜像目录下的全部目录（含新建的），网关据此使缓存失效
+    #[serde(skip)]
+    pub remote_dirs: Vec<u64>,
+}
+
+impl MirrorReport {
+    /// 被归档或移入回收站的远端文件
+    pub fn remote_file_ids(&self) -> Vec<u64> {
+        self.actions
+            .iter()
+            .filter_map(|action| match action {
+                MirrorAction::Archive { file_id, .. } | MirrorAction::Trash { file_id, .. } => {
+                    Some(*file_id)
+                }
+                MirrorAction::Upload { .. } => None,
+            })
+            .collect()
+    }
+}
+
+pub type SyncReportResponse = ApiResponse<SyncReport>;
+pub type MirrorReportResponse = ApiResponse<MirrorReport>;
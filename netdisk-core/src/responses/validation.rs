@@ -0,0 +1,163 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use utoipa::ToSchema;
+
+/// 开放平台单次请求最多接受的 ID 数量与分页大小
+pub const MAX_API_ITEMS: usize = 100;
+
+/// 文件名最多字符数
+pub const MAX_FILE_NAME_CHARS: usize = 255;
+
+/// 批量接口中 `intervalMs` 的上限，即 1 分钟
+pub const MAX_INTERVAL_MS: u64 = 60_000;
+
+/// 文件名中不允许出现的字符
+pub const ILLEGAL_NAME_CHARS: &[char] = &['"', '\\', '/', ':', '*', '?', '|', '>', '<'];
+
+/// 单个字段的校验错误，`field` 为请求中的字段名
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, ToSchema)]
+pub struct FieldError {
+    pub field: String,
+    pub message: String,
+}
+
+/// 一次校验收集到的全部字段错误
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, Default, ToSchema)]
+#[serde(transparent)]
+pub struct FieldErrors(pub Vec<FieldError>);
+
+impl FieldErrors {
+    pub fn single(field: &str, message: impl Into<String>) -> Self {
+        let mut errors = FieldErrors::default();
+        errors.add(field, message);
+        errors
+    }
+
+    pub fn add(&mut self, field: &str, message: impl Into<String>) {
+        self.0.push(FieldError {
+            field: field.to_string(),
+            message: message.into(),
+        });
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// 是否包含该字段的错误
+    pub fn has(&self, field: &str) -> bool {
+        self.0.iter().any(|error| error.field == field)
+    }
+
+    /// 数值须在 `min..=max` 之间
+    pub fn range<T: PartialOrd + fmt::Display>(&mut self, field: &str, value: T, min: T, max: T) {
+        if value < min || value > max {
+            self.add(
+                field,
+                format!("须在 {} 到 {} 之间，当前为 {}", min, max, value),
+            );
+        }
+    }
+
+    /// 可选值存在时须为 `allowed` 之一
+    pub fn one_of<T: PartialEq + fmt::Display>(
+        &mut self,
+        field: &str,
+        value: Option<T>,
+        allowed: &[T],
+    ) {
+        if let Some(value) = value {
+            if !allowed.contains(&value) {
+                let allowed: Vec<String> = allowed.iter().map(T::to_string).collect();
+                self.add(
+                    field,
+                    format!("只能为 {}，当前为 {}", allowed.join("、"), value),
+                );
+            }
+        }
+    }
+
+    /// 列表长度须在 `min..=max` 之间
+    pub fn count(&mut self, field: &str, len: usize, min: usize, max: usize) {
+        if len < min {
+            self.add(field, format!("至少需要 {} 项", min));
+        } else if len > max {
+            self.add(field, format!("最多 {} 项，当前为 {} 项", max, len));
+        }
+    }
+
+    /// 批量接口的请求间隔不能超过 `MAX_INTERVAL_MS`
+    pub fn interval_ms(&mut self, value: Option<u64>) {
+        if let Some(value) = value {
+            self.range("intervalMs", value, 0, MAX_INTERVAL_MS);
+        }
+    }
+
+    /// 字符串去除空白后不能为空
+    pub fn not_blank(&mut self, field: &str, value: &str) {
+        if value.trim().is_empty() {
+            self.add(field, "不能为空");
+        }
+    }
+
+    /// 合法的文件名：非空、不超过 255 个字符且不含 `"\/:*?|><`
+    pub fn file_name(&mut self, field: &str, name: &str) {
+        if let Err(message) = check_file_name(name) {
+            self.add(field, message);
+        }
+    }
+}
+
+impl fmt::Display for FieldErrors {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let errors: Vec<String> = self
+            .0
+            .iter()
+            .map(|error| format!("{}: {}", error.field, error.message))
+            .collect();
+        write!(f, "{}", errors.join("；"))
+    }
+}
+
+impl std::error::Error for FieldErrors {}
+
+/// 校验文件名，不合法时返回原因
+pub fn check_file_name(name: &str) -> Result<(), String> {
+    if name.trim().is_empty() {
+        return Err("文件名不能为空".to_string());
+    }
+    let chars = name.chars().count();
+    if chars > MAX_FILE_NAME_CHARS {
+        return Err(format!(
+            "文件名最多 {} 个字符，当前为 {}",
+            MAX_FILE_NAME_CHARS, chars
+        ));
+    }
+    if let Some(c) = name.chars().find(|c| ILLEGAL_NAME_CHARS.contains(c)) {
+        return Err(format!("文件名不能包含 {}", c));
+    }
+    if name.chars().any(char::is_control) {
+        return Err("文件名不能包含控制字符".to_string());
+    }
+    Ok(())
+}
+
+/// 网关请求参数的校验，由 `ValidJson` / `ValidQuery` 在反序列化后调用；
+/// 没有约束的请求使用默认实现
+pub trait Validate {
+    /// 将不合法的字段记录到 `errors`
+    fn check(&self, errors: &mut FieldErrors) {
+        let _ = errors;
+    }
+
+    /// 全部字段合法时返回 `Ok`，否则返回每个不合法字段的说明
+    fn validate(&self) -> Result<(), FieldErrors> {
+        let mut errors = FieldErrors::default();
+        self.check(&mut errors);
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+}
@@ -0,0 +1,154 @@
+use super::base_config::*;
+use super::validation::*;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+/// 转码成功的状态码，1 为待转码，2 为转码中，3~254 为转码失败
+pub const TRANSCODE_STATUS_SUCCEEDED: i32 = 255;
+
+/// 将文件从云盘空间上传到转码空间
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct TranscodeUploadItem {
+    #[serde(rename = "fileId")]
+    pub file_ids: Vec<TranscodeFileId>,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, Copy)]
+pub struct TranscodeFileId {
+    #[serde(rename = "fileId")]
+    pub file_id: u64,
+}
+
+/// 视频可转码的分辨率，首次查询时上游开始解析，`IsGetResolution` 为 false 表示尚未解析完成
+#[derive(Debug, Deserialize, Serialize, Clone, Default, PartialEq, ToSchema)]
+#[serde(rename_all = "PascalCase", default)]
+pub struct VideoResolutionsData {
+    pub is_get_resolution: bool,
+    /// 逗号分隔，例如 `1080P,720P,480P`
+    pub resolutions: String,
+    /// 正在转码或已转码的分辨率
+    pub now_or_finished_resolutions: String,
+    pub codec_names: String,
+    /// 视频时长（秒）
+    pub video_time: u64,
+}
+
+/// 提交转码的上游请求体
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct TranscodeVideoItem {
+    pub file_id: u64,
+    pub codec_name: String,
+    pub video_time: u64,
+    pub resolutions: String,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+#[serde(rename_all = "PascalCase")]
+pub struct TranscodedFile {
+    pub file_name: String,
+    #[serde(default)]
+    pub file_size: u64,
+    #[serde(default)]
+    pub resolution: String,
+    pub url: String,
+}
+
+/// 单个分辨率的转码结果
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+#[serde(rename_all = "PascalCase")]
+pub struct TranscodedVideo {
+    pub resolution: String,
+    pub status: i32,
+    #[serde(default)]
+    pub files: Vec<TranscodedFile>,
+}
+
+impl TranscodedVideo {
+    /// 转码成功时的 m3u8 播放列表地址
+    pub fn playlist_url(&self) -> Option<&str> {
+        if self.status != TRANSCODE_STATUS_SUCCEEDED {
+            return None;
+        }
+        self.files
+            .iter()
+            .find(|file| file.file_name.to_ascii_lowercase().ends_with(".m3u8"))
+            .map(|file| file.url.as_str())
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, Default)]
+pub struct TranscodeResultData {
+    #[serde(rename = "UserTranscodeVideoList", default)]
+    pub videos: Vec<TranscodedVideo>,
+}
+
+/// 提交转码，`resolutions` 不传时转码全部可用分辨率
+#[derive(Debug, Deserialize, Serialize, Clone, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct VideoTranscodeItem {
+    #[serde(rename = "fileID")]
+    pub file_id: u64,
+    pub resolutions: Option<String>,
+    pub codec_name: Option<String>,
+}
+
+impl Validate for VideoTranscodeItem {}
+
+#[derive(Debug, Deserialize, Default, ToSchema)]
+pub struct VideoPlayQuery {
+    /// 不传时返回包含全部已转码分辨率的主播放列表
+    pub resolution: Option<String>,
+}
+
+impl Validate for VideoPlayQuery {}
+
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum VideoTranscodeStatus {
+    Transcoding,
+    Succeeded,
+    Failed,
+}
+
+impl VideoTranscodeStatus {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            VideoTranscodeStatus::Transcoding => "transcoding",
+            VideoTranscodeStatus::Succeeded => "succeeded",
+            VideoTranscodeStatus::Failed => "failed",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "transcoding" => Some(VideoTranscodeStatus::Transcoding),
+            "succeeded" => Some(VideoTranscodeStatus::Succeeded),
+            "failed" => Some(VideoTranscodeStatus::Failed),
+            _ => None,
+        }
+    }
+}
+
+/// 本地数据库中记录的转码状态
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct VideoTranscode {
+    #[serde(rename = "fileID")]
+    pub file_id: u64,
+    pub status: VideoTranscodeStatus,
+    /// 提交转码的分辨率
+    pub resolutions: Vec<String>,
+    /// 已转码完成、可以播放的分辨率
+    pub ready: Vec<String>,
+    /// 转码失败的分辨率
+    pub failed: Vec<String>,
+    pub submitted_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+pub type VideoResolutionsResponse = ApiResponse<VideoResolutionsData>;
+pub type TranscodeResultResponse = ApiResponse<TranscodeResultData>;
+pub type VideoTranscodeResponse = ApiResponse<VideoTranscode>;
+pub type VideoTranscodesResponse = ApiResponse<Vec<VideoTranscode>>;
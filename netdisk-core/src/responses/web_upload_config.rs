@@ -0,0 +1,57 @@
+use super::base_config::*;
+use super::validation::*;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+/// 浏览器分块上传：创建上传会话
+#[derive(Debug, Deserialize, Serialize, Clone, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct WebUploadItem {
+    #[serde(rename = "parentFileID", alias = "parentFileId")]
+    pub parent_file_id: u64,
+    pub filename: String,
+    pub size: u64,
+    /// 同名文件处理策略：1 保留两者，2 覆盖
+    pub duplicate: Option<u8>,
+}
+
+impl Validate for WebUploadItem {
+    fn check(&self, errors: &mut FieldErrors) {
+        errors.file_name("filename", &self.filename);
+        errors.one_of("duplicate", self.duplicate, &[1, 2]);
+    }
+}
+
+/// 上传会话状态，`received` 为已暂存的字节数，下一个分块应从该偏移开始
+#[derive(Debug, Deserialize, Serialize, Clone, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct WebUploadSession {
+    #[serde(rename = "uploadID")]
+    pub upload_id: String,
+    #[serde(rename = "parentFileID")]
+    pub parent_file_id: u64,
+    pub filename: String,
+    pub size: u64,
+    pub duplicate: Option<u8>,
+    pub received: u64,
+}
+
+/// 追加分块时的查询参数
+#[derive(Debug, Deserialize, Clone, Copy, ToSchema)]
+pub struct WebUploadChunkQuery {
+    pub offset: u64,
+}
+
+impl Validate for WebUploadChunkQuery {}
+
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct WebUploadResult {
+    #[serde(rename = "fileID")]
+    pub file_id: u64,
+    /// 是否命中秒传
+    pub reused: bool,
+}
+
+pub type WebUploadSessionResponse = ApiResponse<WebUploadSession>;
+pub type WebUploadResultResponse = ApiResponse<WebUploadResult>;
@@ -0,0 +1,270 @@
+use crate::local_db::open_local_db;
+use crate::netdisk_api::paid_share_api::list_all_paid_shares;
+use crate::netdisk_api::share_manage_api::list_all_shares;
+use crate::netdisk_auth::basic_env::NetDiskEnv;
+use crate::responses::prelude::*;
+use chrono::{DateTime, TimeDelta, Utc};
+use log::{info, warn};
+use rusqlite::{params, Connection};
+use std::cmp::Reverse;
+use std::collections::BTreeMap;
+use std::error::Error;
+
+/// 由分享列表与付费分享列表生成快照，同一分享同时出现在两个列表时以付费分享为准
+pub fn snapshots_from_shares(
+    shares: &[ShareItemData],
+    paid_shares: &[PayShareItem],
+    taken_at: DateTime<Utc>,
+) -> Vec<ShareStatsSnapshot> {
+    let mut snapshots: BTreeMap<u64, ShareStatsSnapshot> = BTreeMap::new();
+    for share in shares {
+        snapshots.insert(
+            share.share_id as u64,
+            ShareStatsSnapshot {
+                taken_at,
+                share_id: share.share_id as u64,
+                share_key: share.share_key.clone(),
+                share_name: share.share_name.clone(),
+                paid: false,
+                preview_count: u64::from(share.preview_count),
+                download_count: u64::from(share.download_count),
+                save_count: u64::from(share.save_count),
+                bytes_charge: share.bytes_charge,
+                amount: 0,
+            },
+        );
+    }
+    for share in paid_shares {
+        snapshots.insert(
+            share.share_id as u64,
+            ShareStatsSnapshot {
+                taken_at,
+                share_id: share.share_id as u64,
+                share_key: share.share_key.clone(),
+                share_name: share.share_name.clone(),
+                paid: true,
+                preview_count: u64::from(share.preview_count),
+                download_count: u64::from(share.download_count),
+                save_count: u64::from(share.save_count),
+                bytes_charge: share.bytes_charge,
+                amount: i64::from(share.amount),
+            },
+        );
+    }
+    snapshots.into_values().collect()
+}
+
+/// 在一个事务中写入一批快照
+pub fn insert_snapshots(
+    conn: &mut Connection,
+    snapshots: &[ShareStatsSnapshot],
+) -> rusqlite::Result<()> {
+    let tx = conn.transaction()?;
+    {
+        let mut stmt = tx.prepare(
+            "INSERT INTO share_snapshots (taken_at, share_id, share_key, share_name, paid,
+                preview_count, download_count, save_count, bytes_charge, amount)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+        )?;
+        for snapshot in snapshots {
+            stmt.execute(params![
+                snapshot.taken_at,
+                snapshot.share_id as i64,
+                snapshot.share_key,
+                snapshot.share_name,
+                snapshot.paid,
+                snapshot.preview_count as i64,
+                snapshot.download_count as i64,
+                snapshot.save_count as i64,
+                snapshot.bytes_charge as i64,
+                snapshot.amount,
+            ])?;
+        }
+    }
+    tx.commit()
+}
+
+/// 读取 `since` 之后（含）的快照，按分享与时间排序
+pub fn load_snapshots(
+    conn: &Connection,
+    since: Option<DateTime<Utc>>,
+) -> rusqlite::Result<Vec<ShareStatsSnapshot>> {
+    let mut stmt = conn.prepare(
+        "SELECT taken_at, share_id, share_key, share_name, paid,
+            preview_count, download_count, save_count, bytes_charge, amount
+         FROM share_snapshots
+         WHERE ?1 IS NULL OR taken_at >= ?1
+         ORDER BY share_id, taken_at",
+    )?;
+    let rows = stmt.query_map(params![since], |row| {
+        Ok(ShareStatsSnapshot {
+            taken_at: row.get(0)?,
+            share_id: row.get::<_, i64>(1)? as u64,
+            share_key: row.get(2)?,
+            share_name: row.get(3)?,
+            paid: row.get(4)?,
+            preview_count: row.get::<_, i64>(5)? as u64,
+            download_count: row.get::<_, i64>(6)? as u64,
+            save_count: row.get::<_, i64>(7)? as u64,
+            bytes_charge: row.get::<_, i64>(8)? as u64,
+            amount: row.get(9)?,
+        })
+    })?;
+    rows.collect()
+}
+
+fn stats_row(first: &ShareStatsSnapshot, last: &ShareStatsSnapshot) -> ShareStatsRow {
+    ShareStatsRow {
+        share_id: last.share_id,
+        share_key: last.share_key.clone(),
+        share_name: last.share_name.clone(),
+        paid: last.paid,
+        first_at: first.taken_at,
+        last_at: last.taken_at,
+        preview_count: last.preview_count,
+        download_count: last.download_count,
+        save_count: last.save_count,
+        bytes_charge: last.bytes_charge,
+        amount: last.amount,
+        // 计数只增不减，出现回退时（例如上游重置）按 0 计
+        preview_delta: last.preview_count.saturating_sub(first.preview_count),
+        download_delta: last.download_count.saturating_sub(first.download_count),
+        save_delta: last.save_count.saturating_sub(first.save_count),
+        bytes_delta: last.bytes_charge.saturating_sub(first.bytes_charge),
+        revenue_delta: (last.amount - first.amount).max(0),
+    }
+}
+
+/// 按分享汇总快照并计算增量；`snapshots` 须按分享与时间排序（`load_snapshots` 的顺序）
+pub fn build_report(
+    snapshots: &[ShareStatsSnapshot],
+    since: Option<DateTime<Utc>>,
+    sort: ShareStatsSort,
+    top: Option<usize>,
+    generated_at: DateTime<Utc>,
+) -> ShareStatsReport {
+    let mut rows: Vec<ShareStatsRow> = Vec::new();
+    let mut start = 0;
+    while start < snapshots.len() {
+        let share_id = snapshots[start].share_id;
+        let mut end = start;
+        while end + 1 < snapshots.len() && snapshots[end + 1].share_id == share_id {
+            end += 1;
+        }
+        rows.push(stats_row(&snapshots[start], &snapshots[end]));
+        start = end + 1;
+    }
+
+    match sort {
+        ShareStatsSort::Downloads => rows.sort_by_key(|row| Reverse(row.download_delta)),
+        ShareStatsSort::Traffic => rows.sort_by_key(|row| Reverse(row.bytes_delta)),
+        ShareStatsSort::Revenue => rows.sort_by_key(|row| Reverse(row.revenue_delta)),
+    }
+    let download_delta = rows.iter().map(|row| row.download_delta).sum();
+    let bytes_delta = rows.iter().map(|row| row.bytes_delta).sum();
+    let revenue_delta = rows.iter().map(|row| row.revenue_delta).sum();
+    if let Some(top) = top {
+        rows.truncate(top);
+    }
+    ShareStatsReport {
+        generated_at,
+        since,
+        sort,
+        download_delta,
+        bytes_delta,
+        revenue_delta,
+        rows,
+    }
+}
+
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/// 将报告的每个分享输出为一行 CSV，首行为表头
+pub fn report_to_csv(report: &ShareStatsReport) -> String {
+    let mut csv = String::from(
+        "shareID,shareKey,shareName,paid,firstAt,lastAt,previewCount,downloadCount,saveCount,\
+         bytesCharge,amount,previewDelta,downloadDelta,saveDelta,bytesDelta,revenueDelta\n",
+    );
+    for row in &report.rows {
+        csv.push_str(&format!(
+            "{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{}\n",
+            row.share_id,
+            csv_field(&row.share_key),
+            csv_field(&row.share_name),
+            row.paid,
+            row.first_at.to_rfc3339(),
+            row.last_at.to_rfc3339(),
+            row.preview_count,
+            row.download_count,
+            row.save_count,
+            row.bytes_charge,
+            row.amount,
+            row.preview_delta,
+            row.download_delta,
+            row.save_delta,
+            row.bytes_delta,
+            row.revenue_delta,
+        ));
+    }
+    csv
+}
+
+/// 拉取全部分享与付费分享的当前统计并写入本地数据库
+pub async fn take_snapshot(
+    token: &AccessToken,
+    env: &NetDiskEnv,
+) -> Result<ShareSnapshotResult, Box<dyn Error>> {
+    let shares = list_all_shares(token).await?;
+    let paid_shares = list_all_paid_shares(token).await?;
+    let taken_at = Utc::now();
+    let snapshots = snapshots_from_shares(&shares, &paid_shares, taken_at);
+    let mut conn = open_local_db(env)?;
+    insert_snapshots(&mut conn, &snapshots)?;
+    let result = ShareSnapshotResult {
+        taken_at,
+        shares: snapshots.len(),
+        paid: snapshots.iter().filter(|snapshot| snapshot.paid).count(),
+    };
+    info!(
+        "已记录 {} 个分享的统计快照（其中付费分享 {} 个）",
+        result.shares, result.paid
+    );
+    Ok(result)
+}
+
+/// 读取本地快照生成报告，查询参数不合法时返回错误
+pub fn share_stats_report(
+    env: &NetDiskEnv,
+    query: &ShareStatsQuery,
+) -> Result<ShareStatsReport, Box<dyn Error>> {
+    query.validate()?;
+    let now = Utc::now();
+    let since = match query.since_days {
+        Some(days) => Some(
+            TimeDelta::try_days(days)
+                .and_then(|days| now.checked_sub_signed(days))
+                .ok_or("sinceDays 超出范围")?,
+        ),
+        None => None,
+    };
+    let conn = open_local_db(env)?;
+    let snapshots = load_snapshots(&conn, since)?;
+    Ok(build_report(&snapshots, since, query.sort, query.top, now))
+}
+
+/// 网关运行期间按固定间隔记录快照，单次失败只记录日志
+pub async fn run_snapshot_loop(token: AccessToken, env: NetDiskEnv, interval: std::time::Duration) {
+    let mut ticker = tokio::time::interval(interval);
+    loop {
+        ticker.tick().await;
+        if let Err(e) = take_snapshot(&token, &env).await {
+            warn!("记录分享统计快照失败: {}", e);
+        }
+    }
+}
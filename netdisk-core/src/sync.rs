@@ -0,0 +1,5 @@
+pub mod engine;
+pub mod mirror;
+pub mod snapshot;
+pub mod state;
+pub mod watcher;
@@ -0,0 +1,372 @@
+use super::snapshot::*;
+use super::state::{load_state, save_state, SYNC_STATE_DIR};
+use crate::io_basic::checksum::async_file_md5;
+use crate::netdisk_api::file_api::{download_to_path, ensure_dir, fetch_download_url};
+use crate::netdisk_api::file_delete_api::trash_files;
+use crate::netdisk_api::file_move_api::{move_files, rename_file};
+use crate::netdisk_api::file_upload_api::upload_local_file;
+use crate::netdisk_auth::basic_env::NetDiskEnv;
+use crate::responses::prelude::*;
+use chrono::Utc;
+use log::{info, warn};
+use std::collections::{BTreeMap, BTreeSet};
+use std::error::Error;
+use std::path::Path;
+
+/// 根据本地快照、远端快照与上一次同步状态生成同步计划。
+///
+/// 某一侧的内容与同步状态一致，说明该侧自上次同步后未修改，以另一侧为准；
+/// 两侧都与同步状态不一致时视为冲突，不做任何处理。
+pub fn plan_sync(
+    local: &BTreeMap<String, LocalEntry>,
+    remote: &BTreeMap<String, RemoteEntry>,
+    state: &BTreeMap<String, SyncedFile>,
+) -> SyncPlan {
+    let mut plan = SyncPlan::default();
+    let paths: BTreeSet<&String> = local
+        .keys()
+        .chain(remote.keys())
+        .chain(state.keys())
+        .collect();
+    for path in paths {
+        let synced = state.get(path).map(|s| s.md5.as_str());
+        let action = match (local.get(path), remote.get(path)) {
+            (Some(l), Some(r)) if l.md5 == r.etag => {
+                plan.in_sync.push(path.clone());
+                continue;
+            }
+            (Some(l), None) => match synced {
+                None => upload(path, l),
+                Some(s) if s == l.md5 => SyncAction::DeleteLocal { path: path.clone() },
+                Some(_) => conflict(path, "本地已修改，远端已删除"),
+            },
+            (None, Some(r)) => match synced {
+                None => download(path, r),
+                Some(s) if s == r.etag => SyncAction::DeleteRemote {
+                    path: path.clone(),
+                    file_id: r.file_id,
+                },
+                Some(_) => conflict(path, "远端已修改，本地已删除"),
+            },
+            (Some(l), Some(r)) => match synced {
+                Some(s) if s == l.md5 => download(path, r),
+                Some(s) if s == r.etag => upload(path, l),
+                Some(_) => conflict(path, "两端自上次同步后都已修改"),
+                None => conflict(path, "两端都存在且内容不同，且没有同步记录"),
+            },
+            // 两端都已删除，只需从同步状态中移除
+            (None, None) => continue,
+        };
+        plan.actions.push(action);
+    }
+    detect_renames(&mut plan.actions, state);
+    plan
+}
+
+fn upload(path: &str, local: &LocalEntry) -> SyncAction {
+    SyncAction::Upload {
+        path: path.to_string(),
+        size: local.size,
+        md5: local.md5.clone(),
+    }
+}
+
+fn download(path: &str, remote: &RemoteEntry) -> SyncAction {
+    SyncAction::Download {
+        path: path.to_string(),
+        file_id: remote.file_id,
+        size: remote.size,
+        etag: remote.etag.clone(),
+    }
+}
+
+fn conflict(path: &str, reason: &str) -> SyncAction {
+    SyncAction::Conflict {
+        path: path.to_string(),
+        reason: reason.to_string(),
+    }
+}
+
+/// 将“删除旧路径 + 新增同内容的新路径”合并为重命名，避免重复传输
+fn detect_renames(actions: &mut Vec<SyncAction>, state: &BTreeMap<String, SyncedFile>) {
+    let mut merged: Vec<Option<SyncAction>> = actions.drain(..).map(Some).collect();
+    for i in 0..merged.len() {
+        let (old_path, remote_file_id) = match &merged[i] {
+            Some(SyncAction::DeleteRemote { path, file_id }) => (path.clone(), Some(*file_id)),
+            Some(SyncAction::DeleteLocal { path }) => (path.clone(), None),
+            _ => continue,
+        };
+        let md5 = match state.get(&old_path) {
+            Some(synced) => synced.md5.clone(),
+            None => continue,
+        };
+
+        // 本地重命名表现为远端删除 + 上传；远端重命名表现为本地删除 + 下载
+        let target = merged
+            .iter()
+            .position(|action| match (action, remote_file_id) {
+                (Some(SyncAction::Upload { path, md5: m, .. }), Some(_))
+                | (Some(SyncAction::Download { path, etag: m, .. }), None) => {
+                    *m == md5 && !state.contains_key(path)
+                }
+                _ => false,
+            });
+        if let Some(j) = target {
+            let new_path = merged[j]
+                .as_ref()
+                .map(|a| a.path().to_string())
+                .unwrap_or_default();
+            merged[j] = None;
+            merged[i] = Some(match remote_file_id {
+                Some(file_id) => SyncAction::RenameRemote {
+                    from: old_path,
+                    to: new_path,
+                    file_id,
+                },
+                None => SyncAction::RenameLocal {
+                    from: old_path,
+                    to: new_path,
+                },
+            });
+        }
+    }
+    actions.extend(merged.into_iter().flatten());
+}
+
+/// 确保远端存在相对目录 `rel_dir`（相对于 `tree` 的根目录），返回其 `dirID`
+pub async fn remote_dir(
+    token: &AccessToken,
+    tree: &mut RemoteTree,
+    rel_dir: &str,
+) -> Result<u64, Box<dyn Error>> {
+    if let Some(dir_id) = tree.dirs.get(rel_dir) {
+        return Ok(*dir_id);
+    }
+    let mut current = String::new();
+    let mut dir_id = tree.dirs[""];
+    for name in rel_dir.split('/') {
+        current = join_rel(&current, name);
+        dir_id = match tree.dirs.get(&current) {
+            Some(id) => *id,
+            None => {
+                let id = ensure_dir(token, name, dir_id).await?;
+                tree.dirs.insert(current.clone(), id);
+                id
+            }
+        };
+    }
+    Ok(dir_id)
+}
+
+/// 下载远端文件：先写入同目录下的临时文件，校验 MD5 后再替换目标文件
+pub async fn download_verified(
+    token: &AccessToken,
+    file_id: u64,
+    etag: &str,
+    target: &Path,
+) -> Result<(), Box<dyn Error>> {
+    if let Some(dir) = target.parent() {
+        tokio::fs::create_dir_all(dir).await?;
+    }
+    let tmp_path = target.with_file_name(format!(
+        ".{}.netdisk-tmp",
+        target.file_name().unwrap_or_default().to_string_lossy()
+    ));
+    let url = fetch_download_url(token, file_id).await?;
+    download_to_path(&url, &tmp_path).await?;
+    let md5 = async_file_md5(&tmp_path).await?;
+    if !md5.eq_ignore_ascii_case(etag) {
+        let _ = tokio::fs::remove_file(&tmp_path).await;
+        return Err(format!("下载内容校验失败，期望 {}，实际 {}", etag, md5).into());
+    }
+    tokio::fs::rename(&tmp_path, target).await?;
+    Ok(())
+}
+
+async fn apply_action(
+    token: &AccessToken,
+    local_root: &Path,
+    tree: &mut RemoteTree,
+    action: &SyncAction,
+) -> Result<(), Box<dyn Error>> {
+    match action {
+        SyncAction::Upload { path, .. } => {
+            let (dir, name) = split_rel(path);
+            let parent_id = remote_dir(token, tree, dir).await?;
+            // duplicate = 2：覆盖远端同名文件
+            upload_local_file(
+                token,
+                &local_path(local_root, path),
+                parent_id,
+                name,
+                Some(2),
+            )
+            .await?;
+        }
+        SyncAction::Download {
+            path,
+            file_id,
+            etag,
+            ..
+        } => download_verified(token, *file_id, etag, &local_path(local_root, path)).await?,
+        SyncAction::RenameRemote { from, to, file_id } => {
+            let (from_dir, from_name) = split_rel(from);
+            let (to_dir, to_name) = split_rel(to);
+            if from_dir != to_dir {
+                let parent_id = remote_dir(token, tree, to_dir).await?;
+                move_files(token, &[*file_id], parent_id).await?;
+            }
+            if from_name != to_name {
+                rename_file(token, *file_id, to_name).await?;
+            }
+        }
+        SyncAction::RenameLocal { from, to } => {
+            let target = local_path(local_root, to);
+            if let Some(dir) = target.parent() {
+                tokio::fs::create_dir_all(dir).await?;
+            }
+            tokio::fs::rename(local_path(local_root, from), target).await?;
+        }
+        SyncAction::DeleteLocal { path } => {
+            tokio::fs::remove_file(local_path(local_root, path)).await?
+        }
+        SyncAction::DeleteRemote { file_id, .. } => trash_files(token, &[*file_id]).await?,
+        SyncAction::Conflict { .. } => {}
+    }
+    Ok(())
+}
+
+/// 读取本地文件当前的大小与修改时间，生成同步记录
+async fn synced_local(
+    local_root: &Path,
+    path: &str,
+    md5: &str,
+) -> Result<SyncedFile, Box<dyn Error>> {
+    let metadata = tokio::fs::metadata(local_path(local_root, path)).await?;
+    Ok(SyncedFile {
+        size: metadata.len(),
+        md5: md5.to_string(),
+        mtime: mtime_of(&metadata),
+    })
+}
+
+/// 执行同步计划，返回新的同步状态记录、成功执行的动作数与失败列表。
+///
+/// 冲突与失败的路径保留原有的同步记录，下次同步时会再次处理。
+pub async fn apply_plan(
+    token: &AccessToken,
+    local_root: &Path,
+    tree: &mut RemoteTree,
+    local: &BTreeMap<String, LocalEntry>,
+    plan: &SyncPlan,
+    state: &BTreeMap<String, SyncedFile>,
+) -> (BTreeMap<String, SyncedFile>, usize, Vec<SyncFailure>) {
+    let mut new_state = BTreeMap::new();
+    for path in &plan.in_sync {
+        let entry = &local[path];
+        new_state.insert(
+            path.clone(),
+            SyncedFile {
+                size: entry.size,
+                md5: entry.md5.clone(),
+                mtime: entry.mtime,
+            },
+        );
+    }
+
+    let mut applied = 0;
+    let mut failed = Vec::new();
+    for action in &plan.actions {
+        let keep_old = |new_state: &mut BTreeMap<String, SyncedFile>, path: &str| {
+            if let Some(synced) = state.get(path) {
+                new_state.insert(path.to_string(), synced.clone());
+            }
+        };
+        if let SyncAction::Conflict { path, .. } = action {
+            keep_old(&mut new_state, path);
+            continue;
+        }
+
+        let result = match apply_action(token, local_root, tree, action).await {
+            Ok(()) => match action {
+                SyncAction::Upload { path, md5, .. } => {
+                    synced_local(local_root, path, md5).await.map(Some)
+                }
+                SyncAction::Download { path, etag, .. } => {
+                    synced_local(local_root, path, etag).await.map(Some)
+                }
+                SyncAction::RenameRemote { from, to, .. }
+                | SyncAction::RenameLocal { from, to } => {
+                    let md5 = state.get(from).map(|s| s.md5.clone()).unwrap_or_default();
+                    synced_local(local_root, to, &md5).await.map(Some)
+                }
+                _ => Ok(None),
+            },
+            Err(e) => Err(e),
+        };
+
+        match result {
+            Ok(synced) => {
+                applied += 1;
+                if let Some(synced) = synced {
+                    new_state.insert(action.path().to_string(), synced);
+                }
+            }
+            Err(e) => {
+                warn!("同步 {} 失败: {}", action.path(), e);
+                match action {
+                    SyncAction::RenameRemote { from, .. }
+                    | SyncAction::RenameLocal { from, .. } => keep_old(&mut new_state, from),
+                    _ => keep_old(&mut new_state, action.path()),
+                }
+                failed.push(SyncFailure {
+                    path: action.path().to_string(),
+                    message: e.to_string(),
+                });
+            }
+        }
+    }
+    (new_state, applied, failed)
+}
+
+/// 比较本地目录与远端目录并生成同步计划；`dry_run` 为 false 时执行计划并保存同步状态
+pub async fn run_sync(
+    token: &AccessToken,
+    env: &NetDiskEnv,
+    local_root: &Path,
+    remote_dir_id: u64,
+    dry_run: bool,
+) -> Result<SyncReport, Box<dyn Error>> {
+    let local_root = tokio::fs::canonicalize(local_root).await?;
+    let mut state = load_state(env, SYNC_STATE_DIR, &local_root, remote_dir_id).await?;
+    let local = scan_local(&local_root, &state.files).await?;
+    let mut tree = scan_remote(token, remote_dir_id).await?;
+    let plan = plan_sync(&local, &tree.files, &state.files);
+    info!(
+        "同步计划: {} 个动作，{} 个冲突，{} 个文件无需处理",
+        plan.actions.len(),
+        plan.conflicts(),
+        plan.in_sync.len()
+    );
+
+    if dry_run {
+        return Ok(SyncReport {
+            dry_run,
+            plan,
+            ..Default::default()
+        });
+    }
+
+    let (files, applied, failed) =
+        apply_plan(token, &local_root, &mut tree, &local, &plan, &state.files).await;
+    state.files = files;
+    state.synced_at = Some(Utc::now());
+    save_state(env, SYNC_STATE_DIR, &state).await?;
+    Ok(SyncReport {
+        dry_run,
+        plan,
+        applied,
+        failed,
+        remote_dirs: tree.dirs.values().copied().collect(),
+    })
+}
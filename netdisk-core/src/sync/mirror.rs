@@ -0,0 +1,195 @@
+use super::engine::remote_dir;
+use super::snapshot::*;
+use super::state::{load_state, save_state, MIRROR_STATE_DIR};
+use crate::netdisk_api::file_delete_api::trash_files;
+use crate::netdisk_api::file_move_api::move_files;
+use crate::netdisk_api::file_upload_api::upload_local_file;
+use crate::netdisk_auth::basic_env::NetDiskEnv;
+use crate::responses::prelude::*;
+use chrono::{Local, Utc};
+use log::{info, warn};
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::path::Path;
+use tokio::io::AsyncWriteExt;
+
+/// 每次镜像运行的报告追加写入该文件，位于 `<配置目录>/mirror/` 下
+pub const MIRROR_REPORT_LOG: &str = "reports.log";
+
+fn in_archive(path: &str, archive_dir: &str) -> bool {
+    path == archive_dir || path.starts_with(&format!("{}/", archive_dir))
+}
+
+/// 生成单向镜像计划，返回动作列表与内容未变化的文件数。
+///
+/// 归档目录 `archive_dir` 下的文件不参与比较；多余文件归档到 `<archive_dir>/<date>/` 下保持原有相对路径。
+pub fn plan_mirror(
+    local: &BTreeMap<String, LocalEntry>,
+    remote: &BTreeMap<String, RemoteEntry>,
+    policy: OrphanPolicy,
+    archive_dir: &str,
+    date: &str,
+) -> (Vec<MirrorAction>, usize) {
+    let mut actions = Vec::new();
+    let mut unchanged = 0;
+    for (path, entry) in local.iter().filter(|(p, _)| !in_archive(p, archive_dir)) {
+        match remote.get(path) {
+            Some(r) if r.etag == entry.md5 => unchanged += 1,
+            _ => actions.push(MirrorAction::Upload {
+                path: path.clone(),
+                size: entry.size,
+                md5: entry.md5.clone(),
+            }),
+        }
+    }
+
+    for (path, entry) in remote.iter().filter(|(p, _)| !in_archive(p, archive_dir)) {
+        if local.contains_key(path) {
+            continue;
+        }
+        actions.push(match policy {
+            OrphanPolicy::Archive => MirrorAction::Archive {
+                path: path.clone(),
+                to: format!("{}/{}/{}", archive_dir, date, path),
+                file_id: entry.file_id,
+            },
+            OrphanPolicy::Trash => MirrorAction::Trash {
+                path: path.clone(),
+                file_id: entry.file_id,
+            },
+        });
+    }
+    (actions, unchanged)
+}
+
+/// 追加写入镜像运行报告，每行一条 JSON 记录
+async fn append_report(env: &NetDiskEnv, report: &MirrorReport) -> Result<(), Box<dyn Error>> {
+    let dir = env.config_dir.join(MIRROR_STATE_DIR);
+    tokio::fs::create_dir_all(&dir).await?;
+    let mut line = serde_json::to_string(report)?;
+    line.push('\n');
+    let mut log_file = tokio::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(dir.join(MIRROR_REPORT_LOG))
+        .await?;
+    log_file.write_all(line.as_bytes()).await?;
+    Ok(())
+}
+
+/// 将本地目录单向镜像到远端目录：只上传新增或修改的文件，从不下载。
+///
+/// 上传先按 MD5 尝试秒传，内容已在云端的文件不会重复发送；远端多余的文件按 `orphan_policy` 归档或移入回收站。
+pub async fn run_mirror(
+    token: &AccessToken,
+    env: &NetDiskEnv,
+    item: &MirrorItem,
+) -> Result<MirrorReport, Box<dyn Error>> {
+    let started_at = Utc::now();
+    let local_root = tokio::fs::canonicalize(&item.local_path).await?;
+    let mut state = load_state(env, MIRROR_STATE_DIR, &local_root, item.remote_dir_id).await?;
+    let local = scan_local(&local_root, &state.files).await?;
+    let mut tree = scan_remote(token, item.remote_dir_id).await?;
+    let date = Local::now().format("%Y-%m-%d").to_string();
+    let (actions, unchanged) = plan_mirror(
+        &local,
+        &tree.files,
+        item.orphan_policy,
+        &item.archive_dir,
+        &date,
+    );
+
+    let mut report = MirrorReport {
+        dry_run: item.dry_run,
+        started_at: Some(started_at),
+        unchanged,
+        ..Default::default()
+    };
+    if !item.dry_run {
+        for action in &actions {
+            if let Err(e) =
+                apply_mirror_action(token, &local_root, &mut tree, action, &mut report).await
+            {
+                warn!("镜像 {} 失败: {}", action.path(), e);
+                report.failed.push(SyncFailure {
+                    path: action.path().to_string(),
+                    message: e.to_string(),
+                });
+            }
+        }
+
+        // 镜像状态只作为本地 MD5 缓存，记录当前扫描到的全部文件
+        state.files = local
+            .iter()
+            .map(|(path, entry)| {
+                let synced = SyncedFile {
+                    size: entry.size,
+                    md5: entry.md5.clone(),
+                    mtime: entry.mtime,
+                };
+                (path.clone(), synced)
+            })
+            .collect();
+        state.synced_at = Some(Utc::now());
+        save_state(env, MIRROR_STATE_DIR, &state).await?;
+        report.remote_dirs = tree.dirs.values().copied().collect();
+    }
+    report.actions = actions;
+    report.finished_at = Some(Utc::now());
+
+    info!(
+        "镜像完成: 未变化 {}，秒传 {}，上传 {}（{} 字节），归档 {}，回收站 {}，失败 {}",
+        report.unchanged,
+        report.reused,
+        report.uploaded,
+        report.uploaded_bytes,
+        report.archived,
+        report.trashed,
+        report.failed.len()
+    );
+    if !item.dry_run {
+        append_report(env, &report).await?;
+    }
+    Ok(report)
+}
+
+async fn apply_mirror_action(
+    token: &AccessToken,
+    local_root: &Path,
+    tree: &mut RemoteTree,
+    action: &MirrorAction,
+    report: &mut MirrorReport,
+) -> Result<(), Box<dyn Error>> {
+    match action {
+        MirrorAction::Upload { path, size, .. } => {
+            let (dir, name) = split_rel(path);
+            let parent_id = remote_dir(token, tree, dir).await?;
+            // duplicate = 2：覆盖远端同名文件
+            let outcome = upload_local_file(
+                token,
+                &local_path(local_root, path),
+                parent_id,
+                name,
+                Some(2),
+            )
+            .await?;
+            if outcome.reused {
+                report.reused += 1;
+            } else {
+                report.uploaded += 1;
+                report.uploaded_bytes += size;
+            }
+        }
+        MirrorAction::Archive { to, file_id, .. } => {
+            let (dir, _) = split_rel(to);
+            let parent_id = remote_dir(token, tree, dir).await?;
+            move_files(token, &[*file_id], parent_id).await?;
+            report.archived += 1;
+        }
+        MirrorAction::Trash { file_id, .. } => {
+            trash_files(token, &[*file_id]).await?;
+            report.trashed += 1;
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,144 @@
+use crate::io_basic::checksum::file_md5;
+use crate::netdisk_api::file_api::list_folder_all;
+use crate::responses::prelude::*;
+use actix_web::web;
+use std::collections::{BTreeMap, HashMap};
+use std::error::Error;
+use std::fs;
+use std::io::{self, ErrorKind};
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+/// 本地文件快照
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LocalEntry {
+    pub size: u64,
+    pub md5: String,
+    pub mtime: i64,
+}
+
+/// 远端文件快照
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RemoteEntry {
+    pub file_id: u64,
+    pub parent_file_id: u64,
+    pub size: u64,
+    pub etag: String,
+}
+
+/// 远端目录树：文件按相对路径索引，目录记录相对路径到 `dirID` 的映射（根目录为空字符串）
+#[derive(Debug, Clone, Default)]
+pub struct RemoteTree {
+    pub files: BTreeMap<String, RemoteEntry>,
+    pub dirs: HashMap<String, u64>,
+}
+
+/// 拼接以 `/` 分隔的相对路径
+pub fn join_rel(dir: &str, name: &str) -> String {
+    if dir.is_empty() {
+        name.to_string()
+    } else {
+        format!("{}/{}", dir, name)
+    }
+}
+
+/// 拆分相对路径为 (所在目录, 文件名)
+pub fn split_rel(path: &str) -> (&str, &str) {
+    match path.rsplit_once('/') {
+        Some((dir, name)) => (dir, name),
+        None => ("", path),
+    }
+}
+
+/// 相对路径对应的本地路径
+pub fn local_path(root: &Path, rel: &str) -> PathBuf {
+    rel.split('/')
+        .fold(root.to_path_buf(), |path, name| path.join(name))
+}
+
+/// 文件修改时间（Unix 秒）
+pub fn mtime_of(metadata: &fs::Metadata) -> i64 {
+    metadata
+        .modified()
+        .ok()
+        .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or_default()
+}
+
+/// 同步遍历本地目录；大小与修改时间都未变化的文件复用 `known` 中记录的 MD5
+pub fn scan_local_sync(
+    root: &Path,
+    known: &BTreeMap<String, SyncedFile>,
+) -> Result<BTreeMap<String, LocalEntry>, io::Error> {
+    let mut entries = BTreeMap::new();
+    let mut pending = vec![(root.to_path_buf(), String::new())];
+    while let Some((dir, rel_dir)) = pending.pop() {
+        for entry in fs::read_dir(&dir)? {
+            let entry = entry?;
+            let name = entry.file_name().to_string_lossy().into_owned();
+            let rel = join_rel(&rel_dir, &name);
+            let file_type = entry.file_type()?;
+            if file_type.is_dir() {
+                pending.push((entry.path(), rel));
+                continue;
+            }
+            // 跳过非普通文件以及下载过程中的临时文件
+            if !file_type.is_file() || name.ends_with(".netdisk-tmp") {
+                continue;
+            }
+
+            let metadata = entry.metadata()?;
+            let size = metadata.len();
+            let mtime = mtime_of(&metadata);
+            let md5 = match known.get(&rel) {
+                Some(synced) if synced.size == size && synced.mtime == mtime => synced.md5.clone(),
+                _ => file_md5(entry.path())?,
+            };
+            entries.insert(rel, LocalEntry { size, md5, mtime });
+        }
+    }
+    Ok(entries)
+}
+
+/// 在阻塞线程池中遍历本地目录
+pub async fn scan_local(
+    root: &Path,
+    known: &BTreeMap<String, SyncedFile>,
+) -> Result<BTreeMap<String, LocalEntry>, io::Error> {
+    let root = root.to_path_buf();
+    let known = known.clone();
+    web::block(move || scan_local_sync(&root, &known))
+        .await
+        .map_err(|e| io::Error::new(ErrorKind::Other, format!("阻塞线程失败: {}", e)))?
+}
+
+/// 递归遍历远端目录，忽略回收站中的条目
+pub async fn scan_remote(token: &AccessToken, dir_id: u64) -> Result<RemoteTree, Box<dyn Error>> {
+    let mut tree = RemoteTree::default();
+    tree.dirs.insert(String::new(), dir_id);
+    let mut pending = vec![(dir_id, String::new())];
+    while let Some((dir_id, rel_dir)) = pending.pop() {
+        for item in list_folder_all(token, dir_id).await? {
+            if item.trashed == 1 {
+                continue;
+            }
+            let rel = join_rel(&rel_dir, &item.filename);
+            if item.r#type == 1 {
+                tree.dirs.insert(rel.clone(), item.file_id as u64);
+                pending.push((item.file_id as u64, rel));
+            } else {
+                tree.files.insert(
+                    rel,
+                    RemoteEntry {
+                        file_id: item.file_id as u64,
+                        parent_file_id: item.parent_file_id,
+                        size: item.size,
+                        etag: item.etag.to_lowercase(),
+                    },
+                );
+            }
+        }
+    }
+    Ok(tree)
+}
@@ -0,0 +1,65 @@
+use crate::io_basic::checksum::md5_hex;
+use crate::netdisk_auth::basic_env::NetDiskEnv;
+use crate::responses::prelude::*;
+use std::io::{self, ErrorKind};
+use std::path::{Path, PathBuf};
+
+/// 双向同步状态文件所在的子目录
+pub const SYNC_STATE_DIR: &str = "sync";
+/// 单向镜像状态文件所在的子目录，镜像状态只用于缓存本地文件的 MD5
+pub const MIRROR_STATE_DIR: &str = "mirror";
+
+/// 每对（本地目录，远端目录）对应一个状态文件：`<配置目录>/<state_dir>/<remoteDirID>-<本地路径 MD5>.json`
+pub fn state_path(
+    env: &NetDiskEnv,
+    state_dir: &str,
+    local_root: &Path,
+    remote_dir_id: u64,
+) -> PathBuf {
+    env.config_dir.join(state_dir).join(format!(
+        "{}-{}.json",
+        remote_dir_id,
+        md5_hex(local_root.to_string_lossy().as_bytes())
+    ))
+}
+
+/// 读取同步状态，首次同步时返回空状态
+pub async fn load_state(
+    env: &NetDiskEnv,
+    state_dir: &str,
+    local_root: &Path,
+    remote_dir_id: u64,
+) -> Result<SyncState, io::Error> {
+    let path = state_path(env, state_dir, local_root, remote_dir_id);
+    match tokio::fs::read(&path).await {
+        Ok(content) => serde_json::from_slice(&content).map_err(|e| {
+            io::Error::new(
+                ErrorKind::InvalidData,
+                format!("解析同步状态 {} 失败: {}", path.display(), e),
+            )
+        }),
+        Err(e) if e.kind() == ErrorKind::NotFound => Ok(SyncState {
+            local_root: local_root.to_path_buf(),
+            remote_dir_id,
+            ..Default::default()
+        }),
+        Err(e) => Err(e),
+    }
+}
+
+/// 写入同步状态：先写临时文件再重命名，避免中断时留下损坏的状态
+pub async fn save_state(
+    env: &NetDiskEnv,
+    state_dir: &str,
+    state: &SyncState,
+) -> Result<(), io::Error> {
+    let path = state_path(env, state_dir, &state.local_root, state.remote_dir_id);
+    if let Some(dir) = path.parent() {
+        tokio::fs::create_dir_all(dir).await?;
+    }
+    let content =
+        serde_json::to_vec_pretty(state).map_err(|e| io::Error::new(ErrorKind::InvalidData, e))?;
+    let tmp_path = path.with_extension("json.tmp");
+    tokio::fs::write(&tmp_path, content).await?;
+    tokio::fs::rename(&tmp_path, &path).await
+}
@@ -0,0 +1,389 @@
+use crate::io_basic::checksum::async_file_md5;
+use crate::netdisk_api::file_api::ensure_dir_path;
+use crate::netdisk_api::file_delete_api::fetch_files_info;
+use crate::netdisk_api::file_upload_api::upload_with_item;
+use crate::responses::prelude::*;
+use log::{info, warn};
+use notify::event::{AccessKind, AccessMode};
+use notify::{Event, EventKind, RecursiveMode, Watcher};
+use std::collections::HashMap;
+use std::error::Error;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime};
+
+/// 处理待上传队列的间隔
+const TICK_INTERVAL: Duration = Duration::from_millis(500);
+/// 上传失败后最多重试的次数，超过后等到文件再次写入才重新上传
+pub const MAX_UPLOAD_RETRIES: u32 = 5;
+/// 第一次重试前的等待时间，之后每次翻倍
+const RETRY_BASE_DELAY: Duration = Duration::from_secs(5);
+/// 重试等待时间的上限
+const RETRY_MAX_DELAY: Duration = Duration::from_secs(300);
+
+/// 上传并校验成功后对本地文件的处理
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub enum AfterUpload {
+    #[default]
+    Keep,
+    Delete,
+    /// 移动到该目录下，保持相对监听目录的路径
+    MoveTo(PathBuf),
+}
+
+#[derive(Debug, Clone)]
+pub struct WatchConfig {
+    pub local_root: PathBuf,
+    /// 监听目录映射到的远端目录，子目录会在远端按相同结构创建
+    pub remote_dir_id: u64,
+    /// 最后一次写入事件之后至少等待的时间
+    pub debounce: Duration,
+    /// 文件大小与修改时间需要保持不变的时间，之后才认为写入已完成
+    pub stable_for: Duration,
+    pub after_upload: AfterUpload,
+    /// 启动时是否将目录中已有的文件加入上传队列
+    pub upload_existing: bool,
+}
+
+#[derive(Debug)]
+struct PendingFile {
+    last_event: Instant,
+    observed: Option<(u64, SystemTime)>,
+    observed_at: Instant,
+    /// 上传失败后重试前不处理
+    retry_at: Option<Instant>,
+}
+
+/// 等待写入完成的文件队列
+#[derive(Debug, Default)]
+pub struct PendingFiles {
+    files: HashMap<PathBuf, PendingFile>,
+    /// 连续上传失败的次数
+    failures: HashMap<PathBuf, u32>,
+}
+
+impl PendingFiles {
+    /// 记录一次写入事件，重新开始去抖计时；文件内容有变化，之前的失败次数清零
+    pub fn touch(&mut self, path: PathBuf, now: Instant) {
+        self.failures.remove(&path);
+        self.files
+            .entry(path)
+            .and_modify(|pending| {
+                pending.last_event = now;
+                pending.retry_at = None;
+            })
+            .or_insert(PendingFile {
+                last_event: now,
+                observed: None,
+                observed_at: now,
+                retry_at: None,
+            });
+    }
+
+    /// 上传失败后重新加入队列，等待时间从 5 秒起每次翻倍（最多 5 分钟）。
+    ///
+    /// 返回本次的等待时间；失败次数超过 [`MAX_UPLOAD_RETRIES`] 时放弃并返回 `None`。
+    pub fn retry_later(&mut self, path: PathBuf, now: Instant) -> Option<Duration> {
+        let failures = self.failures.entry(path.clone()).or_insert(0);
+        *failures += 1;
+        if *failures > MAX_UPLOAD_RETRIES {
+            self.failures.remove(&path);
+            return None;
+        }
+        let delay = RETRY_BASE_DELAY
+            .saturating_mul(1 << (*failures - 1))
+            .min(RETRY_MAX_DELAY);
+        self.files.insert(
+            path,
+            PendingFile {
+                last_event: now,
+                observed: None,
+                observed_at: now,
+                retry_at: Some(now + delay),
+            },
+        );
+        Some(delay)
+    }
+
+    /// 上传成功，清除失败次数
+    pub fn succeeded(&mut self, path: &Path) {
+        self.failures.remove(path);
+    }
+
+    pub fn len(&self) -> usize {
+        self.files.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.files.is_empty()
+    }
+
+    /// 取出已经稳定、可以上传的文件。
+    ///
+    /// `stat` 返回文件当前的大小与修改时间，文件已不存在时返回 `None`（直接移出队列）。
+    /// 去抖时间过后，连续两次观察到相同的大小与修改时间且间隔不少于 `stable_for` 才视为稳定。
+    pub fn take_stable(
+        &mut self,
+        now: Instant,
+        debounce: Duration,
+        stable_for: Duration,
+        mut stat: impl FnMut(&Path) -> Option<(u64, SystemTime)>,
+    ) -> Vec<PathBuf> {
+        let mut ready = Vec::new();
+        let mut gone = Vec::new();
+        for (path, pending) in self.files.iter_mut() {
+            if pending.retry_at.map_or(false, |at| now < at) {
+                continue;
+            }
+            if now.duration_since(pending.last_event) < debounce {
+                continue;
+            }
+            match stat(path) {
+                None => gone.push(path.clone()),
+                Some(current) if pending.observed != Some(current) => {
+                    pending.observed = Some(current);
+                    pending.observed_at = now;
+                }
+                Some(_) => {
+                    if now.duration_since(pending.observed_at) >= stable_for {
+                        ready.push(path.clone());
+                    }
+                }
+            }
+        }
+        for path in &gone {
+            self.files.remove(path);
+            self.failures.remove(path);
+        }
+        for path in &ready {
+            self.files.remove(path);
+        }
+        ready.sort();
+        ready
+    }
+}
+
+/// 隐藏文件与常见的临时文件不上传
+pub fn is_ignored(path: &Path) -> bool {
+    let name = match path.file_name() {
+        Some(name) => name.to_string_lossy(),
+        None => return true,
+    };
+    name.starts_with('.') || name.ends_with(".part") || name.ends_with(".tmp")
+}
+
+fn is_write_event(kind: &EventKind) -> bool {
+    matches!(
+        kind,
+        EventKind::Create(_)
+            | EventKind::Modify(_)
+            | EventKind::Access(AccessKind::Close(AccessMode::Write))
+    )
+}
+
+fn stat_file(path: &Path) -> Option<(u64, SystemTime)> {
+    let metadata = fs::metadata(path).ok().filter(|m| m.is_file())?;
+    Some((metadata.len(), metadata.modified().ok()?))
+}
+
+/// 递归列出目录中已有的普通文件
+fn collect_files(root: &Path) -> io::Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    let mut pending = vec![root.to_path_buf()];
+    while let Some(dir) = pending.pop() {
+        for entry in fs::read_dir(&dir)? {
+            let entry = entry?;
+            let file_type = entry.file_type()?;
+            if file_type.is_dir() {
+                pending.push(entry.path());
+            } else if file_type.is_file() {
+                files.push(entry.path());
+            }
+        }
+    }
+    Ok(files)
+}
+
+/// 相对监听目录、以 `/` 分隔的上级目录路径
+fn rel_dir(root: &Path, path: &Path) -> Result<String, Box<dyn Error>> {
+    let rel = path.strip_prefix(root)?;
+    let parent = rel.parent().unwrap_or_else(|| Path::new(""));
+    Ok(parent
+        .components()
+        .map(|c| c.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/"))
+}
+
+/// 跨文件系统时 `rename` 会失败，此时复制后删除
+async fn move_local(from: &Path, to: &Path) -> io::Result<()> {
+    if let Some(parent) = to.parent() {
+        tokio::fs::create_dir_all(parent).await?;
+    }
+    if tokio::fs::rename(from, to).await.is_err() {
+        tokio::fs::copy(from, to).await?;
+        tokio::fs::remove_file(from).await?;
+    }
+    Ok(())
+}
+
+/// 上传单个文件并校验远端的大小与 etag，校验通过后按配置处理本地文件，返回远端 fileID
+async fn upload_watched(
+    token: &AccessToken,
+    config: &WatchConfig,
+    dir_cache: &mut HashMap<String, u64>,
+    path: &Path,
+) -> Result<u64, Box<dyn Error>> {
+    let before = stat_file(path).ok_or("文件已不存在")?;
+    let etag = async_file_md5(path).await?;
+    let dir = rel_dir(&config.local_root, path)?;
+    let parent_file_id = match dir_cache.get(&dir) {
+        Some(id) => *id,
+        None => {
+            let id = ensure_dir_path(token, &dir, config.remote_dir_id).await?;
+            dir_cache.insert(dir, id);
+            id
+        }
+    };
+    let filename = path
+        .file_name()
+        .ok_or("无效的文件名")?
+        .to_string_lossy()
+        .into_owned();
+    let item = UploadFileItem {
+        parent_file_id,
+        filename,
+        etag: etag.clone(),
+        size: before.0,
+        // 覆盖远端同名文件
+        duplicate: Some(2),
+        contain_dir: None,
+    };
+    let outcome = upload_with_item(token, &item, path).await?;
+
+    if stat_file(path) != Some(before) {
+        return Err("上传过程中文件被修改，等待下一次写入完成后重新上传".into());
+    }
+    let info = fetch_files_info(token, &[outcome.file_id])
+        .await?
+        .into_iter()
+        .next()
+        .ok_or("无法获取已上传文件的信息")?;
+    if info.size != before.0 || !info.etag.eq_ignore_ascii_case(&etag) {
+        return Err(format!(
+            "校验失败: 远端 {} 字节 etag {}，本地 {} 字节 etag {}",
+            info.size, info.etag, before.0, etag
+        )
+        .into());
+    }
+
+    match &config.after_upload {
+        AfterUpload::Keep => {}
+        AfterUpload::Delete => tokio::fs::remove_file(path).await?,
+        AfterUpload::MoveTo(dir) => {
+            let rel = path.strip_prefix(&config.local_root)?;
+            move_local(path, &dir.join(rel)).await?;
+        }
+    }
+    Ok(outcome.file_id)
+}
+
+/// 持续监听本地目录，将写入完成的新文件上传到远端目录。
+///
+/// 上传失败的文件按递增的间隔重试，超过重试次数后等到下一次写入事件才重新进入队列。
+pub async fn run_watch(token: &AccessToken, config: &WatchConfig) -> Result<(), Box<dyn Error>> {
+    let mut config = config.clone();
+    config.local_root = tokio::fs::canonicalize(&config.local_root).await?;
+    // 移动目标位于监听目录内时，忽略其中的事件，避免重复上传
+    let moved_dir = match &config.after_upload {
+        AfterUpload::MoveTo(dir) => {
+            tokio::fs::create_dir_all(dir).await?;
+            let dir = tokio::fs::canonicalize(dir).await?;
+            config.after_upload = AfterUpload::MoveTo(dir.clone());
+            Some(dir)
+        }
+        _ => None,
+    };
+    let should_upload = |path: &Path| {
+        !is_ignored(path)
+            && !moved_dir
+                .as_ref()
+                .map_or(false, |dir| path.starts_with(dir))
+    };
+
+    let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+    let mut watcher = notify::recommended_watcher(move |res: notify::Result<Event>| match res {
+        Ok(event) if is_write_event(&event.kind) => {
+            for path in event.paths {
+                let _ = tx.send(path);
+            }
+        }
+        Ok(_) => {}
+        Err(e) => warn!("监听事件错误: {}", e),
+    })?;
+    watcher.watch(&config.local_root, RecursiveMode::Recursive)?;
+    info!(
+        "开始监听 {} -> 远端目录 {}",
+        config.local_root.display(),
+        config.remote_dir_id
+    );
+
+    let mut pending = PendingFiles::default();
+    if config.upload_existing {
+        let now = Instant::now();
+        for path in collect_files(&config.local_root)? {
+            if should_upload(&path) {
+                pending.touch(path, now);
+            }
+        }
+        info!("已有 {} 个文件加入上传队列", pending.len());
+    }
+
+    let mut dir_cache = HashMap::new();
+    let mut last_check = Instant::now();
+    loop {
+        match tokio::time::timeout(TICK_INTERVAL, rx.recv()).await {
+            Ok(Some(path)) => {
+                if should_upload(&path) {
+                    pending.touch(path, Instant::now());
+                }
+            }
+            Ok(None) => return Err("文件监听已停止".into()),
+            Err(_) => {}
+        }
+        // 事件持续到达时也按固定间隔检查队列中的文件是否已稳定
+        if last_check.elapsed() < TICK_INTERVAL {
+            continue;
+        }
+        last_check = Instant::now();
+        let ready = pending.take_stable(
+            Instant::now(),
+            config.debounce,
+            config.stable_for,
+            stat_file,
+        );
+        for path in ready {
+            match upload_watched(token, &config, &mut dir_cache, &path).await {
+                Ok(file_id) => {
+                    pending.succeeded(&path);
+                    info!("已上传 {} -> {}", path.display(), file_id);
+                }
+                Err(e) => match pending.retry_later(path.clone(), Instant::now()) {
+                    Some(delay) => warn!(
+                        "上传 {} 失败: {}，{} 秒后重试",
+                        path.display(),
+                        e,
+                        delay.as_secs()
+                    ),
+                    None => warn!(
+                        "上传 {} 失败: {}，已重试 {} 次，等待文件再次写入",
+                        path.display(),
+                        e,
+                        MAX_UPLOAD_RETRIES
+                    ),
+                },
+            }
+        }
+    }
+}
@@ -0,0 +1,142 @@
+use crate::io_basic::checksum::md5_hex;
+use crate::responses::prelude::*;
+use actix_web::dev::{Service, ServiceRequest, ServiceResponse};
+use actix_web::http::header::{HeaderName, HeaderValue};
+use actix_web::web;
+use std::cell::RefCell;
+use std::future::Future;
+use std::time::Instant;
+use tracing::{error, field, info, warn, Instrument, Span};
+use tracing_subscriber::EnvFilter;
+
+/// 网关响应中回显上游 `x-traceID` 的响应头，一次请求调用了多个接口时每个 trace ID 一个值
+pub const TRACE_ID_HEADER: &str = "x-traceid";
+
+tokio::task_local! {
+    /// 当前网关请求中各上游接口返回的 trace ID
+    static TRACE_IDS: RefCell<Vec<String>>;
+}
+
+/// 按 `[log]` 配置安装全局日志输出；`log` 宏的日志同样经由 tracing 输出
+pub fn init_logging(config: &LogConfig) {
+    let filter =
+        EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new(&config.filter));
+    let builder = tracing_subscriber::fmt().with_env_filter(filter);
+    let result = match config.format {
+        LogFormat::Text => builder.try_init(),
+        LogFormat::Json => builder.json().try_init(),
+    };
+    if let Err(e) = result {
+        eprintln!("日志初始化失败: {}", e);
+    }
+}
+
+/// access_token 的短指纹，用于在日志中区分账号而不暴露令牌本身
+pub fn account_fingerprint(token: &AccessToken) -> String {
+    if token.access_token.is_empty() {
+        return "-".to_string();
+    }
+    md5_hex(token.access_token.as_bytes())[..8].to_string()
+}
+
+/// 上游接口调用的 span，收到响应后记录状态码、耗时与 `x-traceID`
+pub fn upstream_span(method: &str, path: &str) -> Span {
+    tracing::info_span!(
+        "upstream",
+        method,
+        path,
+        status = field::Empty,
+        latency_ms = field::Empty,
+        x_trace_id = field::Empty,
+    )
+}
+
+/// 记录上游响应中的 `x-traceID`：写入当前 span，并在网关响应头中回显
+pub fn record_trace_id(trace_id: &str) {
+    if trace_id.is_empty() {
+        return;
+    }
+    Span::current().record("x_trace_id", trace_id);
+    // 不在网关请求中（命令行、后台任务）时没有需要回显的响应
+    let _ = TRACE_IDS.try_with(|ids| {
+        let mut ids = ids.borrow_mut();
+        if !ids.iter().any(|id| id == trace_id) {
+            ids.push(trace_id.to_string());
+        }
+    });
+}
+
+/// 请求日志中间件：每个请求一个 `request` span，上游调用是其子 span；
+/// 结束时记录路由、状态码与耗时，并通过 [`TRACE_ID_HEADER`] 返回上游的 trace ID
+pub fn trace_request<S, B>(
+    req: ServiceRequest,
+    srv: &S,
+) -> impl Future<Output = Result<ServiceResponse<B>, actix_web::Error>>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = actix_web::Error>,
+{
+    let account = req
+        .app_data::<web::Data<AccessToken>>()
+        .map(|token| account_fingerprint(token))
+        .unwrap_or_else(|| "-".to_string());
+    let span = tracing::info_span!(
+        "request",
+        method = %req.method(),
+        path = %req.path(),
+        route = field::Empty,
+        status = field::Empty,
+        latency_ms = field::Empty,
+        account = %account,
+        x_trace_id = field::Empty,
+    );
+    let started = Instant::now();
+    let fut = span.in_scope(|| srv.call(req));
+    let request_span = span.clone();
+    TRACE_IDS.scope(
+        RefCell::new(Vec::new()),
+        async move {
+            let result = fut.await;
+            let trace_ids = TRACE_IDS.with(|ids| ids.take());
+            finish_request(&request_span, result, started, &trace_ids)
+        }
+        .instrument(span),
+    )
+}
+
+fn finish_request<B>(
+    span: &Span,
+    result: Result<ServiceResponse<B>, actix_web::Error>,
+    started: Instant,
+    trace_ids: &[String],
+) -> Result<ServiceResponse<B>, actix_web::Error> {
+    span.record("latency_ms", started.elapsed().as_millis() as u64);
+    if !trace_ids.is_empty() {
+        span.record("x_trace_id", trace_ids.join(",").as_str());
+    }
+    let mut res = match result {
+        Ok(res) => res,
+        Err(e) => {
+            span.record("status", e.as_response_error().status_code().as_u16());
+            error!(error = %e, "请求失败");
+            return Err(e);
+        }
+    };
+    if let Some(route) = res.request().match_pattern() {
+        span.record("route", route.as_str());
+    }
+    let status = res.status();
+    span.record("status", status.as_u16());
+    for trace_id in trace_ids {
+        if let Ok(value) = HeaderValue::from_str(trace_id) {
+            res.headers_mut()
+                .append(HeaderName::from_static(TRACE_ID_HEADER), value);
+        }
+    }
+    match res.response().error() {
+        Some(e) if status.is_server_error() => error!(error = %e, "请求失败"),
+        Some(e) => warn!(error = %e, "请求被拒绝"),
+        None if status.is_server_error() => error!("请求失败"),
+        None => info!("请求完成"),
+    }
+    Ok(res)
+}
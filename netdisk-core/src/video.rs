@@ -0,0 +1,199 @@
+use crate::responses::prelude::*;
+use chrono::{DateTime, Utc};
+use reqwest::Url;
+use rusqlite::{params, Connection, OptionalExtension, Row};
+
+/// 转码使用的默认编码
+pub const DEFAULT_CODEC: &str = "H.264";
+
+/// 拆分逗号分隔的分辨率列表，忽略空项
+pub fn parse_resolutions(resolutions: &str) -> Vec<String> {
+    resolutions
+        .split(',')
+        .map(str::trim)
+        .filter(|resolution| !resolution.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+/// 从可用分辨率中选出要转码的分辨率，保持上游顺序；`requested` 为空时选择全部
+pub fn choose_resolutions(available: &str, requested: Option<&str>) -> Result<Vec<String>, String> {
+    let available = parse_resolutions(available);
+    let requested = requested.map(parse_resolutions).unwrap_or_default();
+    if requested.is_empty() {
+        if available.is_empty() {
+            return Err("该视频没有可转码的分辨率".to_string());
+        }
+        return Ok(available);
+    }
+    let unknown: Vec<&str> = requested
+        .iter()
+        .filter(|resolution| !available.iter().any(|a| a.eq_ignore_ascii_case(resolution)))
+        .map(String::as_str)
+        .collect();
+    if !unknown.is_empty() {
+        return Err(format!(
+            "不支持的分辨率 {}，可选：{}",
+            unknown.join(","),
+            available.join(",")
+        ));
+    }
+    Ok(available
+        .into_iter()
+        .filter(|a| requested.iter().any(|r| r.eq_ignore_ascii_case(a)))
+        .collect())
+}
+
+/// 根据上游转码结果更新记录：仍有分辨率未结束时为转码中，
+/// 全部结束后只要有一个分辨率成功即为成功
+pub fn apply_result(record: &mut VideoTranscode, videos: &[TranscodedVideo], now: DateTime<Utc>) {
+    let mut ready = Vec::new();
+    let mut failed = Vec::new();
+    let mut pending = false;
+    for resolution in &record.resolutions {
+        match videos
+            .iter()
+            .find(|video| video.resolution.eq_ignore_ascii_case(resolution))
+        {
+            Some(video) if video.playlist_url().is_some() => ready.push(resolution.clone()),
+            Some(video) if (3..TRANSCODE_STATUS_SUCCEEDED).contains(&video.status) => {
+                failed.push(resolution.clone())
+            }
+            _ => pending = true,
+        }
+    }
+    record.status = if pending {
+        VideoTranscodeStatus::Transcoding
+    } else if ready.is_empty() {
+        VideoTranscodeStatus::Failed
+    } else {
+        VideoTranscodeStatus::Succeeded
+    };
+    record.ready = ready;
+    record.failed = failed;
+    record.updated_at = now;
+}
+
+/// 按分辨率名称（例如 `1080P`）估算 `(宽, 高, 码率)`，用于主播放列表
+fn stream_info(resolution: &str) -> (u32, u32, u32) {
+    let height: u32 = resolution
+        .trim_end_matches(|c: char| c.is_ascii_alphabetic())
+        .parse()
+        .unwrap_or(720);
+    let bandwidth = match height {
+        h if h >= 2160 => 16_000_000,
+        h if h >= 1440 => 8_000_000,
+        h if h >= 1080 => 5_000_000,
+        h if h >= 720 => 2_800_000,
+        h if h >= 480 => 1_400_000,
+        _ => 800_000,
+    };
+    (height * 16 / 9, height, bandwidth)
+}
+
+/// 生成包含全部已转码分辨率的主播放列表，各分辨率指向 `<play_path>?resolution=`
+pub fn master_playlist(play_path: &str, ready: &[String]) -> String {
+    let mut playlist = String::from("#EXTM3U\n");
+    for resolution in ready {
+        let (width, height, bandwidth) = stream_info(resolution);
+        playlist.push_str(&format!(
+            "#EXT-X-STREAM-INF:BANDWIDTH={},RESOLUTION={}x{},NAME=\"{}\"\n{}?resolution={}\n",
+            bandwidth, width, height, resolution, play_path, resolution
+        ));
+    }
+    playlist
+}
+
+/// 将播放列表中的相对地址（分片与 `URI="..."`）改写为基于 `base` 的绝对地址，
+/// 使网关返回的播放列表可以直接从上游加载分片
+pub fn rewrite_playlist(content: &str, base: &Url) -> String {
+    let resolve = |uri: &str| {
+        base.join(uri)
+            .map(String::from)
+            .unwrap_or_else(|_| uri.to_string())
+    };
+    let mut playlist = String::new();
+    for line in content.lines() {
+        let trimmed = line.trim();
+        if trimmed.is_empty() {
+            playlist.push('\n');
+            continue;
+        }
+        if !trimmed.starts_with('#') {
+            playlist.push_str(&resolve(trimmed));
+        } else if let Some(start) = trimmed.find("URI=\"") {
+            let value_start = start + "URI=\"".len();
+            match trimmed[value_start..].find('"') {
+                Some(len) => {
+                    let uri = &trimmed[value_start..value_start + len];
+                    playlist.push_str(&trimmed[..value_start]);
+                    playlist.push_str(&resolve(uri));
+                    playlist.push_str(&trimmed[value_start + len..]);
+                }
+                None => playlist.push_str(trimmed),
+            }
+        } else {
+            playlist.push_str(trimmed);
+        }
+        playlist.push('\n');
+    }
+    playlist
+}
+
+fn transcode_from_row(row: &Row) -> rusqlite::Result<VideoTranscode> {
+    let status: String = row.get(1)?;
+    Ok(VideoTranscode {
+        file_id: row.get::<_, i64>(0)? as u64,
+        status: VideoTranscodeStatus::parse(&status).unwrap_or(VideoTranscodeStatus::Transcoding),
+        resolutions: parse_resolutions(&row.get::<_, String>(2)?),
+        ready: parse_resolutions(&row.get::<_, String>(3)?),
+        failed: parse_resolutions(&row.get::<_, String>(4)?),
+        submitted_at: row.get(5)?,
+        updated_at: row.get(6)?,
+    })
+}
+
+const TRANSCODE_COLUMNS: &str =
+    "file_id, status, resolutions, ready, failed, submitted_at, updated_at";
+
+/// 写入或覆盖一条转码记录
+pub fn save_transcode(conn: &Connection, record: &VideoTranscode) -> rusqlite::Result<()> {
+    conn.execute(
+        &format!(
+            "INSERT OR REPLACE INTO video_transcodes ({}) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+            TRANSCODE_COLUMNS
+        ),
+        params![
+            record.file_id as i64,
+            record.status.as_str(),
+            record.resolutions.join(","),
+            record.ready.join(","),
+            record.failed.join(","),
+            record.submitted_at,
+            record.updated_at,
+        ],
+    )?;
+    Ok(())
+}
+
+pub fn load_transcode(conn: &Connection, file_id: u64) -> rusqlite::Result<Option<VideoTranscode>> {
+    conn.query_row(
+        &format!(
+            "SELECT {} FROM video_transcodes WHERE file_id = ?1",
+            TRANSCODE_COLUMNS
+        ),
+        params![file_id as i64],
+        transcode_from_row,
+    )
+    .optional()
+}
+
+/// 读取全部转码记录，最近提交的在前
+pub fn load_transcodes(conn: &Connection) -> rusqlite::Result<Vec<VideoTranscode>> {
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {} FROM video_transcodes ORDER BY submitted_at DESC, file_id",
+        TRANSCODE_COLUMNS
+    ))?;
+    let rows = stmt.query_map([], transcode_from_row)?;
+    rows.collect()
+}
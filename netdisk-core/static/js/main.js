@@ -0,0 +1,563 @@
+// static/js/main.js
+// 网页文件管理器：所有操作都通过网关接口完成，目录路径保存在 location.hash（#/目录ID/子目录ID）中
+
+const ROOT = { fileId: 0, filename: "全部文件" };
+/** 文件分类：视频 */
+const VIDEO_CATEGORY = 2;
+/** 浏览器分块上传的分块大小 */
+const CHUNK_SIZE = 8 * 1024 * 1024;
+
+const state = {
+  /** 从根目录到当前目录的路径 */
+  path: [ROOT],
+  items: [],
+  selected: new Set(),
+};
+
+const $ = (id) => document.getElementById(id);
+
+document.addEventListener("DOMContentLoaded", () => {
+  $("btn-upload").addEventListener("click", () => $("upload-input").click());
+  $("upload-input").addEventListener("change", (event) => {
+    const files = Array.from(event.target.files);
+    event.target.value = "";
+    uploadFiles(files);
+  });
+  $("btn-mkdir").addEventListener("click", createFolder);
+  $("btn-rename").addEventListener("click", renameSelected);
+  $("btn-move").addEventListener("click", openMoveDialog);
+  $("btn-share").addEventListener("click", openShareDialog);
+  $("btn-trash").addEventListener("click", trashSelected);
+  $("btn-refresh").addEventListener("click", () => loadCurrent());
+  $("check-all").addEventListener("change", (event) => {
+    state.selected = event.target.checked
+      ? new Set(state.items.map((item) => item.fileId))
+      : new Set();
+    renderList();
+  });
+  $("move-confirm").addEventListener("click", confirmMove);
+  $("share-confirm").addEventListener("click", confirmShare);
+  $("video-dialog").addEventListener("close", () => {
+    $("video-player").pause();
+    $("video-player").removeAttribute("src");
+  });
+  window.addEventListener("hashchange", () => navigateFromHash());
+
+  navigateFromHash();
+});
+
+// ---------------------------------------------------------------------------
+// 网关请求
+// ---------------------------------------------------------------------------
+
+/** 调用网关接口，返回 `data`；HTTP 错误或 `code` 非 0 时抛出异常 */
+async function api(method, url, body) {
+  const options = { method, headers: {} };
+  if (body !== undefined) {
+    options.headers["Content-Type"] = "application/json";
+    options.body = JSON.stringify(body);
+  }
+  const response = await fetch(url, options);
+  const text = await response.text();
+  let json = null;
+  try {
+    json = text ? JSON.parse(text) : null;
+  } catch (_) {
+    // 网关内部错误返回纯文本
+  }
+  if (!response.ok || (json && json.code !== 0)) {
+    throw new Error((json && json.message) || text || `HTTP ${response.status}`);
+  }
+  return json ? json.data : null;
+}
+
+/** 分页拉取目录下未删除的全部条目，目录排在前面 */
+async function listFolder(parentFileId) {
+  const items = [];
+  let lastFileId = null;
+  do {
+    const params = new URLSearchParams({ parentFileId, limit: 100 });
+    if (lastFileId !== null) params.set("last_file_id", lastFileId);
+    const data = await api("GET", `/file/file_lists_query?${params}`);
+    items.push(...data.fileList);
+    lastFileId = data.lastFileId;
+  } while (lastFileId !== -1);
+  return items
+    .filter((item) => item.trashed === 0)
+    .sort(
+      (a, b) =>
+        (b.type === 1) - (a.type === 1) || a.filename.localeCompare(b.filename),
+    );
+}
+
+// ---------------------------------------------------------------------------
+// 导航与渲染
+// ---------------------------------------------------------------------------
+
+function currentDir() {
+  return state.path[state.path.length - 1];
+}
+
+function navigateTo(path) {
+  state.path = path;
+  const hash = "#/" + path.slice(1).map((dir) => dir.fileId).join("/");
+  if (location.hash !== hash) {
+    history.pushState(null, "", hash);
+  }
+  loadCurrent();
+}
+
+/** 刷新页面时根据 hash 中的目录 ID 还原面包屑名称 */
+async function navigateFromHash() {
+  const ids = location.hash
+    .replace(/^#\/?/, "")
+    .split("/")
+    .filter(Boolean)
+    .map(Number)
+    .filter((id) => Number.isInteger(id) && id > 0);
+  const known = new Map(state.path.map((dir) => [dir.fileId, dir]));
+  let path = [ROOT];
+  if (ids.length > 0) {
+    try {
+      const missing = ids.filter((id) => !known.has(id));
+      if (missing.length > 0) {
+        const data = await api("POST", "/file/files_info", { fileIds: missing });
+        data.fileList.forEach((info) => known.set(info.fileId, info));
+      }
+      path = path.concat(
+        ids.map((id) => ({ fileId: id, filename: (known.get(id) || {}).filename || String(id) })),
+      );
+    } catch (error) {
+      showMessage(`无法解析目录路径：${error.message}`, true);
+    }
+  }
+  state.path = path;
+  loadCurrent();
+}
+
+async function loadCurrent() {
+  renderBreadcrumbs($("breadcrumbs"), state.path, navigateTo);
+  const list = $("file-list");
+  list.replaceChildren(placeholderRow("正在加载文件..."));
+  try {
+    state.items = await listFolder(currentDir().fileId);
+    state.selected = new Set();
+    renderList();
+  } catch (error) {
+    list.replaceChildren(placeholderRow("加载失败"));
+    showMessage(`加载目录失败：${error.message}`, true);
+  }
+}
+
+function renderBreadcrumbs(container, path, onNavigate) {
+  container.replaceChildren();
+  path.forEach((dir, index) => {
+    if (index > 0) {
+      const separator = document.createElement("span");
+      separator.className = "separator";
+      separator.textContent = "/";
+      container.appendChild(separator);
+    }
+    if (index === path.length - 1) {
+      const current = document.createElement("span");
+      current.textContent = dir.filename;
+      container.appendChild(current);
+    } else {
+      const link = document.createElement("a");
+      link.textContent = dir.filename;
+      link.addEventListener("click", () => onNavigate(path.slice(0, index + 1)));
+      container.appendChild(link);
+    }
+  });
+}
+
+function placeholderRow(text) {
+  const row = document.createElement("tr");
+  const cell = document.createElement("td");
+  cell.colSpan = 5;
+  cell.className = "placeholder";
+  cell.textContent = text;
+  row.appendChild(cell);
+  return row;
+}
+
+function renderList() {
+  const list = $("file-list");
+  list.replaceChildren();
+  if (state.items.length === 0) {
+    list.appendChild(placeholderRow("此目录为空"));
+  }
+  state.items.forEach((item) => list.appendChild(renderRow(item)));
+  $("check-all").checked =
+    state.items.length > 0 && state.selected.size === state.items.length;
+  updateToolbar();
+}
+
+function renderRow(item) {
+  const isDir = item.type === 1;
+  const row = document.createElement("tr");
+  row.classList.toggle("selected", state.selected.has(item.fileId));
+
+  const checkCell = document.createElement("td");
+  const checkbox = document.createElement("input");
+  checkbox.type = "checkbox";
+  checkbox.checked = state.selected.has(item.fileId);
+  checkbox.addEventListener("change", () => {
+    if (checkbox.checked) state.selected.add(item.fileId);
+    else state.selected.delete(item.fileId);
+    renderList();
+  });
+  checkCell.appendChild(checkbox);
+
+  const nameCell = document.createElement("td");
+  const icon = document.createElement("span");
+  icon.className = "file-icon";
+  icon.textContent = isDir ? "📁" : "📄";
+  const name = document.createElement(isDir ? "a" : "span");
+  name.className = "file-name";
+  name.textContent = item.filename;
+  if (isDir) {
+    name.addEventListener("click", () =>
+      navigateTo(state.path.concat({ fileId: item.fileId, filename: item.filename })),
+    );
+  }
+  nameCell.append(icon, name);
+
+  const sizeCell = document.createElement("td");
+  sizeCell.className = "col-size";
+  sizeCell.textContent = isDir ? "---" : formatBytes(item.size);
+
+  const dateCell = document.createElement("td");
+  dateCell.className = "col-date";
+  dateCell.textContent = formatTime(item.updateAt);
+
+  const actionCell = document.createElement("td");
+  actionCell.className = "col-actions";
+  if (!isDir) {
+    const download = document.createElement("button");
+    download.className = "link";
+    download.textContent = "下载";
+    download.addEventListener("click", () => downloadFile(item));
+    actionCell.appendChild(download);
+    if (item.category === VIDEO_CATEGORY) {
+      const play = document.createElement("button");
+      play.className = "link";
+      play.textContent = "播放";
+      play.addEventListener("click", () => playVideo(item));
+      actionCell.appendChild(play);
+    }
+  }
+
+  row.append(checkCell, nameCell, sizeCell, dateCell, actionCell);
+  return row;
+}
+
+function updateToolbar() {
+  document.querySelectorAll(".toolbar button[data-needs]").forEach((button) => {
+    const count = state.selected.size;
+    button.disabled = button.dataset.needs === "one" ? count !== 1 : count === 0;
+  });
+}
+
+function selectedItems() {
+  return state.items.filter((item) => state.selected.has(item.fileId));
+}
+
+function showMessage(text, isError = false) {
+  const message = $("message");
+  message.textContent = text;
+  message.classList.toggle("error", isError);
+  message.hidden = false;
+  clearTimeout(showMessage.timer);
+  showMessage.timer = setTimeout(() => (message.hidden = true), isError ? 8000 : 4000);
+}
+
+// ---------------------------------------------------------------------------
+// 文件操作
+// ---------------------------------------------------------------------------
+
+async function downloadFile(item) {
+  try {
+    const data = await api("GET", `/file/download?fileId=${item.fileId}`);
+    window.open(data.downloadUrl, "_blank");
+  } catch (error) {
+    showMessage(`获取下载地址失败：${error.message}`, true);
+  }
+}
+
+async function createFolder() {
+  const name = prompt("新建文件夹名称");
+  if (!name) return;
+  try {
+    await api("POST", "/file/mkdir", { name, parentID: currentDir().fileId });
+    showMessage(`已创建文件夹 ${name}`);
+    loadCurrent();
+  } catch (error) {
+    showMessage(`创建文件夹失败：${error.message}`, true);
+  }
+}
+
+async function renameSelected() {
+  const [item] = selectedItems();
+  const fileName = prompt("新名称", item.filename);
+  if (!fileName || fileName === item.filename) return;
+  try {
+    await api("PUT", "/file/rename", { fileId: item.fileId, fileName });
+    showMessage(`已重命名为 ${fileName}`);
+    loadCurrent();
+  } catch (error) {
+    showMessage(`重命名失败：${error.message}`, true);
+  }
+}
+
+async function trashSelected() {
+  const items = selectedItems();
+  if (!confirm(`确定将 ${items.length} 个条目移入回收站？`)) return;
+  try {
+    const report = await api("POST", "/batch/trash", {
+      fileIds: items.map((item) => item.fileId),
+    });
+    reportBatch("移入回收站", report);
+    loadCurrent();
+  } catch (error) {
+    showMessage(`删除失败：${error.message}`, true);
+  }
+}
+
+function reportBatch(action, report) {
+  const failed = (report && report.failed) || [];
+  if (failed.length > 0) {
+    showMessage(`${action}：${failed.length} 个条目处理失败`, true);
+  } else {
+    showMessage(`${action}完成`);
+  }
+}
+
+// --- 移动 ---
+
+const moveState = { path: [ROOT] };
+
+function openMoveDialog() {
+  moveState.path = state.path.slice();
+  loadMoveFolders();
+  $("move-dialog").showModal();
+}
+
+async function loadMoveFolders() {
+  renderBreadcrumbs($("move-breadcrumbs"), moveState.path, (path) => {
+    moveState.path = path;
+    loadMoveFolders();
+  });
+  const list = $("move-folders");
+  list.replaceChildren();
+  try {
+    const moving = state.selected;
+    const folders = (await listFolder(moveState.path[moveState.path.length - 1].fileId)).filter(
+      (item) => item.type === 1 && !moving.has(item.fileId),
+    );
+    folders.forEach((folder) => {
+      const entry = document.createElement("li");
+      entry.textContent = `📁 ${folder.filename}`;
+      entry.addEventListener("click", () => {
+        moveState.path = moveState.path.concat({
+          fileId: folder.fileId,
+          filename: folder.filename,
+        });
+        loadMoveFolders();
+      });
+      list.appendChild(entry);
+    });
+  } catch (error) {
+    showMessage(`加载目录失败：${error.message}`, true);
+  }
+}
+
+async function confirmMove(event) {
+  event.preventDefault();
+  const target = moveState.path[moveState.path.length - 1];
+  $("move-dialog").close();
+  if (target.fileId === currentDir().fileId) return;
+  try {
+    const report = await api("POST", "/batch/move", {
+      fileIDs: selectedItems().map((item) => item.fileId),
+      toParentFileID: target.fileId,
+    });
+    reportBatch(`移动到 ${target.filename} `, report);
+    loadCurrent();
+  } catch (error) {
+    showMessage(`移动失败：${error.message}`, true);
+  }
+}
+
+// --- 分享 ---
+
+function openShareDialog() {
+  const items = selectedItems();
+  $("share-name").value =
+    items.length === 1 ? items[0].filename : `${items[0].filename} 等 ${items.length} 个文件`;
+  $("share-pwd").value = "";
+  $("share-result").hidden = true;
+  $("share-confirm").disabled = false;
+  $("share-dialog").showModal();
+}
+
+async function confirmShare(event) {
+  event.preventDefault();
+  const sharePwd = $("share-pwd").value.trim();
+  const body = {
+    shareName: $("share-name").value.trim(),
+    shareExpire: $("share-expire").value,
+    fileIDs: selectedItems().map((item) => item.fileId),
+  };
+  if (sharePwd) body.sharePwd = sharePwd;
+  try {
+    const data = await api("POST", "/share/link", body);
+    const result = $("share-result");
+    result.textContent = data.message;
+    result.hidden = false;
+    $("share-confirm").disabled = true;
+  } catch (error) {
+    showMessage(`创建分享失败：${error.message}`, true);
+  }
+}
+
+// --- 视频播放 ---
+
+/** 已转码的视频直接播放网关返回的 m3u8，未转码时询问是否提交转码 */
+async function playVideo(item) {
+  let record = null;
+  try {
+    record = await api("GET", `/video/${item.fileId}/status`);
+  } catch (_) {
+    // 没有转码记录
+  }
+  if (record && record.ready.length > 0) {
+    $("video-title").textContent = item.filename;
+    $("video-player").src = `/video/${item.fileId}/play`;
+    $("video-dialog").showModal();
+    return;
+  }
+  if (record && record.status === "transcoding") {
+    showMessage(`${item.filename} 正在转码，请稍后再试`);
+    return;
+  }
+  if (!confirm(`${item.filename} 尚未转码，是否提交转码？`)) return;
+  try {
+    const data = await api("POST", "/video/transcode", { fileID: item.fileId });
+    showMessage(`已提交转码：${data.resolutions.join("、")}`);
+  } catch (error) {
+    showMessage(`提交转码失败：${error.message}`, true);
+  }
+}
+
+// ---------------------------------------------------------------------------
+// 分块上传
+// ---------------------------------------------------------------------------
+
+async function uploadFiles(files) {
+  if (files.length === 0) return;
+  const parent = currentDir();
+  $("uploads").hidden = false;
+  for (const file of files) {
+    const task = createUploadTask(file);
+    try {
+      const result = await uploadFile(file, parent.fileId, task);
+      task.done(result.reused ? "秒传完成" : "上传完成");
+    } catch (error) {
+      task.done(`失败：${error.message}`, true);
+    }
+  }
+  if (currentDir().fileId === parent.fileId) loadCurrent();
+}
+
+function createUploadTask(file) {
+  const entry = document.createElement("li");
+  const name = document.createElement("span");
+  name.className = "upload-name";
+  name.textContent = file.name;
+  const progress = document.createElement("progress");
+  progress.max = file.size || 1;
+  progress.value = 0;
+  const status = document.createElement("span");
+  status.className = "upload-status";
+  status.textContent = "等待中";
+  entry.append(name, progress, status);
+  $("upload-list").prepend(entry);
+  return {
+    progress(loaded) {
+      progress.value = loaded;
+      status.textContent = `${Math.floor((loaded / (file.size || 1)) * 100)}%`;
+    },
+    done(text, isError = false) {
+      progress.value = progress.max;
+      status.textContent = text;
+      status.style.color = isError ? "#dc3545" : "";
+    },
+  };
+}
+
+/** 创建会话后按顺序上传分块，偏移不一致（409）时从网关记录的位置续传 */
+async function uploadFile(file, parentFileId, task) {
+  const session = await api("POST", "/upload/session", {
+    parentFileID: parentFileId,
+    filename: file.name,
+    size: file.size,
+  });
+  const url = `/upload/session/${session.uploadID}`;
+  try {
+    let offset = 0;
+    while (offset < file.size) {
+      const chunk = file.slice(offset, offset + CHUNK_SIZE);
+      const start = offset;
+      const result = await putChunk(`${url}?offset=${start}`, chunk, (loaded) =>
+        task.progress(start + loaded),
+      );
+      offset = result.received;
+    }
+    task.progress(file.size);
+    return await api("POST", `${url}/complete`);
+  } catch (error) {
+    fetch(url, { method: "DELETE" }).catch(() => {});
+    throw error;
+  }
+}
+
+function putChunk(url, chunk, onProgress) {
+  return new Promise((resolve, reject) => {
+    const xhr = new XMLHttpRequest();
+    xhr.open("PUT", url);
+    xhr.setRequestHeader("Content-Type", "application/octet-stream");
+    xhr.upload.addEventListener("progress", (event) => onProgress(event.loaded));
+    xhr.addEventListener("load", () => {
+      let json = null;
+      try {
+        json = JSON.parse(xhr.responseText);
+      } catch (_) {
+        // 网关内部错误返回纯文本
+      }
+      if ((xhr.status === 200 || xhr.status === 409) && json && json.data) {
+        resolve(json.data);
+      } else {
+        reject(new Error((json && json.message) || xhr.responseText || `HTTP ${xhr.status}`));
+      }
+    });
+    xhr.addEventListener("error", () => reject(new Error("网络错误")));
+    xhr.send(chunk);
+  });
+}
+
+/** 格式化字节大小 */
+function formatBytes(bytes, decimals = 2) {
+  if (bytes === 0) return "0 Bytes";
+  const k = 1024;
+  const dm = decimals < 0 ? 0 : decimals;
+  const sizes = ["Bytes", "KB", "MB", "GB", "TB"];
+  const i = Math.floor(Math.log(bytes) / Math.log(k));
+  return parseFloat((bytes / Math.pow(k, i)).toFixed(dm)) + " " + sizes[i];
+}
+
+/** 将 RFC 3339 时间显示为 `2024-06-01 12:00:00`；网关使用旧格式时原样显示 */
+function formatTime(value) {
+  const match = /^(\d{4}-\d{2}-\d{2})T(\d{2}:\d{2}:\d{2})/.exec(value || "");
+  return match ? `${match[1]} ${match[2]}` : value;
+}
@@ -0,0 +1,46 @@
+#[cfg(test)]
+mod tests {
+    use netdisk_core::netdisk_api::prelude::*;
+    use std::error::Error;
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn test_run_batched_reports_each_id() {
+        let file_ids: Vec<u64> = (1..=250).collect();
+        let mut limiter = RateLimiter::new(Duration::from_millis(0));
+        let mut calls = Vec::new();
+
+        let report = run_batched(&file_ids, &mut limiter, |ids| {
+            calls.push(ids.len());
+            async move {
+                if ids.contains(&150) {
+                    Err::<(), Box<dyn Error>>("文件不存在".into())
+                } else {
+                    Ok(())
+                }
+            }
+        })
+        .await;
+
+        assert_eq!(report.total, 250);
+        assert_eq!(report.succeeded.len(), 249);
+        assert_eq!(report.failed.len(), 1);
+        assert_eq!(report.failed[0].file_id, 150);
+        // 三个批次，其中第二批失败后立即逐个重试 100 次
+        assert_eq!(calls.len(), 3 + 100);
+        assert_eq!(&calls[..2], &[100, 100]);
+        assert!(calls[2..102].iter().all(|len| *len == 1));
+        assert_eq!(calls[102], 50);
+    }
+
+    #[tokio::test]
+    async fn test_run_batched_dedups_ids() {
+        let mut limiter = RateLimiter::new(Duration::from_millis(0));
+        let report = run_batched(&[1, 1, 2], &mut limiter, |_| async {
+            Ok::<(), Box<dyn Error>>(())
+        })
+        .await;
+        assert_eq!(report.total, 2);
+        assert_eq!(report.succeeded, vec![1, 2]);
+    }
+}
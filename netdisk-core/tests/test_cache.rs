@@ -0,0 +1,211 @@
+#[cfg(test)]
+mod tests {
+    use actix_web::test as actix_test;
+    use actix_web::web;
+    use chrono::{Duration, Utc};
+    use netdisk_core::cache::*;
+    use netdisk_core::create_app_with_cache;
+    use netdisk_core::netdisk_auth::basic_env::NetDiskEnv;
+    use netdisk_core::responses::prelude::*;
+    use tempfile::TempDir;
+
+    fn env(dir: &TempDir) -> NetDiskEnv {
+        NetDiskEnv {
+            config_dir: dir.path().to_path_buf(),
+        }
+    }
+
+    #[test]
+    fn test_cache_policy() {
+        assert_eq!(CachePolicy::from_cache_control(None), CachePolicy::Use);
+        assert_eq!(
+            CachePolicy::from_cache_control(Some("max-age=0, no-store")),
+            CachePolicy::NoStore
+        );
+        assert_eq!(
+            CachePolicy::from_cache_control(Some("No-Cache")),
+            CachePolicy::Refresh
+        );
+        assert_eq!(
+            CachePolicy::from_cache_control(Some("max-age=10")),
+            CachePolicy::MaxAge(10)
+        );
+        assert_eq!(
+            CachePolicy::from_cache_control(Some("max-age=0")),
+            CachePolicy::Refresh
+        );
+        assert_eq!(
+            CachePolicy::from_cache_control(Some("max-age=99999999999999999999999")),
+            CachePolicy::MaxAge(MAX_AGE_LIMIT)
+        );
+        assert_eq!(
+            CachePolicy::from_cache_control(Some("max-age=-1")),
+            CachePolicy::Use
+        );
+        assert_eq!(CachePolicy::from_flag(false), CachePolicy::NoStore);
+
+        let config = CacheConfig::default();
+        assert_eq!(config.ttl_for("api/v1/user/info"), 300);
+        assert_eq!(config.ttl_for("api/v1/share/list"), config.ttl_secs);
+        let disabled = CacheConfig {
+            enabled: false,
+            ..CacheConfig::default()
+        };
+        assert_eq!(disabled.ttl_for("api/v1/user/info"), 0);
+    }
+
+    #[actix_web::test]
+    async fn test_expiry_and_invalidation() {
+        let dir = TempDir::new().expect("无法创建临时目录");
+        let cache = ResponseCache::new(&CacheConfig::default(), &env(&dir));
+        let now = Utc::now();
+
+        cache
+            .put(
+                "list",
+                "a".to_string(),
+                30,
+                vec![CacheTag::Folder(1), CacheTag::File(7)],
+                now,
+            )
+            .await;
+        assert_eq!(
+            cache.get("list", CachePolicy::Use).await,
+            Some("a".to_string())
+        );
+        assert_eq!(cache.get("list", CachePolicy::Refresh).await, None);
+
+        let old = now - Duration::seconds(20);
+        cache.put("old", "b".to_string(), 30, Vec::new(), old).await;
+        assert_eq!(
+            cache.get("old", CachePolicy::Use).await,
+            Some("b".to_string())
+        );
+        assert_eq!(cache.get("old", CachePolicy::MaxAge(10)).await, None);
+        cache
+            .put("expired", "c".to_string(), 10, Vec::new(), old)
+            .await;
+        assert_eq!(cache.get("expired", CachePolicy::Use).await, None);
+
+        // 列表中的文件发生变化，列表随之失效
+        cache.invalidate_files(&[7]);
+        assert_eq!(cache.get("list", CachePolicy::Use).await, None);
+        // 失效之前发起的请求，结果不再写入
+        cache
+            .put("list", "a".to_string(), 30, vec![CacheTag::File(7)], now)
+            .await;
+        assert_eq!(cache.get("list", CachePolicy::Use).await, None);
+        assert_eq!(
+            cache.get("old", CachePolicy::Use).await,
+            Some("b".to_string())
+        );
+    }
+
+    #[actix_web::test]
+    async fn test_disk_cache() {
+        let dir = TempDir::new().expect("无法创建临时目录");
+        let config = CacheConfig {
+            disk: true,
+            capacity: 1,
+            ..CacheConfig::default()
+        };
+        let cache = ResponseCache::new(&config, &env(&dir));
+        let now = Utc::now();
+        cache.put("a", "1".to_string(), 30, Vec::new(), now).await;
+        cache.put("b", "2".to_string(), 30, Vec::new(), now).await;
+        // 内存只保留一条，被淘汰的从磁盘读回
+        assert_eq!(
+            cache.get("a", CachePolicy::Use).await,
+            Some("1".to_string())
+        );
+
+        let reopened = ResponseCache::new(&config, &env(&dir));
+        assert_eq!(
+            reopened.get("b", CachePolicy::Use).await,
+            Some("2".to_string())
+        );
+
+        // 重启前的失效对磁盘中的条目仍然有效
+        let tagged = Utc::now();
+        reopened
+            .put("c", "3".to_string(), 30, vec![CacheTag::Folder(7)], tagged)
+            .await;
+        reopened.invalidate_folders(&[7]);
+        let reopened = ResponseCache::new(&config, &env(&dir));
+        assert_eq!(reopened.get("c", CachePolicy::Use).await, None);
+        reopened.clear().await;
+        assert!(!dir.path().join(CACHE_DIR).exists());
+    }
+
+    #[actix_web::test]
+    async fn test_huge_ttl_and_max_age() {
+        let dir = TempDir::new().expect("无法创建临时目录");
+        let config = CacheConfig {
+            disk: true,
+            ttl_secs: u64::MAX,
+            ..CacheConfig::default()
+        };
+        let cache = ResponseCache::new(&config, &env(&dir));
+        let now = Utc::now();
+        cache
+            .put(
+                "a",
+                "1".to_string(),
+                u64::MAX,
+                vec![CacheTag::Folder(1)],
+                now,
+            )
+            .await;
+        assert_eq!(
+            cache.get("a", CachePolicy::MaxAge(u64::MAX)).await,
+            Some("1".to_string())
+        );
+        let reopened = ResponseCache::new(&config, &env(&dir));
+        assert_eq!(
+            reopened.get("a", CachePolicy::Use).await,
+            Some("1".to_string())
+        );
+        cache.invalidate_folders(&[1]);
+        assert_eq!(cache.get("a", CachePolicy::Use).await, None);
+    }
+
+    #[actix_web::test]
+    async fn test_gateway_cache_headers() {
+        let dir = TempDir::new().expect("无法创建临时目录");
+        let gateway = GatewayConfig::default();
+        let token = AccessToken::default();
+        let cache = ResponseCache::new(&gateway.cache, &env(&dir));
+        let cached = UserInfoResponse {
+            code: 0,
+            message: "ok".to_string(),
+            data: None,
+            x_trace_id: "cached".to_string(),
+        };
+        let key = ResponseCache::key(&token, "GET", "api/v1/user/info", &());
+        cache
+            .put(
+                &key,
+                serde_json::to_string(&cached).unwrap(),
+                60,
+                Vec::new(),
+                Utc::now(),
+            )
+            .await;
+
+        let app = actix_test::init_service(create_app_with_cache(
+            web::Data::new(env(&dir)),
+            web::Data::new(token),
+            web::Data::new(gateway),
+            web::Data::new(cache),
+        ))
+        .await;
+        let req = actix_test::TestRequest::get()
+            .uri("/user_info")
+            .to_request();
+        let resp = actix_test::call_service(&app, req).await;
+        assert!(resp.status().is_success());
+        assert_eq!(resp.headers().get(CACHE_STATUS_HEADER).unwrap(), "HIT");
+        let body: UserInfoResponse = actix_test::read_body_json(resp).await;
+        assert_eq!(body.x_trace_id, "cached");
+    }
+}
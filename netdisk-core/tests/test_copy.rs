@@ -0,0 +1,42 @@
+#[cfg(test)]
+mod tests {
+    use netdisk_core::netdisk_api::prelude::*;
+    use netdisk_core::responses::prelude::*;
+    use std::collections::HashMap;
+    use std::error::Error;
+
+    /// 目录树：1 -> 2 -> 3，以及与之无关的 4
+    fn parents() -> HashMap<u64, u64> {
+        HashMap::from([(1, 0), (2, 1), (3, 2), (4, 0)])
+    }
+
+    async fn within(dir_id: u64, ancestor_id: u64) -> bool {
+        let parents = parents();
+        is_same_or_descendant(dir_id, ancestor_id, |id| {
+            let parent = parents.get(&id).copied();
+            async move { parent.ok_or_else(|| Box::<dyn Error>::from("目录不存在")) }
+        })
+        .await
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_is_same_or_descendant() {
+        assert!(within(1, 1).await);
+        assert!(within(3, 1).await);
+        assert!(within(3, 2).await);
+        assert!(!within(1, 3).await);
+        assert!(!within(4, 1).await);
+        // 任何目录都在根目录之下
+        assert!(within(3, 0).await);
+    }
+
+    #[tokio::test]
+    async fn test_copy_dir_rejects_own_subtree() {
+        // 复制到自身时在请求上游之前即被拒绝
+        let err = copy_dir(&AccessToken::default(), 18529409, "资料", 18529409)
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("自身或其子目录"));
+    }
+}
@@ -0,0 +1,11 @@
+#[cfg(test)]
+mod tests {
+    use netdisk_core::netdisk_api::prelude::*;
+
+    #[test]
+    fn test_split_dir_path() {
+        assert_eq!(split_dir_path("a/b/c"), vec!["a", "b", "c"]);
+        assert_eq!(split_dir_path("/a//b/./c/"), vec!["a", "b", "c"]);
+        assert!(split_dir_path("/").is_empty());
+    }
+}
@@ -0,0 +1,101 @@
+#[cfg(test)]
+mod tests {
+    use actix_web::http::StatusCode;
+    use actix_web::test as actix_test;
+    use actix_web::web;
+    use chrono::{TimeZone, Utc};
+    use netdisk_core::create_app;
+    use netdisk_core::io_basic::checksum::md5_hex;
+    use netdisk_core::netdisk_api::prelude::*;
+    use netdisk_core::netdisk_auth::basic_env::NetDiskEnv;
+    use netdisk_core::responses::prelude::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_sign_direct_url() {
+        let expires_at = Utc.with_ymd_and_hms(2024, 6, 1, 0, 0, 0).unwrap();
+        let timestamp = expires_at.timestamp();
+        let signed = sign_direct_url(
+            "https://vip.123pan.cn/1815309870/%E8%A7%86%E9%A2%91/a.mp4?v=1",
+            1815309870,
+            "secret",
+            expires_at,
+            "abc",
+        )
+        .unwrap();
+        // 签名使用解码后的路径
+        let hash = md5_hex(
+            format!("/1815309870/视频/a.mp4-{}-abc-1815309870-secret", timestamp).as_bytes(),
+        );
+        assert_eq!(
+            signed,
+            format!(
+                "https://vip.123pan.cn/1815309870/%E8%A7%86%E9%A2%91/a.mp4?v=1&auth_key={}-abc-1815309870-{}",
+                timestamp, hash
+            )
+        );
+        assert!(sign_direct_url("not a url", 1, "secret", expires_at, "abc").is_err());
+
+        let now = Utc.with_ymd_and_hms(2024, 6, 1, 0, 0, 0).unwrap();
+        assert_eq!(
+            direct_link_expires_at(now, 3600),
+            Some(Utc.with_ymd_and_hms(2024, 6, 1, 1, 0, 0).unwrap())
+        );
+        assert_eq!(direct_link_expires_at(now, u64::MAX), None);
+        assert_eq!(direct_link_expires_at(now, i64::MAX as u64), None);
+
+        let query = |expire_secs| DirectLinkUrlQuery {
+            file_id: 1,
+            expire_secs,
+        };
+        assert!(query(None).validate().is_ok());
+        assert!(query(Some(MAX_DIRECT_LINK_EXPIRE_SECS)).validate().is_ok());
+        assert!(query(Some(0)).validate().is_err());
+        assert!(query(Some(MAX_DIRECT_LINK_EXPIRE_SECS + 1))
+            .validate()
+            .is_err());
+    }
+
+    #[test]
+    fn test_direct_link_path() {
+        assert_eq!(
+            direct_link_path("/d/%E5%9B%BE%E7%89%87/a.png", "/d", "/static"),
+            Some("/static/图片/a.png".to_string())
+        );
+        assert_eq!(
+            direct_link_path("/d/a.png", "/d", "/"),
+            Some("/a.png".to_string())
+        );
+        assert_eq!(direct_link_path("/data/a.png", "/d", "/"), None);
+        assert_eq!(direct_link_path("/d/../secret.txt", "/d", "/static"), None);
+
+        let config: GatewayConfig = toml::from_str("[direct_link]\nprefix = \"files/\"\n").unwrap();
+        assert_eq!(config.direct_link.normalized_prefix(), "/files");
+        assert_eq!(config.direct_link.expire_secs, 3600);
+        assert!(config.direct_link.auth_key.is_empty());
+        assert!(!config.direct_link.enabled);
+    }
+
+    #[actix_web::test]
+    async fn test_direct_link_redirect_rejects_parent_dir() {
+        let dir = TempDir::new().expect("无法创建临时目录");
+        let env = NetDiskEnv {
+            config_dir: dir.path().to_path_buf(),
+        };
+        let mut gateway = GatewayConfig::default();
+        gateway.direct_link.enabled = true;
+        let app = actix_test::init_service(create_app(
+            web::Data::new(env),
+            web::Data::new(AccessToken::default()),
+            web::Data::new(gateway),
+        ))
+        .await;
+        let req = actix_test::TestRequest::get()
+            .uri("/d/%2E%2E/secret.txt")
+            .to_request();
+        let resp = actix_test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+        let body = actix_test::read_body(resp).await;
+        assert!(String::from_utf8_lossy(&body).contains("路径不存在"));
+    }
+}
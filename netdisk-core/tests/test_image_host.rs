@@ -0,0 +1,152 @@
+#[cfg(test)]
+mod tests {
+    use actix_web::http::StatusCode;
+    use actix_web::test as actix_test;
+    use actix_web::web;
+    use chrono::{Local, TimeZone};
+    use image::{DynamicImage, ImageOutputFormat, RgbImage};
+    use netdisk_core::create_app;
+    use netdisk_core::image_host::*;
+    use netdisk_core::netdisk_auth::basic_env::NetDiskEnv;
+    use netdisk_core::responses::prelude::*;
+    use tempfile::TempDir;
+
+    fn png(width: u32, height: u32) -> Vec<u8> {
+        let img = DynamicImage::ImageRgb8(RgbImage::new(width, height));
+        let mut data = Vec::new();
+        img.write_to(&mut data, ImageOutputFormat::Png).unwrap();
+        data
+    }
+
+    #[test]
+    fn test_prepare_image() {
+        assert_eq!(ImageFormat::sniff(&png(1, 1)), Some(ImageFormat::Png));
+        assert_eq!(
+            ImageFormat::sniff(b"RIFF\x00\x00\x00\x00WEBPVP8 "),
+            Some(ImageFormat::Webp)
+        );
+        assert_eq!(ImageFormat::sniff(b"<svg></svg>"), None);
+
+        let image = prepare_image(png(400, 200), 1 << 20, 100, 1 << 20).unwrap();
+        assert!(image.resized);
+        assert_eq!((image.width, image.height), (Some(100), Some(50)));
+        assert_eq!(ImageFormat::sniff(&image.data), Some(ImageFormat::Png));
+
+        let image = prepare_image(png(40, 20), 1 << 20, 100, 1 << 20).unwrap();
+        assert!(!image.resized);
+        assert_eq!(image.width, Some(40));
+
+        assert!(prepare_image(b"not an image".to_vec(), 1 << 20, 0, 1 << 20).is_err());
+        assert!(prepare_image(png(40, 20), 10, 0, 1 << 20).is_err());
+        // 像素数超过上限时在解码前拒绝
+        let err = prepare_image(png(400, 200), 1 << 20, 100, 400 * 200 - 1).unwrap_err();
+        assert!(err.contains("400x200"));
+        assert!(prepare_image(png(400, 200), 1 << 20, 0, 400 * 200).is_ok());
+        // 文件头正确但内容损坏
+        assert!(prepare_image(b"\x89PNG\r\n\x1a\nbroken".to_vec(), 1 << 20, 0, 1 << 20).is_err());
+    }
+
+    #[test]
+    fn test_names_and_snippets() {
+        let data = png(1, 1);
+        let name = image_file_name(Some("C:\\shots\\截图 1.jpeg"), &data, ImageFormat::Png);
+        assert!(name.starts_with("截图 1-"));
+        assert!(name.ends_with(".png"));
+        let name = image_file_name(Some("a:b?*c|d.png"), &data, ImageFormat::Png);
+        assert!(name.starts_with("a_b__c_d-"));
+        assert!(check_file_name(&name).is_ok());
+        let long = format!("{}.png", "图".repeat(300));
+        assert!(check_file_name(&image_file_name(Some(&long), &data, ImageFormat::Png)).is_ok());
+        assert_eq!(
+            image_file_name(None, &data, ImageFormat::Png).len(),
+            "0123456789abcdef.png".len()
+        );
+
+        let now = Local.with_ymd_and_hms(2024, 6, 1, 12, 0, 0).unwrap();
+        assert_eq!(
+            dated_dir("/static/images/", "%Y/%m/%d", now).unwrap(),
+            "/static/images/2024/06/01"
+        );
+        assert!(dated_dir("/images", "%Q", now).is_err());
+
+        let (markdown, html) = image_snippets("https://example.com/a(1).png", "a \"b\" [c]");
+        assert_eq!(
+            markdown,
+            "![a \"b\" \\[c\\]](https://example.com/a%281%29.png)"
+        );
+        assert_eq!(
+            html,
+            "<img src=\"https://example.com/a(1).png\" alt=\"a &quot;b&quot; [c]\">"
+        );
+
+        let mut direct_link = DirectLinkConfig {
+            enabled: true,
+            root: "/static".to_string(),
+            ..DirectLinkConfig::default()
+        };
+        assert_eq!(
+            public_image_url(
+                "https://pan.example.com/",
+                &direct_link,
+                "/static/images/2024/截图.png"
+            ),
+            Some("https://pan.example.com/d/images/2024/%E6%88%AA%E5%9B%BE.png".to_string())
+        );
+        assert_eq!(
+            public_image_url("https://pan.example.com", &direct_link, "/images/a.png"),
+            None
+        );
+        // 未挂载 `/d` 跳转时不返回经跳转的链接
+        direct_link.enabled = false;
+        assert_eq!(
+            public_image_url("https://pan.example.com", &direct_link, "/static/a.png"),
+            None
+        );
+    }
+
+    #[actix_web::test]
+    async fn test_image_upload_rejects_non_image() {
+        let dir = TempDir::new().expect("无法创建临时目录");
+        let env = NetDiskEnv {
+            config_dir: dir.path().to_path_buf(),
+        };
+        let mut gateway = GatewayConfig::default();
+        gateway.image_host.max_bytes = 64;
+        let app = actix_test::init_service(create_app(
+            web::Data::new(env),
+            web::Data::new(AccessToken::default()),
+            web::Data::new(gateway),
+        ))
+        .await;
+        let body = "--XBOUNDARY\r\n\
+            Content-Disposition: form-data; name=\"file\"; filename=\"a.png\"\r\n\
+            Content-Type: image/png\r\n\r\n\
+            not an image\r\n\
+            --XBOUNDARY--\r\n";
+        let req = actix_test::TestRequest::post()
+            .uri("/image/upload")
+            .insert_header(("Content-Type", "multipart/form-data; boundary=XBOUNDARY"))
+            .set_payload(body)
+            .to_request();
+        let resp = actix_test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+
+        // 只有超过大小上限才返回 413，表单不完整时返回 400
+        let upload = |content: String| {
+            actix_test::TestRequest::post()
+                .uri("/image/upload")
+                .insert_header(("Content-Type", "multipart/form-data; boundary=XBOUNDARY"))
+                .set_payload(content)
+                .to_request()
+        };
+        let head = "--XBOUNDARY\r\n\
+            Content-Disposition: form-data; name=\"file\"; filename=\"a.png\"\r\n\
+            Content-Type: image/png\r\n\r\n";
+        let oversized = format!("{}{}\r\n--XBOUNDARY--\r\n", head, "x".repeat(100));
+        let resp = actix_test::call_service(&app, upload(oversized)).await;
+        assert_eq!(resp.status(), StatusCode::PAYLOAD_TOO_LARGE);
+        let truncated = format!("{}abc", head);
+        let resp = actix_test::call_service(&app, upload(truncated)).await;
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+    }
+}
@@ -1,4 +1,5 @@
 mod tests {
+    use netdisk_core::io_basic::checksum::*;
     use netdisk_core::io_basic::read_and_write::*;
     use netdisk_core::responses::prelude::*;
     use std::path::Path;
@@ -30,4 +31,13 @@ mod tests {
             }
         }
     }
+    #[tokio::test]
+    async fn test_file_md5() {
+        let temp_file = NamedTempFile::new().expect("无法创建临时文件");
+        std::fs::write(temp_file.path(), b"hello netdisk").unwrap();
+        let expected = md5_hex(b"hello netdisk");
+        assert_eq!(file_md5(temp_file.path()).unwrap(), expected);
+        assert_eq!(async_file_md5(temp_file.path()).await.unwrap(), expected);
+        assert_eq!(md5_hex(b""), "d41d8cd98f00b204e9800998ecf8427e");
+    }
 }
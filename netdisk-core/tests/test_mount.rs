@@ -0,0 +1,91 @@
+#[cfg(test)]
+mod tests {
+    use netdisk_core::mount::cache::*;
+    use netdisk_core::mount::*;
+    use netdisk_core::responses::prelude::*;
+    use serde_json::json;
+    use std::time::{Duration, Instant};
+
+    fn item(file_id: u64, filename: &str) -> FileItem {
+        serde_json::from_value(json!({
+            "fileId": file_id,
+            "parentFileId": 0,
+            "type": 0,
+            "size": 1024,
+            "category": 0,
+            "status": 0,
+            "punishFlag": 0,
+            "trashed": 0,
+            "filename": filename,
+            "etag": "e325c611ea19f1bc3bef16f0eac7cb92",
+            "createAt": "2024-01-01 00:00:00",
+            "updateAt": "2024-01-01 00:00:00",
+        }))
+        .expect("构造 FileItem 失败")
+    }
+
+    #[test]
+    fn test_inode_mapping() {
+        assert_eq!(file_id_to_ino(0, 0), ROOT_INO);
+        assert_eq!(file_id_to_ino(42, 0), 43);
+        assert_eq!(ino_to_file_id(43, 0), 42);
+        // 挂载子目录时，该目录本身是根
+        assert_eq!(file_id_to_ino(18529409, 18529409), ROOT_INO);
+        assert_eq!(ino_to_file_id(ROOT_INO, 18529409), 18529409);
+    }
+
+    #[test]
+    fn test_block_ranges() {
+        let block = 10;
+        assert_eq!(block_range(0, 10, 25, block), 0..1);
+        assert_eq!(block_range(5, 10, 25, block), 0..2);
+        // 超出文件末尾的部分被截掉
+        assert_eq!(block_range(18, 100, 25, block), 1..3);
+        assert_eq!(block_range(25, 10, 25, block), 0..0);
+        assert_eq!(block_bounds(2, 25, block), 20..25);
+
+        // 读取 [5, 15)：第 0 块取 5..10，第 1 块取 0..5
+        assert_eq!(block_slice(0, 5, 15, block), 5..10);
+        assert_eq!(block_slice(1, 5, 15, block), 0..5);
+        assert_eq!(block_slice(2, 5, 15, block), 0..0);
+    }
+
+    #[test]
+    fn test_block_cache_evicts_least_recently_used() {
+        let mut cache = BlockCache::new(10);
+        cache.insert(1, 0, vec![0; 4]);
+        cache.insert(1, 1, vec![1; 4]);
+        // 访问第 0 块后，第 1 块成为最久未使用
+        assert!(cache.get(1, 0).is_some());
+        cache.insert(2, 0, vec![2; 4]);
+        assert_eq!(cache.used(), 8);
+        assert!(cache.get(1, 1).is_none());
+        assert_eq!(cache.get(1, 0), Some(&[0u8; 4][..]));
+
+        // 超过容量的块不缓存
+        cache.insert(3, 0, vec![3; 11]);
+        assert!(cache.get(3, 0).is_none());
+
+        cache.invalidate(1);
+        assert_eq!(cache.len(), 1);
+        assert_eq!(cache.used(), 4);
+    }
+
+    #[test]
+    fn test_metadata_cache_expires_dirs_but_keeps_items() {
+        let start = Instant::now();
+        let mut cache = MetadataCache::new(Duration::from_secs(30));
+        cache.insert_dir(0, vec![item(1, "a.mp4"), item(2, "b.mp4")], start);
+
+        let names: Vec<_> = cache
+            .dir(0, start + Duration::from_secs(1))
+            .unwrap()
+            .iter()
+            .map(|item| item.filename.clone())
+            .collect();
+        assert_eq!(names, vec!["a.mp4", "b.mp4"]);
+        assert!(cache.dir(0, start + Duration::from_secs(30)).is_none());
+        assert!(cache.dir(7, start).is_none());
+        assert_eq!(cache.item(2).unwrap().filename, "b.mp4");
+    }
+}
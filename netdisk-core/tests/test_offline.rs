@@ -0,0 +1,159 @@
+#[cfg(test)]
+mod tests {
+    use actix_web::http::StatusCode;
+    use actix_web::test as actix_test;
+    use actix_web::web;
+    use chrono::{TimeZone, Utc};
+    use netdisk_core::create_app;
+    use netdisk_core::local_db::open_local_db;
+    use netdisk_core::netdisk_auth::basic_env::NetDiskEnv;
+    use netdisk_core::offline::*;
+    use netdisk_core::responses::prelude::*;
+    use rusqlite::{params, Connection};
+    use serde_json::json;
+    use tempfile::TempDir;
+
+    fn task(task_id: u64, url: &str, status: OfflineTaskStatus) -> OfflineTask {
+        let submitted_at = Utc.with_ymd_and_hms(2024, 6, 1, 4, 0, 0).unwrap();
+        OfflineTask {
+            task_id,
+            url: url.to_string(),
+            file_name: None,
+            dir_id: Some(100),
+            source: None,
+            submitted_at,
+            status,
+            process: 0.0,
+            updated_at: submitted_at,
+            file_ids: Vec::new(),
+        }
+    }
+
+    fn file_item(file_id: i64, filename: &str, create_at: &str) -> FileItem {
+        serde_json::from_value(json!({
+            "fileId": file_id,
+            "parentFileId": 100,
+            "type": 0,
+            "size": 1,
+            "category": 0,
+            "status": 0,
+            "punishFlag": 0,
+            "trashed": 0,
+            "filename": filename,
+            "etag": "",
+            "createAt": create_at,
+            "updateAt": create_at
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_links_from_file_and_catalog() {
+        let dir = TempDir::new().expect("无法创建临时目录");
+        let links_path = dir.path().join("links.txt");
+        std::fs::write(
+            &links_path,
+            "# 注释\nmagnet:?xt=urn:btih:abc\n\n  https://example.com/a.mkv  \n",
+        )
+        .unwrap();
+        let links = read_links_file(&links_path).unwrap();
+        let urls: Vec<&str> = links.iter().map(|link| link.url.as_str()).collect();
+        assert_eq!(
+            urls,
+            vec!["magnet:?xt=urn:btih:abc", "https://example.com/a.mkv"]
+        );
+        assert!(is_supported_url(urls[0]) && is_supported_url(urls[1]));
+        assert!(!is_supported_url("ftp://example.com/a.mkv"));
+
+        let db_path = dir.path().join("movies.db");
+        let conn = Connection::open(&db_path).unwrap();
+        conn.execute_batch(
+            "CREATE TABLE movies (id INTEGER PRIMARY KEY AUTOINCREMENT, title TEXT NOT NULL, magnetlink TEXT);",
+        )
+        .unwrap();
+        for (title, link) in [
+            ("a", Some("magnet:?xt=1")),
+            ("b", None),
+            ("c", Some("magnet:?xt=3")),
+        ] {
+            conn.execute(
+                "INSERT INTO movies (title, magnetlink) VALUES (?1, ?2)",
+                params![title, link],
+            )
+            .unwrap();
+        }
+        let links = catalog_links(&db_path, None).unwrap();
+        assert_eq!(links.len(), 2);
+        assert_eq!(links[1].source.as_deref(), Some("catalog:3"));
+        assert_eq!(catalog_links(&db_path, Some(1)).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_task_records() {
+        let dir = TempDir::new().expect("无法创建临时目录");
+        let env = NetDiskEnv {
+            config_dir: dir.path().to_path_buf(),
+        };
+        let conn = open_local_db(&env).unwrap();
+        let mut done = task(1, "magnet:?xt=1", OfflineTaskStatus::Succeeded);
+        done.file_ids = vec![7, 8];
+        save_task(&conn, &done).unwrap();
+        save_task(&conn, &task(2, "magnet:?xt=2", OfflineTaskStatus::Failed)).unwrap();
+        save_task(&conn, &task(3, "magnet:?xt=3", OfflineTaskStatus::Retrying)).unwrap();
+
+        assert_eq!(load_tasks(&conn, false).unwrap().len(), 3);
+        let pending = load_tasks(&conn, true).unwrap();
+        assert_eq!(pending.len(), 1);
+        assert_eq!(pending[0].task_id, 3);
+        assert_eq!(load_tasks(&conn, false).unwrap()[0].file_ids, vec![7, 8]);
+        // 失败的链接允许重新提交
+        assert!(already_submitted(&conn, "magnet:?xt=1").unwrap());
+        assert!(!already_submitted(&conn, "magnet:?xt=2").unwrap());
+    }
+
+    #[test]
+    fn test_task_dir_and_result_files() {
+        let name = task_dir_name("magnet:?xt=urn:btih:abc");
+        assert!(name.starts_with("offline-"));
+        assert_eq!(name.len(), "offline-".len() + 12);
+        assert_eq!(name, task_dir_name("magnet:?xt=urn:btih:abc"));
+        assert_ne!(name, task_dir_name("magnet:?xt=urn:btih:abd"));
+
+        let items = vec![
+            file_item(1, "a.mkv", "2024-06-01 12:05:00"),
+            file_item(2, "a.nfo", "2024-06-01 12:05:00"),
+        ];
+        assert_eq!(task_result_files(&items), vec![1, 2]);
+        assert!(task_result_files(&[]).is_empty());
+    }
+
+    #[actix_web::test]
+    async fn test_single_download_reports_skipped_link() {
+        let dir = TempDir::new().expect("无法创建临时目录");
+        let env = NetDiskEnv {
+            config_dir: dir.path().to_path_buf(),
+        };
+        let conn = open_local_db(&env).unwrap();
+        save_task(&conn, &task(1, "magnet:?xt=1", OfflineTaskStatus::Retrying)).unwrap();
+        let app = actix_test::init_service(create_app(
+            web::Data::new(env),
+            web::Data::new(AccessToken::default()),
+            web::Data::new(GatewayConfig::default()),
+        ))
+        .await;
+
+        // 链接未被提交时不能返回成功
+        let req = actix_test::TestRequest::post()
+            .uri("/offline/download")
+            .set_json(json!({"url": "magnet:?xt=1", "dirID": 5}))
+            .to_request();
+        let resp = actix_test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+        let body: OfflineBulkResponse = actix_test::read_body_json(resp).await;
+        assert_eq!(body.code, 400);
+        assert_eq!(body.message, "链接已提交过");
+        let report = body.data.unwrap();
+        assert!(report.submitted.is_empty());
+        assert_eq!(report.skipped.len(), 1);
+    }
+}
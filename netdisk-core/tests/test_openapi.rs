@@ -0,0 +1,136 @@
+#[cfg(test)]
+mod tests {
+    use actix_web::http::{Method, StatusCode};
+    use actix_web::test as actix_test;
+    use actix_web::{web, HttpRequest, HttpResponse};
+    use netdisk_core::create_app;
+    use netdisk_core::netdisk_auth::basic_env::NetDiskEnv;
+    use netdisk_core::openapi::{gateway_openapi, openapi_path, OPENAPI_PATH};
+    use netdisk_core::responses::prelude::*;
+    use serde_json::Value;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_spec_references_resolve() {
+        let spec = serde_json::to_value(gateway_openapi()).unwrap();
+        let schemas = spec["components"]["schemas"].as_object().unwrap();
+        let text = spec.to_string();
+        for reference in text.split("\"$ref\":\"").skip(1) {
+            let name = reference[..reference.find('"').unwrap()]
+                .trim_start_matches("#/components/schemas/");
+            assert!(schemas.contains_key(name), "未定义的类型: {}", name);
+        }
+        let list = &spec["paths"]["/file/file_lists_query"]["get"]["parameters"];
+        assert!(list
+            .as_array()
+            .unwrap()
+            .iter()
+            .any(|parameter| parameter["name"] == "parentFileId" && parameter["required"] == true));
+    }
+
+    /// 在应用内按路由表匹配规范中的每个路径，返回匹配不到的路径
+    async fn unmatched_paths(req: HttpRequest) -> HttpResponse {
+        let spec = serde_json::to_value(gateway_openapi()).unwrap();
+        let unmatched: Vec<String> = spec["paths"]
+            .as_object()
+            .unwrap()
+            .keys()
+            .filter(|path| {
+                let concrete = path
+                    .split('/')
+                    .map(|segment| {
+                        if segment.starts_with('{') {
+                            "1"
+                        } else {
+                            segment
+                        }
+                    })
+                    .collect::<Vec<_>>()
+                    .join("/");
+                let matched = req.resource_map().match_pattern(&concrete);
+                matched.map(|pattern| openapi_path(&pattern)).as_ref() != Some(*path)
+            })
+            .cloned()
+            .collect();
+        HttpResponse::Ok().json(unmatched)
+    }
+
+    /// 可配置前缀下的路径在路由表中匹配到的模板
+    async fn prefix_patterns(req: HttpRequest) -> HttpResponse {
+        let patterns: Vec<Option<String>> =
+            ["/webdav/a.txt", "/objects/renders/a.txt", "/files/a.txt"]
+                .iter()
+                .map(|path| req.resource_map().match_pattern(path))
+                .collect();
+        HttpResponse::Ok().json(patterns)
+    }
+
+    #[actix_web::test]
+    async fn test_spec_served_and_routes_registered() {
+        let dir = TempDir::new().expect("无法创建临时目录");
+        let mut gateway = GatewayConfig::default();
+        gateway.webdav.enabled = true;
+        gateway.webdav.prefix = "/webdav/".to_string();
+        gateway.s3.enabled = true;
+        gateway.s3.prefix = "objects".to_string();
+        gateway.direct_link.enabled = true;
+        gateway.direct_link.prefix = "/files".to_string();
+        let app = actix_test::init_service(
+            create_app(
+                web::Data::new(NetDiskEnv {
+                    config_dir: dir.path().to_path_buf(),
+                }),
+                web::Data::new(AccessToken::default()),
+                web::Data::new(gateway),
+            )
+            .route("/__unmatched", web::get().to(unmatched_paths))
+            .route("/__prefixes", web::get().to(prefix_patterns)),
+        )
+        .await;
+
+        let req = actix_test::TestRequest::get()
+            .uri(OPENAPI_PATH)
+            .to_request();
+        let spec: Value = actix_test::call_and_read_body_json(&app, req).await;
+        assert!(spec["openapi"].as_str().unwrap().starts_with("3."));
+        assert!(spec["paths"]["/file/move"]["post"].is_object());
+
+        let req = actix_test::TestRequest::get().uri("/docs").to_request();
+        let resp = actix_test::call_service(&app, req).await;
+        assert!(resp.status().is_success());
+
+        let req = actix_test::TestRequest::get()
+            .uri("/__unmatched")
+            .to_request();
+        let unmatched: Vec<String> = actix_test::call_and_read_body_json(&app, req).await;
+        assert!(unmatched.is_empty(), "以下路径没有注册: {:?}", unmatched);
+
+        // 可配置前缀不在路由表中，由各自的服务处理
+        let req = actix_test::TestRequest::get()
+            .uri("/__prefixes")
+            .to_request();
+        let patterns: Vec<Option<String>> = actix_test::call_and_read_body_json(&app, req).await;
+        assert_eq!(patterns, vec![None, None, None]);
+        let paths = spec["paths"].as_object().unwrap();
+        assert!(paths.keys().all(|path| !["/webdav", "/objects", "/files"]
+            .iter()
+            .any(|prefix| path.starts_with(prefix))));
+
+        let req = actix_test::TestRequest::default()
+            .method(Method::OPTIONS)
+            .uri("/webdav/")
+            .to_request();
+        let resp = actix_test::call_service(&app, req).await;
+        assert_eq!(resp.headers().get("DAV").unwrap(), "1");
+
+        let req = actix_test::TestRequest::get()
+            .uri("/objects/renders/a.txt")
+            .to_request();
+        let resp = actix_test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+
+        let req = actix_test::TestRequest::get().uri("/files/").to_request();
+        let body = actix_test::call_and_read_body(&app, req).await;
+        assert!(String::from_utf8_lossy(&body).contains("文件不存在"));
+    }
+}
@@ -0,0 +1,135 @@
+#[cfg(test)]
+mod tests {
+    use netdisk_core::netdisk_api::prelude::*;
+    use netdisk_core::netdisk_auth::basic_env::NetDiskEnv;
+    use netdisk_core::responses::prelude::*;
+    use serde_json::json;
+
+    fn trashed_item(filename: &str, update_at: &str) -> FileItem {
+        serde_json::from_value(json!({
+            "fileId": 1,
+            "parentFileId": 0,
+            "type": 0,
+            "size": 1024,
+            "category": 0,
+            "status": 0,
+            "punishFlag": 0,
+            "trashed": 1,
+            "filename": filename,
+            "etag": "e325c611ea19f1bc3bef16f0eac7cb92",
+            "createAt": "2024-01-01 00:00:00",
+            "updateAt": update_at,
+        }))
+        .expect("构造 FileItem 失败")
+    }
+
+    #[test]
+    fn test_purge_defaults_to_dry_run() {
+        let item: PurgeItem = serde_json::from_value(json!({})).unwrap();
+        assert!(item.dry_run);
+        assert_eq!(item.parent_file_id, 0);
+    }
+
+    #[test]
+    fn test_matches_purge_filter() {
+        let filter: PurgeItem = serde_json::from_value(json!({
+            "olderThanDays": 30,
+            "filter": ".mkv",
+        }))
+        .unwrap();
+
+        assert!(matches_purge_filter(
+            &trashed_item("movie.mkv", "2020-01-01 00:00:00"),
+            &filter
+        ));
+        assert!(!matches_purge_filter(
+            &trashed_item("movie.mp4", "2020-01-01 00:00:00"),
+            &filter
+        ));
+        let recent = chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string();
+        assert!(!matches_purge_filter(
+            &trashed_item("movie.mkv", &recent),
+            &filter
+        ));
+    }
+
+    #[test]
+    fn test_delete_confirm_token() {
+        let tmp_dir = tempfile::TempDir::new().expect("无法创建临时目录");
+        let env = NetDiskEnv {
+            config_dir: tmp_dir.path().to_path_buf(),
+        };
+        // 并发首次生成时所有调用方拿到同一个密钥
+        let secrets: Vec<Vec<u8>> = std::thread::scope(|scope| {
+            let handles: Vec<_> = (0..8)
+                .map(|_| scope.spawn(|| load_delete_secret(&env).unwrap()))
+                .collect();
+            handles.into_iter().map(|h| h.join().unwrap()).collect()
+        });
+        let secret = load_delete_secret(&env).unwrap();
+        assert!(secrets.iter().all(|s| *s == secret));
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let path = env.config_dir.join(DELETE_SECRET_FILE);
+            let mode = std::fs::metadata(path).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+
+        let token = AccessToken::default();
+        let expires_at = chrono::Utc::now().timestamp() + 60;
+        let confirm = delete_confirm_token(&secret, &token, &[3, 1, 2], expires_at);
+
+        // 令牌与文件 ID 顺序无关，但必须覆盖同一组文件
+        assert!(verify_delete_token(&secret, &token, &[1, 2, 3], &confirm));
+        assert!(!verify_delete_token(&secret, &token, &[1, 2], &confirm));
+        assert!(!verify_delete_token(
+            &secret,
+            &token,
+            &[1, 2, 3],
+            "not-a-token"
+        ));
+        // 其他密钥签发或篡改过期时间的令牌无效
+        assert!(!verify_delete_token(
+            b"other-secret",
+            &token,
+            &[1, 2, 3],
+            &confirm
+        ));
+        let (_, signature) = confirm.split_once('-').unwrap();
+        let extended = format!("{}-{}", expires_at + 3600, signature);
+        assert!(!verify_delete_token(&secret, &token, &[1, 2, 3], &extended));
+
+        let expired =
+            delete_confirm_token(&secret, &token, &[1], chrono::Utc::now().timestamp() - 1);
+        assert!(!verify_delete_token(&secret, &token, &[1], &expired));
+    }
+
+    #[tokio::test]
+    async fn test_append_delete_audit() {
+        let tmp_dir = tempfile::TempDir::new().expect("无法创建临时目录");
+        let env = NetDiskEnv {
+            config_dir: tmp_dir.path().to_path_buf(),
+        };
+        let file = DeleteCandidate {
+            file_id: 1,
+            filename: "movie.mkv".to_string(),
+            path: "/videos/movie.mkv".to_string(),
+            size: 1024,
+            etag: "e325c611ea19f1bc3bef16f0eac7cb92".to_string(),
+        };
+        append_delete_audit(&env, "delete", &[file.clone()])
+            .await
+            .unwrap();
+        append_delete_audit(&env, "purge", &[file]).await.unwrap();
+
+        let content = std::fs::read_to_string(tmp_dir.path().join(DELETE_AUDIT_LOG)).unwrap();
+        let entries: Vec<DeleteAuditEntry> = content
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[1].source, "purge");
+        assert_eq!(entries[0].file.path, "/videos/movie.mkv");
+    }
+}
@@ -0,0 +1,289 @@
+#[cfg(test)]
+mod tests {
+    use actix_web::http::StatusCode;
+    use actix_web::test as actix_test;
+    use actix_web::web;
+    use chrono::{Duration, Local, TimeZone, Utc};
+    use netdisk_core::create_app;
+    use netdisk_core::netdisk_api::prelude::*;
+    use netdisk_core::netdisk_auth::basic_env::NetDiskEnv;
+    use netdisk_core::responses::prelude::*;
+    use serde_json::json;
+    use tempfile::TempDir;
+
+    fn share(share_id: i64, expiration: &str, expired: u8) -> ShareItemData {
+        serde_json::from_value(json!({
+            "shareId": share_id,
+            "shareKey": format!("key{}", share_id),
+            "shareName": format!("分享{}", share_id),
+            "expiration": expiration,
+            "expired": expired,
+            "sharePwd": "",
+            "trafficSwitch": 1,
+            "trafficLimitSwitch": 1,
+            "trafficLimit": 0,
+            "bytesCharge": 0,
+            "previewCount": 0,
+            "downloadCount": 0,
+            "saveCount": 0
+        }))
+        .unwrap()
+    }
+
+    fn record(share_id: u64, file_ids: Vec<u64>, recreated_from: Option<u64>) -> ShareRecord {
+        ShareRecord {
+            share_id,
+            share_key: format!("key{}", share_id),
+            share_name: format!("分享{}", share_id),
+            file_ids,
+            share_pwd: Some("abcd".to_string()),
+            share_expire: ShareExpireDays::SevenDays,
+            created_at: Utc::now(),
+            recreated_from,
+        }
+    }
+
+    #[test]
+    fn test_share_list_last_page() {
+        let data: ShareListData =
+            serde_json::from_value(json!({"lastShareId": -1, "shareList": []})).unwrap();
+        assert_eq!(data.last_share_id, -1);
+        assert_eq!(parse_file_id_list("1, 2,x,3"), vec![1, 2, 3]);
+    }
+
+    #[test]
+    fn test_expiring_within() {
+        let now = Local.with_ymd_and_hms(2024, 6, 1, 12, 0, 0).unwrap();
+        let shares = vec![
+            share(1, "2024-06-05 12:00:00", 0),
+            share(2, "2024-06-02 12:00:00", 0),
+            share(3, "2024-07-01 12:00:00", 0),
+            share(4, "2024-05-30 12:00:00", 1),
+        ];
+        let expiring = expiring_within(&shares, now, 7).unwrap();
+        let ids: Vec<i64> = expiring.iter().map(|share| share.share_id).collect();
+        assert_eq!(ids, vec![2, 1]);
+        assert!(expiring_within(&shares, now + Duration::days(60), 7)
+            .unwrap()
+            .is_empty());
+        assert!(expiring_within(&shares, now, 1_000_000_000).is_none());
+        assert!(expiring_within(&shares, now, i64::MAX).is_none());
+        assert!(ShareExpiringQuery {
+            days: MAX_EXPIRING_DAYS
+        }
+        .validate()
+        .is_ok());
+        assert!(ShareExpiringQuery {
+            days: 1_000_000_000
+        }
+        .validate()
+        .unwrap_err()
+        .has("days"));
+    }
+
+    #[test]
+    fn test_plan_recreate() {
+        let now = Local.with_ymd_and_hms(2024, 6, 1, 12, 0, 0).unwrap();
+        let shares = vec![
+            share(1, "2024-05-01 12:00:00", 1),
+            share(2, "2024-05-01 12:00:00", 1),
+            share(3, "2024-05-01 12:00:00", 1),
+            share(4, "2024-07-01 12:00:00", 0),
+        ];
+        let mut records = ShareRecords::default();
+        records.shares.insert(1, record(1, vec![10, 11], None));
+        records.shares.insert(9, record(9, vec![12], Some(3)));
+
+        let (planned, skipped) = plan_recreate(&shares, &records, None, now);
+        assert_eq!(planned.len(), 1);
+        assert_eq!(planned[0].1.file_ids, vec![10, 11]);
+        let skipped_ids: Vec<u64> = skipped.iter().map(|s| s.share_id).collect();
+        // 2 没有本地记录，3 已经重建为 9，4 未过期不在默认范围内
+        assert_eq!(skipped_ids, vec![2, 3]);
+        assert!(skipped[1].reason.contains('9'));
+
+        let (planned, skipped) = plan_recreate(&shares, &records, Some(&[4, 5]), now);
+        assert!(planned.is_empty());
+        let skipped_ids: Vec<u64> = skipped.iter().map(|s| s.share_id).collect();
+        assert_eq!(skipped_ids, vec![5, 4]);
+    }
+
+    #[test]
+    fn test_share_create_item_validation() {
+        let mut item: ShareCreateItem = serde_json::from_value(json!({
+            "shareName": "资料",
+            "shareExpire": "7",
+            "fileIDs": [3, 1, 3],
+            "sharePwd": ""
+        }))
+        .unwrap();
+        let share_item = item.to_share_item().unwrap();
+        assert_eq!(share_item.file_id_list, "3,1");
+        assert_eq!(share_item.share_pwd, None);
+
+        item.file_ids = (1..=101).collect();
+        assert!(item.to_share_item().unwrap_err().contains("100"));
+        item.file_ids.clear();
+        assert!(item.to_share_item().is_err());
+    }
+
+    #[test]
+    fn test_share_link_message() {
+        let link = ShareLink::from(&record(7, vec![1], None));
+        assert_eq!(link.url, "https://www.123pan.com/s/key7");
+        assert!(link.message.contains(&link.url));
+        assert!(link.message.ends_with("提取码：abcd"));
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn test_concurrent_record_share_keeps_all_records() {
+        let config_dir = TempDir::new().expect("无法创建临时目录");
+        let env = NetDiskEnv {
+            config_dir: config_dir.path().to_path_buf(),
+        };
+        let tasks: Vec<_> = (1..=20)
+            .map(|share_id| {
+                let env = env.clone();
+                tokio::spawn(async move {
+                    record_share(&env, record(share_id, vec![share_id * 10], None)).await
+                })
+            })
+            .collect();
+        for task in tasks {
+            task.await.unwrap().unwrap();
+        }
+        let records = load_share_records(&env).await.unwrap();
+        assert_eq!(records.shares.len(), 20);
+    }
+
+    #[actix_web::test]
+    async fn test_share_lookup_by_file_and_key() {
+        let config_dir = TempDir::new().expect("无法创建临时目录");
+        let env = NetDiskEnv {
+            config_dir: config_dir.path().to_path_buf(),
+        };
+        record_share(&env, record(1, vec![10, 11], None))
+            .await
+            .unwrap();
+        record_share(&env, record(2, vec![11], None)).await.unwrap();
+        let app = actix_test::init_service(create_app(
+            web::Data::new(env),
+            web::Data::new(AccessToken::default()),
+            web::Data::new(GatewayConfig::default()),
+        ))
+        .await;
+
+        let req = actix_test::TestRequest::get()
+            .uri("/share/by-file?fileId=11")
+            .to_request();
+        let resp: ShareLinksResponse = actix_test::call_and_read_body_json(&app, req).await;
+        let ids: Vec<u64> = resp
+            .data
+            .unwrap()
+            .iter()
+            .map(|link| link.share_id)
+            .collect();
+        assert_eq!(ids, vec![1, 2]);
+
+        let req = actix_test::TestRequest::get()
+            .uri("/share/link/key1")
+            .to_request();
+        let resp: ShareLinkResponse = actix_test::call_and_read_body_json(&app, req).await;
+        assert_eq!(resp.data.unwrap().file_ids, vec![10, 11]);
+
+        let req = actix_test::TestRequest::get()
+            .uri("/share/link/unknown")
+            .to_request();
+        let resp = actix_test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+
+        // 超出 100 个文件时不请求上游，直接返回 400
+        let req = actix_test::TestRequest::post()
+            .uri("/share/link")
+            .set_json(json!({
+                "shareName": "太多文件",
+                "shareExpire": "1",
+                "fileIDs": (1..=101).collect::<Vec<u64>>()
+            }))
+            .to_request();
+        let resp = actix_test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+    }
+
+    fn pay_link(pay_amount: u32, is_reward: u8, desc_len: usize) -> PayLinkItem {
+        serde_json::from_value(json!({
+            "shareName": "付费资料",
+            "fileIDList": "11522388,11522389",
+            "payAmount": pay_amount,
+            "isReward": is_reward,
+            "resourceDesc": "字".repeat(desc_len)
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_pay_link_validation() {
+        assert!(pay_link(10, 0, 200).validate().is_ok());
+        assert!(pay_link(0, 0, 10).validate().is_err());
+        assert!(pay_link(100, 0, 10).validate().is_err());
+        assert!(pay_link(10, 2, 10).validate().is_err());
+        assert!(pay_link(10, 1, 201).validate().is_err());
+        let mut item = pay_link(10, 1, 10);
+        item.file_id_list = "1,x".to_string();
+        assert!(item.validate().is_err());
+    }
+
+    #[test]
+    fn test_paid_share_listing_and_earnings() {
+        // 真实的 lastShareId 超出 i8 范围
+        let data: PayListItem = serde_json::from_value(json!({
+            "lastShareId": 69692575,
+            "shareList": [
+                {"shareId": 1, "shareKey": "a", "shareName": "甲", "payAmount": 5, "amount": 15,
+                 "expiration": "2099-01-01 00:00:00", "expired": 0, "trafficSwitch": 1,
+                 "trafficLimitSwitch": 1, "trafficLimit": 0, "bytesCharge": 0,
+                 "previewCount": 0, "downloadCount": 3, "saveCount": 0},
+                {"shareId": 2, "shareKey": "b", "shareName": "乙", "payAmount": 9, "amount": 90,
+                 "expiration": "2020-01-01 00:00:00", "expired": 1, "trafficSwitch": 1,
+                 "trafficLimitSwitch": 1, "trafficLimit": 0, "bytesCharge": 0,
+                 "previewCount": 0, "downloadCount": 10, "saveCount": 0},
+                // 付费分享的 expiration 可能早于当前时间，但未失效
+                {"shareId": 3, "shareKey": "c", "shareName": "丙", "payAmount": 1, "amount": 0,
+                 "expiration": "2020-01-01 00:00:00", "expired": 0, "trafficSwitch": 1,
+                 "trafficLimitSwitch": 1, "trafficLimit": 0, "bytesCharge": 0,
+                 "previewCount": 0, "downloadCount": 0, "saveCount": 0}
+            ]
+        }))
+        .unwrap();
+        assert_eq!(data.last_share_id, 69692575);
+
+        let shares = data.share_list.unwrap();
+        assert_eq!(active_paid_share_ids(&shares), vec![1, 3]);
+        let earnings = paid_share_earnings(&shares);
+        assert_eq!(earnings.total_amount, 105);
+        assert_eq!(earnings.share_count, 3);
+        assert_eq!(earnings.active_count, 2);
+        assert_eq!(earnings.shares[0].share_id, 2);
+        assert_eq!(earnings.shares[0].url, "https://www.123pan.com/s/b");
+    }
+
+    #[actix_web::test]
+    async fn test_pay_link_rejects_invalid_amount() {
+        let config_dir = TempDir::new().expect("无法创建临时目录");
+        let env = NetDiskEnv {
+            config_dir: config_dir.path().to_path_buf(),
+        };
+        let app = actix_test::init_service(create_app(
+            web::Data::new(env),
+            web::Data::new(AccessToken::default()),
+            web::Data::new(GatewayConfig::default()),
+        ))
+        .await;
+        let req = actix_test::TestRequest::post()
+            .uri("/share/content-payment/create")
+            .set_json(pay_link(0, 0, 10))
+            .to_request();
+        let resp = actix_test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+    }
+}
@@ -0,0 +1,112 @@
+#[cfg(test)]
+mod tests {
+    use chrono::{Duration, TimeZone, Utc};
+    use netdisk_core::local_db::init_schema;
+    use netdisk_core::netdisk_auth::basic_env::NetDiskEnv;
+    use netdisk_core::responses::prelude::*;
+    use netdisk_core::share_stats::*;
+    use rusqlite::Connection;
+    use serde_json::json;
+    use tempfile::TempDir;
+
+    fn share(share_id: i64, name: &str, downloads: u32, bytes: u64) -> ShareItemData {
+        serde_json::from_value(json!({
+            "shareId": share_id,
+            "shareKey": format!("key{}", share_id),
+            "shareName": name,
+            "expiration": "2099-01-01 00:00:00",
+            "expired": 0,
+            "sharePwd": "",
+            "trafficSwitch": 1,
+            "trafficLimitSwitch": 1,
+            "trafficLimit": 0,
+            "bytesCharge": bytes,
+            "previewCount": downloads * 2,
+            "downloadCount": downloads,
+            "saveCount": 0
+        }))
+        .unwrap()
+    }
+
+    fn paid_share(share_id: i64, amount: i32) -> PayShareItem {
+        serde_json::from_value(json!({
+            "shareId": share_id,
+            "shareKey": format!("key{}", share_id),
+            "shareName": "付费资料",
+            "payAmount": 10,
+            "amount": amount,
+            "expiration": "2099-01-01 00:00:00",
+            "expired": 0,
+            "trafficSwitch": 1,
+            "trafficLimitSwitch": 1,
+            "trafficLimit": 0,
+            "bytesCharge": 0,
+            "previewCount": 0,
+            "downloadCount": 1,
+            "saveCount": 0
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_snapshot_report_deltas() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        init_schema(&conn).unwrap();
+        let t0 = Utc.with_ymd_and_hms(2024, 6, 1, 0, 0, 0).unwrap();
+        let t1 = t0 + Duration::days(1);
+
+        let first = snapshots_from_shares(
+            &[share(1, "电影, 合集", 10, 100), share(2, "文档", 5, 0)],
+            &[paid_share(3, 20)],
+            t0,
+        );
+        insert_snapshots(&mut conn, &first).unwrap();
+        let second = snapshots_from_shares(
+            &[share(1, "电影, 合集", 12, 500), share(2, "文档", 9, 0)],
+            &[paid_share(3, 50)],
+            t1,
+        );
+        insert_snapshots(&mut conn, &second).unwrap();
+
+        let snapshots = load_snapshots(&conn, None).unwrap();
+        assert_eq!(snapshots.len(), 6);
+        let report = build_report(&snapshots, None, ShareStatsSort::Downloads, None, t1);
+        let ids: Vec<u64> = report.rows.iter().map(|row| row.share_id).collect();
+        assert_eq!(ids, vec![2, 1, 3]);
+        assert_eq!(report.download_delta, 6);
+        assert_eq!(report.bytes_delta, 400);
+        assert_eq!(report.revenue_delta, 30);
+
+        let report = build_report(&snapshots, None, ShareStatsSort::Revenue, Some(1), t1);
+        assert_eq!(report.rows.len(), 1);
+        assert!(report.rows[0].paid);
+
+        // 只取第二次快照时区间内没有增量
+        let recent = load_snapshots(&conn, Some(t1)).unwrap();
+        let report = build_report(&recent, Some(t1), ShareStatsSort::Traffic, None, t1);
+        assert_eq!(report.rows.len(), 3);
+        assert_eq!(report.download_delta, 0);
+
+        let csv = report_to_csv(&report);
+        assert_eq!(csv.lines().count(), 4);
+        assert!(csv.contains("\"电影, 合集\""));
+    }
+
+    #[test]
+    fn test_report_rejects_huge_since_days() {
+        let dir = TempDir::new().expect("无法创建临时目录");
+        let env = NetDiskEnv {
+            config_dir: dir.path().to_path_buf(),
+        };
+        let mut query = ShareStatsQuery {
+            since_days: Some(MAX_SINCE_DAYS),
+            ..Default::default()
+        };
+        assert!(share_stats_report(&env, &query).is_ok());
+        for days in [1_000_000_000, i64::MAX] {
+            query.since_days = Some(days);
+            assert!(query.validate().unwrap_err().has("sinceDays"));
+            assert!(share_stats_report(&env, &query).is_err());
+        }
+    }
+}
@@ -0,0 +1,235 @@
+#[cfg(test)]
+mod tests {
+    use netdisk_core::netdisk_auth::basic_env::NetDiskEnv;
+    use netdisk_core::responses::prelude::*;
+    use netdisk_core::sync::engine::plan_sync;
+    use netdisk_core::sync::mirror::plan_mirror;
+    use netdisk_core::sync::snapshot::*;
+    use netdisk_core::sync::state::*;
+    use std::collections::BTreeMap;
+    use std::path::Path;
+    use tempfile::TempDir;
+
+    fn local(md5: &str) -> LocalEntry {
+        LocalEntry {
+            size: 1,
+            md5: md5.to_string(),
+            mtime: 0,
+        }
+    }
+
+    fn remote(file_id: u64, etag: &str) -> RemoteEntry {
+        RemoteEntry {
+            file_id,
+            parent_file_id: 0,
+            size: 1,
+            etag: etag.to_string(),
+        }
+    }
+
+    fn synced(md5: &str) -> SyncedFile {
+        SyncedFile {
+            size: 1,
+            md5: md5.to_string(),
+            mtime: 0,
+        }
+    }
+
+    #[test]
+    fn test_plan_sync_first_run() {
+        let local_files = BTreeMap::from([
+            ("same.txt".to_string(), local("a")),
+            ("local_only.txt".to_string(), local("b")),
+            ("differs.txt".to_string(), local("c")),
+        ]);
+        let remote_files = BTreeMap::from([
+            ("same.txt".to_string(), remote(1, "a")),
+            ("remote_only.txt".to_string(), remote(2, "d")),
+            ("differs.txt".to_string(), remote(3, "e")),
+        ]);
+        let plan = plan_sync(&local_files, &remote_files, &BTreeMap::new());
+
+        assert_eq!(plan.in_sync, vec!["same.txt".to_string()]);
+        assert!(plan.actions.contains(&SyncAction::Upload {
+            path: "local_only.txt".to_string(),
+            size: 1,
+            md5: "b".to_string(),
+        }));
+        assert!(plan.actions.contains(&SyncAction::Download {
+            path: "remote_only.txt".to_string(),
+            file_id: 2,
+            size: 1,
+            etag: "d".to_string(),
+        }));
+        // 没有同步记录时无法判断哪一侧更新
+        assert_eq!(plan.conflicts(), 1);
+    }
+
+    #[test]
+    fn test_plan_sync_with_state() {
+        let state = BTreeMap::from([
+            ("changed_local.txt".to_string(), synced("a")),
+            ("changed_remote.txt".to_string(), synced("b")),
+            ("deleted_local.txt".to_string(), synced("c")),
+            ("deleted_remote.txt".to_string(), synced("d")),
+            ("both.txt".to_string(), synced("e")),
+        ]);
+        let local_files = BTreeMap::from([
+            ("changed_local.txt".to_string(), local("a2")),
+            ("changed_remote.txt".to_string(), local("b")),
+            ("deleted_remote.txt".to_string(), local("d")),
+            ("both.txt".to_string(), local("e1")),
+        ]);
+        let remote_files = BTreeMap::from([
+            ("changed_local.txt".to_string(), remote(1, "a")),
+            ("changed_remote.txt".to_string(), remote(2, "b2")),
+            ("deleted_local.txt".to_string(), remote(3, "c")),
+            ("both.txt".to_string(), remote(5, "e2")),
+        ]);
+        let plan = plan_sync(&local_files, &remote_files, &state);
+
+        assert!(plan.actions.contains(&SyncAction::Upload {
+            path: "changed_local.txt".to_string(),
+            size: 1,
+            md5: "a2".to_string(),
+        }));
+        assert!(plan.actions.contains(&SyncAction::Download {
+            path: "changed_remote.txt".to_string(),
+            file_id: 2,
+            size: 1,
+            etag: "b2".to_string(),
+        }));
+        assert!(plan.actions.contains(&SyncAction::DeleteRemote {
+            path: "deleted_local.txt".to_string(),
+            file_id: 3,
+        }));
+        assert!(plan.actions.contains(&SyncAction::DeleteLocal {
+            path: "deleted_remote.txt".to_string(),
+        }));
+        assert_eq!(plan.conflicts(), 1);
+        assert_eq!(plan.actions.len(), 5);
+    }
+
+    #[test]
+    fn test_plan_sync_detects_renames() {
+        let state = BTreeMap::from([
+            ("old_local.txt".to_string(), synced("a")),
+            ("old_remote.txt".to_string(), synced("b")),
+        ]);
+        let local_files = BTreeMap::from([
+            ("dir/new_local.txt".to_string(), local("a")),
+            ("old_remote.txt".to_string(), local("b")),
+        ]);
+        let remote_files = BTreeMap::from([
+            ("old_local.txt".to_string(), remote(1, "a")),
+            ("new_remote.txt".to_string(), remote(2, "b")),
+        ]);
+        let plan = plan_sync(&local_files, &remote_files, &state);
+
+        assert_eq!(plan.actions.len(), 2);
+        assert!(plan.actions.contains(&SyncAction::RenameRemote {
+            from: "old_local.txt".to_string(),
+            to: "dir/new_local.txt".to_string(),
+            file_id: 1,
+        }));
+        assert!(plan.actions.contains(&SyncAction::RenameLocal {
+            from: "old_remote.txt".to_string(),
+            to: "new_remote.txt".to_string(),
+        }));
+        // 只有远端改名的文件需要使缓存失效
+        let report = SyncReport {
+            plan,
+            ..Default::default()
+        };
+        assert_eq!(report.remote_file_ids(), vec![1]);
+    }
+
+    #[test]
+    fn test_plan_mirror() {
+        let local_files = BTreeMap::from([
+            ("same.txt".to_string(), local("a")),
+            ("changed.txt".to_string(), local("b2")),
+            ("new.txt".to_string(), local("c")),
+        ]);
+        let remote_files = BTreeMap::from([
+            ("same.txt".to_string(), remote(1, "a")),
+            ("changed.txt".to_string(), remote(2, "b")),
+            ("dir/gone.txt".to_string(), remote(3, "d")),
+            ("_archive/2024-01-01/old.txt".to_string(), remote(4, "e")),
+        ]);
+
+        let (actions, unchanged) = plan_mirror(
+            &local_files,
+            &remote_files,
+            OrphanPolicy::Archive,
+            "_archive",
+            "2024-06-01",
+        );
+        assert_eq!(unchanged, 1);
+        assert_eq!(
+            actions.iter().map(|a| a.path()).collect::<Vec<_>>(),
+            vec!["changed.txt", "new.txt", "dir/gone.txt"]
+        );
+        assert_eq!(
+            actions[2],
+            MirrorAction::Archive {
+                path: "dir/gone.txt".to_string(),
+                to: "_archive/2024-06-01/dir/gone.txt".to_string(),
+                file_id: 3,
+            }
+        );
+
+        let (actions, _) = plan_mirror(
+            &local_files,
+            &remote_files,
+            OrphanPolicy::Trash,
+            "_archive",
+            "2024-06-01",
+        );
+        assert!(actions.contains(&MirrorAction::Trash {
+            path: "dir/gone.txt".to_string(),
+            file_id: 3,
+        }));
+    }
+
+    #[tokio::test]
+    async fn test_scan_local_and_state_roundtrip() {
+        let local_dir = TempDir::new().expect("无法创建临时目录");
+        std::fs::create_dir_all(local_dir.path().join("sub")).unwrap();
+        std::fs::write(local_dir.path().join("sub").join("a.txt"), b"hello").unwrap();
+        std::fs::write(local_dir.path().join(".a.txt.netdisk-tmp"), b"partial").unwrap();
+
+        let files = scan_local(local_dir.path(), &BTreeMap::new())
+            .await
+            .unwrap();
+        assert_eq!(files.keys().collect::<Vec<_>>(), vec!["sub/a.txt"]);
+        assert_eq!(files["sub/a.txt"].md5, "5d41402abc4b2a76b9719d911017c592");
+
+        let config_dir = TempDir::new().expect("无法创建临时目录");
+        let env = NetDiskEnv {
+            config_dir: config_dir.path().to_path_buf(),
+        };
+        let mut state = load_state(&env, SYNC_STATE_DIR, local_dir.path(), 42)
+            .await
+            .unwrap();
+        assert!(state.files.is_empty());
+        state.files.insert("sub/a.txt".to_string(), synced("x"));
+        save_state(&env, SYNC_STATE_DIR, &state).await.unwrap();
+
+        let loaded = load_state(&env, SYNC_STATE_DIR, local_dir.path(), 42)
+            .await
+            .unwrap();
+        assert_eq!(loaded.files["sub/a.txt"], synced("x"));
+        assert_eq!(loaded.remote_dir_id, 42);
+
+        // 状态文件名由路径的 MD5 决定
+        let path = state_path(&env, SYNC_STATE_DIR, Path::new("/data/assets"), 42);
+        assert_eq!(
+            path,
+            config_dir
+                .path()
+                .join(SYNC_STATE_DIR)
+                .join("42-fe3d6929d231dce45a049179de55181f.json")
+        );
+    }
+}
@@ -0,0 +1,99 @@
+#[cfg(test)]
+mod tests {
+    use actix_web::test as actix_test;
+    use actix_web::{web, App, HttpResponse};
+    use netdisk_core::responses::prelude::*;
+    use netdisk_core::telemetry::{
+        account_fingerprint, record_trace_id, trace_request, upstream_span, TRACE_ID_HEADER,
+    };
+    use serde_json::Value;
+    use std::io;
+    use std::sync::{Arc, Mutex};
+
+    /// 收集日志输出的缓冲区
+    #[derive(Clone, Default)]
+    struct LogBuffer(Arc<Mutex<Vec<u8>>>);
+
+    impl io::Write for LogBuffer {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    /// 模拟两次上游调用，其中一次返回重复的 trace ID
+    async fn upstream_calls() -> HttpResponse {
+        for trace_id in ["trace-a", "trace-b", "trace-a"] {
+            let span = upstream_span("GET", "api/v1/file/detail");
+            span.in_scope(|| record_trace_id(trace_id));
+        }
+        HttpResponse::Ok().finish()
+    }
+
+    #[test]
+    fn test_log_config() {
+        let config: GatewayConfig = toml::from_str("[log]\nformat = \"json\"\n").unwrap();
+        assert_eq!(config.log.format, LogFormat::Json);
+        assert_eq!(config.log.filter, "info");
+        assert_eq!(GatewayConfig::default().log.format, LogFormat::Text);
+
+        let empty = AccessToken::new(String::new(), chrono::Utc::now());
+        assert_eq!(account_fingerprint(&empty), "-");
+        let token = AccessToken::new("secret-token".to_string(), chrono::Utc::now());
+        let fingerprint = account_fingerprint(&token);
+        assert_eq!(fingerprint.len(), 8);
+        assert_eq!(fingerprint, account_fingerprint(&token));
+        assert!(!"secret-token".contains(&fingerprint));
+        // 不在网关请求中时只记录到 span
+        record_trace_id("trace-cli");
+    }
+
+    #[actix_web::test]
+    async fn test_request_span_and_trace_header() {
+        let logs = LogBuffer::default();
+        let writer = logs.clone();
+        let subscriber = tracing_subscriber::fmt()
+            .json()
+            .with_writer(move || writer.clone())
+            .finish();
+        let _guard = tracing::subscriber::set_default(subscriber);
+
+        let token = AccessToken::new("secret-token".to_string(), chrono::Utc::now());
+        let account = account_fingerprint(&token);
+        let app = actix_test::init_service(
+            App::new()
+                .wrap_fn(trace_request)
+                .app_data(web::Data::new(token))
+                .route("/files/{file_id}", web::get().to(upstream_calls)),
+        )
+        .await;
+        let req = actix_test::TestRequest::get().uri("/files/42").to_request();
+        let resp = actix_test::call_service(&app, req).await;
+        let trace_ids: Vec<&str> = resp
+            .headers()
+            .get_all(TRACE_ID_HEADER)
+            .map(|value| value.to_str().unwrap())
+            .collect();
+        assert_eq!(trace_ids, ["trace-a", "trace-b"]);
+
+        let output = String::from_utf8(logs.0.lock().unwrap().clone()).unwrap();
+        let finished: Value = output
+            .lines()
+            .map(|line| serde_json::from_str::<Value>(line).unwrap())
+            .find(|line| line["fields"]["message"] == "请求完成")
+            .expect("没有请求完成的日志");
+        let span = &finished["span"];
+        assert_eq!(span["name"], "request");
+        assert_eq!(span["method"], "GET");
+        assert_eq!(span["route"], "/files/{file_id}");
+        assert_eq!(span["status"], 200);
+        assert_eq!(span["account"], account.as_str());
+        assert_eq!(span["x_trace_id"], "trace-a,trace-b");
+        assert!(span["latency_ms"].is_u64());
+        assert!(!output.contains("secret-token"));
+    }
+}
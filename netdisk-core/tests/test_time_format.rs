@@ -0,0 +1,92 @@
+#[cfg(test)]
+mod tests {
+    use chrono::{TimeZone, Utc};
+    use netdisk_core::responses::prelude::*;
+    use serde_json::json;
+
+    fn vip(start_time: serde_json::Value) -> Result<VipInfo, serde_json::Error> {
+        serde_json::from_value(json!({
+            "vipLevel": 1,
+            "vipLabel": "VIP",
+            "startTime": start_time,
+            "endTime": "2025-06-01 00:00:00"
+        }))
+    }
+
+    #[test]
+    fn test_parse_platform_time() {
+        // 不带时区的时间按北京时间解释，与服务器时区无关
+        let expected = Utc.with_ymd_and_hms(2024, 6, 1, 4, 0, 0).unwrap();
+        for s in [
+            "2024-06-01 12:00:00",
+            "2024-06-01T12:00:00",
+            "2024-06-01 12:00:00.000",
+            "2024/06/01 12:00:00",
+            "2024-06-01T04:00:00Z",
+            "2024-06-01T12:00:00+08:00",
+            "1717214400",
+            "1717214400000",
+        ] {
+            let parsed = standard_format::parse_platform_time(s).unwrap();
+            assert_eq!(parsed.with_timezone(&Utc), expected, "{}", s);
+        }
+        assert_eq!(
+            standard_format::parse_platform_time("2024-06-01")
+                .unwrap()
+                .with_timezone(&Utc),
+            Utc.with_ymd_and_hms(2024, 5, 31, 16, 0, 0).unwrap()
+        );
+        assert!(standard_format::parse_platform_time("昨天").is_none());
+
+        // 1991 年夏令时结束时重复的时刻取较早者，开始时跳过的时刻按 UTC+8
+        let ambiguous = standard_format::parse_platform_time("1991-09-15 01:30:00").unwrap();
+        assert_eq!(
+            ambiguous.with_timezone(&Utc),
+            Utc.with_ymd_and_hms(1991, 9, 14, 16, 30, 0).unwrap()
+        );
+        let skipped = standard_format::parse_platform_time("1991-04-14 02:30:00").unwrap();
+        assert_eq!(
+            skipped.with_timezone(&Utc),
+            Utc.with_ymd_and_hms(1991, 4, 13, 18, 30, 0).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_time_fields() {
+        let info = vip(json!(1717214400)).unwrap();
+        assert_eq!(
+            info.start_time.with_timezone(&Utc),
+            Utc.with_ymd_and_hms(2024, 6, 1, 4, 0, 0).unwrap()
+        );
+        // 无效输入返回错误而不是 panic
+        assert!(vip(json!("2024-13-01 00:00:00")).is_err());
+        assert!(vip(json!("")).is_err());
+        assert!(vip(json!(null)).is_err());
+
+        let developer: DeveloperInfo = serde_json::from_value(json!({
+            "startTime": "",
+            "endTime": "0000-00-00 00:00:00"
+        }))
+        .unwrap();
+        assert!(developer.start_time.is_none());
+        assert!(developer.end_time.is_none());
+        assert!(serde_json::from_value::<DeveloperInfo>(json!({
+            "startTime": "not a time",
+            "endTime": null
+        }))
+        .is_err());
+
+        let value = serde_json::to_value(&info).unwrap();
+        assert_eq!(value["startTime"], "2024-06-01T12:00:00+08:00");
+        // RFC 3339 输出可以再次解析，缓存等场景可以往返
+        assert_eq!(
+            vip(value["startTime"].clone()).unwrap().start_time,
+            info.start_time
+        );
+
+        standard_format::set_output_format(TimeFormat::Legacy);
+        let value = serde_json::to_value(&info).unwrap();
+        standard_format::set_output_format(TimeFormat::Rfc3339);
+        assert_eq!(value["startTime"], "2024-06-01 12:00:00");
+    }
+}
@@ -0,0 +1,121 @@
+#[cfg(test)]
+mod tests {
+    use actix_web::test as actix_test;
+    use actix_web::{http::StatusCode, web};
+    use netdisk_core::create_app;
+    use netdisk_core::netdisk_auth::basic_env::NetDiskEnv;
+    use netdisk_core::responses::prelude::*;
+    use tempfile::TempDir;
+
+    fn share_item(file_id_list: &str) -> ShareItem {
+        serde_json::from_value(serde_json::json!({
+            "shareName": "资料",
+            "shareExpire": "7",
+            "fileIDList": file_id_list,
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_check_file_name() {
+        assert!(check_file_name("报告 2024.pdf").is_ok());
+        assert!(check_file_name("").is_err());
+        assert!(check_file_name("   ").is_err());
+        assert!(check_file_name("a/b").is_err());
+        assert!(check_file_name("a?b").is_err());
+        assert!(check_file_name(&"名".repeat(MAX_FILE_NAME_CHARS)).is_ok());
+        assert!(check_file_name(&"名".repeat(MAX_FILE_NAME_CHARS + 1)).is_err());
+    }
+
+    #[test]
+    fn test_request_validation() {
+        let query = FileListQuery {
+            parent_file_id: 0,
+            limit: 200,
+            search_data: None,
+            search_mode: Some(3),
+            last_file_id: None,
+        };
+        let errors = query.validate().unwrap_err();
+        assert!(errors.has("limit"));
+        assert!(errors.has("search_mode"));
+        assert!(!errors.has("parentFileId"));
+
+        let entry = EntryItem {
+            name: "a:b".to_string(),
+            parentID: 0,
+        };
+        assert!(entry.validate().unwrap_err().has("name"));
+
+        assert!(share_item("1,2,3").validate().is_ok());
+        let ids: Vec<String> = (1..=101).map(|id| id.to_string()).collect();
+        assert!(share_item(&ids.join(","))
+            .validate()
+            .unwrap_err()
+            .has("fileIDList"));
+
+        // 批量接口的请求间隔有上限
+        let mut batch = BatchTrashItem {
+            file_ids: vec![1],
+            interval_ms: Some(MAX_INTERVAL_MS),
+        };
+        assert!(batch.validate().is_ok());
+        batch.interval_ms = Some(MAX_INTERVAL_MS + 1);
+        assert!(batch.validate().unwrap_err().has("intervalMs"));
+        let offline = OfflineBulkItem {
+            urls: vec!["magnet:?xt=1".to_string()],
+            dir_id: None,
+            interval_ms: Some(u64::MAX),
+        };
+        assert!(offline.validate().unwrap_err().has("intervalMs"));
+
+        // 超过 100 项的 ID 列表在反序列化时即被拒绝
+        let ids: Vec<u64> = (1..=101).collect();
+        let result: Result<FilesQuery, _> =
+            serde_json::from_value(serde_json::json!({ "fileIds": ids }));
+        assert!(result.is_err());
+    }
+
+    #[actix_web::test]
+    async fn test_gateway_rejects_invalid_request() {
+        let dir = TempDir::new().expect("无法创建临时目录");
+        let app = actix_test::init_service(create_app(
+            web::Data::new(NetDiskEnv {
+                config_dir: dir.path().to_path_buf(),
+            }),
+            web::Data::new(AccessToken::default()),
+            web::Data::new(GatewayConfig::default()),
+        ))
+        .await;
+
+        let req = actix_test::TestRequest::get()
+            .uri("/file/file_lists_query?parentFileId=0&limit=200")
+            .to_request();
+        let resp = actix_test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+        let body: ApiResponse<FieldErrors> = actix_test::read_body_json(resp).await;
+        assert_eq!(body.code, 400);
+        let errors = body.data.unwrap();
+        assert_eq!(errors.0.len(), 1);
+        assert_eq!(errors.0[0].field, "limit");
+
+        let req = actix_test::TestRequest::post()
+            .uri("/file/mkdir")
+            .set_json(serde_json::json!({ "name": "a|b", "parentID": 0 }))
+            .to_request();
+        let resp = actix_test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+        let body: ApiResponse<FieldErrors> = actix_test::read_body_json(resp).await;
+        assert!(body.data.unwrap().has("name"));
+
+        // 缺少必填字段同样返回结构化的 400
+        let req = actix_test::TestRequest::post()
+            .uri("/file/mkdir")
+            .set_json(serde_json::json!({ "parentID": 0 }))
+            .to_request();
+        let resp = actix_test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+        let body: ApiResponse<FieldErrors> = actix_test::read_body_json(resp).await;
+        assert!(body.data.unwrap().has("body"));
+    }
+}
@@ -0,0 +1,123 @@
+#[cfg(test)]
+mod tests {
+    use chrono::{Duration, TimeZone, Utc};
+    use netdisk_core::local_db::open_local_db;
+    use netdisk_core::netdisk_auth::basic_env::NetDiskEnv;
+    use netdisk_core::responses::prelude::*;
+    use netdisk_core::video::*;
+    use reqwest::Url;
+    use serde_json::json;
+    use tempfile::TempDir;
+
+    fn record(resolutions: &[&str]) -> VideoTranscode {
+        let submitted_at = Utc.with_ymd_and_hms(2024, 6, 1, 4, 0, 0).unwrap();
+        VideoTranscode {
+            file_id: 42,
+            status: VideoTranscodeStatus::Transcoding,
+            resolutions: resolutions.iter().map(|r| r.to_string()).collect(),
+            ready: Vec::new(),
+            failed: Vec::new(),
+            submitted_at,
+            updated_at: submitted_at,
+        }
+    }
+
+    fn video(resolution: &str, status: i32) -> TranscodedVideo {
+        serde_json::from_value(json!({
+            "Resolution": resolution,
+            "Status": status,
+            "Files": [
+                {"FileName": "index.m3u8", "FileSize": 512, "Resolution": resolution, "Url": "https://cdn.example.com/v/42/index.m3u8?sign=1"}
+            ]
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_choose_resolutions() {
+        let available = "1080P, 720P,480P";
+        assert_eq!(
+            choose_resolutions(available, None).unwrap(),
+            vec!["1080P", "720P", "480P"]
+        );
+        assert_eq!(
+            choose_resolutions(available, Some("480p,1080P")).unwrap(),
+            vec!["1080P", "480P"]
+        );
+        assert!(choose_resolutions(available, Some("2160P")).is_err());
+        assert!(choose_resolutions("", None).is_err());
+
+        let data: VideoResolutionsData = serde_json::from_value(json!({
+            "IsGetResolution": true,
+            "Resolutions": "1080P,720P",
+            "NowOrFinishedResolutions": "",
+            "CodecNames": "H.264",
+            "VideoTime": 61
+        }))
+        .unwrap();
+        assert!(data.is_get_resolution);
+        assert_eq!(data.video_time, 61);
+    }
+
+    #[test]
+    fn test_apply_result() {
+        let now = Utc.with_ymd_and_hms(2024, 6, 1, 5, 0, 0).unwrap();
+        let mut transcode = record(&["1080P", "720P"]);
+        apply_result(&mut transcode, &[video("720P", 255)], now);
+        assert_eq!(transcode.status, VideoTranscodeStatus::Transcoding);
+        assert_eq!(transcode.ready, vec!["720P"]);
+
+        apply_result(
+            &mut transcode,
+            &[video("720P", 255), video("1080P", 3)],
+            now,
+        );
+        assert_eq!(transcode.status, VideoTranscodeStatus::Succeeded);
+        assert_eq!(transcode.failed, vec!["1080P"]);
+        assert_eq!(transcode.updated_at, now);
+
+        let mut failed = record(&["720P"]);
+        apply_result(&mut failed, &[video("720P", 100)], now);
+        assert_eq!(failed.status, VideoTranscodeStatus::Failed);
+    }
+
+    #[test]
+    fn test_playlists() {
+        let master = master_playlist("/video/42/play", &["1080P".to_string(), "720P".to_string()]);
+        assert!(master.starts_with("#EXTM3U\n"));
+        assert!(master.contains("RESOLUTION=1920x1080"));
+        assert!(master.contains("\n/video/42/play?resolution=720P\n"));
+
+        let base = Url::parse("https://cdn.example.com/v/42/index.m3u8?sign=1").unwrap();
+        let content = "#EXTM3U\n#EXT-X-KEY:METHOD=AES-128,URI=\"key.bin\"\n#EXTINF:10.0,\nseg0.ts\nhttps://other.example.com/seg1.ts\n#EXT-X-ENDLIST\n";
+        let rewritten = rewrite_playlist(content, &base);
+        assert!(rewritten.contains("URI=\"https://cdn.example.com/v/42/key.bin\""));
+        assert!(rewritten.contains("\nhttps://cdn.example.com/v/42/seg0.ts\n"));
+        assert!(rewritten.contains("\nhttps://other.example.com/seg1.ts\n"));
+        assert!(rewritten.ends_with("#EXT-X-ENDLIST\n"));
+    }
+
+    #[test]
+    fn test_transcode_records() {
+        let dir = TempDir::new().expect("无法创建临时目录");
+        let env = NetDiskEnv {
+            config_dir: dir.path().to_path_buf(),
+        };
+        let conn = open_local_db(&env).unwrap();
+        let mut first = record(&["1080P", "720P"]);
+        first.ready = vec!["720P".to_string()];
+        save_transcode(&conn, &first).unwrap();
+        let mut second = record(&["480P"]);
+        second.file_id = 43;
+        second.submitted_at += Duration::hours(1);
+        save_transcode(&conn, &second).unwrap();
+
+        assert_eq!(load_transcode(&conn, 42).unwrap(), Some(first));
+        assert_eq!(load_transcode(&conn, 44).unwrap(), None);
+        let all = load_transcodes(&conn).unwrap();
+        assert_eq!(
+            all.iter().map(|r| r.file_id).collect::<Vec<_>>(),
+            vec![43, 42]
+        );
+    }
+}
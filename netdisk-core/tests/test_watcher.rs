@@ -0,0 +1,147 @@
+#[cfg(test)]
+mod tests {
+    use netdisk_core::sync::watcher::*;
+    use std::path::{Path, PathBuf};
+    use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+    fn at(secs: u64) -> SystemTime {
+        UNIX_EPOCH + Duration::from_secs(secs)
+    }
+
+    #[test]
+    fn test_pending_files_wait_until_stable() {
+        let start = Instant::now();
+        let debounce = Duration::from_secs(2);
+        let stable_for = Duration::from_secs(3);
+        let mut pending = PendingFiles::default();
+        pending.touch(PathBuf::from("/w/render.mp4"), start);
+        pending.touch(PathBuf::from("/w/gone.mp4"), start);
+
+        let mut size = 100;
+        let mut stat = |path: &Path| {
+            if path.ends_with("gone.mp4") {
+                None
+            } else {
+                Some((size, at(1)))
+            }
+        };
+        // 去抖时间内不检查
+        assert!(pending
+            .take_stable(
+                start + Duration::from_secs(1),
+                debounce,
+                stable_for,
+                &mut stat
+            )
+            .is_empty());
+        assert_eq!(pending.len(), 2);
+        // 第一次观察，已删除的文件移出队列
+        assert!(pending
+            .take_stable(
+                start + Duration::from_secs(2),
+                debounce,
+                stable_for,
+                &mut stat
+            )
+            .is_empty());
+        assert_eq!(pending.len(), 1);
+
+        // 文件仍在增长，重新开始计时
+        size = 200;
+        let mut stat = |_: &Path| Some((size, at(1)));
+        assert!(pending
+            .take_stable(
+                start + Duration::from_secs(4),
+                debounce,
+                stable_for,
+                &mut stat
+            )
+            .is_empty());
+        assert!(pending
+            .take_stable(
+                start + Duration::from_secs(6),
+                debounce,
+                stable_for,
+                &mut stat
+            )
+            .is_empty());
+        assert_eq!(
+            pending.take_stable(
+                start + Duration::from_secs(7),
+                debounce,
+                stable_for,
+                &mut stat
+            ),
+            vec![PathBuf::from("/w/render.mp4")]
+        );
+        assert!(pending.is_empty());
+    }
+
+    #[test]
+    fn test_touch_restarts_debounce() {
+        let start = Instant::now();
+        let mut pending = PendingFiles::default();
+        let path = PathBuf::from("/w/a.exr");
+        pending.touch(path.clone(), start);
+        pending.touch(path.clone(), start + Duration::from_secs(5));
+
+        let stat = |_: &Path| Some((1, at(1)));
+        let ready = pending.take_stable(
+            start + Duration::from_secs(6),
+            Duration::from_secs(2),
+            Duration::ZERO,
+            stat,
+        );
+        assert!(ready.is_empty());
+        assert_eq!(pending.len(), 1);
+    }
+
+    #[test]
+    fn test_failed_upload_is_retried_with_backoff() {
+        let start = Instant::now();
+        let mut pending = PendingFiles::default();
+        let path = PathBuf::from("/w/a.exr");
+        let stat = |_: &Path| Some((1, at(1)));
+        let take = |pending: &mut PendingFiles, secs: u64| {
+            pending.take_stable(
+                start + Duration::from_secs(secs),
+                Duration::ZERO,
+                Duration::ZERO,
+                stat,
+            )
+        };
+
+        assert_eq!(
+            pending.retry_later(path.clone(), start),
+            Some(Duration::from_secs(5))
+        );
+        assert_eq!(pending.len(), 1);
+        // 等待期间不处理，之后重新观察并上传
+        assert!(take(&mut pending, 4).is_empty());
+        assert!(take(&mut pending, 5).is_empty());
+        assert_eq!(take(&mut pending, 6), vec![path.clone()]);
+
+        let delays: Vec<Option<Duration>> = (0..MAX_UPLOAD_RETRIES)
+            .map(|_| pending.retry_later(path.clone(), start))
+            .collect();
+        assert_eq!(delays[0], Some(Duration::from_secs(10)));
+        assert_eq!(delays[3], Some(Duration::from_secs(80)));
+        assert_eq!(delays[4], None);
+
+        // 再次写入后重新计数
+        pending.touch(path.clone(), start);
+        assert_eq!(
+            pending.retry_later(path.clone(), start),
+            Some(Duration::from_secs(5))
+        );
+        pending.succeeded(&path);
+    }
+
+    #[test]
+    fn test_is_ignored() {
+        assert!(is_ignored(Path::new("/w/.hidden")));
+        assert!(is_ignored(Path::new("/w/.a.mp4.netdisk-tmp")));
+        assert!(is_ignored(Path::new("/w/a.mp4.part")));
+        assert!(!is_ignored(Path::new("/w/sub/a.mp4")));
+    }
+}
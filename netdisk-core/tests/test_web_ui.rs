@@ -0,0 +1,145 @@
+#[cfg(test)]
+mod tests {
+    use actix_web::http::StatusCode;
+    use actix_web::test as actix_test;
+    use actix_web::web;
+    use netdisk_core::create_app;
+    use netdisk_core::netdisk_api::prelude::*;
+    use netdisk_core::netdisk_auth::basic_env::NetDiskEnv;
+    use netdisk_core::responses::prelude::*;
+    use serde_json::json;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_embedded_assets() {
+        let (content_type, body) = embedded_asset("").unwrap();
+        assert!(content_type.starts_with("text/html"));
+        let html = std::str::from_utf8(body).unwrap();
+        // 页面引用的脚本必须是嵌入的资源
+        assert!(html.contains("/static/js/main.js"));
+        assert!(embedded_asset("js/main.js").is_some());
+        assert!(embedded_asset("style.css").is_some());
+        assert!(embedded_asset("script.js").is_none());
+        assert!(embedded_asset("../Cargo.toml").is_none());
+    }
+
+    #[actix_web::test]
+    async fn test_static_ui_and_upload_session() {
+        let config_dir = TempDir::new().expect("无法创建临时目录");
+        let env = NetDiskEnv {
+            config_dir: config_dir.path().to_path_buf(),
+        };
+        let app = actix_test::init_service(create_app(
+            web::Data::new(env),
+            web::Data::new(AccessToken::default()),
+            web::Data::new(GatewayConfig::default()),
+        ))
+        .await;
+
+        let req = actix_test::TestRequest::get().uri("/").to_request();
+        let resp = actix_test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::FOUND);
+        let req = actix_test::TestRequest::get()
+            .uri("/static/js/main.js")
+            .to_request();
+        let resp = actix_test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+
+        let req = actix_test::TestRequest::post()
+            .uri("/upload/session")
+            .set_json(json!({"parentFileID": 0, "filename": "a.txt", "size": 5}))
+            .to_request();
+        let resp: WebUploadSessionResponse = actix_test::call_and_read_body_json(&app, req).await;
+        let session = resp.data.unwrap();
+        assert_eq!(session.received, 0);
+        let url = format!("/upload/session/{}", session.upload_id);
+
+        let req = actix_test::TestRequest::put()
+            .uri(&format!("{}?offset=0", url))
+            .set_payload("abc")
+            .to_request();
+        let resp: WebUploadSessionResponse = actix_test::call_and_read_body_json(&app, req).await;
+        assert_eq!(resp.data.unwrap().received, 3);
+
+        // 偏移不一致时返回当前进度
+        let req = actix_test::TestRequest::put()
+            .uri(&format!("{}?offset=0", url))
+            .set_payload("abc")
+            .to_request();
+        let resp = actix_test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::CONFLICT);
+
+        // 超出声明大小的数据被丢弃
+        let req = actix_test::TestRequest::put()
+            .uri(&format!("{}?offset=3", url))
+            .set_payload("defg")
+            .to_request();
+        let resp = actix_test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+        let req = actix_test::TestRequest::get().uri(&url).to_request();
+        let resp: WebUploadSessionResponse = actix_test::call_and_read_body_json(&app, req).await;
+        assert_eq!(resp.data.unwrap().received, 3);
+
+        // 数据未收齐时不能完成
+        let req = actix_test::TestRequest::post()
+            .uri(&format!("{}/complete", url))
+            .to_request();
+        let resp = actix_test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::CONFLICT);
+
+        let req = actix_test::TestRequest::delete().uri(&url).to_request();
+        let resp = actix_test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::NO_CONTENT);
+        let req = actix_test::TestRequest::get().uri(&url).to_request();
+        let resp = actix_test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[actix_web::test]
+    async fn test_concurrent_chunks_and_cleanup() {
+        let config_dir = TempDir::new().expect("无法创建临时目录");
+        let env = NetDiskEnv {
+            config_dir: config_dir.path().to_path_buf(),
+        };
+        let app = actix_test::init_service(create_app(
+            web::Data::new(env.clone()),
+            web::Data::new(AccessToken::default()),
+            web::Data::new(GatewayConfig::default()),
+        ))
+        .await;
+
+        let req = actix_test::TestRequest::post()
+            .uri("/upload/session")
+            .set_json(json!({"parentFileID": 0, "filename": "a.txt", "size": 6}))
+            .to_request();
+        let resp: WebUploadSessionResponse = actix_test::call_and_read_body_json(&app, req).await;
+        let url = format!("/upload/session/{}", resp.data.unwrap().upload_id);
+
+        // 同一偏移量的两个分块只有一个被写入
+        let chunk = || {
+            actix_test::TestRequest::put()
+                .uri(&format!("{}?offset=0", url))
+                .set_payload("abc")
+                .to_request()
+        };
+        let (first, second) = futures_util::join!(
+            actix_test::call_service(&app, chunk()),
+            actix_test::call_service(&app, chunk())
+        );
+        let mut statuses = vec![first.status(), second.status()];
+        statuses.sort();
+        assert_eq!(statuses, vec![StatusCode::OK, StatusCode::CONFLICT]);
+        let req = actix_test::TestRequest::get().uri(&url).to_request();
+        let resp: WebUploadSessionResponse = actix_test::call_and_read_body_json(&app, req).await;
+        assert_eq!(resp.data.unwrap().received, 3);
+
+        let ttl = std::time::Duration::from_secs(3600);
+        assert_eq!(cleanup_web_uploads(&env, ttl).await.unwrap(), 0);
+        tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+        let ttl = std::time::Duration::from_millis(10);
+        assert_eq!(cleanup_web_uploads(&env, ttl).await.unwrap(), 1);
+        let req = actix_test::TestRequest::get().uri(&url).to_request();
+        let resp = actix_test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+    }
+}
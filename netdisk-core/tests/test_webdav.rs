@@ -0,0 +1,114 @@
+#[cfg(test)]
+mod tests {
+    use actix_web::http::StatusCode;
+    use actix_web::test as actix_test;
+    use actix_web::web;
+    use netdisk_core::create_app;
+    use netdisk_core::netdisk_api::prelude::*;
+    use netdisk_core::netdisk_auth::basic_env::NetDiskEnv;
+    use netdisk_core::responses::prelude::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_dav_path_and_href() {
+        assert_eq!(dav_path("/dav", "/dav"), Some("/".to_string()));
+        assert_eq!(
+            dav_path("/dav/%E6%96%87%E6%A1%A3/a%20b.txt", "/dav"),
+            Some("/文档/a b.txt".to_string())
+        );
+        assert_eq!(dav_path("/davx/a", "/dav"), None);
+
+        assert!(is_same_or_within("/a/b/c", "/a/b"));
+        assert!(is_same_or_within("/a/b", "/a/b"));
+        assert!(!is_same_or_within("/a/bc", "/a/b"));
+
+        assert_eq!(dav_href("/dav", "/", true), "/dav/");
+        assert_eq!(
+            dav_href("/dav", "/文档/a b#1.txt", false),
+            "/dav/%E6%96%87%E6%A1%A3/a%20b%231.txt"
+        );
+    }
+
+    #[test]
+    fn test_propfind_root() {
+        let body = multistatus(&[propfind_entry("/dav/", &RemoteNode::Root)]);
+        assert!(body.starts_with("<?xml"));
+        assert!(body.contains("<D:href>/dav/</D:href>"));
+        assert!(body.contains("<D:collection/>"));
+    }
+
+    #[actix_web::test]
+    async fn test_disabled_by_default() {
+        let config_dir = TempDir::new().expect("无法创建临时目录");
+        let env = NetDiskEnv {
+            config_dir: config_dir.path().to_path_buf(),
+        };
+        let gateway: GatewayConfig = toml::from_str("[webdav]\nprefix = \"/dav\"\n").unwrap();
+        assert!(!gateway.webdav.enabled);
+        let app = actix_test::init_service(create_app(
+            web::Data::new(env),
+            web::Data::new(AccessToken::default()),
+            web::Data::new(gateway),
+        ))
+        .await;
+
+        let req = actix_test::TestRequest::default()
+            .method(actix_web::http::Method::OPTIONS)
+            .uri("/dav/")
+            .to_request();
+        let resp = actix_test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[actix_web::test]
+    async fn test_options_under_configured_prefix() {
+        let config_dir = TempDir::new().expect("无法创建临时目录");
+        let env = NetDiskEnv {
+            config_dir: config_dir.path().to_path_buf(),
+        };
+        let mut gateway = GatewayConfig::default();
+        gateway.webdav.enabled = true;
+        gateway.webdav.prefix = "/webdav/".to_string();
+        let app = actix_test::init_service(create_app(
+            web::Data::new(env),
+            web::Data::new(AccessToken::default()),
+            web::Data::new(gateway),
+        ))
+        .await;
+
+        let req = actix_test::TestRequest::default()
+            .method(actix_web::http::Method::OPTIONS)
+            .uri("/webdav/")
+            .to_request();
+        let resp = actix_test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(resp.headers().get("DAV").unwrap(), "1");
+
+        let req = actix_test::TestRequest::default()
+            .method(actix_web::http::Method::from_bytes(b"LOCK").unwrap())
+            .uri("/webdav/a.txt")
+            .to_request();
+        let resp = actix_test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::METHOD_NOT_ALLOWED);
+    }
+
+    #[actix_web::test]
+    async fn test_root_prefix_is_rejected() {
+        let config_dir = TempDir::new().expect("无法创建临时目录");
+        let env = NetDiskEnv {
+            config_dir: config_dir.path().to_path_buf(),
+        };
+        std::fs::write(
+            config_dir.path().join(GATEWAY_CONFIG_FILE),
+            "[webdav]\nprefix = \"/\"\n",
+        )
+        .unwrap();
+        let err = GatewayConfig::load(&env).await.unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+
+        let mut gateway = GatewayConfig::default();
+        gateway.webdav.prefix = String::new();
+        assert!(gateway.validate().is_err());
+        assert!(GatewayConfig::default().validate().is_ok());
+    }
+}
@@ -0,0 +1,177 @@
+use clap::{Parser, Subcommand};
+use netdisk_core::responses::prelude::{ReportFormat, ShareStatsSort};
+use std::path::PathBuf;
+
+/// 123 云盘命令行工具，不带子命令时启动 HTTP 网关
+#[derive(Debug, Parser)]
+#[command(name = "netdisk-tools", version)]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// 启动 HTTP 网关
+    Serve {
+        /// 监听地址
+        #[arg(long, default_value = "127.0.0.1:8080")]
+        bind: String,
+    },
+    /// 创建目录，路径形如 `a/b/c`
+    Mkdir {
+        /// 自动创建缺失的上级目录，目录已存在时不报错
+        #[arg(short, long)]
+        parents: bool,
+        /// 相对于 `--parent-id` 的目录路径
+        path: String,
+        /// 起始目录 ID，根目录为 0
+        #[arg(long, default_value = "0")]
+        parent_id: u64,
+    },
+    /// 本地目录与远端目录双向同步
+    Sync {
+        /// 本地目录
+        local_path: PathBuf,
+        /// 远端目录 ID
+        remote_dir_id: u64,
+        /// 只输出同步计划，不执行
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// 本地目录单向镜像（备份）到远端目录，只上传不下载
+    Mirror {
+        /// 本地目录
+        local_path: PathBuf,
+        /// 远端目录 ID
+        remote_dir_id: u64,
+        /// 远端多余的文件移入回收站，而不是移动到归档目录
+        #[arg(long)]
+        trash_orphans: bool,
+        /// 归档目录名，位于远端目录下
+        #[arg(long, default_value = "_archive")]
+        archive_dir: String,
+        /// 只输出镜像计划，不执行
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// 监听本地目录，持续将写入完成的新文件上传到远端目录
+    Watch {
+        /// 监听的本地目录，子目录按相同结构上传
+        local_path: PathBuf,
+        /// 远端目录 ID
+        remote_dir_id: u64,
+        /// 最后一次写入事件之后等待的毫秒数
+        #[arg(long, default_value = "2000")]
+        debounce_ms: u64,
+        /// 文件大小与修改时间保持不变多少秒后才上传
+        #[arg(long, default_value = "5")]
+        stable_secs: u64,
+        /// 上传并校验成功后删除本地文件
+        #[arg(long, conflicts_with = "move_to")]
+        delete_after: bool,
+        /// 上传并校验成功后将本地文件移动到该目录
+        #[arg(long)]
+        move_to: Option<PathBuf>,
+        /// 启动时同时上传目录中已有的文件
+        #[arg(long)]
+        upload_existing: bool,
+    },
+    /// 分享统计：记录快照、导出报告
+    ShareStats {
+        #[command(subcommand)]
+        action: ShareStatsCommand,
+    },
+    /// 离线下载：批量提交链接、轮询任务进度
+    Offline {
+        #[command(subcommand)]
+        action: OfflineCommand,
+    },
+    /// 图床：上传图片到按日期划分的目录，输出链接与 Markdown/HTML 片段
+    ImageUpload {
+        /// 图片文件，`-` 表示从标准输入读取
+        file: PathBuf,
+        /// 网盘中使用的名称，同时作为替代文字；从标准输入读取时建议指定
+        #[arg(long)]
+        name: Option<String>,
+        /// 宽度超过该值时等比缩小，为 0 时不缩放；默认使用配置中的 `max_width`
+        #[arg(long)]
+        max_width: Option<u32>,
+        /// 只输出 Markdown 片段
+        #[arg(long)]
+        markdown: bool,
+    },
+    /// 将网盘只读挂载到本地目录（需启用 `fuse` 特性），使用 `fusermount -u` 卸载
+    #[cfg(all(feature = "fuse", target_os = "linux"))]
+    Mount {
+        /// 本地挂载点，需为已存在的空目录
+        mountpoint: PathBuf,
+        /// 挂载的网盘目录路径，默认为根目录
+        #[arg(long, default_value = "/")]
+        root: String,
+        /// 内存块缓存大小（MiB）
+        #[arg(long, default_value = "256")]
+        cache_mb: usize,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+pub enum ShareStatsCommand {
+    /// 记录一次全部分享的统计快照，可配合 cron 定期执行
+    Snapshot,
+    /// 根据本地快照输出报告
+    Report {
+        /// 只统计最近 N 天的快照，最多 36500 天
+        #[arg(long)]
+        since_days: Option<i64>,
+        /// 排序方式：downloads、traffic 或 revenue
+        #[arg(long, default_value = "downloads")]
+        sort: ShareStatsSort,
+        /// 只输出前 N 个分享
+        #[arg(long)]
+        top: Option<usize>,
+        /// 输出格式：json 或 csv
+        #[arg(long, default_value = "json")]
+        format: ReportFormat,
+        /// 写入文件而不是标准输出
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+pub enum OfflineCommand {
+    /// 从链接文件或 SQLite 目录批量提交离线下载任务，已提交过的链接会被跳过
+    Submit {
+        /// 链接文件，每行一个 http(s) 或磁力链接
+        #[arg(long, required_unless_present = "catalog")]
+        file: Option<PathBuf>,
+        /// python 流水线生成的 SQLite 目录，例如 python/movies.db
+        #[arg(long, conflicts_with = "file")]
+        catalog: Option<PathBuf>,
+        /// 最多提交的链接数
+        #[arg(long)]
+        limit: Option<usize>,
+        /// 保存到的目录 ID，不传时保存到上游默认的离线下载目录
+        #[arg(long)]
+        dir_id: Option<u64>,
+        /// 相邻两次提交的最小间隔（毫秒）
+        #[arg(long, default_value = "500")]
+        interval_ms: u64,
+    },
+    /// 轮询未结束的任务，记录完成状态与下载得到的文件 ID
+    Poll {
+        /// 轮询间隔（秒）
+        #[arg(long, default_value = "30")]
+        interval_secs: u64,
+        /// 只查询一次
+        #[arg(long)]
+        once: bool,
+    },
+    /// 列出本地记录的任务
+    List {
+        /// 只列出未结束的任务
+        #[arg(long)]
+        pending: bool,
+    },
+}
@@ -1,15 +1,47 @@
+mod cli;
+
 use actix_web::web;
 use actix_web::HttpServer;
+use clap::Parser;
+use cli::{Cli, Command, OfflineCommand, ShareStatsCommand};
 use log::{debug, error};
-use netdisk_core::create_app;
+use netdisk_core::cache::ResponseCache;
+use netdisk_core::create_app_with_cache;
+use netdisk_core::image_host::{host_image, prepare_image};
+use netdisk_core::local_db::open_local_db;
 use netdisk_core::netdisk_api::prelude::*;
 use netdisk_core::netdisk_auth::basic_env::NetDiskEnv;
+use netdisk_core::offline::{
+    catalog_links, load_tasks, poll_once, read_links_file, run_poller, submit_links,
+};
 use netdisk_core::responses::prelude::*;
+use netdisk_core::share_stats::{
+    report_to_csv, run_snapshot_loop, share_stats_report, take_snapshot,
+};
+use netdisk_core::sync::engine::run_sync;
+use netdisk_core::sync::mirror::run_mirror;
+use netdisk_core::sync::watcher::{run_watch, AfterUpload, WatchConfig};
+use netdisk_core::telemetry::init_logging;
+use std::error::Error;
+use std::io::Read;
+use std::time::Duration;
+
 #[actix_web::main]
 async fn main() -> std::io::Result<()> {
-    env_logger::init();
+    let cli = Cli::parse();
+
+    let env = NetDiskEnv::new();
+    // 日志格式来自网关配置；读取失败时先用默认格式，错误在 serve 中报告
+    let log_config = match &env {
+        Ok(env) => GatewayConfig::load(env)
+            .await
+            .map(|config| config.log)
+            .unwrap_or_default(),
+        Err(_) => LogConfig::default(),
+    };
+    init_logging(&log_config);
 
-    let env = match NetDiskEnv::new() {
+    let env = match env {
         Ok(env) => env,
         Err(e) => {
             error!("❌ 致命错误：无法初始化 NetDiskEnv：{}", e);
@@ -27,12 +59,269 @@ async fn main() -> std::io::Result<()> {
         }
     }
 
+    match cli.command {
+        None => serve(env, access_token, "127.0.0.1:8080").await,
+        Some(Command::Serve { bind }) => serve(env, access_token, &bind).await,
+        Some(command) => {
+            if let Err(e) = run_command(command, &env, &access_token).await {
+                error!("❌ 命令执行失败：{}", e);
+                std::process::exit(1);
+            }
+            Ok(())
+        }
+    }
+}
+
+async fn serve(env: NetDiskEnv, access_token: AccessToken, bind: &str) -> std::io::Result<()> {
+    let gateway_config = GatewayConfig::load(&env).await?;
+    standard_format::set_output_format(gateway_config.time_format);
+    let interval_mins = gateway_config.share_stats.snapshot_interval_mins;
+    if interval_mins > 0 {
+        actix_web::rt::spawn(run_snapshot_loop(
+            access_token.clone(),
+            env.clone(),
+            Duration::from_secs(interval_mins * 60),
+        ));
+    }
+    let multipart_ttl_hours = gateway_config.s3.multipart_ttl_hours;
+    if gateway_config.s3.enabled && multipart_ttl_hours > 0 {
+        actix_web::rt::spawn(run_multipart_cleanup_loop(
+            env.clone(),
+            Duration::from_secs(multipart_ttl_hours.saturating_mul(3600)),
+        ));
+    }
+    let session_ttl_hours = gateway_config.web_upload.session_ttl_hours;
+    if session_ttl_hours > 0 {
+        actix_web::rt::spawn(run_web_upload_cleanup_loop(
+            env.clone(),
+            Duration::from_secs(session_ttl_hours.saturating_mul(3600)),
+        ));
+    }
     // 注入全局数据
+    let cache_data = web::Data::new(ResponseCache::new(&gateway_config.cache, &env));
     let config_path_data = web::Data::new(env);
     let access_token_data = web::Data::new(access_token);
+    let gateway_config_data = web::Data::new(gateway_config);
+
+    HttpServer::new(move || {
+        create_app_with_cache(
+            config_path_data.clone(),
+            access_token_data.clone(),
+            gateway_config_data.clone(),
+            cache_data.clone(),
+        )
+    })
+    .bind(bind)?
+    .run()
+    .await
+}
+
+async fn run_command(
+    command: Command,
+    env: &NetDiskEnv,
+    token: &AccessToken,
+) -> Result<(), Box<dyn Error>> {
+    match command {
+        Command::Serve { .. } => unreachable!("serve 在 main 中处理"),
+        Command::Mkdir {
+            parents,
+            path,
+            parent_id,
+        } => {
+            let dir_id = if parents {
+                ensure_dir_path(token, &path, parent_id).await?
+            } else {
+                mkdir_single(token, &path, parent_id).await?
+            };
+            println!("{}", dir_id);
+        }
+        Command::Sync {
+            local_path,
+            remote_dir_id,
+            dry_run,
+        } => {
+            let report = run_sync(token, env, &local_path, remote_dir_id, dry_run).await?;
+            println!("{}", serde_json::to_string_pretty(&report)?);
+        }
+        Command::Mirror {
+            local_path,
+            remote_dir_id,
+            trash_orphans,
+            archive_dir,
+            dry_run,
+        } => {
+            let item = MirrorItem {
+                local_path,
+                remote_dir_id,
+                orphan_policy: if trash_orphans {
+                    OrphanPolicy::Trash
+                } else {
+                    OrphanPolicy::Archive
+                },
+                archive_dir,
+                dry_run,
+            };
+            let report = run_mirror(token, env, &item).await?;
+            println!("{}", serde_json::to_string_pretty(&report)?);
+        }
+        Command::Watch {
+            local_path,
+            remote_dir_id,
+            debounce_ms,
+            stable_secs,
+            delete_after,
+            move_to,
+            upload_existing,
+        } => {
+            let after_upload = match (delete_after, move_to) {
+                (true, _) => AfterUpload::Delete,
+                (false, Some(dir)) => AfterUpload::MoveTo(dir),
+                (false, None) => AfterUpload::Keep,
+            };
+            let config = WatchConfig {
+                local_root: local_path,
+                remote_dir_id,
+                debounce: Duration::from_millis(debounce_ms),
+                stable_for: Duration::from_secs(stable_secs),
+                after_upload,
+                upload_existing,
+            };
+            run_watch(token, &config).await?;
+        }
+        Command::ShareStats { action } => match action {
+            ShareStatsCommand::Snapshot => {
+                let result = take_snapshot(token, env).await?;
+                println!("{}", serde_json::to_string_pretty(&result)?);
+            }
+            ShareStatsCommand::Report {
+                since_days,
+                sort,
+                top,
+                format,
+                output,
+            } => {
+                let query = ShareStatsQuery {
+                    since_days,
+                    sort,
+                    top,
+                    format,
+                };
+                let report = share_stats_report(env, &query)?;
+                let content = match format {
+                    ReportFormat::Json => serde_json::to_string_pretty(&report)?,
+                    ReportFormat::Csv => report_to_csv(&report),
+                };
+                match output {
+                    Some(path) => std::fs::write(path, content)?,
+                    None => println!("{}", content),
+                }
+            }
+        },
+        Command::Offline { action } => match action {
+            OfflineCommand::Submit {
+                file,
+                catalog,
+                limit,
+                dir_id,
+                interval_ms,
+            } => {
+                let mut links = match (file, catalog) {
+                    (Some(path), _) => read_links_file(&path)?,
+                    (None, Some(path)) => catalog_links(&path, limit)?,
+                    (None, None) => return Err("需要 --file 或 --catalog".into()),
+                };
+                if let Some(limit) = limit {
+                    links.truncate(limit);
+                }
+                let mut limiter = RateLimiter::new(Duration::from_millis(interval_ms));
+                let report = submit_links(token, env, links, dir_id, true, &mut limiter).await?;
+                println!("{}", serde_json::to_string_pretty(&report)?);
+            }
+            OfflineCommand::Poll {
+                interval_secs,
+                once,
+            } => {
+                let tasks = if once {
+                    poll_once(token, env).await?
+                } else {
+                    run_poller(token, env, Duration::from_secs(interval_secs)).await?
+                };
+                println!("{}", serde_json::to_string_pretty(&tasks)?);
+            }
+            OfflineCommand::List { pending } => {
+                let tasks = load_tasks(&open_local_db(env)?, pending)?;
+                println!("{}", serde_json::to_string_pretty(&tasks)?);
+            }
+        },
+        Command::ImageUpload {
+            file,
+            name,
+            max_width,
+            markdown,
+        } => {
+            let gateway = GatewayConfig::load(env).await?;
+            let (data, file_name) = if file.as_os_str() == "-" {
+                let mut data = Vec::new();
+                std::io::stdin().read_to_end(&mut data)?;
+                (data, None)
+            } else {
+                let file_name = file
+                    .file_name()
+                    .map(|name| name.to_string_lossy().into_owned());
+                (std::fs::read(&file)?, file_name)
+            };
+            let config = &gateway.image_host;
+            let image = prepare_image(
+                data,
+                config.max_bytes,
+                max_width.unwrap_or(config.max_width),
+                config.max_pixels,
+            )?;
+            let name = name.or(file_name);
+            let result = host_image(token, &gateway, image, name.as_deref()).await?;
+            if markdown {
+                println!("{}", result.markdown);
+            } else {
+                println!("{}", serde_json::to_string_pretty(&result)?);
+            }
+        }
+        #[cfg(all(feature = "fuse", target_os = "linux"))]
+        Command::Mount {
+            mountpoint,
+            root,
+            cache_mb,
+        } => {
+            let root_id = match resolve_path(token, &root).await? {
+                Some(node) if node.is_dir() => node.file_id(),
+                _ => return Err(format!("网盘目录 {} 不存在", root).into()),
+            };
+            let token = token.clone();
+            // 文件系统内部自带运行时，需在独立线程中阻塞运行
+            actix_web::rt::task::spawn_blocking(move || {
+                netdisk_core::mount::fuse_fs::mount(token, root_id, &mountpoint, cache_mb << 20)
+            })
+            .await??;
+        }
+    }
+    Ok(())
+}
 
-    HttpServer::new(move || create_app(config_path_data.clone(), access_token_data.clone()))
-        .bind(("127.0.0.1", 8080))?
-        .run()
-        .await
+/// 不带 `-p` 时要求上级目录已存在且目标目录不存在
+async fn mkdir_single(
+    token: &AccessToken,
+    path: &str,
+    root_id: u64,
+) -> Result<u64, Box<dyn Error>> {
+    let names = split_dir_path(path);
+    let (name, parents) = names.split_last().ok_or("目录路径不能为空")?;
+    let mut parent_id = root_id;
+    for parent in parents {
+        parent_id = find_dir(token, parent, parent_id)
+            .await?
+            .ok_or_else(|| format!("上级目录 {} 不存在，可使用 -p 自动创建", parent))?;
+    }
+    if find_dir(token, name, parent_id).await?.is_some() {
+        return Err(format!("目录 {} 已存在", path).into());
+    }
+    create_dir(token, name, parent_id).await
 }